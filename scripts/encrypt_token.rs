@@ -1,12 +1,14 @@
 //! Token Encryption Utility
 //!
 //! This script encrypts tokens using AES-256-GCM for secure database storage.
-//! Requires TOKEN_ENCRYPTION_KEY environment variable to be set.
+//! Uses the same keys as the server: TOKEN_ENCRYPTION_KEY, or the key file or passphrase
+//! selected by TOKEN_ENCRYPTION_KEY_PROVIDER.
 
 use std::io::{self, Write};
 
 // Re-use the crypto module from the main crate
-use reputest::crypto::encrypt_token;
+use reputest::config::load_config_file;
+use reputest::crypto::{is_encryption_configured, load_keyring};
 
 fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!("🔐 Token Encryption Utility");
@@ -14,8 +16,9 @@ fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     println!();
 
     // Check if encryption key is configured
-    if std::env::var("TOKEN_ENCRYPTION_KEY").is_err() {
-        eprintln!("❌ Error: TOKEN_ENCRYPTION_KEY environment variable is not set.");
+    load_config_file()?;
+    if !is_encryption_configured() {
+        eprintln!("❌ Error: No token encryption key is configured.");
         eprintln!();
         eprintln!("Generate a key with:");
         eprintln!("  openssl rand -hex 32");
@@ -38,15 +41,19 @@ fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
     }
 
     // Encrypt the token
-    match encrypt_token(token) {
+    // The row id is not known yet, so the token is bound to its row later
+    match load_keyring().and_then(|keyring| keyring.encrypt_unbound(token)) {
         Ok(encrypted) => {
             println!();
             println!("✅ Token encrypted successfully!");
             println!();
-            println!("Encrypted value (hex):");
+            println!("Encrypted value:");
             println!("{}", encrypted);
             println!();
-            println!("📝 Use this value in your database INSERT statement.");
+            println!("📝 Use this value in your database INSERT statement, then run");
+            println!(
+                "   `cargo run --bin rotate_keys` (or restart the server) to bind it to its row."
+            );
         }
         Err(e) => {
             eprintln!("❌ Encryption failed: {}", e);
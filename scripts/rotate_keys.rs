@@ -0,0 +1,62 @@
+//! Token Encryption Key Rotation Utility
+//!
+//! Re-encrypts every stored token (`access_tokens`, `refresh_tokens` and `sessions`)
+//! with the current key. To rotate the key:
+//!
+//! 1. Generate a new key with `openssl rand -hex 32`
+//! 2. Set `TOKEN_ENCRYPTION_KEY` to the new key and add the old one to
+//!    `TOKEN_ENCRYPTION_PREVIOUS_KEYS`, then restart the server. With a key file
+//!    (`TOKEN_ENCRYPTION_KEY_PROVIDER=file`), put the new key on the first line and keep the
+//!    old one below it instead; with a passphrase, put the old passphrase in
+//!    `TOKEN_ENCRYPTION_PREVIOUS_FILE`
+//! 3. Run this binary with the same settings
+//! 4. Once it reports no failures, remove the old key
+
+use reputest::config::load_config_file;
+use reputest::crypto::load_keyring;
+use reputest::db::{get_db_pool, reencrypt_tokens};
+
+#[tokio::main]
+async fn main() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    env_logger::init();
+
+    println!("🔑 Token Encryption Key Rotation Utility");
+    println!("========================================");
+    println!();
+
+    load_config_file()?;
+    let keyring = match load_keyring() {
+        Ok(keyring) => keyring,
+        Err(e) => {
+            eprintln!("❌ Error: {}", e);
+            std::process::exit(1);
+        }
+    };
+    println!("Keys: {:?}", keyring);
+    println!(
+        "Re-encrypting stored tokens with key {}...",
+        keyring.current_key_id()
+    );
+
+    let pool = get_db_pool().await?;
+    let report = reencrypt_tokens(&pool, &keyring).await?;
+
+    println!();
+    println!("access_tokens:  {} re-encrypted", report.access_tokens);
+    println!("refresh_tokens: {} re-encrypted", report.refresh_tokens);
+    println!("sessions:       {} re-encrypted", report.sessions);
+
+    if report.failed > 0 {
+        eprintln!();
+        eprintln!(
+            "❌ {} rows could not be decrypted with any configured key and were left unchanged.",
+            report.failed
+        );
+        eprintln!("   Add the key they were encrypted with to the previous keys and run again.");
+        std::process::exit(1);
+    }
+
+    println!();
+    println!("✅ All stored tokens use the current key. Previous keys can now be removed.");
+    Ok(())
+}
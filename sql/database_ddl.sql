@@ -14,13 +14,38 @@ COMMENT ON COLUMN refresh_tokens.created_at IS 'Timestamp when the refresh token
 CREATE TABLE access_tokens (
     id         SERIAL                    PRIMARY KEY,  -- Auto-incrementing ID
     token      TEXT                      NOT NULL,     -- The access token value
-    created_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()  -- When the token was created
+    created_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),  -- When the token was created
+    expires_at TIMESTAMP WITH TIME ZONE             -- When the token expires, NULL if unknown
 );
 
 COMMENT ON TABLE access_tokens IS 'OAuth 2.0 access tokens used for Twitter API requests';
 COMMENT ON COLUMN access_tokens.id IS 'Auto-incrementing primary key';
 COMMENT ON COLUMN access_tokens.token IS 'The OAuth 2.0 access token value';
 COMMENT ON COLUMN access_tokens.created_at IS 'Timestamp when the access token was created';
+COMMENT ON COLUMN access_tokens.expires_at IS 'When the access token expires (from expires_in of the token endpoint), NULL if unknown';
+
+-- One row per attempt to refresh the bot's access token
+CREATE TABLE token_refreshes (
+    id              BIGSERIAL                 PRIMARY KEY,
+    refreshed_at    TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    reason          TEXT                      NOT NULL,  -- Why the token was refreshed: unauthorized, expiring or manual
+    triggered_by    TEXT                      NOT NULL,  -- Operation that refreshed it, e.g. search_mentions
+    outcome         TEXT                      NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+    refresh_rotated BOOLEAN                   NOT NULL DEFAULT FALSE,  -- Whether X issued a new refresh token
+    error           TEXT                                 -- Why the refresh failed, or what could not be saved
+);
+
+COMMENT ON TABLE token_refreshes IS 'Audit log of bot access token refreshes: when, why and by which operation';
+COMMENT ON COLUMN token_refreshes.refreshed_at IS 'When the refresh was attempted';
+COMMENT ON COLUMN token_refreshes.reason IS 'Why the token was refreshed: unauthorized (X answered 401), expiring (refreshed before it expires) or manual (refresh_token binary)';
+COMMENT ON COLUMN token_refreshes.triggered_by IS 'Name of the X API operation that refreshed the token, e.g. search_mentions';
+COMMENT ON COLUMN token_refreshes.outcome IS 'succeeded or failed';
+COMMENT ON COLUMN token_refreshes.refresh_rotated IS 'Whether X returned a new refresh token, replacing the stored one';
+COMMENT ON COLUMN token_refreshes.error IS 'Error of a failed refresh, or of saving the new tokens';
+
+CREATE INDEX idx_token_refreshes_refreshed_at ON token_refreshes(refreshed_at DESC);
+
+COMMENT ON INDEX idx_token_refreshes_refreshed_at IS 'Index on refreshed_at for the latest refreshes';
 
 -- Web login sessions (OAuth 2.0 user context per session)
 CREATE TABLE sessions (
@@ -30,7 +55,10 @@ CREATE TABLE sessions (
     access_token  TEXT                     NOT NULL,
     refresh_token TEXT,
     created_at    TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
-    expires_at    TIMESTAMP WITH TIME ZONE NOT NULL
+    expires_at    TIMESTAMP WITH TIME ZONE NOT NULL,
+    token_expires_at TIMESTAMP WITH TIME ZONE,            -- When the access token expires, NULL if unknown
+    last_seen_at  TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW(),
+    user_agent    TEXT                                    -- Browser that logged in
 );
 
 COMMENT ON TABLE sessions IS 'Web login sessions: per-user OAuth 2.0 tokens (encrypted)';
@@ -41,26 +69,59 @@ COMMENT ON COLUMN sessions.access_token IS 'Encrypted OAuth 2.0 access token';
 COMMENT ON COLUMN sessions.refresh_token IS 'Encrypted OAuth 2.0 refresh token (optional)';
 COMMENT ON COLUMN sessions.created_at IS 'When the session was created';
 COMMENT ON COLUMN sessions.expires_at IS 'When the session expires';
+COMMENT ON COLUMN sessions.token_expires_at IS 'When the access token expires (from expires_in of the token endpoint), NULL if unknown';
+COMMENT ON COLUMN sessions.last_seen_at IS 'When the session was last used';
+COMMENT ON COLUMN sessions.user_agent IS 'User-Agent of the browser that logged in, shown on /sessions';
 
 CREATE INDEX idx_sessions_expires_at ON sessions(expires_at);
 
 COMMENT ON INDEX idx_sessions_expires_at IS 'Speed up cleanup of expired sessions';
 
+CREATE INDEX idx_sessions_user_id ON sessions(user_id);
+
+COMMENT ON INDEX idx_sessions_user_id IS 'Speed up listing the sessions of a user on /sessions';
+
+-- Roles of the accounts that may log in to the web pages
+CREATE TABLE user_roles (
+    user_id    TEXT                      PRIMARY KEY,  -- X user ID
+    username   TEXT,                                   -- X username, for display only
+    role       TEXT                      NOT NULL CHECK (role IN ('admin', 'operator', 'viewer')),
+    granted_by TEXT,                                 -- Admin who granted the role; NULL for the initial admin
+    granted_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE user_roles IS 'Roles of the accounts that may log in: viewer (read-only pages), operator (also runs tasks and the bot), admin (also manages roles)';
+COMMENT ON COLUMN user_roles.user_id IS 'X/Twitter user ID of the account';
+COMMENT ON COLUMN user_roles.username IS 'X/Twitter username at the last login or grant, for display only; NULL until the first login of an admin seeded by ID';
+COMMENT ON COLUMN user_roles.role IS 'admin, operator or viewer';
+COMMENT ON COLUMN user_roles.granted_by IS 'Username of the admin who granted the role; NULL for the initial admin';
+COMMENT ON COLUMN user_roles.granted_at IS 'When the role was granted';
+
 -- Twitter users who have given or received good vibes
 CREATE TABLE users (
     id             TEXT                      PRIMARY KEY,  -- Twitter user ID (used as primary key)
     username       TEXT                      NOT NULL,     -- Twitter username/handle
     name           TEXT                      NOT NULL,     -- Twitter display name
     created_at     TIMESTAMP WITH TIME ZONE  NOT NULL,     -- When the Twitter account was created
-    follower_count INTEGER                  NOT NULL DEFAULT 0  -- Number of accounts that follow this user
+    api_follower_count            INTEGER,                   -- Follower count reported by the X API
+    api_follower_count_fetched_at TIMESTAMP WITH TIME ZONE,  -- When api_follower_count was fetched
+    known_follower_count          INTEGER  NOT NULL DEFAULT 0, -- Followers known from the following table
+    cluster_id                    INTEGER                      -- Community from the latest clustering run
 );
 
 COMMENT ON TABLE users IS 'Twitter users who have given or received good vibes';
-COMMENT ON COLUMN users.follower_count IS 'Number of accounts that follow this user (from following table)';
 COMMENT ON COLUMN users.id IS 'Twitter user ID, used as primary key';
 COMMENT ON COLUMN users.username IS 'Twitter username/handle (e.g., @username)';
 COMMENT ON COLUMN users.name IS 'Twitter display name';
 COMMENT ON COLUMN users.created_at IS 'Timestamp when the Twitter account was created';
+COMMENT ON COLUMN users.api_follower_count IS 'Follower count reported by the X API (public_metrics.followers_count), NULL if never fetched';
+COMMENT ON COLUMN users.api_follower_count_fetched_at IS 'When api_follower_count was last fetched from the X API';
+COMMENT ON COLUMN users.known_follower_count IS 'Number of accounts known to follow this user (rows in the following table), maintained by trigger';
+COMMENT ON COLUMN users.cluster_id IS 'Community detected by the latest clustering run (see clusters table), NULL if not in the good vibes graph';
+
+CREATE INDEX idx_users_cluster_id ON users(cluster_id);
+
+COMMENT ON INDEX idx_users_cluster_id IS 'Index on cluster_id to list the members of a cluster';
 
 -- Records of good vibes relationships: emitter sends good vibes to sensor
 -- Represents a directed edge in the good vibes graph (emitter -> sensor)
@@ -78,6 +139,50 @@ COMMENT ON COLUMN good_vibes.emitter_id IS 'User ID of the person sending good v
 COMMENT ON COLUMN good_vibes.sensor_id IS 'User ID of the person receiving good vibes (sensor)';
 COMMENT ON COLUMN good_vibes.created_at IS 'Timestamp when the tweet was created';
 
+-- Registry of hashtags the bot searches for, each mapped to an action
+CREATE TABLE hashtags (
+    tag        TEXT                      PRIMARY KEY CHECK (tag ~ '^[a-z0-9_]{1,50}$'),  -- Hashtag without '#', lowercase
+    action     TEXT                      NOT NULL CHECK (action IN ('vibe', 'revoke', 'transfer')),
+    category   TEXT                      CHECK (category ~ '^[a-z0-9_]{1,50}$'),          -- Edge category recorded (or revoked) by this hashtag
+    enabled    BOOLEAN                   NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE hashtags IS 'Registry of hashtags the bot searches for and what a tweet using each one means';
+COMMENT ON COLUMN hashtags.tag IS 'Hashtag without the leading #, lowercase (e.g. gmgv)';
+COMMENT ON COLUMN hashtags.action IS 'vibe ("emitter #tag" declares a good vibes edge), revoke ("emitter #tag" removes it) or transfer ("N #tag to receiver" sends megajoules)';
+COMMENT ON COLUMN hashtags.category IS 'Optional edge category: vibe hashtags label the edge with it, revoke hashtags remove only that label';
+COMMENT ON COLUMN hashtags.enabled IS 'Whether the scheduled search includes this hashtag';
+COMMENT ON COLUMN hashtags.created_at IS 'When the hashtag was registered';
+
+INSERT INTO hashtags (tag, action) VALUES
+    ('gmgv', 'vibe'),
+    ('megajoules', 'transfer');
+
+-- Categories (labels) of good vibes edges; an edge can carry several
+CREATE TABLE good_vibes_categories (
+    emitter_id TEXT                      NOT NULL,
+    sensor_id  TEXT                      NOT NULL,
+    category   TEXT                      NOT NULL,
+    tweet_id   TEXT                      NOT NULL,  -- Tweet that declared the category
+    created_at TIMESTAMP WITH TIME ZONE  NOT NULL,
+    PRIMARY KEY (emitter_id, sensor_id, category),
+    FOREIGN KEY (emitter_id, sensor_id) REFERENCES good_vibes(emitter_id, sensor_id) ON DELETE CASCADE
+);
+
+COMMENT ON TABLE good_vibes_categories IS 'Categories of good vibes edges (e.g. helpful); an edge can carry several and is deleted with them';
+COMMENT ON COLUMN good_vibes_categories.emitter_id IS 'User ID of the emitter of the edge';
+COMMENT ON COLUMN good_vibes_categories.sensor_id IS 'User ID of the sensor of the edge';
+COMMENT ON COLUMN good_vibes_categories.category IS 'Category label of the edge';
+COMMENT ON COLUMN good_vibes_categories.tweet_id IS 'ID of the tweet that declared the category';
+COMMENT ON COLUMN good_vibes_categories.created_at IS 'Timestamp when the declaring tweet was created';
+
+CREATE INDEX idx_good_vibes_categories_tweet_id ON good_vibes_categories(tweet_id);
+CREATE INDEX idx_good_vibes_categories_category ON good_vibes_categories(category);
+
+COMMENT ON INDEX idx_good_vibes_categories_tweet_id IS 'Index on tweet_id for the already-processed check';
+COMMENT ON INDEX idx_good_vibes_categories_category IS 'Index on category for filtering edges by category';
+
 -- Records of megajoule transfers: sender sends amount megajoules to receiver
 CREATE TABLE megajoule (
     tweet_id   TEXT,                                    -- ID of the tweet containing the megajoules
@@ -107,11 +212,13 @@ COMMENT ON INDEX idx_megajoule_created_at IS 'Index on created_at column to spee
 
 -- Tracks which tweets have been processed for vibe requests
 CREATE TABLE vibe_requests (
-    tweet_id TEXT PRIMARY KEY  -- Tweet ID that has been processed
+    tweet_id   TEXT PRIMARY KEY,  -- Tweet ID that has been processed
+    revoked_by TEXT               -- Revocation tweet that removed what this declaration recorded
 );
 
 COMMENT ON TABLE vibe_requests IS 'Tracks which tweets have been processed for vibe requests';
 COMMENT ON COLUMN vibe_requests.tweet_id IS 'Tweet ID that has been processed for vibe requests';
+COMMENT ON COLUMN vibe_requests.revoked_by IS 'For a declaration whose edge or category was revoked: the revocation tweet. Such rows are kept when the tweet is reprocessed';
 
 -- Records of following relationships: follower follows followed
 CREATE TABLE following (
@@ -132,6 +239,224 @@ CREATE INDEX idx_following_followed ON following(followed);
 COMMENT ON INDEX idx_following_follower IS 'Index on follower column to speed up queries filtering by follower';
 COMMENT ON INDEX idx_following_followed IS 'Index on followed column to speed up queries filtering by followed';
 
+-- Keeps users.known_follower_count in sync with the following table
+CREATE OR REPLACE FUNCTION maintain_known_follower_count()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE users SET known_follower_count = known_follower_count + 1 WHERE id = NEW.followed;
+    END IF;
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        UPDATE users SET known_follower_count = GREATEST(known_follower_count - 1, 0) WHERE id = OLD.followed;
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+COMMENT ON FUNCTION maintain_known_follower_count() IS 'Trigger function keeping users.known_follower_count in sync with the following table (insert, delete, and update of followed)';
+
+CREATE TRIGGER trg_following_known_follower_count
+AFTER INSERT OR DELETE OR UPDATE OF followed ON following
+FOR EACH ROW EXECUTE FUNCTION maintain_known_follower_count();
+
+-- Recomputes known_follower_count for every user from the following table (repairs drift)
+CREATE OR REPLACE FUNCTION recompute_known_follower_counts()
+RETURNS void
+LANGUAGE sql
+AS $$
+    UPDATE users u
+    SET known_follower_count = COALESCE(f.cnt, 0)
+    FROM (
+        SELECT u2.id, COUNT(f2.follower) AS cnt
+        FROM users u2
+        LEFT JOIN following f2 ON f2.followed = u2.id
+        GROUP BY u2.id
+    ) f
+    WHERE u.id = f.id
+      AND u.known_follower_count IS DISTINCT FROM COALESCE(f.cnt, 0);
+$$;
+
+COMMENT ON FUNCTION recompute_known_follower_counts() IS 'Recomputes users.known_follower_count from the following table';
+
+-- Suggestions shown by the "who should I vibe?" recommender, for measuring conversion
+CREATE TABLE vibe_suggestions (
+    id           SERIAL                    PRIMARY KEY,
+    requester_id TEXT                      NOT NULL REFERENCES users(id),  -- User the suggestion was shown to
+    suggested_id TEXT                      NOT NULL REFERENCES users(id),  -- User suggested to vibe
+    source       TEXT                      NOT NULL,                       -- Where it was shown: 'bot' or 'profile'
+    score        DOUBLE PRECISION          NOT NULL,                       -- Ranking score at the time it was shown
+    shown_at     TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE vibe_suggestions IS 'Suggestions shown by the recommender (who should I vibe?), used to measure conversion into good_vibes';
+COMMENT ON COLUMN vibe_suggestions.requester_id IS 'User ID of the account the suggestion was shown to (would be the sensor)';
+COMMENT ON COLUMN vibe_suggestions.suggested_id IS 'User ID of the suggested account (would be the emitter)';
+COMMENT ON COLUMN vibe_suggestions.source IS 'Where the suggestion was shown: bot (@reputest suggest) or profile (profile page)';
+COMMENT ON COLUMN vibe_suggestions.score IS 'Recommender score when the suggestion was shown';
+COMMENT ON COLUMN vibe_suggestions.shown_at IS 'When the suggestion was shown';
+
+CREATE INDEX idx_vibe_suggestions_pair ON vibe_suggestions(requester_id, suggested_id);
+
+COMMENT ON INDEX idx_vibe_suggestions_pair IS 'Index on (requester_id, suggested_id) for de-duplication and conversion lookups';
+
+-- A suggestion converts when the requester declares good vibes from the suggested account after it was shown
+CREATE VIEW view_vibe_suggestion_conversion AS
+SELECT
+    s.source,
+    COUNT(*) AS shown_count,
+    COUNT(g.sensor_id) AS converted_count
+FROM vibe_suggestions s
+LEFT JOIN good_vibes g
+       ON g.sensor_id = s.requester_id
+      AND g.emitter_id = s.suggested_id
+      AND g.created_at >= s.shown_at
+GROUP BY s.source;
+
+COMMENT ON VIEW view_vibe_suggestion_conversion IS 'Per source: suggestions shown and how many were followed by a good_vibes declaration from the requester';
+
+-- Community detection results (label propagation over good_vibes), replaced on every run
+CREATE TABLE clusters (
+    cluster_id          INTEGER                   PRIMARY KEY,  -- Cluster number (1 = largest)
+    member_count        INTEGER                   NOT NULL,     -- Users in the cluster
+    internal_edge_count INTEGER                   NOT NULL,     -- good_vibes edges with both ends in the cluster
+    external_edge_count INTEGER                   NOT NULL,     -- good_vibes edges with exactly one end in the cluster
+    density             DOUBLE PRECISION          NOT NULL,     -- internal edges / possible directed edges
+    top_member_id       TEXT                      REFERENCES users(id) ON DELETE SET NULL,  -- Member receiving most vibes from inside the cluster
+    seed                BIGINT                    NOT NULL,     -- Seed used by the detection run
+    computed_at         TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE clusters IS 'Summary statistics for communities detected in the good vibes graph (label propagation), replaced on every detection run';
+COMMENT ON COLUMN clusters.cluster_id IS 'Cluster number, 1 being the largest cluster; matches users.cluster_id';
+COMMENT ON COLUMN clusters.member_count IS 'Number of users in the cluster';
+COMMENT ON COLUMN clusters.internal_edge_count IS 'Number of good_vibes edges with both ends in the cluster';
+COMMENT ON COLUMN clusters.external_edge_count IS 'Number of good_vibes edges with exactly one end in the cluster';
+COMMENT ON COLUMN clusters.density IS 'Internal edges divided by possible directed edges (n * (n - 1)), 0 for single-member clusters';
+COMMENT ON COLUMN clusters.top_member_id IS 'Member who received the most good vibes from inside the cluster';
+COMMENT ON COLUMN clusters.seed IS 'Random seed of the detection run (results are deterministic for a given graph and seed)';
+COMMENT ON COLUMN clusters.computed_at IS 'When the detection run completed';
+
+-- Graph-wide statistics snapshots, one row per cron run, for tracking growth over time
+CREATE TABLE graph_stats (
+    id                   SERIAL                    PRIMARY KEY,
+    node_count           INTEGER                   NOT NULL,  -- Users with at least one good_vibes edge
+    edge_count           INTEGER                   NOT NULL,  -- good_vibes edges (self-loops excluded)
+    reciprocal_edge_count INTEGER                 NOT NULL,  -- Edges whose reverse edge also exists
+    reciprocity_rate     DOUBLE PRECISION          NOT NULL,  -- reciprocal_edge_count / edge_count
+    largest_scc_size     INTEGER                   NOT NULL,  -- Nodes in the largest strongly connected component
+    in_degree_histogram  INTEGER[]                 NOT NULL,  -- Nodes per in-degree (last bucket is open-ended)
+    out_degree_histogram INTEGER[]                 NOT NULL,  -- Nodes per out-degree (last bucket is open-ended)
+    new_edges_last_day   INTEGER                   NOT NULL,  -- Edges created in the 24 hours before the snapshot
+    computed_at          TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE graph_stats IS 'Snapshots of graph-wide good vibes statistics, appended on every cron run';
+COMMENT ON COLUMN graph_stats.node_count IS 'Number of users with at least one good_vibes edge (as emitter or sensor)';
+COMMENT ON COLUMN graph_stats.edge_count IS 'Number of good_vibes edges, excluding self-loops';
+COMMENT ON COLUMN graph_stats.reciprocal_edge_count IS 'Number of edges whose reverse edge also exists';
+COMMENT ON COLUMN graph_stats.reciprocity_rate IS 'Fraction of edges that are reciprocated (0 when there are no edges)';
+COMMENT ON COLUMN graph_stats.largest_scc_size IS 'Number of users in the largest strongly connected component';
+COMMENT ON COLUMN graph_stats.in_degree_histogram IS 'Element k (0-based) is the number of users with in-degree k; the last element counts every larger in-degree';
+COMMENT ON COLUMN graph_stats.out_degree_histogram IS 'Element k (0-based) is the number of users with out-degree k; the last element counts every larger out-degree';
+COMMENT ON COLUMN graph_stats.new_edges_last_day IS 'Number of edges whose tweet was created in the 24 hours before the snapshot';
+COMMENT ON COLUMN graph_stats.computed_at IS 'When the snapshot was taken';
+
+CREATE INDEX idx_graph_stats_computed_at ON graph_stats(computed_at);
+
+COMMENT ON INDEX idx_graph_stats_computed_at IS 'Index on computed_at to speed up history queries';
+
+-- Durable queue of bot replies, drained by the reply worker with retries
+CREATE TABLE outbound_replies (
+    id                   BIGSERIAL                 PRIMARY KEY,
+    in_reply_to_tweet_id TEXT                      NOT NULL UNIQUE,  -- Tweet being answered (at most one reply each)
+    reply_text           TEXT                      NOT NULL,
+    status               TEXT                      NOT NULL DEFAULT 'pending'
+                                                   CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts             INTEGER                   NOT NULL DEFAULT 0,
+    last_error           TEXT,
+    next_attempt_at      TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    reply_tweet_id       TEXT,                                       -- ID of the posted reply, once sent
+    created_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE outbound_replies IS 'Durable queue of bot replies; the reply worker posts due replies and retries failures with backoff';
+COMMENT ON COLUMN outbound_replies.in_reply_to_tweet_id IS 'Tweet the reply answers; unique so a tweet is never answered twice';
+COMMENT ON COLUMN outbound_replies.reply_text IS 'Text of the reply to post';
+COMMENT ON COLUMN outbound_replies.status IS 'pending (waiting to be sent or retried), sent, or dead (gave up after too many attempts or a permanent error)';
+COMMENT ON COLUMN outbound_replies.attempts IS 'Number of send attempts so far';
+COMMENT ON COLUMN outbound_replies.last_error IS 'Error of the most recent failed attempt';
+COMMENT ON COLUMN outbound_replies.next_attempt_at IS 'Earliest time of the next attempt; pushed forward while a worker holds the reply';
+COMMENT ON COLUMN outbound_replies.reply_tweet_id IS 'ID of the posted reply tweet, once sent';
+COMMENT ON COLUMN outbound_replies.created_at IS 'When the reply was queued';
+COMMENT ON COLUMN outbound_replies.updated_at IS 'When the reply was last attempted or changed';
+
+CREATE INDEX idx_outbound_replies_due ON outbound_replies(next_attempt_at) WHERE status = 'pending';
+
+COMMENT ON INDEX idx_outbound_replies_due IS 'Partial index on next_attempt_at of pending replies, for the reply worker';
+
+-- Replies the bot would have posted while running with BOT_MODE=shadow
+CREATE TABLE shadow_replies (
+    id                   BIGSERIAL                 PRIMARY KEY,
+    in_reply_to_tweet_id TEXT                      NOT NULL,  -- Tweet that would have been answered
+    reply_text           TEXT                      NOT NULL,
+    created_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE shadow_replies IS 'Replies recorded instead of posted while the bot runs with BOT_MODE=shadow';
+COMMENT ON COLUMN shadow_replies.in_reply_to_tweet_id IS 'Tweet the reply would have answered';
+COMMENT ON COLUMN shadow_replies.reply_text IS 'Text that would have been posted';
+COMMENT ON COLUMN shadow_replies.created_at IS 'When the reply would have been posted';
+
+CREATE INDEX idx_shadow_replies_created_at ON shadow_replies(created_at);
+
+COMMENT ON INDEX idx_shadow_replies_created_at IS 'Index on created_at for reviewing the latest shadow replies';
+
+-- One row per run of a scheduled task (see the cronjob module)
+CREATE TABLE job_runs (
+    id          BIGSERIAL                 PRIMARY KEY,
+    task        TEXT                      NOT NULL,  -- Task name, e.g. hashtag_search
+    started_at  TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,            -- NULL while running
+    outcome     TEXT                      NOT NULL DEFAULT 'running'
+                CONSTRAINT job_runs_outcome_check
+                CHECK (outcome IN ('running', 'succeeded', 'failed', 'skipped', 'paused')),
+    items       BIGINT,                              -- What the run handled (hashtags, mentions, replies, clusters)
+    error       TEXT,                                -- Error message of a failed run
+    triggered_by TEXT                                -- Admin who started the run from /admin; NULL for scheduled runs
+);
+
+COMMENT ON TABLE job_runs IS 'One row per run of a scheduled task: timing, outcome and counts';
+COMMENT ON COLUMN job_runs.task IS 'Name of the scheduled task (as in the CRON_<TASK>_* variables, lowercase)';
+COMMENT ON COLUMN job_runs.started_at IS 'When the run started';
+COMMENT ON COLUMN job_runs.finished_at IS 'When the run ended; NULL while it is running';
+COMMENT ON COLUMN job_runs.outcome IS 'running, succeeded, failed, skipped (the previous run of the task was still in progress) or paused (the bot was paused)';
+COMMENT ON COLUMN job_runs.items IS 'Number of things the run handled (e.g. mentions found, replies sent); NULL for tasks with nothing to count';
+COMMENT ON COLUMN job_runs.error IS 'Error message of a failed run';
+COMMENT ON COLUMN job_runs.triggered_by IS 'Username of the admin who started the run from /admin; NULL for scheduled runs';
+
+CREATE INDEX idx_job_runs_task_started_at ON job_runs(task, started_at DESC);
+
+COMMENT ON INDEX idx_job_runs_task_started_at IS 'Index on (task, started_at) for the latest runs of each task';
+
+-- Single-row switch to pause the bot from /admin
+CREATE TABLE bot_control (
+    id         BOOLEAN                   PRIMARY KEY DEFAULT TRUE CHECK (id),  -- Always TRUE: one row
+    paused     BOOLEAN                   NOT NULL DEFAULT FALSE,
+    updated_by TEXT,                                 -- Admin who last paused or resumed the bot
+    updated_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE bot_control IS 'Single-row switch to pause the bot (hashtag searches, mentions and replies) from /admin';
+COMMENT ON COLUMN bot_control.id IS 'Always TRUE, so the table has one row';
+COMMENT ON COLUMN bot_control.paused IS 'Whether scheduled runs of the bot tasks are skipped';
+COMMENT ON COLUMN bot_control.updated_by IS 'Username of the admin who last paused or resumed the bot';
+COMMENT ON COLUMN bot_control.updated_at IS 'When the bot was last paused or resumed';
+
+INSERT INTO bot_control (id) VALUES (TRUE);
+
 -- Tracks materialized view refresh performance
 CREATE TABLE vibe_materialize_time (
     id SERIAL PRIMARY KEY,
@@ -501,9 +826,16 @@ BEGIN
 END;
 $$;
 
--- Grant execute permission to the application user
+-- Grant execute permission to the application user (skipped if the role does not exist,
+-- e.g. in local or test databases)
 -- Replace 'reputest-rust-app' with your actual application database username if different
-GRANT EXECUTE ON FUNCTION refresh_all_materialized_views() TO "reputest-rust-app";
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'reputest-rust-app') THEN
+        GRANT EXECUTE ON FUNCTION refresh_all_materialized_views() TO "reputest-rust-app";
+    END IF;
+END;
+$$;
 
 -- Add a comment explaining the function
 COMMENT ON FUNCTION refresh_all_materialized_views() IS 
@@ -525,4 +857,18 @@ COMMENT ON INDEX idx_good_vibes_created_at IS 'Index on created_at column to spe
 COMMENT ON INDEX idx_users_username IS 'Index on username column to speed up username lookups';
 COMMENT ON INDEX idx_users_id IS 'Index on id column to speed up user ID lookups (though primary key is already indexed)';
 COMMENT ON INDEX idx_refresh_tokens_token IS 'Index on token column to speed up refresh token lookups';
-COMMENT ON INDEX idx_access_tokens_token IS 'Index on token column to speed up access token lookups';
\ No newline at end of file
+COMMENT ON INDEX idx_access_tokens_token IS 'Index on token column to speed up access token lookups';
+
+-- Schema migrations applied by the migration runner (`reputest migrate`)
+CREATE TABLE schema_migrations (
+    version    INTEGER                   PRIMARY KEY,  -- Migration number (file prefix in sql/migrations)
+    name       TEXT                      NOT NULL,     -- Migration name (file name without prefix)
+    checksum   TEXT                      NOT NULL,     -- SHA-256 of the migration SQL, hex encoded
+    applied_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()  -- When the migration was applied
+);
+
+COMMENT ON TABLE schema_migrations IS 'Schema migrations applied by the migration runner; a fresh install from this file records every migration as applied';
+COMMENT ON COLUMN schema_migrations.version IS 'Migration number, the numeric prefix of the file in sql/migrations';
+COMMENT ON COLUMN schema_migrations.name IS 'Migration name, the file name without the numeric prefix and extension';
+COMMENT ON COLUMN schema_migrations.checksum IS 'Hex-encoded SHA-256 of the migration SQL when it was applied; a mismatch with the binary is treated as drift';
+COMMENT ON COLUMN schema_migrations.applied_at IS 'When the migration was applied (or recorded by a fresh install)';
@@ -0,0 +1,66 @@
+-- Migration: Split users.follower_count into API-reported and known (derived) counts
+-- Run this on existing databases that were created before the follower count split.
+-- For fresh installs, database_ddl.sql already includes these changes.
+--
+-- follower_count used to be overwritten by save_user with the API's public_metrics value
+-- and incremented by one for every new row in the following table, so it drifted.
+-- It is replaced by:
+--   api_follower_count            - followers_count as last reported by the X API (NULL if never fetched)
+--   api_follower_count_fetched_at - when api_follower_count was last fetched
+--   known_follower_count          - number of rows in the following table where the user is followed,
+--                                   maintained by a trigger on the following table
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS api_follower_count INTEGER;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS api_follower_count_fetched_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE users ADD COLUMN IF NOT EXISTS known_follower_count INTEGER NOT NULL DEFAULT 0;
+
+-- The old column mixes both meanings, so its values cannot be trusted as either one.
+ALTER TABLE users DROP COLUMN IF EXISTS follower_count;
+
+COMMENT ON COLUMN users.api_follower_count IS 'Follower count reported by the X API (public_metrics.followers_count), NULL if never fetched';
+COMMENT ON COLUMN users.api_follower_count_fetched_at IS 'When api_follower_count was last fetched from the X API';
+COMMENT ON COLUMN users.known_follower_count IS 'Number of accounts known to follow this user (rows in the following table), maintained by trigger';
+
+-- Keeps users.known_follower_count in sync with the following table
+CREATE OR REPLACE FUNCTION maintain_known_follower_count()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF TG_OP = 'INSERT' THEN
+        UPDATE users SET known_follower_count = known_follower_count + 1 WHERE id = NEW.followed;
+    ELSIF TG_OP = 'DELETE' THEN
+        UPDATE users SET known_follower_count = GREATEST(known_follower_count - 1, 0) WHERE id = OLD.followed;
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+COMMENT ON FUNCTION maintain_known_follower_count() IS 'Trigger function keeping users.known_follower_count in sync with the following table';
+
+DROP TRIGGER IF EXISTS trg_following_known_follower_count ON following;
+CREATE TRIGGER trg_following_known_follower_count
+AFTER INSERT OR DELETE ON following
+FOR EACH ROW EXECUTE FUNCTION maintain_known_follower_count();
+
+-- Recomputes known_follower_count for every user from the following table (repairs drift)
+CREATE OR REPLACE FUNCTION recompute_known_follower_counts()
+RETURNS void
+LANGUAGE sql
+AS $$
+    UPDATE users u
+    SET known_follower_count = COALESCE(f.cnt, 0)
+    FROM (
+        SELECT u2.id, COUNT(f2.follower) AS cnt
+        FROM users u2
+        LEFT JOIN following f2 ON f2.followed = u2.id
+        GROUP BY u2.id
+    ) f
+    WHERE u.id = f.id
+      AND u.known_follower_count IS DISTINCT FROM COALESCE(f.cnt, 0);
+$$;
+
+COMMENT ON FUNCTION recompute_known_follower_counts() IS 'Recomputes users.known_follower_count from the following table';
+
+-- Backfill from the existing following rows
+SELECT recompute_known_follower_counts();
@@ -0,0 +1,39 @@
+-- Migration: Add vibe_suggestions table and conversion view for the recommender
+-- Run this on existing databases that were created before the recommender.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Suggestions shown by the "who should I vibe?" recommender, for measuring conversion
+CREATE TABLE IF NOT EXISTS vibe_suggestions (
+    id           SERIAL                    PRIMARY KEY,
+    requester_id TEXT                      NOT NULL REFERENCES users(id),  -- User the suggestion was shown to
+    suggested_id TEXT                      NOT NULL REFERENCES users(id),  -- User suggested to vibe
+    source       TEXT                      NOT NULL,                       -- Where it was shown: 'bot' or 'profile'
+    score        DOUBLE PRECISION          NOT NULL,                       -- Ranking score at the time it was shown
+    shown_at     TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE vibe_suggestions IS 'Suggestions shown by the recommender (who should I vibe?), used to measure conversion into good_vibes';
+COMMENT ON COLUMN vibe_suggestions.requester_id IS 'User ID of the account the suggestion was shown to (would be the sensor)';
+COMMENT ON COLUMN vibe_suggestions.suggested_id IS 'User ID of the suggested account (would be the emitter)';
+COMMENT ON COLUMN vibe_suggestions.source IS 'Where the suggestion was shown: bot (@reputest suggest) or profile (profile page)';
+COMMENT ON COLUMN vibe_suggestions.score IS 'Recommender score when the suggestion was shown';
+COMMENT ON COLUMN vibe_suggestions.shown_at IS 'When the suggestion was shown';
+
+CREATE INDEX IF NOT EXISTS idx_vibe_suggestions_pair ON vibe_suggestions(requester_id, suggested_id);
+
+COMMENT ON INDEX idx_vibe_suggestions_pair IS 'Index on (requester_id, suggested_id) for de-duplication and conversion lookups';
+
+-- A suggestion converts when the requester declares good vibes from the suggested account after it was shown
+CREATE OR REPLACE VIEW view_vibe_suggestion_conversion AS
+SELECT
+    s.source,
+    COUNT(*) AS shown_count,
+    COUNT(g.sensor_id) AS converted_count
+FROM vibe_suggestions s
+LEFT JOIN good_vibes g
+       ON g.sensor_id = s.requester_id
+      AND g.emitter_id = s.suggested_id
+      AND g.created_at >= s.shown_at
+GROUP BY s.source;
+
+COMMENT ON VIEW view_vibe_suggestion_conversion IS 'Per source: suggestions shown and how many were followed by a good_vibes declaration from the requester';
@@ -0,0 +1,33 @@
+-- Migration: Add community detection (users.cluster_id and clusters table)
+-- Run this on existing databases that were created before community detection.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+ALTER TABLE users ADD COLUMN IF NOT EXISTS cluster_id INTEGER;
+
+COMMENT ON COLUMN users.cluster_id IS 'Community detected by the latest clustering run (see clusters table), NULL if not in the good vibes graph';
+
+CREATE INDEX IF NOT EXISTS idx_users_cluster_id ON users(cluster_id);
+
+COMMENT ON INDEX idx_users_cluster_id IS 'Index on cluster_id to list the members of a cluster';
+
+-- Community detection results (label propagation over good_vibes), replaced on every run
+CREATE TABLE IF NOT EXISTS clusters (
+    cluster_id          INTEGER                   PRIMARY KEY,  -- Cluster number (1 = largest)
+    member_count        INTEGER                   NOT NULL,     -- Users in the cluster
+    internal_edge_count INTEGER                   NOT NULL,     -- good_vibes edges with both ends in the cluster
+    external_edge_count INTEGER                   NOT NULL,     -- good_vibes edges with exactly one end in the cluster
+    density             DOUBLE PRECISION          NOT NULL,     -- internal edges / possible directed edges
+    top_member_id       TEXT                      REFERENCES users(id) ON DELETE SET NULL,  -- Member receiving most vibes from inside the cluster
+    seed                BIGINT                    NOT NULL,     -- Seed used by the detection run
+    computed_at         TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE clusters IS 'Summary statistics for communities detected in the good vibes graph (label propagation), replaced on every detection run';
+COMMENT ON COLUMN clusters.cluster_id IS 'Cluster number, 1 being the largest cluster; matches users.cluster_id';
+COMMENT ON COLUMN clusters.member_count IS 'Number of users in the cluster';
+COMMENT ON COLUMN clusters.internal_edge_count IS 'Number of good_vibes edges with both ends in the cluster';
+COMMENT ON COLUMN clusters.external_edge_count IS 'Number of good_vibes edges with exactly one end in the cluster';
+COMMENT ON COLUMN clusters.density IS 'Internal edges divided by possible directed edges (n * (n - 1)), 0 for single-member clusters';
+COMMENT ON COLUMN clusters.top_member_id IS 'Member who received the most good vibes from inside the cluster';
+COMMENT ON COLUMN clusters.seed IS 'Random seed of the detection run (results are deterministic for a given graph and seed)';
+COMMENT ON COLUMN clusters.computed_at IS 'When the detection run completed';
@@ -0,0 +1,32 @@
+-- Migration: Add graph_stats snapshot table
+-- Run this on existing databases that were created before graph statistics history.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Graph-wide statistics snapshots, one row per cron run, for tracking growth over time
+CREATE TABLE IF NOT EXISTS graph_stats (
+    id                   SERIAL                    PRIMARY KEY,
+    node_count           INTEGER                   NOT NULL,  -- Users with at least one good_vibes edge
+    edge_count           INTEGER                   NOT NULL,  -- good_vibes edges (self-loops excluded)
+    reciprocal_edge_count INTEGER                 NOT NULL,  -- Edges whose reverse edge also exists
+    reciprocity_rate     DOUBLE PRECISION          NOT NULL,  -- reciprocal_edge_count / edge_count
+    largest_scc_size     INTEGER                   NOT NULL,  -- Nodes in the largest strongly connected component
+    in_degree_histogram  INTEGER[]                 NOT NULL,  -- Nodes per in-degree (last bucket is open-ended)
+    out_degree_histogram INTEGER[]                 NOT NULL,  -- Nodes per out-degree (last bucket is open-ended)
+    new_edges_last_day   INTEGER                   NOT NULL,  -- Edges created in the 24 hours before the snapshot
+    computed_at          TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE graph_stats IS 'Snapshots of graph-wide good vibes statistics, appended on every cron run';
+COMMENT ON COLUMN graph_stats.node_count IS 'Number of users with at least one good_vibes edge (as emitter or sensor)';
+COMMENT ON COLUMN graph_stats.edge_count IS 'Number of good_vibes edges, excluding self-loops';
+COMMENT ON COLUMN graph_stats.reciprocal_edge_count IS 'Number of edges whose reverse edge also exists';
+COMMENT ON COLUMN graph_stats.reciprocity_rate IS 'Fraction of edges that are reciprocated (0 when there are no edges)';
+COMMENT ON COLUMN graph_stats.largest_scc_size IS 'Number of users in the largest strongly connected component';
+COMMENT ON COLUMN graph_stats.in_degree_histogram IS 'Element k (0-based) is the number of users with in-degree k; the last element counts every larger in-degree';
+COMMENT ON COLUMN graph_stats.out_degree_histogram IS 'Element k (0-based) is the number of users with out-degree k; the last element counts every larger out-degree';
+COMMENT ON COLUMN graph_stats.new_edges_last_day IS 'Number of edges whose tweet was created in the 24 hours before the snapshot';
+COMMENT ON COLUMN graph_stats.computed_at IS 'When the snapshot was taken';
+
+CREATE INDEX IF NOT EXISTS idx_graph_stats_computed_at ON graph_stats(computed_at);
+
+COMMENT ON INDEX idx_graph_stats_computed_at IS 'Index on computed_at to speed up history queries';
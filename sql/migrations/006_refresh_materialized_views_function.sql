@@ -1,12 +1,10 @@
--- Create a database function to refresh all materialized views
--- This function uses SECURITY DEFINER to run with the privileges of the function owner,
--- allowing the reputest-rust-app user to refresh views owned by another role.
+-- Migration: Create the function that refreshes all materialized views
+-- Run this on existing databases that were created before the migration runner.
+-- For fresh installs, database_ddl.sql already includes these changes.
 --
--- INSTRUCTIONS:
--- 1. Connect to your database as a superuser (postgres) or the owner of the materialized views
--- 2. Run this script to create the function
--- 3. The function will be owned by the user who creates it (should have permission to refresh the views)
--- 4. Grant EXECUTE permission to reputest-rust-app user
+-- The function uses SECURITY DEFINER to run with the privileges of the function owner,
+-- allowing the reputest-rust-app user to refresh views owned by another role. Apply it
+-- (with `reputest migrate`) as a superuser or the owner of the materialized views.
 --
 -- Usage from Rust code:
 --   sqlx::query("SELECT refresh_all_materialized_views()").execute(pool).await?;
@@ -78,16 +76,18 @@ BEGIN
 END;
 $$;
 
--- Grant execute permission to the application user
+-- Grant execute permission to the application user (skipped if the role does not exist,
+-- e.g. in local or test databases)
 -- Replace 'reputest-rust-app' with your actual application database username if different
-GRANT EXECUTE ON FUNCTION refresh_all_materialized_views() TO "reputest-rust-app";
+DO $$
+BEGIN
+    IF EXISTS (SELECT 1 FROM pg_roles WHERE rolname = 'reputest-rust-app') THEN
+        GRANT EXECUTE ON FUNCTION refresh_all_materialized_views() TO "reputest-rust-app";
+    END IF;
+END;
+$$;
 
 -- Add a comment explaining the function
 COMMENT ON FUNCTION refresh_all_materialized_views() IS 
 'Refreshes all materialized views (degree 1-6 and combined view) and records timing metrics. '
 'Uses SECURITY DEFINER to allow the application user to refresh views owned by another role.';
-
-
-
-
-
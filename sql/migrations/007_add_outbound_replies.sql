@@ -0,0 +1,33 @@
+-- Migration: Add outbound_replies queue
+-- Run this on existing databases that were created before the durable reply queue.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Durable queue of bot replies, drained by the reply worker with retries
+CREATE TABLE IF NOT EXISTS outbound_replies (
+    id                   BIGSERIAL                 PRIMARY KEY,
+    in_reply_to_tweet_id TEXT                      NOT NULL UNIQUE,  -- Tweet being answered (at most one reply each)
+    reply_text           TEXT                      NOT NULL,
+    status               TEXT                      NOT NULL DEFAULT 'pending'
+                                                   CHECK (status IN ('pending', 'sent', 'dead')),
+    attempts             INTEGER                   NOT NULL DEFAULT 0,
+    last_error           TEXT,
+    next_attempt_at      TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    reply_tweet_id       TEXT,                                       -- ID of the posted reply, once sent
+    created_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    updated_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE outbound_replies IS 'Durable queue of bot replies; the reply worker posts due replies and retries failures with backoff';
+COMMENT ON COLUMN outbound_replies.in_reply_to_tweet_id IS 'Tweet the reply answers; unique so a tweet is never answered twice';
+COMMENT ON COLUMN outbound_replies.reply_text IS 'Text of the reply to post';
+COMMENT ON COLUMN outbound_replies.status IS 'pending (waiting to be sent or retried), sent, or dead (gave up after too many attempts or a permanent error)';
+COMMENT ON COLUMN outbound_replies.attempts IS 'Number of send attempts so far';
+COMMENT ON COLUMN outbound_replies.last_error IS 'Error of the most recent failed attempt';
+COMMENT ON COLUMN outbound_replies.next_attempt_at IS 'Earliest time of the next attempt; pushed forward while a worker holds the reply';
+COMMENT ON COLUMN outbound_replies.reply_tweet_id IS 'ID of the posted reply tweet, once sent';
+COMMENT ON COLUMN outbound_replies.created_at IS 'When the reply was queued';
+COMMENT ON COLUMN outbound_replies.updated_at IS 'When the reply was last attempted or changed';
+
+CREATE INDEX IF NOT EXISTS idx_outbound_replies_due ON outbound_replies(next_attempt_at) WHERE status = 'pending';
+
+COMMENT ON INDEX idx_outbound_replies_due IS 'Partial index on next_attempt_at of pending replies, for the reply worker';
@@ -0,0 +1,20 @@
+-- Migration: Add shadow_replies table
+-- Run this on existing databases that were created before shadow mode.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Replies the bot would have posted while running with BOT_MODE=shadow
+CREATE TABLE IF NOT EXISTS shadow_replies (
+    id                   BIGSERIAL                 PRIMARY KEY,
+    in_reply_to_tweet_id TEXT                      NOT NULL,  -- Tweet that would have been answered
+    reply_text           TEXT                      NOT NULL,
+    created_at           TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE shadow_replies IS 'Replies recorded instead of posted while the bot runs with BOT_MODE=shadow';
+COMMENT ON COLUMN shadow_replies.in_reply_to_tweet_id IS 'Tweet the reply would have answered';
+COMMENT ON COLUMN shadow_replies.reply_text IS 'Text that would have been posted';
+COMMENT ON COLUMN shadow_replies.created_at IS 'When the reply would have been posted';
+
+CREATE INDEX IF NOT EXISTS idx_shadow_replies_created_at ON shadow_replies(created_at);
+
+COMMENT ON INDEX idx_shadow_replies_created_at IS 'Index on created_at for reviewing the latest shadow replies';
@@ -0,0 +1,24 @@
+-- Migration: Add hashtag registry
+-- Run this on existing databases that were created before the hashtag registry.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Registry of hashtags the bot searches for, each mapped to an action
+CREATE TABLE IF NOT EXISTS hashtags (
+    tag        TEXT                      PRIMARY KEY CHECK (tag ~ '^[a-z0-9_]{1,50}$'),  -- Hashtag without '#', lowercase
+    action     TEXT                      NOT NULL CHECK (action IN ('vibe', 'revoke', 'transfer')),
+    category   TEXT                      CHECK (category ~ '^[a-z0-9_]{1,50}$'),          -- Edge category recorded (or revoked) by this hashtag
+    enabled    BOOLEAN                   NOT NULL DEFAULT TRUE,
+    created_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE hashtags IS 'Registry of hashtags the bot searches for and what a tweet using each one means';
+COMMENT ON COLUMN hashtags.tag IS 'Hashtag without the leading #, lowercase (e.g. gmgv)';
+COMMENT ON COLUMN hashtags.action IS 'vibe ("emitter #tag" declares a good vibes edge), revoke ("emitter #tag" removes it) or transfer ("N #tag to receiver" sends megajoules)';
+COMMENT ON COLUMN hashtags.category IS 'Optional edge category: vibe hashtags label the edge with it, revoke hashtags remove only that label';
+COMMENT ON COLUMN hashtags.enabled IS 'Whether the scheduled search includes this hashtag';
+COMMENT ON COLUMN hashtags.created_at IS 'When the hashtag was registered';
+
+INSERT INTO hashtags (tag, action) VALUES
+    ('gmgv', 'vibe'),
+    ('megajoules', 'transfer')
+ON CONFLICT (tag) DO NOTHING;
@@ -0,0 +1,27 @@
+-- Migration: Add job_runs table
+-- Run this on existing databases that were created before per-task job scheduling.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- One row per run of a scheduled task (see the cronjob module)
+CREATE TABLE IF NOT EXISTS job_runs (
+    id          BIGSERIAL                 PRIMARY KEY,
+    task        TEXT                      NOT NULL,  -- Task name, e.g. hashtag_search
+    started_at  TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    finished_at TIMESTAMP WITH TIME ZONE,            -- NULL while running
+    outcome     TEXT                      NOT NULL DEFAULT 'running'
+                CHECK (outcome IN ('running', 'succeeded', 'failed', 'skipped')),
+    items       BIGINT,                              -- What the run handled (hashtags, mentions, replies, clusters)
+    error       TEXT                                 -- Error message of a failed run
+);
+
+COMMENT ON TABLE job_runs IS 'One row per run of a scheduled task: timing, outcome and counts';
+COMMENT ON COLUMN job_runs.task IS 'Name of the scheduled task (as in the CRON_<TASK>_* variables, lowercase)';
+COMMENT ON COLUMN job_runs.started_at IS 'When the run started';
+COMMENT ON COLUMN job_runs.finished_at IS 'When the run ended; NULL while it is running';
+COMMENT ON COLUMN job_runs.outcome IS 'running, succeeded, failed, or skipped (the previous run of the task was still in progress)';
+COMMENT ON COLUMN job_runs.items IS 'Number of things the run handled (e.g. mentions found, replies sent); NULL for tasks with nothing to count';
+COMMENT ON COLUMN job_runs.error IS 'Error message of a failed run';
+
+CREATE INDEX IF NOT EXISTS idx_job_runs_task_started_at ON job_runs(task, started_at DESC);
+
+COMMENT ON INDEX idx_job_runs_task_started_at IS 'Index on (task, started_at) for the latest runs of each task';
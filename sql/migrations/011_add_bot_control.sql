@@ -0,0 +1,29 @@
+-- Migration: Add bot_control and manual job runs
+-- Run this on existing databases that were created before the admin console.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+ALTER TABLE job_runs ADD COLUMN IF NOT EXISTS triggered_by TEXT;
+
+COMMENT ON COLUMN job_runs.triggered_by IS 'Username of the admin who started the run from /admin; NULL for scheduled runs';
+
+ALTER TABLE job_runs DROP CONSTRAINT IF EXISTS job_runs_outcome_check;
+ALTER TABLE job_runs ADD CONSTRAINT job_runs_outcome_check
+    CHECK (outcome IN ('running', 'succeeded', 'failed', 'skipped', 'paused'));
+
+COMMENT ON COLUMN job_runs.outcome IS 'running, succeeded, failed, skipped (the previous run of the task was still in progress) or paused (the bot was paused)';
+
+-- Single-row switch to pause the bot from /admin
+CREATE TABLE IF NOT EXISTS bot_control (
+    id         BOOLEAN                   PRIMARY KEY DEFAULT TRUE CHECK (id),  -- Always TRUE: one row
+    paused     BOOLEAN                   NOT NULL DEFAULT FALSE,
+    updated_by TEXT,                                 -- Admin who last paused or resumed the bot
+    updated_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE bot_control IS 'Single-row switch to pause the bot (hashtag searches, mentions and replies) from /admin';
+COMMENT ON COLUMN bot_control.id IS 'Always TRUE, so the table has one row';
+COMMENT ON COLUMN bot_control.paused IS 'Whether scheduled runs of the bot tasks are skipped';
+COMMENT ON COLUMN bot_control.updated_by IS 'Username of the admin who last paused or resumed the bot';
+COMMENT ON COLUMN bot_control.updated_at IS 'When the bot was last paused or resumed';
+
+INSERT INTO bot_control (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
@@ -0,0 +1,20 @@
+-- Migration: Add user_roles table
+-- Run this on existing databases that were created before role-based access control.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Roles of the accounts that may log in to the web pages
+CREATE TABLE IF NOT EXISTS user_roles (
+    username   TEXT                      PRIMARY KEY CHECK (username = lower(username)),  -- X username, lowercase
+    role       TEXT                      NOT NULL CHECK (role IN ('admin', 'operator', 'viewer')),
+    granted_by TEXT,                                 -- Admin who granted the role; NULL for the initial admin
+    granted_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+);
+
+COMMENT ON TABLE user_roles IS 'Roles of the accounts that may log in: viewer (read-only pages), operator (also runs tasks and the bot), admin (also manages roles)';
+COMMENT ON COLUMN user_roles.username IS 'X/Twitter username, lowercase';
+COMMENT ON COLUMN user_roles.role IS 'admin, operator or viewer';
+COMMENT ON COLUMN user_roles.granted_by IS 'Username of the admin who granted the role; NULL for the initial admin';
+COMMENT ON COLUMN user_roles.granted_at IS 'When the role was granted';
+
+-- The account that was allowed to log in before roles existed stays an admin
+INSERT INTO user_roles (username, role) VALUES ('julian_le_roux', 'admin') ON CONFLICT DO NOTHING;
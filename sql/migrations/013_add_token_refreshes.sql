@@ -0,0 +1,26 @@
+-- Migration: Add token_refreshes table
+-- Run this on existing databases that were created before bot token refreshes were audited.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- One row per attempt to refresh the bot's access token
+CREATE TABLE IF NOT EXISTS token_refreshes (
+    id              BIGSERIAL                 PRIMARY KEY,
+    refreshed_at    TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW(),
+    reason          TEXT                      NOT NULL,  -- Why the token was refreshed, e.g. unauthorized
+    triggered_by    TEXT                      NOT NULL,  -- Operation that refreshed it, e.g. search_mentions
+    outcome         TEXT                      NOT NULL CHECK (outcome IN ('succeeded', 'failed')),
+    refresh_rotated BOOLEAN                   NOT NULL DEFAULT FALSE,  -- Whether X issued a new refresh token
+    error           TEXT                                 -- Why the refresh failed, or what could not be saved
+);
+
+COMMENT ON TABLE token_refreshes IS 'Audit log of bot access token refreshes: when, why and by which operation';
+COMMENT ON COLUMN token_refreshes.refreshed_at IS 'When the refresh was attempted';
+COMMENT ON COLUMN token_refreshes.reason IS 'Why the token was refreshed: unauthorized (X answered 401)';
+COMMENT ON COLUMN token_refreshes.triggered_by IS 'Name of the X API operation that refreshed the token, e.g. search_mentions';
+COMMENT ON COLUMN token_refreshes.outcome IS 'succeeded or failed';
+COMMENT ON COLUMN token_refreshes.refresh_rotated IS 'Whether X returned a new refresh token, replacing the stored one';
+COMMENT ON COLUMN token_refreshes.error IS 'Error of a failed refresh, or of saving the new tokens';
+
+CREATE INDEX IF NOT EXISTS idx_token_refreshes_refreshed_at ON token_refreshes(refreshed_at DESC);
+
+COMMENT ON INDEX idx_token_refreshes_refreshed_at IS 'Index on refreshed_at for the latest refreshes';
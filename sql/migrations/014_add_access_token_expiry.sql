@@ -0,0 +1,9 @@
+-- Migration: Add access_tokens.expires_at
+-- Run this on existing databases that were created before access token expiry was stored.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- When X says the access token expires, so it can be refreshed before it does
+ALTER TABLE access_tokens ADD COLUMN IF NOT EXISTS expires_at TIMESTAMP WITH TIME ZONE;
+
+COMMENT ON COLUMN access_tokens.expires_at IS 'When the access token expires (from expires_in of the token endpoint), NULL if unknown';
+COMMENT ON COLUMN token_refreshes.reason IS 'Why the token was refreshed: unauthorized (X answered 401) or expiring (refreshed before it expires)';
@@ -0,0 +1,15 @@
+-- Migration: Add session token expiry and activity
+-- Run this on existing databases that were created before session tokens were refreshed.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS token_expires_at TIMESTAMP WITH TIME ZONE;
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS last_seen_at TIMESTAMP WITH TIME ZONE NOT NULL DEFAULT NOW();
+ALTER TABLE sessions ADD COLUMN IF NOT EXISTS user_agent TEXT;
+
+COMMENT ON COLUMN sessions.token_expires_at IS 'When the access token expires (from expires_in of the token endpoint), NULL if unknown';
+COMMENT ON COLUMN sessions.last_seen_at IS 'When the session was last used';
+COMMENT ON COLUMN sessions.user_agent IS 'User-Agent of the browser that logged in, shown on /sessions';
+
+CREATE INDEX IF NOT EXISTS idx_sessions_user_id ON sessions(user_id);
+
+COMMENT ON INDEX idx_sessions_user_id IS 'Speed up listing the sessions of a user on /sessions';
@@ -0,0 +1,29 @@
+-- Migration: Keep known_follower_count in sync when following.followed is updated
+-- Run this on existing databases whose trigger only handled INSERT and DELETE.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Keeps users.known_follower_count in sync with the following table
+CREATE OR REPLACE FUNCTION maintain_known_follower_count()
+RETURNS trigger
+LANGUAGE plpgsql
+AS $$
+BEGIN
+    IF TG_OP IN ('INSERT', 'UPDATE') THEN
+        UPDATE users SET known_follower_count = known_follower_count + 1 WHERE id = NEW.followed;
+    END IF;
+    IF TG_OP IN ('DELETE', 'UPDATE') THEN
+        UPDATE users SET known_follower_count = GREATEST(known_follower_count - 1, 0) WHERE id = OLD.followed;
+    END IF;
+    RETURN NULL;
+END;
+$$;
+
+COMMENT ON FUNCTION maintain_known_follower_count() IS 'Trigger function keeping users.known_follower_count in sync with the following table (insert, delete, and update of followed)';
+
+DROP TRIGGER IF EXISTS trg_following_known_follower_count ON following;
+CREATE TRIGGER trg_following_known_follower_count
+AFTER INSERT OR DELETE OR UPDATE OF followed ON following
+FOR EACH ROW EXECUTE FUNCTION maintain_known_follower_count();
+
+-- Repair counts that an update of followed left wrong
+SELECT recompute_known_follower_counts();
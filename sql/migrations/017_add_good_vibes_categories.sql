@@ -0,0 +1,27 @@
+-- Migration: Add good vibes edge categories
+-- Run this on existing databases that were created before edges had categories.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Categories (labels) of good vibes edges; an edge can carry several
+CREATE TABLE IF NOT EXISTS good_vibes_categories (
+    emitter_id TEXT                      NOT NULL,
+    sensor_id  TEXT                      NOT NULL,
+    category   TEXT                      NOT NULL,
+    tweet_id   TEXT                      NOT NULL,  -- Tweet that declared the category
+    created_at TIMESTAMP WITH TIME ZONE  NOT NULL,
+    PRIMARY KEY (emitter_id, sensor_id, category),
+    FOREIGN KEY (emitter_id, sensor_id) REFERENCES good_vibes(emitter_id, sensor_id) ON DELETE CASCADE
+);
+
+COMMENT ON TABLE good_vibes_categories IS 'Categories of good vibes edges (e.g. helpful); an edge can carry several and is deleted with them';
+COMMENT ON COLUMN good_vibes_categories.emitter_id IS 'User ID of the emitter of the edge';
+COMMENT ON COLUMN good_vibes_categories.sensor_id IS 'User ID of the sensor of the edge';
+COMMENT ON COLUMN good_vibes_categories.category IS 'Category label of the edge';
+COMMENT ON COLUMN good_vibes_categories.tweet_id IS 'ID of the tweet that declared the category';
+COMMENT ON COLUMN good_vibes_categories.created_at IS 'Timestamp when the declaring tweet was created';
+
+CREATE INDEX IF NOT EXISTS idx_good_vibes_categories_tweet_id ON good_vibes_categories(tweet_id);
+CREATE INDEX IF NOT EXISTS idx_good_vibes_categories_category ON good_vibes_categories(category);
+
+COMMENT ON INDEX idx_good_vibes_categories_tweet_id IS 'Index on tweet_id for the already-processed check';
+COMMENT ON INDEX idx_good_vibes_categories_category IS 'Index on category for filtering edges by category';
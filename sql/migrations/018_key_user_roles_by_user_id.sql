@@ -0,0 +1,37 @@
+-- Migration: Key user_roles on the X user ID
+-- Run this on existing databases whose roles were granted to usernames.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- X usernames can be changed and then taken by another account, which would inherit a
+-- role granted to the name. Grants are keyed on the user ID instead; the username is
+-- kept for display only.
+ALTER TABLE user_roles ADD COLUMN IF NOT EXISTS user_id TEXT;
+
+-- Resolve the existing grants: the account that last logged in with the username, else
+-- the user of that name in the graph
+UPDATE user_roles r
+SET user_id = s.user_id
+FROM (
+    SELECT DISTINCT ON (lower(username)) lower(username) AS username, user_id
+    FROM sessions
+    ORDER BY lower(username), created_at DESC
+) s
+WHERE r.user_id IS NULL AND r.username = s.username;
+
+UPDATE user_roles r
+SET user_id = u.id
+FROM users u
+WHERE r.user_id IS NULL AND r.username = lower(u.username);
+
+-- A grant whose account is unknown cannot be keyed; grant it again from /admin/roles,
+-- or with ADMIN_USER_ID for the first admin
+DELETE FROM user_roles WHERE user_id IS NULL;
+
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_pkey;
+ALTER TABLE user_roles ALTER COLUMN user_id SET NOT NULL;
+ALTER TABLE user_roles ADD PRIMARY KEY (user_id);
+ALTER TABLE user_roles ALTER COLUMN username DROP NOT NULL;
+ALTER TABLE user_roles DROP CONSTRAINT IF EXISTS user_roles_username_check;
+
+COMMENT ON COLUMN user_roles.user_id IS 'X/Twitter user ID of the account';
+COMMENT ON COLUMN user_roles.username IS 'X/Twitter username at the last login or grant, for display only; NULL until the first login of an admin seeded by ID';
@@ -0,0 +1,5 @@
+-- Migration: Document the manual token refresh reason
+-- Run this on existing databases created before the refresh_token binary recorded its refreshes.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+COMMENT ON COLUMN token_refreshes.reason IS 'Why the token was refreshed: unauthorized (X answered 401), expiring (refreshed before it expires) or manual (refresh_token binary)';
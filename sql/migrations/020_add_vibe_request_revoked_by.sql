@@ -0,0 +1,10 @@
+-- Migration: Record which revocation removed a declaration
+-- Run this on existing databases created before revoked declarations were marked.
+-- For fresh installs, database_ddl.sql already includes these changes.
+
+-- Reprocessing a tweet from the admin console clears its vibe_requests row, but the row
+-- of a revoked declaration is what keeps it from being recorded again. Declarations
+-- revoked before this migration are not marked.
+ALTER TABLE vibe_requests ADD COLUMN IF NOT EXISTS revoked_by TEXT;
+
+COMMENT ON COLUMN vibe_requests.revoked_by IS 'For a declaration whose edge or category was revoked: the revocation tweet. Such rows are kept when the tweet is reprocessed';
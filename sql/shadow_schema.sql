@@ -0,0 +1,39 @@
+-- Shadow schema for BOT_MODE=shadow
+-- Creates the schema "shadow" with copies of the tables the ingestion pipeline and the
+-- scheduled jobs write to, seeded with the current graph so scoring matches production.
+-- Run it against an up-to-date database (after `reputest migrate`), then start the shadow
+-- instance with BOT_MODE=shadow and BOT_SHADOW_SCHEMA=shadow.
+--
+-- Tables not copied here (tokens, sessions, the hashtag registry, schema_migrations) and the materialized views
+-- still resolve to public, so the shadow instance searches with the bot's credentials but
+-- the dashboards keep showing production data. Re-running the script keeps existing
+-- shadow tables; drop the schema to start over:
+--   DROP SCHEMA shadow CASCADE;
+
+CREATE SCHEMA IF NOT EXISTS shadow;
+
+-- Graph data, copied so vibe scores and replies match production
+CREATE TABLE IF NOT EXISTS shadow.users (LIKE public.users INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.good_vibes (LIKE public.good_vibes INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.good_vibes_categories (LIKE public.good_vibes_categories INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.megajoule (LIKE public.megajoule INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.following (LIKE public.following INCLUDING ALL);
+-- Copied so tweets production has already answered are not answered again
+CREATE TABLE IF NOT EXISTS shadow.vibe_requests (LIKE public.vibe_requests INCLUDING ALL);
+
+INSERT INTO shadow.users SELECT * FROM public.users ON CONFLICT DO NOTHING;
+INSERT INTO shadow.good_vibes SELECT * FROM public.good_vibes ON CONFLICT DO NOTHING;
+INSERT INTO shadow.good_vibes_categories SELECT * FROM public.good_vibes_categories ON CONFLICT DO NOTHING;
+INSERT INTO shadow.megajoule SELECT * FROM public.megajoule ON CONFLICT DO NOTHING;
+INSERT INTO shadow.following SELECT * FROM public.following ON CONFLICT DO NOTHING;
+INSERT INTO shadow.vibe_requests SELECT * FROM public.vibe_requests ON CONFLICT DO NOTHING;
+
+-- Written by the pipeline and the scheduled jobs; start empty
+CREATE TABLE IF NOT EXISTS shadow.vibe_suggestions (LIKE public.vibe_suggestions INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.clusters (LIKE public.clusters INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.graph_stats (LIKE public.graph_stats INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.outbound_replies (LIKE public.outbound_replies INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.shadow_replies (LIKE public.shadow_replies INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.job_runs (LIKE public.job_runs INCLUDING ALL);
+CREATE TABLE IF NOT EXISTS shadow.bot_control (LIKE public.bot_control INCLUDING ALL);
+INSERT INTO shadow.bot_control (id) VALUES (TRUE) ON CONFLICT DO NOTHING;
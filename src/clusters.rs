@@ -0,0 +1,122 @@
+//! Community detection over the good vibes graph.
+//!
+//! A periodic job runs label propagation (see [`crate::graph::label_propagation`]) over
+//! `good_vibes`, stores a `cluster_id` per user and summary statistics per cluster in
+//! the clusters table. Runs are deterministic for a given graph and seed (`CLUSTER_SEED`).
+
+use log::info;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::db::{get_good_vibes_graph, save_clusters, ClusterStats};
+use crate::error::ReputestError;
+use crate::graph::label_propagation;
+
+/// Upper bound on label propagation rounds (it usually converges in a handful).
+const MAX_ITERATIONS: usize = 100;
+
+/// Computes summary statistics for each cluster.
+///
+/// # Parameters
+///
+/// - `graph`: Good vibes graph as emitter -> sensors
+/// - `assignments`: Cluster ID per user ID (as returned by `label_propagation`)
+///
+/// # Returns
+///
+/// One entry per cluster, ordered by cluster ID. The top member is the one who received
+/// the most good vibes from inside the cluster (ties broken by user ID).
+pub fn compute_cluster_stats(
+    graph: &HashMap<String, Vec<String>>,
+    assignments: &HashMap<String, usize>,
+) -> Vec<ClusterStats> {
+    let mut member_counts: HashMap<usize, usize> = HashMap::new();
+    for cluster in assignments.values() {
+        *member_counts.entry(*cluster).or_default() += 1;
+    }
+
+    let mut internal: HashMap<usize, usize> = HashMap::new();
+    let mut external: HashMap<usize, usize> = HashMap::new();
+    // Vibes received from inside the cluster, per emitter
+    let mut received: HashMap<&str, usize> = HashMap::new();
+
+    for (emitter, sensors) in graph {
+        for sensor in sensors {
+            if emitter == sensor {
+                continue;
+            }
+            let (Some(a), Some(b)) = (assignments.get(emitter), assignments.get(sensor)) else {
+                continue;
+            };
+            if a == b {
+                *internal.entry(*a).or_default() += 1;
+                *received.entry(emitter.as_str()).or_default() += 1;
+            } else {
+                *external.entry(*a).or_default() += 1;
+                *external.entry(*b).or_default() += 1;
+            }
+        }
+    }
+
+    let mut top_members: HashMap<usize, (&str, usize)> = HashMap::new();
+    for (user, count) in &received {
+        let cluster = assignments[*user];
+        let entry = top_members.entry(cluster).or_insert((user, *count));
+        if *count > entry.1 || (*count == entry.1 && *user < entry.0) {
+            *entry = (user, *count);
+        }
+    }
+
+    let mut stats: Vec<ClusterStats> = member_counts
+        .into_iter()
+        .map(|(cluster, members)| {
+            let internal_edges = internal.get(&cluster).copied().unwrap_or(0);
+            let possible = members * members.saturating_sub(1);
+            ClusterStats {
+                cluster_id: cluster as i32,
+                member_count: members as i32,
+                internal_edge_count: internal_edges as i32,
+                external_edge_count: external.get(&cluster).copied().unwrap_or(0) as i32,
+                density: if possible > 0 {
+                    internal_edges as f64 / possible as f64
+                } else {
+                    0.0
+                },
+                top_member_id: top_members.get(&cluster).map(|(id, _)| id.to_string()),
+            }
+        })
+        .collect();
+    stats.sort_by_key(|s| s.cluster_id);
+    stats
+}
+
+/// Runs community detection over the current good vibes graph and stores the result.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `seed`: Seed for label propagation
+///
+/// # Returns
+///
+/// - `Ok(usize)`: Number of clusters found
+/// - `Err(ReputestError)`: If loading or storing fails
+pub async fn run_cluster_detection(pool: &PgPool, seed: u64) -> Result<usize, ReputestError> {
+    let graph = get_good_vibes_graph(pool).await?;
+    let assignments = label_propagation(&graph, seed, MAX_ITERATIONS);
+    let stats = compute_cluster_stats(&graph, &assignments);
+
+    let stored: HashMap<String, i32> = assignments
+        .into_iter()
+        .map(|(id, cluster)| (id, cluster as i32))
+        .collect();
+    save_clusters(pool, &stored, &stats, seed).await?;
+
+    info!(
+        "Community detection found {} clusters over {} users (seed {})",
+        stats.len(),
+        stored.len(),
+        seed
+    );
+    Ok(stats.len())
+}
@@ -2,369 +2,1129 @@
 //!
 //! This module contains configuration structures and environment variable handling
 //! for the Twitter/X API integration.
+//!
+//! Every setting is read through [`setting`]: an environment variable wins over the
+//! matching key of the optional `reputest.toml` file (see [`ConfigFile`]). [`AppConfig`]
+//! validates the effective settings at startup.
 
 use crate::db;
-use log::{debug, error, info, warn};
-use sqlx::PgPool;
+use crate::error::ReputestError;
+use log::warn;
+use serde::Deserialize;
+use std::collections::BTreeMap;
 use std::env;
+use std::str::FromStr;
+use std::sync::{Arc, OnceLock};
 
-/// Configuration struct for Twitter/X API credentials.
+/// Gets the server port from environment variables or returns the default.
 ///
-/// This struct holds the credentials required to authenticate with the Twitter/X API v2 endpoints.
-/// It uses OAuth 2.0 User Context (Access Token) for all operations including posting tweets
-/// and searching tweets. It also includes refresh token for automatic token renewal.
-#[derive(Debug)]
-pub struct TwitterConfig {
-    /// The Access Token for OAuth 2.0 User Context authentication (all operations)
-    pub access_token: String,
-    /// The Refresh Token for automatically refreshing expired access tokens
-    pub refresh_token: Option<String>,
-    /// The Client ID for OAuth 2.0 operations
-    pub client_id: Option<String>,
-    /// The Client Secret for OAuth 2.0 operations
-    pub client_secret: Option<String>,
-}
+/// This function reads the `PORT` environment variable and parses it as a u16.
+/// If the environment variable is not set or cannot be parsed, it defaults to 3000.
+///
+/// # Returns
+///
+/// The port number as a u16.
+///
+/// # Example
+///
+/// ```rust
+/// use reputest::get_server_port;
+///
+/// // With PORT=8080 set in environment
+/// let port = get_server_port(); // Returns 8080
+///
+/// // With no PORT set
+/// let port = get_server_port(); // Returns 3000
+/// ```
+pub fn get_server_port() -> u16 {
+    const DEFAULT_PORT: u16 = 3000;
 
-impl TwitterConfig {
-    /// Saves a refresh token to the database.
-    ///
-    /// # Parameters
-    ///
-    /// - `pool`: A reference to the PostgreSQL connection pool
-    /// - `token`: The refresh token to save
-    ///
-    /// # Returns
-    ///
-    /// - `Ok(())`: If the token was successfully saved
-    /// - `Err(...)`: If saving failed
-    async fn save_refresh_token_to_db(
-        pool: &PgPool,
-        token: &str,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        info!("Attempting to save refresh token to database");
-
-        match db::save_refresh_token(pool, token).await {
-            Ok(_) => {
-                info!("Successfully saved refresh token to database");
-                Ok(())
+    match setting("PORT") {
+        Some(port_str) => match port_str.parse::<u16>() {
+            Ok(port) => port,
+            Err(e) => {
+                log::warn!(
+                    "Invalid PORT value '{}': {}. Using default port {}",
+                    port_str,
+                    e,
+                    DEFAULT_PORT
+                );
+                DEFAULT_PORT
             }
+        },
+        None => DEFAULT_PORT,
+    }
+}
+
+/// Gets the seed for community detection from the `CLUSTER_SEED` environment variable.
+///
+/// Clustering results are deterministic for a given graph and seed. If the variable is not
+/// set or cannot be parsed as a u64, it defaults to 42.
+pub fn get_cluster_seed() -> u64 {
+    const DEFAULT_SEED: u64 = 42;
+
+    match setting("CLUSTER_SEED") {
+        Some(seed_str) => match seed_str.parse::<u64>() {
+            Ok(seed) => seed,
             Err(e) => {
-                warn!("Failed to save refresh token to database: {}", e);
-                Err(e)
+                log::warn!(
+                    "Invalid CLUSTER_SEED value '{}': {}. Using default seed {}",
+                    seed_str,
+                    e,
+                    DEFAULT_SEED
+                );
+                DEFAULT_SEED
             }
+        },
+        None => DEFAULT_SEED,
+    }
+}
+
+/// Connection pool settings for PostgreSQL.
+///
+/// One pool is created at startup (see `db::get_db_pool`) and shared by the HTTP handlers
+/// and the scheduled jobs.
+#[derive(Debug, Clone, PartialEq)]
+pub struct DbPoolConfig {
+    /// Maximum number of open connections (`DB_MAX_CONNECTIONS`, default 10)
+    pub max_connections: u32,
+    /// How long to wait for a free connection (`DB_ACQUIRE_TIMEOUT_SECS`, default 10)
+    pub acquire_timeout: std::time::Duration,
+    /// Server-side timeout for every statement (`DB_STATEMENT_TIMEOUT_SECS`, default 300,
+    /// 0 disables it). Must leave room for the materialized view refresh.
+    pub statement_timeout: std::time::Duration,
+}
+
+impl DbPoolConfig {
+    /// Reads the pool settings from the environment; unset or invalid values use the defaults.
+    pub fn from_env() -> Self {
+        DbPoolConfig {
+            max_connections: env_or_default("DB_MAX_CONNECTIONS", 10),
+            acquire_timeout: std::time::Duration::from_secs(env_or_default(
+                "DB_ACQUIRE_TIMEOUT_SECS",
+                10,
+            )),
+            statement_timeout: std::time::Duration::from_secs(env_or_default(
+                "DB_STATEMENT_TIMEOUT_SECS",
+                300,
+            )),
         }
     }
 
-    /// Creates a new `TwitterConfig` instance by loading credentials from the database and environment variables.
-    ///
-    /// # Required Parameters
-    ///
-    /// - `pool`: A reference to the PostgreSQL connection pool to fetch tokens from
-    ///
-    /// # Required Database Tables
+    /// Rejects a pool of fewer than two connections.
     ///
-    /// - `access_tokens`: Must contain at least one access token
-    ///
-    /// # Optional Environment Variables (for automatic token refresh)
-    ///
-    /// - `XAPI_CLIENT_ID`: Client ID for OAuth 2.0 operations
-    /// - `XAPI_CLIENT_SECRET`: Client Secret for OAuth 2.0 operations
-    ///
-    /// # Token Loading
-    ///
-    /// - Access token: Loaded from the `access_tokens` table (required)
-    /// - Refresh token: Loaded from the `refresh_tokens` table (optional)
-    /// - Client credentials: Loaded from environment variables (optional)
-    ///
-    /// # Returns
-    ///
-    /// - `Ok(TwitterConfig)`: If the required access token is found in the database
-    /// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the access token is missing from the database
-    ///
-    /// # Example
-    ///
-    /// ```rust,no_run
-    /// use reputest::{TwitterConfig, db};
-    ///
-    /// #[tokio::main]
-    /// async fn main() {
-    ///     let pool = db::get_db_pool().await.unwrap();
-    ///     // Optionally set environment variables
-    ///     std::env::set_var("XAPI_CLIENT_ID", "your_client_id");
-    ///     std::env::set_var("XAPI_CLIENT_SECRET", "your_client_secret");
+    /// Refreshing the bot's token holds one connection for the refresh lock while the
+    /// handler that needed the token already holds another, so a single connection
+    /// deadlocks until the acquire timeout.
+    pub fn validate(&self) -> Result<(), ReputestError> {
+        if self.max_connections < 2 {
+            return Err(ReputestError::Config(format!(
+                "DB_MAX_CONNECTIONS must be at least 2, got {}",
+                self.max_connections
+            )));
+        }
+        Ok(())
+    }
+}
+
+/// Retry and rate limit settings for X API requests.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TwitterRetryConfig {
+    /// Retries after a 429 or 5xx response (`TWITTER_MAX_RETRIES`, default 3)
+    pub max_retries: u32,
+    /// Base delay of the jittered exponential backoff (`TWITTER_BACKOFF_BASE_MS`, default 1000)
+    pub backoff_base: std::time::Duration,
+    /// Longest wait for an exhausted rate limit window to reset before the call is skipped
+    /// instead (`TWITTER_RATE_LIMIT_MAX_WAIT_SECS`, default 60)
+    pub max_wait: std::time::Duration,
+}
+
+impl TwitterRetryConfig {
+    /// Reads the retry settings from the environment; unset or invalid values use the defaults.
+    pub fn from_env() -> Self {
+        TwitterRetryConfig {
+            max_retries: env_or_default("TWITTER_MAX_RETRIES", 3),
+            backoff_base: std::time::Duration::from_millis(env_or_default(
+                "TWITTER_BACKOFF_BASE_MS",
+                1000,
+            )),
+            max_wait: std::time::Duration::from_secs(env_or_default(
+                "TWITTER_RATE_LIMIT_MAX_WAIT_SECS",
+                60,
+            )),
+        }
+    }
+}
+
+/// Schedule of one scheduled task (see `crate::cronjob`).
+#[derive(Debug, Clone, PartialEq)]
+pub struct CronTaskConfig {
+    /// Cron expression with seconds (`CRON_<TASK>_SCHEDULE`)
+    pub schedule: String,
+    /// Whether the task is registered at all (`CRON_<TASK>_ENABLED`, default true)
+    pub enabled: bool,
+}
+
+impl CronTaskConfig {
+    /// Reads the schedule of `task` from the environment; unset or invalid values use the defaults.
     ///
-    ///     let config = TwitterConfig::from_env(&pool).await.unwrap();
-    /// }
-    /// ```
-    pub async fn from_env(pool: &PgPool) -> Result<Self, Box<dyn std::error::Error + Send + Sync>> {
-        info!("Loading Twitter configuration from database");
-
-        // Load required access token from database
-        let access_token = match db::get_latest_access_token(pool).await {
-            Ok(Some(token)) => {
-                info!("Access token loaded from database successfully");
-
-                // Validate token format (basic checks)
-                if token.is_empty() {
-                    error!("Access token is empty");
-                    return Err("Access token cannot be empty".into());
-                }
+    /// The variables are named after the task in uppercase, e.g. `CRON_HASHTAG_SEARCH_SCHEDULE`
+    /// and `CRON_HASHTAG_SEARCH_ENABLED` for `hashtag_search`. The expression itself is
+    /// checked when the task is registered.
+    pub fn from_env(task: &str, default_schedule: &str) -> Self {
+        let prefix = format!("CRON_{}", task.to_ascii_uppercase());
+        let schedule = setting(&format!("{}_SCHEDULE", prefix))
+            .map(|s| s.trim().to_string())
+            .filter(|s| !s.is_empty())
+            .unwrap_or_else(|| default_schedule.to_string());
+        CronTaskConfig {
+            schedule,
+            enabled: env_or_default(&format!("{}_ENABLED", prefix), true),
+        }
+    }
+}
 
-                if token.len() < 10 {
-                    warn!("Access token seems unusually short");
-                }
+/// Send attempts allowed for a queued bot reply before it is dead-lettered
+/// (`OUTBOUND_REPLY_MAX_ATTEMPTS`, default 5).
+pub fn get_reply_max_attempts() -> i32 {
+    env_or_default("OUTBOUND_REPLY_MAX_ATTEMPTS", 5).max(1)
+}
 
-                token
-            }
-            Ok(None) => {
-                error!("No access token found in database");
-                return Err("No access token found in database".into());
-            }
-            Err(_) => {
-                error!("Failed to load access token from database");
-                return Err("Failed to load access token from database".into());
-            }
-        };
+/// How many of the bot's newest access and refresh tokens are kept in the database
+/// (`TOKEN_RETENTION_COUNT`, default 3). Older ones are deleted when a new one is stored.
+pub fn get_token_retention() -> i64 {
+    env_or_default("TOKEN_RETENTION_COUNT", 3).max(1)
+}
 
-        // Load optional refresh token from database
-        let refresh_token = match db::get_latest_refresh_token(pool).await {
-            Ok(Some(token)) => {
-                debug!("Successfully loaded refresh token from database");
-                Some(token)
-            }
-            Ok(None) => {
-                warn!("No refresh token found in database");
-                None
-            }
+/// Whether the bot posts its replies to X.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BotMode {
+    /// Replies are posted to X (the default)
+    Live,
+    /// The full pipeline runs, but replies are recorded in `shadow_replies` instead of posted
+    Shadow,
+}
+
+impl std::str::FromStr for BotMode {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s.trim().to_ascii_lowercase().as_str() {
+            "live" => Ok(BotMode::Live),
+            "shadow" => Ok(BotMode::Shadow),
+            other => Err(format!(
+                "expected \"live\" or \"shadow\", got \"{}\"",
+                other
+            )),
+        }
+    }
+}
+
+impl std::fmt::Display for BotMode {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.write_str(match self {
+            BotMode::Live => "live",
+            BotMode::Shadow => "shadow",
+        })
+    }
+}
+
+/// Gets the bot mode from `BOT_MODE` ("live" or "shadow", default "live").
+pub fn get_bot_mode() -> BotMode {
+    env_or_default("BOT_MODE", BotMode::Live)
+}
+
+/// Schema that takes database writes in shadow mode (`BOT_SHADOW_SCHEMA`).
+///
+/// Only used when `BOT_MODE=shadow`. The schema is put first on the connection
+/// `search_path`, so tables that exist in it (see `sql/shadow_schema.sql`) are read and
+/// written there while all other tables, such as the bot's tokens, still resolve to
+/// `public`. Returns `None` in live mode, when unset, or when the value is not a plain
+/// lowercase identifier.
+pub fn get_shadow_schema() -> Option<String> {
+    if get_bot_mode() != BotMode::Shadow {
+        return None;
+    }
+    let schema = setting("BOT_SHADOW_SCHEMA")?;
+    let schema = schema.trim();
+    if schema.is_empty() {
+        return None;
+    }
+    if !is_valid_schema_name(schema) {
+        warn!(
+            "Invalid BOT_SHADOW_SCHEMA value '{}': expected a lowercase identifier. Writing to the default schema",
+            schema
+        );
+        return None;
+    }
+    Some(schema.to_string())
+}
+
+/// Whether `schema` is a plain lowercase identifier, usable unquoted in `search_path`.
+fn is_valid_schema_name(schema: &str) -> bool {
+    schema.starts_with(|c: char| c.is_ascii_lowercase() || c == '_')
+        && schema
+            .chars()
+            .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+}
+
+/// Parses a setting, logging a warning and using `default` if it is invalid.
+fn env_or_default<T>(name: &str, default: T) -> T
+where
+    T: std::str::FromStr + std::fmt::Display,
+    T::Err: std::fmt::Display,
+{
+    match setting(name) {
+        Some(value) => match value.parse::<T>() {
+            Ok(parsed) => parsed,
             Err(e) => {
-                warn!("Failed to load refresh token from database: {}", e);
-                None
+                log::warn!(
+                    "Invalid {} value '{}': {}. Using default {}",
+                    name,
+                    value,
+                    e,
+                    default
+                );
+                default
             }
-        };
+        },
+        None => default,
+    }
+}
 
-        // Load optional client credentials
-        let client_id = match env::var("XAPI_CLIENT_ID") {
-            Ok(id) => {
-                debug!("XAPI_CLIENT_ID loaded from environment");
-                Some(id)
-            }
-            Err(_) => {
-                warn!("No XAPI_CLIENT_ID found in environment variables");
-                None
-            }
-        };
+/// Whether pending schema migrations are applied at startup (`MIGRATE_ON_STARTUP`).
+///
+/// Defaults to false: the application role usually lacks the privileges to change the
+/// schema, so migrations are applied separately with `reputest migrate` and startup only
+/// verifies the schema. Accepts "true"/"false" (also "1"/"0"); other values log a warning
+/// and use the default.
+pub fn get_migrate_on_startup() -> bool {
+    const DEFAULT_MIGRATE_ON_STARTUP: bool = false;
 
-        let client_secret = match env::var("XAPI_CLIENT_SECRET") {
-            Ok(secret) => {
-                debug!("XAPI_CLIENT_SECRET loaded from environment");
-                Some(secret)
-            }
-            Err(_) => {
-                warn!("No XAPI_CLIENT_SECRET found in environment variables");
-                None
+    match setting("MIGRATE_ON_STARTUP") {
+        Some(value) => match parse_migrate_on_startup(&value) {
+            Some(migrate) => migrate,
+            None => {
+                log::warn!(
+                    "Invalid MIGRATE_ON_STARTUP value '{}'. Using default {}",
+                    value,
+                    DEFAULT_MIGRATE_ON_STARTUP
+                );
+                DEFAULT_MIGRATE_ON_STARTUP
             }
-        };
-
-        // Check if we have all required credentials for automatic refresh
-        if refresh_token.is_some() && (client_id.is_none() || client_secret.is_none()) {
-            warn!("Refresh token is provided but client credentials are missing - automatic token refresh will be disabled");
-        }
+        },
+        None => DEFAULT_MIGRATE_ON_STARTUP,
+    }
+}
 
-        let config = TwitterConfig {
-            access_token,
-            refresh_token,
-            client_id,
-            client_secret,
-        };
+/// Parses a `MIGRATE_ON_STARTUP` value ("true"/"false", also "1"/"0").
+fn parse_migrate_on_startup(value: &str) -> Option<bool> {
+    match value.trim().to_ascii_lowercase().as_str() {
+        "true" | "1" => Some(true),
+        "false" | "0" => Some(false),
+        _ => None,
+    }
+}
 
-        debug!("Twitter configuration loaded successfully");
-        if config.refresh_token.is_some()
-            && config.client_id.is_some()
-            && config.client_secret.is_some()
-        {
-            debug!("Automatic token refresh is enabled");
-        } else {
-            debug!("Automatic token refresh is disabled - manual token refresh required");
-        }
+/// Path of the OAuth callback route when `OAUTH_CALLBACK_PATH` is unset.
+pub const DEFAULT_OAUTH_CALLBACK_PATH: &str = "/oauth/callback";
 
-        Ok(config)
-    }
+/// Where the web pages are served, for the "Login with X" flow and its cookies.
+///
+/// X redirects to [`WebConfig::callback_url`] after authorization, so that URL must match
+/// the Callback URI configured in the X Developer Portal.
+#[derive(Debug, Clone, PartialEq)]
+pub struct WebConfig {
+    /// Origin the pages are served from, without a trailing slash (`BASE_URL`, e.g.
+    /// `https://reputest.fly.dev` or `http://localhost:3000`)
+    pub base_url: String,
+    /// Path of the OAuth callback route (`OAUTH_CALLBACK_PATH`, default `/oauth/callback`)
+    pub callback_path: String,
+    /// `Domain` of the login cookies (`COOKIE_DOMAIN`); unset means host-only cookies
+    pub cookie_domain: Option<String>,
+}
 
-    /// Attempts to refresh the access token using the stored refresh token and client credentials.
-    ///
-    /// This method automatically refreshes an expired access token if all required credentials
-    /// are available. It updates the access token in the config and saves both tokens to the database.
-    ///
-    /// # Parameters
-    ///
-    /// - `pool`: A reference to the PostgreSQL connection pool to save tokens to
+impl WebConfig {
+    /// Reads the web login settings from the environment.
     ///
     /// # Returns
     ///
-    /// - `Ok(())`: If the token was successfully refreshed
-    /// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If refresh failed or credentials are missing
-    ///
-    /// # Example
-    ///
-    /// ```rust,no_run
-    /// use reputest::{TwitterConfig, db};
+    /// - `Ok(Some(WebConfig))`: The validated settings
+    /// - `Ok(None)`: If `BASE_URL` is unset (web login is disabled)
+    /// - `Err(ReputestError::Config)`: If `BASE_URL`, `OAUTH_CALLBACK_PATH` or `COOKIE_DOMAIN` is invalid
+    pub fn from_env() -> Result<Option<Self>, ReputestError> {
+        let non_empty = |name: &str| setting(name).filter(|v| !v.trim().is_empty());
+        let Some(base_url) = non_empty("BASE_URL") else {
+            return Ok(None);
+        };
+        let callback_path = non_empty("OAUTH_CALLBACK_PATH")
+            .unwrap_or_else(|| DEFAULT_OAUTH_CALLBACK_PATH.to_string());
+        let cookie_domain = non_empty("COOKIE_DOMAIN");
+        WebConfig::new(&base_url, &callback_path, cookie_domain.as_deref()).map(Some)
+    }
+
+    /// Validates and normalizes the web login settings.
     ///
-    /// #[tokio::main]
-    /// async fn main() {
-    ///     let pool = db::get_db_pool().await.unwrap();
-    ///     let mut config = TwitterConfig::from_env(&pool).await.unwrap();
-    ///     match config.refresh_access_token(&pool).await {
-    ///         Ok(_) => println!("Token refreshed successfully"),
-    ///         Err(e) => eprintln!("Token refresh failed: {}", e),
-    ///     }
-    /// }
-    /// ```
-    pub async fn refresh_access_token(
-        &mut self,
-        pool: &PgPool,
-    ) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-        debug!("Attempting to refresh access token");
-
-        // Check if we have all required credentials for refresh
-        let (client_id, client_secret, refresh_token) = match (
-            self.client_id.as_ref(),
-            self.client_secret.as_ref(),
-            self.refresh_token.as_ref(),
-        ) {
-            (Some(id), Some(secret), Some(token)) => (id, secret, token),
+    /// The base URL must be an http(s) origin without a path, the callback path a plain
+    /// absolute path, and the cookie domain the base URL's host or one of its parents.
+    pub fn new(
+        base_url: &str,
+        callback_path: &str,
+        cookie_domain: Option<&str>,
+    ) -> Result<Self, ReputestError> {
+        let url = url::Url::parse(base_url.trim()).map_err(|e| {
+            ReputestError::Config(format!("Invalid BASE_URL '{}': {}", base_url, e))
+        })?;
+        let host = match url.host_str() {
+            Some(host) if matches!(url.scheme(), "http" | "https") => host.to_ascii_lowercase(),
             _ => {
-                error!("Cannot refresh token: missing required credentials");
-                if self.client_id.is_none() {
-                    error!("Missing XAPI_CLIENT_ID");
-                }
-                if self.client_secret.is_none() {
-                    error!("Missing XAPI_CLIENT_SECRET");
-                }
-                if self.refresh_token.is_none() {
-                    error!("Missing refresh token (should be loaded from database)");
-                }
-                return Err("Missing required credentials for token refresh".into());
+                return Err(ReputestError::Config(format!(
+                    "Invalid BASE_URL '{}': expected an http or https URL",
+                    base_url
+                )))
             }
         };
+        if url.path() != "/"
+            || url.query().is_some()
+            || url.fragment().is_some()
+            || !url.username().is_empty()
+            || url.password().is_some()
+        {
+            return Err(ReputestError::Config(format!(
+                "Invalid BASE_URL '{}': expected an origin such as https://example.com, without a path",
+                base_url
+            )));
+        }
 
-        debug!("All required credentials available for token refresh");
+        let callback_path = callback_path.trim();
+        let valid_path = callback_path.len() > 1
+            && callback_path.starts_with('/')
+            && !callback_path.starts_with("//")
+            && callback_path
+                .chars()
+                .all(|c| c.is_ascii_alphanumeric() || matches!(c, '/' | '-' | '_' | '.'))
+            && url
+                .join(callback_path)
+                .is_ok_and(|joined| joined.path() == callback_path);
+        if !valid_path {
+            return Err(ReputestError::Config(format!(
+                "Invalid OAUTH_CALLBACK_PATH '{}': expected a path such as {}",
+                callback_path, DEFAULT_OAUTH_CALLBACK_PATH
+            )));
+        }
 
-        // Import the refresh function from oauth module
-        use crate::oauth::refresh_access_token;
+        let cookie_domain = match cookie_domain.map(|d| d.trim().trim_start_matches('.')) {
+            None | Some("") => None,
+            Some(domain) => {
+                let domain = domain.to_ascii_lowercase();
+                let matches_host = host == domain || host.ends_with(&format!(".{}", domain));
+                if !matches_host
+                    || !domain
+                        .chars()
+                        .all(|c| c.is_ascii_alphanumeric() || matches!(c, '.' | '-'))
+                {
+                    return Err(ReputestError::Config(format!(
+                        "Invalid COOKIE_DOMAIN '{}': expected the BASE_URL host {} or a parent domain",
+                        domain, host
+                    )));
+                }
+                Some(domain)
+            }
+        };
 
-        // Attempt to refresh the token
-        match refresh_access_token(client_id, client_secret, refresh_token).await {
-            Ok((new_access_token, new_refresh_token)) => {
-                debug!("Access token refreshed successfully");
+        Ok(WebConfig {
+            base_url: url.origin().ascii_serialization(),
+            callback_path: callback_path.to_string(),
+            cookie_domain,
+        })
+    }
 
-                // Update the access token in the config
-                self.access_token = new_access_token.clone();
+    /// The OAuth redirect URI: the base URL followed by the callback path.
+    pub fn callback_url(&self) -> String {
+        format!("{}{}", self.base_url, self.callback_path)
+    }
 
-                // Save new access token to database
-                if db::save_access_token(pool, &new_access_token)
-                    .await
-                    .is_err()
-                {
-                    warn!("Failed to save access token to database");
-                    warn!("Access token updated in memory only");
-                } else {
-                    debug!("Access token successfully saved to database");
-                }
+    /// Whether the pages are served over HTTPS, so cookies can be `Secure`.
+    pub fn is_https(&self) -> bool {
+        self.base_url.starts_with("https://")
+    }
 
-                // Update refresh token if a new one was provided
-                if let Some(new_refresh) = new_refresh_token {
-                    debug!("Updating refresh token with new token from Twitter");
-                    self.refresh_token = Some(new_refresh.clone());
-
-                    // Try to save to database
-                    if Self::save_refresh_token_to_db(pool, &new_refresh)
-                        .await
-                        .is_err()
-                    {
-                        warn!("Failed to save refresh token to database");
-                        warn!("Refresh token updated in memory only");
-                    } else {
-                        debug!("Refresh token successfully saved to database");
-                    }
-                }
+    /// The `; Domain=...` cookie attribute, or an empty string for host-only cookies.
+    pub fn cookie_domain_attribute(&self) -> String {
+        self.cookie_domain
+            .as_deref()
+            .map(|domain| format!("; Domain={}", domain))
+            .unwrap_or_default()
+    }
+}
 
-                debug!("Access token updated successfully");
+/// HTTP rate limiting per client IP (a token bucket in front of every route).
+#[derive(Debug, Clone, PartialEq)]
+pub struct HttpRateLimitConfig {
+    /// Tokens refilled per second (`HTTP_RATE_LIMIT_PER_SECOND`, default 2)
+    pub per_second: u64,
+    /// Size of the bucket, i.e. the longest burst of requests (`HTTP_RATE_LIMIT_BURST`, default 30)
+    pub burst: u32,
+}
 
-                Ok(())
-            }
-            Err(e) => {
-                error!("Failed to refresh access token: {}", e);
-                Err(e)
-            }
+impl HttpRateLimitConfig {
+    /// Reads the rate limit from the environment; unset or invalid values use the defaults.
+    pub fn from_env() -> Self {
+        HttpRateLimitConfig {
+            per_second: env_or_default("HTTP_RATE_LIMIT_PER_SECOND", 2),
+            burst: env_or_default("HTTP_RATE_LIMIT_BURST", 30),
         }
     }
+}
 
-    /// Checks if automatic token refresh is available.
-    ///
-    /// Returns true if all required credentials (client_id, client_secret, refresh_token)
-    /// are available for automatic token refresh.
-    ///
-    /// # Returns
-    ///
-    /// `true` if automatic refresh is available, `false` otherwise.
-    pub fn can_refresh_token(&self) -> bool {
-        self.client_id.is_some() && self.client_secret.is_some() && self.refresh_token.is_some()
-    }
+/// Path of the configuration file when `REPUTEST_CONFIG` is unset.
+pub const DEFAULT_CONFIG_PATH: &str = "reputest.toml";
+
+/// The configuration file loaded by [`load_config_file`], if any.
+static CONFIG_FILE: OnceLock<ConfigFile> = OnceLock::new();
+
+/// Reads a setting by its environment variable name.
+///
+/// The environment variable wins; otherwise the matching key of the loaded configuration
+/// file is used (see [`ConfigFile::value`]). Returns `None` if neither is set.
+pub fn setting(name: &str) -> Option<String> {
+    env::var(name)
+        .ok()
+        .or_else(|| CONFIG_FILE.get().and_then(|file| file.value(name)))
 }
 
-/// Gets the server port from environment variables or returns the default.
+/// Loads the configuration file that [`setting`] falls back to.
 ///
-/// This function reads the `PORT` environment variable and parses it as a u16.
-/// If the environment variable is not set or cannot be parsed, it defaults to 3000.
+/// The file is `REPUTEST_CONFIG`, or `reputest.toml` in the working directory. A missing
+/// `reputest.toml` is fine (every setting then comes from the environment), but a missing
+/// `REPUTEST_CONFIG` file is not. Only the first successful call has an effect.
 ///
 /// # Returns
 ///
-/// The port number as a u16.
+/// - `Ok(Some(path))`: The file that was loaded
+/// - `Ok(None)`: If there is no configuration file
+/// - `Err(ReputestError::Config)`: If the file cannot be read or is invalid
+pub fn load_config_file() -> Result<Option<String>, ReputestError> {
+    let explicit = env::var("REPUTEST_CONFIG")
+        .ok()
+        .filter(|path| !path.trim().is_empty());
+    let path = explicit
+        .clone()
+        .unwrap_or_else(|| DEFAULT_CONFIG_PATH.to_string());
+
+    let contents = match std::fs::read_to_string(&path) {
+        Ok(contents) => contents,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound && explicit.is_none() => {
+            return Ok(None)
+        }
+        Err(e) => {
+            return Err(ReputestError::Config(format!(
+                "Cannot read configuration file {}: {}",
+                path, e
+            )))
+        }
+    };
+    let file = ConfigFile::parse(&contents).map_err(|e| {
+        ReputestError::Config(format!("Invalid configuration file {}: {}", path, e))
+    })?;
+    let _ = CONFIG_FILE.set(file);
+    Ok(Some(path))
+}
+
+/// Contents of `reputest.toml`.
 ///
-/// # Example
+/// Every key is optional and has an environment variable that overrides it, except the
+/// `[hashtags]` table. The encryption keys and passphrase (`TOKEN_ENCRYPTION_KEY`,
+/// `TOKEN_ENCRYPTION_PREVIOUS_KEYS` and `TOKEN_ENCRYPTION_PASSPHRASE`) can only be set in
+/// the environment; the `[encryption]` table only says where to find them.
+/// Unknown keys are rejected so a typo does not silently fall back to a default.
 ///
-/// ```rust
-/// use reputest::get_server_port;
+/// ```toml
+/// [server]
+/// port = 8080                      # PORT
 ///
-/// // With PORT=8080 set in environment
-/// let port = get_server_port(); // Returns 8080
+/// [rate_limit]
+/// per_second = 2                   # HTTP_RATE_LIMIT_PER_SECOND
+/// burst = 30                       # HTTP_RATE_LIMIT_BURST
 ///
-/// // With no PORT set
-/// let port = get_server_port(); // Returns 3000
+/// [cron.view_refresh]
+/// schedule = "0 */10 * * * *"      # CRON_VIEW_REFRESH_SCHEDULE
+///
+/// [hashtags.gmgv]
+/// action = "vibe"
 /// ```
-pub fn get_server_port() -> u16 {
-    const DEFAULT_PORT: u16 = 3000;
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ConfigFile {
+    pub server: ServerSection,
+    pub database: DatabaseSection,
+    pub rate_limit: RateLimitSection,
+    pub twitter: TwitterSection,
+    pub bot: BotSection,
+    pub oauth: OAuthSection,
+    pub encryption: EncryptionSection,
+    /// Schedules keyed by task name (see `crate::cronjob::CRON_TASKS`)
+    pub cron: BTreeMap<String, CronSection>,
+    /// Hashtags written to the `hashtags` registry at startup, keyed by tag
+    pub hashtags: BTreeMap<String, HashtagSection>,
+}
 
-    match env::var("PORT") {
-        Ok(port_str) => match port_str.parse::<u16>() {
-            Ok(port) => port,
-            Err(e) => {
-                log::warn!(
-                    "Invalid PORT value '{}': {}. Using default port {}",
-                    port_str,
-                    e,
-                    DEFAULT_PORT
-                );
-                DEFAULT_PORT
+/// `[server]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct ServerSection {
+    pub port: Option<u16>,
+    pub migrate_on_startup: Option<bool>,
+}
+
+/// `[database]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct DatabaseSection {
+    pub url: Option<String>,
+    pub max_connections: Option<u32>,
+    pub acquire_timeout_secs: Option<u64>,
+    pub statement_timeout_secs: Option<u64>,
+}
+
+/// `[rate_limit]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct RateLimitSection {
+    pub per_second: Option<u64>,
+    pub burst: Option<u32>,
+}
+
+/// `[twitter]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct TwitterSection {
+    pub max_retries: Option<u32>,
+    pub backoff_base_ms: Option<u64>,
+    pub rate_limit_max_wait_secs: Option<u64>,
+    pub outbound_reply_max_attempts: Option<i32>,
+    pub token_retention_count: Option<i64>,
+}
+
+/// `[bot]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct BotSection {
+    pub mode: Option<String>,
+    pub shadow_schema: Option<String>,
+    pub cluster_seed: Option<u64>,
+}
+
+/// `[oauth]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct OAuthSection {
+    pub base_url: Option<String>,
+    pub callback_path: Option<String>,
+    pub cookie_domain: Option<String>,
+    pub client_id: Option<String>,
+    pub client_secret: Option<String>,
+    pub admin_user_id: Option<String>,
+}
+
+/// `[encryption]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct EncryptionSection {
+    pub provider: Option<String>,
+    pub key_file: Option<String>,
+    pub passphrase_file: Option<String>,
+    pub previous_file: Option<String>,
+    pub kdf_salt: Option<String>,
+}
+
+/// `[cron.<task>]` table of [`ConfigFile`].
+#[derive(Debug, Clone, Default, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct CronSection {
+    pub schedule: Option<String>,
+    pub enabled: Option<bool>,
+}
+
+/// `[hashtags.<tag>]` table of [`ConfigFile`].
+#[derive(Debug, Clone, PartialEq, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct HashtagSection {
+    /// "vibe", "revoke" or "transfer"
+    pub action: String,
+    pub category: Option<String>,
+    #[serde(default = "default_true")]
+    pub enabled: bool,
+}
+
+fn default_true() -> bool {
+    true
+}
+
+/// A hashtag of the configuration file, ready for the `hashtags` registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashtagConfig {
+    pub rule: db::HashtagRule,
+    pub enabled: bool,
+}
+
+impl ConfigFile {
+    /// Parses the TOML contents of a configuration file.
+    pub fn parse(contents: &str) -> Result<Self, ReputestError> {
+        toml::from_str(contents).map_err(|e| ReputestError::Config(e.to_string()))
+    }
+
+    /// The value of the key that the environment variable `name` overrides.
+    pub fn value(&self, name: &str) -> Option<String> {
+        fn text<T: ToString>(value: &Option<T>) -> Option<String> {
+            value.as_ref().map(ToString::to_string)
+        }
+
+        match name {
+            "PORT" => text(&self.server.port),
+            "MIGRATE_ON_STARTUP" => text(&self.server.migrate_on_startup),
+            "DATABASE_URL" => text(&self.database.url),
+            "DB_MAX_CONNECTIONS" => text(&self.database.max_connections),
+            "DB_ACQUIRE_TIMEOUT_SECS" => text(&self.database.acquire_timeout_secs),
+            "DB_STATEMENT_TIMEOUT_SECS" => text(&self.database.statement_timeout_secs),
+            "HTTP_RATE_LIMIT_PER_SECOND" => text(&self.rate_limit.per_second),
+            "HTTP_RATE_LIMIT_BURST" => text(&self.rate_limit.burst),
+            "TWITTER_MAX_RETRIES" => text(&self.twitter.max_retries),
+            "TWITTER_BACKOFF_BASE_MS" => text(&self.twitter.backoff_base_ms),
+            "TWITTER_RATE_LIMIT_MAX_WAIT_SECS" => text(&self.twitter.rate_limit_max_wait_secs),
+            "OUTBOUND_REPLY_MAX_ATTEMPTS" => text(&self.twitter.outbound_reply_max_attempts),
+            "TOKEN_RETENTION_COUNT" => text(&self.twitter.token_retention_count),
+            "BOT_MODE" => text(&self.bot.mode),
+            "BOT_SHADOW_SCHEMA" => text(&self.bot.shadow_schema),
+            "CLUSTER_SEED" => text(&self.bot.cluster_seed),
+            "BASE_URL" => text(&self.oauth.base_url),
+            "OAUTH_CALLBACK_PATH" => text(&self.oauth.callback_path),
+            "COOKIE_DOMAIN" => text(&self.oauth.cookie_domain),
+            "XAPI_CLIENT_ID" => text(&self.oauth.client_id),
+            "XAPI_CLIENT_SECRET" => text(&self.oauth.client_secret),
+            "ADMIN_USER_ID" => text(&self.oauth.admin_user_id),
+            "TOKEN_ENCRYPTION_KEY_PROVIDER" => text(&self.encryption.provider),
+            "TOKEN_ENCRYPTION_KEY_FILE" => text(&self.encryption.key_file),
+            "TOKEN_ENCRYPTION_PASSPHRASE_FILE" => text(&self.encryption.passphrase_file),
+            "TOKEN_ENCRYPTION_PREVIOUS_FILE" => text(&self.encryption.previous_file),
+            "TOKEN_ENCRYPTION_KDF_SALT" => text(&self.encryption.kdf_salt),
+            _ => {
+                let task = name.strip_prefix("CRON_")?;
+                if let Some(task) = task.strip_suffix("_SCHEDULE") {
+                    text(&self.cron.get(&task.to_ascii_lowercase())?.schedule)
+                } else if let Some(task) = task.strip_suffix("_ENABLED") {
+                    text(&self.cron.get(&task.to_ascii_lowercase())?.enabled)
+                } else {
+                    None
+                }
             }
-        },
-        Err(_) => DEFAULT_PORT,
+        }
+    }
+
+    /// Validates the `[hashtags]` table.
+    ///
+    /// Tags may be written with a leading `#` and in any case. Categories are only allowed
+    /// on vibe and revoke hashtags.
+    pub fn hashtag_configs(&self) -> Result<Vec<HashtagConfig>, ReputestError> {
+        let is_label = |value: &str| {
+            (1..=50).contains(&value.len())
+                && value
+                    .chars()
+                    .all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '_')
+        };
+
+        let mut configs = Vec::with_capacity(self.hashtags.len());
+        for (key, section) in &self.hashtags {
+            let tag = key.trim_start_matches('#').to_ascii_lowercase();
+            if !is_label(&tag) {
+                return Err(ReputestError::Config(format!(
+                    "Invalid hashtag '{}': expected letters, digits or underscores",
+                    key
+                )));
+            }
+            let action = db::HashtagAction::parse(&section.action).ok_or_else(|| {
+                ReputestError::Config(format!(
+                    "Invalid action '{}' for hashtag #{}: expected \"vibe\", \"revoke\" or \"transfer\"",
+                    section.action, tag
+                ))
+            })?;
+            let category = section.category.as_deref().map(str::to_ascii_lowercase);
+            if let Some(category) = &category {
+                if action == db::HashtagAction::Transfer {
+                    return Err(ReputestError::Config(format!(
+                        "Hashtag #{} is a transfer hashtag and cannot have a category",
+                        tag
+                    )));
+                }
+                if !is_label(category) {
+                    return Err(ReputestError::Config(format!(
+                        "Invalid category '{}' for hashtag #{}",
+                        category, tag
+                    )));
+                }
+            }
+            configs.push(HashtagConfig {
+                rule: db::HashtagRule::new(&tag, action, category.as_deref()),
+                enabled: section.enabled,
+            });
+        }
+        Ok(configs)
     }
 }
 
-/// OAuth callback URI. X redirects here after authorization.
-/// Must match the Callback URI configured in the X Developer Portal (e.g. https://reputest.fly.dev/reputest).
-const CALLBACK_URI: &str = "https://reputest.fly.dev/reputest";
+/// Replaces the password of a database URL with `REDACTED`, for logging.
+pub fn redact_database_url(database_url: &str) -> String {
+    match url::Url::parse(database_url) {
+        Ok(mut url) => {
+            if url.password().is_some() {
+                let _ = url.set_password(Some("REDACTED"));
+            }
+            url.to_string()
+        }
+        Err(_) => "REDACTED".to_string(),
+    }
+}
 
-/// Gets the base URL for app state (same as callback URI for this app).
-pub fn get_base_url() -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    Ok(CALLBACK_URI.to_string())
+/// The effective configuration of the server: the environment over `reputest.toml` over
+/// the defaults.
+///
+/// [`AppConfig::load`] is stricter than the individual getters: a value that does not
+/// parse, an invalid cron expression or an unknown task fails startup instead of falling
+/// back to the default. The `Display` output is safe to log, with secrets redacted.
+#[derive(Debug, Clone)]
+pub struct AppConfig {
+    pub port: u16,
+    pub migrate_on_startup: bool,
+    pub database_url: Option<String>,
+    pub db_pool: DbPoolConfig,
+    pub rate_limit: HttpRateLimitConfig,
+    pub twitter_retry: TwitterRetryConfig,
+    pub reply_max_attempts: i32,
+    pub token_retention: i64,
+    /// Schedule of every task of `crate::cronjob::CRON_TASKS`, in that order
+    pub cron: Vec<(&'static str, CronTaskConfig)>,
+    /// Hashtags of the configuration file
+    pub hashtags: Vec<HashtagConfig>,
+    pub bot_mode: BotMode,
+    pub shadow_schema: Option<String>,
+    pub cluster_seed: u64,
+    pub web: Option<WebConfig>,
+    pub oauth_client_id: Option<String>,
+    pub oauth_client_secret: Option<String>,
+    /// X user ID made admin at startup unless it already has a role (`ADMIN_USER_ID`)
+    pub admin_user_id: Option<String>,
+    pub encryption_configured: bool,
+    /// Where the encryption keys come from (see `crate::crypto::KeyProvider`)
+    pub encryption_provider: Option<String>,
 }
 
-/// Allowed username for web login. Only this X username may log in; others receive 403.
-const ALLOWED_USERNAME: &str = "julian_le_roux";
+impl AppConfig {
+    /// Reads and validates the effective configuration.
+    ///
+    /// Call [`load_config_file`] first for the file layer to be included.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(AppConfig)`: The validated configuration
+    /// - `Err(ReputestError::Config)`: Naming the first invalid setting
+    pub fn load() -> Result<Self, ReputestError> {
+        check_setting::<u16>("PORT")?;
+        check_setting::<u32>("DB_MAX_CONNECTIONS")?;
+        check_setting::<u64>("DB_ACQUIRE_TIMEOUT_SECS")?;
+        check_setting::<u64>("DB_STATEMENT_TIMEOUT_SECS")?;
+        check_setting::<u64>("HTTP_RATE_LIMIT_PER_SECOND")?;
+        check_setting::<u32>("HTTP_RATE_LIMIT_BURST")?;
+        check_setting::<u32>("TWITTER_MAX_RETRIES")?;
+        check_setting::<u64>("TWITTER_BACKOFF_BASE_MS")?;
+        check_setting::<u64>("TWITTER_RATE_LIMIT_MAX_WAIT_SECS")?;
+        check_setting::<i32>("OUTBOUND_REPLY_MAX_ATTEMPTS")?;
+        check_setting::<i64>("TOKEN_RETENTION_COUNT")?;
+        check_setting::<BotMode>("BOT_MODE")?;
+        check_setting::<u64>("CLUSTER_SEED")?;
+        if let Some(value) = setting("MIGRATE_ON_STARTUP") {
+            if parse_migrate_on_startup(&value).is_none() {
+                return Err(ReputestError::Config(format!(
+                    "Invalid MIGRATE_ON_STARTUP value '{}': expected true or false",
+                    value
+                )));
+            }
+        }
+        if let Some(schema) = setting("BOT_SHADOW_SCHEMA") {
+            let schema = schema.trim();
+            if !schema.is_empty() && !is_valid_schema_name(schema) {
+                return Err(ReputestError::Config(format!(
+                    "Invalid BOT_SHADOW_SCHEMA value '{}': expected a lowercase identifier",
+                    schema
+                )));
+            }
+        }
 
-/// Gets the allowed username for web login.
-pub fn get_allowed_username() -> Option<String> {
-    Some(ALLOWED_USERNAME.to_string())
+        DbPoolConfig::from_env().validate()?;
+
+        let rate_limit = HttpRateLimitConfig::from_env();
+        if rate_limit.per_second == 0 || rate_limit.burst == 0 {
+            return Err(ReputestError::Config(
+                "HTTP_RATE_LIMIT_PER_SECOND and HTTP_RATE_LIMIT_BURST must be at least 1"
+                    .to_string(),
+            ));
+        }
+
+        let file = CONFIG_FILE.get().cloned().unwrap_or_default();
+        if let Some(task) = file
+            .cron
+            .keys()
+            .find(|task| !crate::cronjob::is_cron_task(task))
+        {
+            return Err(ReputestError::Config(format!(
+                "Unknown task [cron.{}] in the configuration file",
+                task
+            )));
+        }
+        let mut cron = Vec::with_capacity(crate::cronjob::CRON_TASKS.len());
+        for &(task, default_schedule) in crate::cronjob::CRON_TASKS {
+            let prefix = format!("CRON_{}", task.to_ascii_uppercase());
+            check_setting::<bool>(&format!("{}_ENABLED", prefix))?;
+            let config = CronTaskConfig::from_env(task, default_schedule);
+            if let Err(e) = cron::Schedule::from_str(&config.schedule) {
+                return Err(ReputestError::Config(format!(
+                    "Invalid {}_SCHEDULE \"{}\": {}",
+                    prefix, config.schedule, e
+                )));
+            }
+            cron.push((task, config));
+        }
+
+        let web = WebConfig::from_env()?;
+        if web.is_some()
+            && (setting("XAPI_CLIENT_ID").is_none() || setting("XAPI_CLIENT_SECRET").is_none())
+        {
+            warn!("BASE_URL is set but XAPI_CLIENT_ID or XAPI_CLIENT_SECRET is missing: web login will fail");
+        }
+        if let Some(user_id) = setting("ADMIN_USER_ID") {
+            let user_id = user_id.trim();
+            if !user_id.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(ReputestError::Config(format!(
+                    "Invalid ADMIN_USER_ID value '{}': expected a numeric X user ID",
+                    user_id
+                )));
+            }
+        }
+
+        Ok(AppConfig::read(cron, file.hashtag_configs()?, web))
+    }
+
+    /// Builds the configuration from the settings, each invalid one falling back to its
+    /// default. [`AppConfig::load`] validates them first.
+    fn read(
+        cron: Vec<(&'static str, CronTaskConfig)>,
+        hashtags: Vec<HashtagConfig>,
+        web: Option<WebConfig>,
+    ) -> Self {
+        AppConfig {
+            port: get_server_port(),
+            migrate_on_startup: get_migrate_on_startup(),
+            database_url: setting("DATABASE_URL"),
+            db_pool: DbPoolConfig::from_env(),
+            rate_limit: HttpRateLimitConfig::from_env(),
+            twitter_retry: TwitterRetryConfig::from_env(),
+            reply_max_attempts: get_reply_max_attempts(),
+            token_retention: get_token_retention(),
+            cron,
+            hashtags,
+            bot_mode: get_bot_mode(),
+            shadow_schema: get_shadow_schema(),
+            cluster_seed: get_cluster_seed(),
+            web,
+            oauth_client_id: setting("XAPI_CLIENT_ID"),
+            oauth_client_secret: setting("XAPI_CLIENT_SECRET"),
+            admin_user_id: setting("ADMIN_USER_ID")
+                .map(|value| value.trim().to_string())
+                .filter(|value| !value.is_empty() && value.bytes().all(|b| b.is_ascii_digit())),
+            encryption_configured: crate::crypto::is_encryption_configured(),
+            encryption_provider: crate::crypto::key_provider()
+                .ok()
+                .map(|provider| provider.describe()),
+        }
+    }
+
+    /// Makes this the configuration returned by [`AppConfig::current`].
+    ///
+    /// `main` installs the configuration it loaded and validated at startup, before
+    /// anything reads it; installing a second one has no effect.
+    pub fn install(self) -> Arc<AppConfig> {
+        APP_CONFIG.get_or_init(|| Arc::new(self)).clone()
+    }
+
+    /// The configuration of this process, read once.
+    ///
+    /// Returns the configuration installed by `main`. Other entry points (the utility
+    /// binaries and tests) get one read from the settings on first use, where an invalid
+    /// setting falls back to its default instead of failing.
+    pub fn current() -> Arc<AppConfig> {
+        APP_CONFIG
+            .get_or_init(|| {
+                let file = CONFIG_FILE.get().cloned().unwrap_or_default();
+                let cron = crate::cronjob::CRON_TASKS
+                    .iter()
+                    .map(|&(task, default_schedule)| {
+                        (task, CronTaskConfig::from_env(task, default_schedule))
+                    })
+                    .collect();
+                let web = WebConfig::from_env().unwrap_or_else(|e| {
+                    warn!("Invalid web login settings, web login is disabled: {}", e);
+                    None
+                });
+                Arc::new(AppConfig::read(
+                    cron,
+                    file.hashtag_configs().unwrap_or_default(),
+                    web,
+                ))
+            })
+            .clone()
+    }
+
+    /// Schedule of the task `name` of `crate::cronjob::CRON_TASKS`.
+    pub fn cron_task(&self, name: &str) -> Option<&CronTaskConfig> {
+        self.cron
+            .iter()
+            .find(|(task, _)| *task == name)
+            .map(|(_, config)| config)
+    }
+}
+
+/// The configuration returned by [`AppConfig::current`].
+static APP_CONFIG: OnceLock<Arc<AppConfig>> = OnceLock::new();
+
+impl std::fmt::Display for AppConfig {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let or_unset = |value: Option<&str>| value.unwrap_or("(not set)").to_string();
+
+        writeln!(f, "server.port = {}", self.port)?;
+        writeln!(f, "server.migrate_on_startup = {}", self.migrate_on_startup)?;
+        writeln!(
+            f,
+            "database.url = {}",
+            or_unset(
+                self.database_url
+                    .as_deref()
+                    .map(redact_database_url)
+                    .as_deref()
+            )
+        )?;
+        writeln!(
+            f,
+            "database.max_connections = {}",
+            self.db_pool.max_connections
+        )?;
+        writeln!(
+            f,
+            "database.acquire_timeout_secs = {}",
+            self.db_pool.acquire_timeout.as_secs()
+        )?;
+        writeln!(
+            f,
+            "database.statement_timeout_secs = {}",
+            self.db_pool.statement_timeout.as_secs()
+        )?;
+        writeln!(f, "rate_limit.per_second = {}", self.rate_limit.per_second)?;
+        writeln!(f, "rate_limit.burst = {}", self.rate_limit.burst)?;
+        writeln!(
+            f,
+            "twitter.max_retries = {}",
+            self.twitter_retry.max_retries
+        )?;
+        writeln!(
+            f,
+            "twitter.backoff_base_ms = {}",
+            self.twitter_retry.backoff_base.as_millis()
+        )?;
+        writeln!(
+            f,
+            "twitter.rate_limit_max_wait_secs = {}",
+            self.twitter_retry.max_wait.as_secs()
+        )?;
+        writeln!(
+            f,
+            "twitter.outbound_reply_max_attempts = {}",
+            self.reply_max_attempts
+        )?;
+        writeln!(
+            f,
+            "twitter.token_retention_count = {}",
+            self.token_retention
+        )?;
+        writeln!(f, "bot.mode = {}", self.bot_mode)?;
+        writeln!(
+            f,
+            "bot.shadow_schema = {}",
+            or_unset(self.shadow_schema.as_deref())
+        )?;
+        writeln!(f, "bot.cluster_seed = {}", self.cluster_seed)?;
+        match &self.web {
+            Some(web) => {
+                writeln!(f, "oauth.base_url = {}", web.base_url)?;
+                writeln!(f, "oauth.callback_path = {}", web.callback_path)?;
+                writeln!(
+                    f,
+                    "oauth.cookie_domain = {}",
+                    or_unset(web.cookie_domain.as_deref())
+                )?;
+            }
+            None => writeln!(f, "oauth.base_url = (not set, web login disabled)")?,
+        }
+        writeln!(
+            f,
+            "oauth.client_id = {}",
+            or_unset(self.oauth_client_id.as_deref())
+        )?;
+        writeln!(
+            f,
+            "oauth.client_secret = {}",
+            or_unset(self.oauth_client_secret.as_ref().map(|_| "REDACTED"))
+        )?;
+        writeln!(
+            f,
+            "oauth.admin_user_id = {}",
+            or_unset(self.admin_user_id.as_deref())
+        )?;
+        match (&self.encryption_provider, self.encryption_configured) {
+            (Some(provider), true) => {
+                writeln!(f, "token_encryption_key = REDACTED (from {})", provider)?
+            }
+            _ => writeln!(f, "token_encryption_key = (not set)")?,
+        }
+        for (task, config) in &self.cron {
+            if config.enabled {
+                writeln!(f, "cron.{}.schedule = \"{}\"", task, config.schedule)?;
+            } else {
+                writeln!(f, "cron.{}.enabled = false", task)?;
+            }
+        }
+        for hashtag in &self.hashtags {
+            let rule = &hashtag.rule;
+            write!(f, "hashtags.{}.action = {}", rule.tag, rule.action.as_str())?;
+            if let Some(category) = &rule.category {
+                write!(f, ", category = {}", category)?;
+            }
+            if !hashtag.enabled {
+                write!(f, ", enabled = false")?;
+            }
+            writeln!(f)?;
+        }
+        Ok(())
+    }
+}
+
+/// Fails if the setting `name` is set but does not parse as `T`.
+fn check_setting<T>(name: &str) -> Result<(), ReputestError>
+where
+    T: FromStr,
+    T::Err: std::fmt::Display,
+{
+    match setting(name) {
+        Some(value) => value.parse::<T>().map(|_| ()).map_err(|e| {
+            ReputestError::Config(format!("Invalid {} value '{}': {}", name, value, e))
+        }),
+        None => Ok(()),
+    }
 }
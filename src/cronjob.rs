@@ -3,211 +3,234 @@
 //! This module contains functionality for running scheduled tasks, specifically
 //! for searching Twitter for tweets with specific hashtags and processing vibe-related queries.
 
-use crate::config::TwitterConfig;
+use crate::clusters::run_cluster_detection;
+use crate::config::AppConfig;
 use crate::db::{
-    get_good_vibes_count, get_user_id_by_username, get_vibe_score_five, get_vibe_score_four,
+    enqueue_reply, finish_job_run, get_bot_paused, get_category_vibe_scores, get_followed_ids,
+    get_good_vibes_count, get_hashtag_rules, get_mutual_follow_suggestions,
+    get_trusted_vibe_scores, get_user_id_by_username, get_vibe_score_five, get_vibe_score_four,
     get_vibe_score_one, get_vibe_score_six, get_vibe_score_three, get_vibe_score_two,
-    has_vibe_request, increment_follower_count, refresh_materialized_views, save_following,
-    save_user, save_vibe_request,
+    has_vibe_request, recompute_known_follower_counts, record_skipped_job_run,
+    refresh_materialized_views, save_following, save_user, save_vibe_request, start_job_run,
+    JobOutcome,
 };
+use crate::error::ReputestError;
+use crate::recommend::{recommend_for_user, record_suggestions, SuggestionSource};
+use crate::replies::{drain_outbound_replies, DrainSummary};
+use crate::stats::record_graph_stats;
 use crate::twitter::lookup_user_by_username;
 use crate::twitter::{
-    extract_mention_with_following, fetch_user_following, reply_to_tweet, sanitize_for_logging,
+    extract_mention_with_category, extract_mention_with_following, extract_mention_with_trusted,
+    fetch_user_following, is_mutuals_request, is_suggest_request, sanitize_for_logging,
     search_mentions, search_tweets_with_hashtag,
 };
-use log::{debug, error, info};
+use log::{debug, error, info, warn};
 use sqlx::PgPool;
+use std::collections::HashMap;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
 use tokio_cron_scheduler::{Job, JobScheduler};
 
-/// Starts the cronjob scheduler for searching tweets with hashtag "gmgv" and processing vibe queries every 5 minutes.
+/// Shared state for scheduled jobs.
 ///
-/// This function creates a new job scheduler and adds a job that runs every 5 minutes
-/// to perform three tasks:
-/// 1. Search for tweets containing the hashtag "gmgv" from the past 24 hours
-/// 2. Check for mentions of @reputest from the past 24 hours and reply to:
-///    - Specific vibe score queries (e.g., "@reputest @username?")
-///    - General requests for the total vibes count (messages containing "vibecount")
-/// 3. Refresh all materialized views (degree 1-4 and combined view) and record timing metrics
-///
-/// The job will log all found tweets and mentions to the application logs.
-///
-/// # Returns
-///
-/// - `Ok(JobScheduler)`: The configured job scheduler
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If there's an error creating or configuring the scheduler
-///
-/// # Job Schedule
-///
-/// The job runs every 5 minutes using the cron expression "0 0/5 * * * * *"
-/// which means:
-/// - 0 seconds
-/// - Every 5 minutes (0/5)
-/// - Every hour
-/// - Every day
-/// - Every month
-/// - Every day of the week
-///
-/// # Example
-///
-/// ```rust,no_run
-/// use reputest::start_gmgv_cronjob;
-///
-/// #[tokio::main]
-/// async fn main() {
-///     let scheduler = start_gmgv_cronjob().await.unwrap();
-///     scheduler.start().await.unwrap();
-///     
-///     // Keep the scheduler running
-///     tokio::signal::ctrl_c().await.unwrap();
-/// }
-/// ```
-///
-/// # Errors
-///
-/// This function can fail if:
-/// - The job scheduler cannot be created
-/// - The cron expression is invalid
-/// - There's an error adding the job to the scheduler
-///
-/// Processes the scheduled search for #gmgv tweets
-async fn process_hashtag_search() {
-    info!("Starting scheduled search for #gmgv tweets");
-    match search_tweets_with_hashtag("gmgv").await {
-        Ok(_) => {
-            info!("Scheduled search for #gmgv tweets completed successfully");
-        }
-        Err(e) => {
-            error!("Scheduled search for #gmgv tweets failed: {}", e);
+/// Created once in `main` and cloned into each job and the admin console, so every run
+/// reuses the application's connection pool and configuration instead of opening or
+/// reading its own, and scheduled and manual runs of a task share its running flag.
+#[derive(Clone)]
+pub struct JobContext {
+    pub pool: PgPool,
+    pub config: Arc<AppConfig>,
+    /// Running flag of each task of [`CRON_TASKS`]
+    running: Arc<HashMap<&'static str, Arc<AtomicBool>>>,
+}
+
+impl JobContext {
+    pub fn new(pool: PgPool, config: Arc<AppConfig>) -> Self {
+        let running = CRON_TASKS
+            .iter()
+            .map(|&(name, _)| (name, Arc::new(AtomicBool::new(false))))
+            .collect();
+        JobContext {
+            pool,
+            config,
+            running: Arc::new(running),
         }
     }
+
+    /// Whether a run of the task `name` is in progress.
+    pub fn is_running(&self, name: &str) -> bool {
+        self.running_flag(name)
+            .is_some_and(|running| running.load(Ordering::Acquire))
+    }
+
+    /// The running flag of the task `name`, shared by its scheduled and manual runs.
+    pub(crate) fn running_flag(&self, name: &str) -> Option<Arc<AtomicBool>> {
+        self.running.get(name).cloned()
+    }
 }
 
-/// Processes the scheduled search for #megajoules tweets
-async fn process_megajoule_search() {
-    info!("Starting scheduled search for #megajoules tweets");
-    match search_tweets_with_hashtag("megajoules").await {
-        Ok(_) => {
-            info!("Scheduled search for #megajoules tweets completed successfully");
-        }
-        Err(e) => {
-            error!("Scheduled search for #megajoules tweets failed: {}", e);
+/// Searches for the enabled hashtags of the registry; counts the hashtags searched.
+///
+/// A failed search does not stop the others; the run fails with the last error.
+async fn process_hashtag_searches(pool: &PgPool) -> TaskResult {
+    let rules = get_hashtag_rules(pool).await?;
+    let mut searched = 0;
+    let mut last_error = None;
+    for rule in &rules {
+        info!(
+            "Starting scheduled search for #{} tweets ({})",
+            rule.tag,
+            rule.action.as_str()
+        );
+        match search_tweets_with_hashtag(pool, &rule.tag).await {
+            Ok(_) => {
+                info!(
+                    "Scheduled search for #{} tweets completed successfully",
+                    rule.tag
+                );
+                searched += 1;
+            }
+            Err(e) => {
+                error!("Scheduled search for #{} tweets failed: {}", rule.tag, e);
+                last_error = Some(e);
+            }
         }
     }
+    match last_error {
+        Some(e) => Err(e),
+        None => Ok(Some(searched)),
+    }
 }
 
-/// Processes scheduled checks for @reputest mentions and replies to vibe queries
-async fn process_mentions() {
+/// Checks for @reputest mentions and replies to vibe queries; counts the mentions found.
+async fn process_mentions(pool: &PgPool) -> TaskResult {
     debug!("Starting scheduled check for @reputest mentions");
-    match search_mentions().await {
-        Ok(mentions) => {
-            if mentions.is_empty() {
-                info!("No mentions found to reply to");
-                return;
-            }
+    let mentions = search_mentions(pool).await?;
+    if mentions.is_empty() {
+        info!("No mentions found to reply to");
+        return Ok(Some(0));
+    }
 
-            info!("Found {} mentions to reply to", mentions.len());
+    let found = mentions.len() as i64;
+    info!("Found {} mentions to reply to", found);
+
+    // Process each mention (following, category, trusted, mutuals and suggest queries take precedence over vibe query)
+    for (tweet_id, tweet_text, author_username, mentioned_user, created_at) in mentions {
+        if let Some(mentioned_username) = extract_mention_with_following(&tweet_text) {
+            process_following_query(
+                pool,
+                &tweet_id,
+                &tweet_text,
+                &author_username,
+                &mentioned_username,
+                &created_at,
+            )
+            .await;
+        } else if let Some((mentioned_username, category)) =
+            extract_mention_with_category(&tweet_text)
+        {
+            process_vibe_query(
+                pool,
+                &tweet_id,
+                &tweet_text,
+                &author_username,
+                &mentioned_username,
+                &created_at,
+                ScoreFilter::Category(&category),
+            )
+            .await;
+        } else if let Some(mentioned_username) = extract_mention_with_trusted(&tweet_text) {
+            process_vibe_query(
+                pool,
+                &tweet_id,
+                &tweet_text,
+                &author_username,
+                &mentioned_username,
+                &created_at,
+                ScoreFilter::Trusted,
+            )
+            .await;
+        } else if is_mutuals_request(&tweet_text) {
+            process_mutuals_request(pool, &tweet_id, &author_username, &created_at).await;
+        } else if is_suggest_request(&tweet_text) {
+            process_suggest_request(pool, &tweet_id, &author_username, &created_at).await;
+        } else if let Some(mentioned_username) = mentioned_user {
+            process_vibe_query(
+                pool,
+                &tweet_id,
+                &tweet_text,
+                &author_username,
+                &mentioned_username,
+                &created_at,
+                ScoreFilter::All,
+            )
+            .await;
+        } else if tweet_text.to_lowercase().contains("vibecount") {
+            process_vibecount_request(pool, &tweet_id, &tweet_text, &author_username, &created_at)
+                .await;
+        } else {
+            info!("Skipping general mention from @{} at {} - no vibecount request or specific vibe/following query", author_username, created_at);
+        }
+    }
 
-            // Get the database pool for user lookups and vibe checks
-            let pool = match crate::db::get_db_pool().await {
-                Ok(pool) => pool,
-                Err(e) => {
-                    error!("Failed to get database pool for mentions processing: {}", e);
-                    return;
-                }
-            };
+    info!("Scheduled check for mentions completed successfully");
+    Ok(Some(found))
+}
 
-            // Twitter config for looking up users when the query author is not yet in the DB
-            let mut config = match TwitterConfig::from_env(&pool).await {
-                Ok(c) => c,
-                Err(e) => {
-                    error!(
-                        "Failed to load Twitter config for mentions processing: {}",
-                        e
-                    );
-                    return;
-                }
-            };
-
-            // Process each mention (following query takes precedence over vibe query)
-            for (tweet_id, tweet_text, author_username, mentioned_user, created_at) in mentions {
-                if let Some(mentioned_username) = extract_mention_with_following(&tweet_text) {
-                    process_following_query(
-                        &pool,
-                        &mut config,
-                        &tweet_id,
-                        &tweet_text,
-                        &author_username,
-                        &mentioned_username,
-                        &created_at,
-                    )
-                    .await;
-                } else if let Some(mentioned_username) = mentioned_user {
-                    process_vibe_query(
-                        &pool,
-                        &mut config,
-                        &tweet_id,
-                        &tweet_text,
-                        &author_username,
-                        &mentioned_username,
-                        &created_at,
-                    )
-                    .await;
-                } else if tweet_text.to_lowercase().contains("vibecount") {
-                    process_vibecount_request(
-                        &pool,
-                        &tweet_id,
-                        &tweet_text,
-                        &author_username,
-                        &created_at,
-                    )
-                    .await;
-                } else {
-                    info!("Skipping general mention from @{} at {} - no vibecount request or specific vibe/following query", author_username, created_at);
-                }
-            }
+/// Refreshes the materialized views (degree 1-6 and combined view) and records timing metrics
+async fn process_materialized_view_refresh(pool: &PgPool) -> TaskResult {
+    refresh_materialized_views(pool).await?;
+    Ok(None)
+}
 
-            info!("Scheduled check for mentions completed successfully");
-        }
-        Err(e) => {
-            error!("Scheduled check for mentions failed: {}", e);
-        }
-    }
+/// Appends a graph statistics snapshot; best scheduled after the view refresh
+async fn process_graph_stats_snapshot(pool: &PgPool) -> TaskResult {
+    record_graph_stats(pool).await?;
+    Ok(None)
 }
 
-/// Processes materialized view refresh as the last step of the cronjob
-async fn process_materialized_view_refresh() {
-    info!("Starting materialized view refresh");
-    let pool = match crate::db::get_db_pool().await {
-        Ok(pool) => pool,
-        Err(e) => {
-            error!(
-                "Failed to get database pool for materialized view refresh: {}",
-                e
-            );
-            return;
-        }
-    };
+/// Recomputes known follower counts from the following table to repair any drift
+async fn process_follower_count_recompute(pool: &PgPool) -> TaskResult {
+    recompute_known_follower_counts(pool).await?;
+    Ok(None)
+}
 
-    match refresh_materialized_views(&pool).await {
-        Ok(_) => {
-            info!("Materialized view refresh completed successfully");
-        }
-        Err(e) => {
-            error!("Materialized view refresh failed: {}", e);
-        }
-    }
+/// Runs community detection over the good vibes graph and stores clusters; counts the clusters
+async fn process_cluster_detection(pool: &PgPool, seed: u64) -> TaskResult {
+    let count = run_cluster_detection(pool, seed).await?;
+    Ok(Some(count as i64))
+}
+
+/// Which paths a vibe score query counts.
+#[derive(Debug, Clone, Copy)]
+enum ScoreFilter<'a> {
+    /// Every path (the materialized views)
+    All,
+    /// Paths whose intermediaries the author follows ("@reputest @username trusted?")
+    Trusted,
+    /// Paths whose edges all carry the category ("@reputest vibes for @username in #dev")
+    Category(&'a str),
 }
 
 /// Processes a specific vibe score query (e.g., "@reputest @username?")
+///
+/// `filter` selects the paths counted: all of them, only those through accounts the
+/// author follows, or only those within a category.
 async fn process_vibe_query(
     pool: &PgPool,
-    config: &mut TwitterConfig,
     tweet_id: &str,
     _tweet_text: &str,
     author_username: &str,
     mentioned_username: &str,
     created_at: &str,
+    filter: ScoreFilter<'_>,
 ) {
+    let label = match filter {
+        ScoreFilter::All => "vibes".to_string(),
+        ScoreFilter::Trusted => "trusted vibes (via accounts you follow)".to_string(),
+        ScoreFilter::Category(category) => format!("#{} vibes", category),
+    };
+
     // First, check if this tweet has already been processed
     match has_vibe_request(pool, tweet_id).await {
         Ok(true) => {
@@ -234,7 +257,7 @@ async fn process_vibe_query(
         Ok(Some(id)) => id,
         Ok(None) => {
             if let Ok(Some((user_id, name, created_at_utc, follower_count))) =
-                lookup_user_by_username(config, pool, author_username).await
+                lookup_user_by_username(pool, author_username).await
             {
                 if let Err(e) = save_user(
                     pool,
@@ -270,10 +293,10 @@ async fn process_vibe_query(
                 mentioned_username
             );
             let reply_text = format!(
-                "Your vibes for {} are:\n1st degree: 0\n2nd degree: 0\n3rd degree: 0\n4th degree: 0\n5th degree: 0\n6th degree: 0",
-                mentioned_username
+                "Your {} for {} are:\n1st degree: 0\n2nd degree: 0\n3rd degree: 0\n4th degree: 0\n5th degree: 0\n6th degree: 0",
+                label, mentioned_username
             );
-            send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+            queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
             return;
         }
         Err(e) => {
@@ -286,20 +309,42 @@ async fn process_vibe_query(
     };
 
     // Calculate the vibe scores (degrees 1, 2, 3, 4, 5, and 6)
-    match tokio::try_join!(
-        get_vibe_score_one(pool, &author_user_id, &mentioned_user_id),
-        get_vibe_score_two(pool, &author_user_id, &mentioned_user_id),
-        get_vibe_score_three(pool, &author_user_id, &mentioned_user_id),
-        get_vibe_score_four(pool, &author_user_id, &mentioned_user_id),
-        get_vibe_score_five(pool, &author_user_id, &mentioned_user_id),
-        get_vibe_score_six(pool, &author_user_id, &mentioned_user_id)
-    ) {
-        Ok((score_one, score_two, score_three, score_four, score_five, score_six)) => {
-            let reply_text = format!(
-                "Your vibes for {} are:\n1st degree: {}\n2nd degree: {}\n3rd degree: {}\n4th degree: {}\n5th degree: {}\n6th degree: {}",
-                mentioned_username, score_one, score_two, score_three, score_four, score_five, score_six
+    let scores = match filter {
+        ScoreFilter::Trusted => {
+            get_trusted_vibe_scores(pool, &author_user_id, &mentioned_user_id).await
+        }
+        ScoreFilter::Category(category) => {
+            get_category_vibe_scores(pool, &author_user_id, &mentioned_user_id, category).await
+        }
+        ScoreFilter::All => tokio::try_join!(
+            get_vibe_score_one(pool, &author_user_id, &mentioned_user_id),
+            get_vibe_score_two(pool, &author_user_id, &mentioned_user_id),
+            get_vibe_score_three(pool, &author_user_id, &mentioned_user_id),
+            get_vibe_score_four(pool, &author_user_id, &mentioned_user_id),
+            get_vibe_score_five(pool, &author_user_id, &mentioned_user_id),
+            get_vibe_score_six(pool, &author_user_id, &mentioned_user_id)
+        )
+        .map(|(one, two, three, four, five, six)| vec![one, two, three, four, five, six]),
+    };
+
+    match scores {
+        Ok(scores) => {
+            let mut reply_text = format!(
+                "Your {} for {} are:\n1st degree: {}\n2nd degree: {}\n3rd degree: {}\n4th degree: {}\n5th degree: {}\n6th degree: {}",
+                label, mentioned_username, scores[0], scores[1], scores[2], scores[3], scores[4], scores[5]
             );
-            send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+            if let ScoreFilter::Trusted = filter {
+                // Without a stored following list every trusted path beyond degree 1 is empty
+                if let Ok(followed) = get_followed_ids(pool, &author_user_id).await {
+                    if followed.is_empty() {
+                        reply_text.push_str(&format!(
+                            "\n(No following list stored for you yet: ask \"@reputest @{} following?\")",
+                            author_username
+                        ));
+                    }
+                }
+            }
+            queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
         }
         Err(e) => {
             error!(
@@ -317,24 +362,7 @@ async fn reply_author_no_good_vibes(pool: &PgPool, tweet_id: &str, author_userna
         author_username
     );
     let reply_text = "You have not declared any good vibes yet.";
-
-    match reply_to_tweet(reply_text, tweet_id).await {
-        Ok(_) => {
-            info!(
-                "Successfully replied to vibe query from @{} (author has no good vibes)",
-                author_username
-            );
-            if let Err(e) = save_vibe_request(pool, tweet_id).await {
-                error!("Failed to save vibe request for tweet {}: {}", tweet_id, e);
-            }
-        }
-        Err(e) => {
-            error!(
-                "Failed to reply to vibe query from @{}: {}",
-                author_username, e
-            );
-        }
-    }
+    queue_reply_and_mark_processed(pool, reply_text, tweet_id, author_username).await;
 }
 
 /// Processes a vibecount request
@@ -373,7 +401,7 @@ async fn process_vibecount_request(
                 "Hello @{}! The current good vibes count is: {}",
                 author_username, vibes_count
             );
-            send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+            queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
         }
         Err(e) => {
             error!(
@@ -384,10 +412,160 @@ async fn process_vibecount_request(
     }
 }
 
+/// Maximum number of accounts listed in a reply to "@reputest mutuals?"
+const MUTUALS_REPLY_LIMIT: i64 = 5;
+
+/// Processes a mutuals request ("@reputest mutuals?"): replies with accounts that mutually
+/// follow the author but share no good vibes with them yet.
+async fn process_mutuals_request(
+    pool: &PgPool,
+    tweet_id: &str,
+    author_username: &str,
+    created_at: &str,
+) {
+    match has_vibe_request(pool, tweet_id).await {
+        Ok(true) => {
+            info!(
+                "Skipping mutuals request tweet {} from @{} (posted at {}) - already processed",
+                tweet_id, author_username, created_at
+            );
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!(
+                "Failed to check if tweet {} has been processed: {}",
+                tweet_id, e
+            );
+            return;
+        }
+    }
+
+    let no_following_reply = format!(
+        "No mutual follows without good vibes found. Ask \"@reputest @{} following?\" to store who you follow.",
+        author_username
+    );
+
+    let author_user_id = match get_user_id_by_username(pool, author_username).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            queue_reply_and_mark_processed(pool, &no_following_reply, tweet_id, author_username)
+                .await;
+            return;
+        }
+        Err(e) => {
+            error!("Failed to get user ID for @{}: {}", author_username, e);
+            return;
+        }
+    };
+
+    match get_mutual_follow_suggestions(pool, &author_user_id, MUTUALS_REPLY_LIMIT).await {
+        Ok(usernames) if usernames.is_empty() => {
+            queue_reply_and_mark_processed(pool, &no_following_reply, tweet_id, author_username)
+                .await;
+        }
+        Ok(usernames) => {
+            // Plain usernames (no @) so the reply does not notify the suggested accounts
+            let reply_text = format!(
+                "You follow each other but have no good vibes yet: {}",
+                usernames.join(", ")
+            );
+            queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+        }
+        Err(e) => {
+            error!(
+                "Failed to get mutual follow suggestions for @{}: {}",
+                author_username, e
+            );
+        }
+    }
+}
+
+/// Maximum number of accounts listed in a reply to "@reputest suggest"
+const SUGGEST_REPLY_LIMIT: usize = 3;
+
+/// Processes a suggest request ("@reputest suggest"): replies with the top recommended
+/// accounts to vibe and records them for conversion tracking.
+async fn process_suggest_request(
+    pool: &PgPool,
+    tweet_id: &str,
+    author_username: &str,
+    created_at: &str,
+) {
+    match has_vibe_request(pool, tweet_id).await {
+        Ok(true) => {
+            info!(
+                "Skipping suggest request tweet {} from @{} (posted at {}) - already processed",
+                tweet_id, author_username, created_at
+            );
+            return;
+        }
+        Ok(false) => {}
+        Err(e) => {
+            error!(
+                "Failed to check if tweet {} has been processed: {}",
+                tweet_id, e
+            );
+            return;
+        }
+    }
+
+    let author_user_id = match get_user_id_by_username(pool, author_username).await {
+        Ok(Some(id)) => id,
+        Ok(None) => {
+            reply_author_no_good_vibes(pool, tweet_id, author_username).await;
+            return;
+        }
+        Err(e) => {
+            error!("Failed to get user ID for @{}: {}", author_username, e);
+            return;
+        }
+    };
+
+    let suggestions = match recommend_for_user(pool, &author_user_id, SUGGEST_REPLY_LIMIT).await {
+        Ok(suggestions) => suggestions,
+        Err(e) => {
+            error!(
+                "Failed to compute suggestions for @{}: {}",
+                author_username, e
+            );
+            return;
+        }
+    };
+
+    if suggestions.is_empty() {
+        let reply_text =
+            "No suggestions yet: declare good vibes with #gmgv to grow your vibe paths.";
+        queue_reply_and_mark_processed(pool, reply_text, tweet_id, author_username).await;
+        return;
+    }
+
+    // Plain usernames (no @) so the reply does not notify the suggested accounts
+    let lines: Vec<String> = suggestions
+        .iter()
+        .map(|s| {
+            format!(
+                "{} (2nd: {}, 3rd: {}, follow overlap: {})",
+                s.username, s.degree_two_paths, s.degree_three_paths, s.follow_overlap
+            )
+        })
+        .collect();
+    let reply_text = format!("Accounts you might vibe:\n{}", lines.join("\n"));
+    queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+
+    if let Err(e) =
+        record_suggestions(pool, &author_user_id, &suggestions, SuggestionSource::Bot).await
+    {
+        error!(
+            "Failed to record suggestions for @{}: {}",
+            author_username, e
+        );
+    }
+}
+
 /// Processes a following query (e.g., "@reputest @username following?")
 async fn process_following_query(
     pool: &PgPool,
-    config: &mut TwitterConfig,
     tweet_id: &str,
     _tweet_text: &str,
     author_username: &str,
@@ -417,7 +595,7 @@ async fn process_following_query(
         Ok(Some(id)) => id,
         Ok(None) => {
             if let Ok(Some((user_id, name, created_at_utc, follower_count))) =
-                lookup_user_by_username(config, pool, mentioned_username).await
+                lookup_user_by_username(pool, mentioned_username).await
             {
                 if let Err(e) = save_user(
                     pool,
@@ -441,7 +619,7 @@ async fn process_following_query(
                     mentioned_username
                 );
                 let reply_text = format!("User @{} not found.", mentioned_username);
-                send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+                queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
                 return;
             }
         }
@@ -452,18 +630,27 @@ async fn process_following_query(
     };
 
     // Fetch following list via API
-    let followed_users = match fetch_user_following(config, pool, &follower_user_id).await {
+    let followed_users = match fetch_user_following(pool, &follower_user_id).await {
         Ok(users) => users,
-        Err(e) => {
-            error!(
-                "Failed to fetch following list for @{}: {}",
+        Err(e) if e.is_twitter_status(403) => {
+            info!(
+                "Following list for @{} is not accessible: {}",
                 mentioned_username, e
             );
             let reply_text = format!(
                 "Could not fetch @{}'s following list (account may be protected or suspended).",
                 mentioned_username
             );
-            send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+            queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+            return;
+        }
+        Err(e) => {
+            // Rate limits, network and database errors are transient: leave the tweet
+            // unprocessed so the next run retries it
+            error!(
+                "Failed to fetch following list for @{}: {}",
+                mentioned_username, e
+            );
             return;
         }
     };
@@ -475,16 +662,11 @@ async fn process_following_query(
         if followed.id == follower_user_id {
             continue; // Skip self-follow
         }
+        // known_follower_count of the followed user is maintained by a trigger on insert
         match save_following(pool, &follower_user_id, &followed.id, now).await {
             Ok(inserted) => {
                 if inserted {
                     new_count += 1;
-                    if let Err(e) = increment_follower_count(pool, &followed.id).await {
-                        error!(
-                            "Failed to increment follower_count for {}: {}",
-                            followed.id, e
-                        );
-                    }
                 }
             }
             Err(e) => {
@@ -502,11 +684,13 @@ async fn process_following_query(
         mentioned_username,
         new_count
     );
-    send_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
+    queue_reply_and_mark_processed(pool, &reply_text, tweet_id, author_username).await;
 }
 
-/// Sends a reply to a tweet and marks it as processed
-async fn send_reply_and_mark_processed(
+/// Queues a reply to a tweet for the reply worker and marks the tweet as processed.
+///
+/// If the reply cannot be queued the tweet is left unprocessed, so the next run retries it.
+async fn queue_reply_and_mark_processed(
     pool: &PgPool,
     reply_text: &str,
     tweet_id: &str,
@@ -514,45 +698,257 @@ async fn send_reply_and_mark_processed(
 ) {
     // SECURITY: Sanitize text before logging to prevent log injection
     info!(
-        "Replying to tweet {} with: {}",
+        "Queueing reply to tweet {}: {}",
         tweet_id,
         sanitize_for_logging(reply_text, 150)
     );
 
-    match reply_to_tweet(reply_text, tweet_id).await {
-        Ok(_) => {
-            info!("Successfully replied to request from @{}", author_username);
-            // Mark this tweet as processed
+    match enqueue_reply(pool, tweet_id, reply_text).await {
+        Ok(queued) => {
+            if !queued {
+                info!("Reply to tweet {} was already queued", tweet_id);
+            }
+            // Mark this tweet as processed; the queue takes care of delivery
             if let Err(e) = save_vibe_request(pool, tweet_id).await {
                 error!("Failed to save vibe request for tweet {}: {}", tweet_id, e);
             }
         }
         Err(e) => {
             error!(
-                "Failed to reply to request from @{}: {}",
+                "Failed to queue reply to request from @{}: {}",
                 author_username, e
             );
         }
     }
 }
 
-pub async fn start_gmgv_cronjob() -> Result<JobScheduler, Box<dyn std::error::Error + Send + Sync>>
-{
+/// Sends due replies from the outbound reply queue; counts the replies sent
+async fn process_outbound_replies(pool: &PgPool, config: &AppConfig) -> TaskResult {
+    let summary = drain_outbound_replies(pool, config).await?;
+    if summary != DrainSummary::default() {
+        info!(
+            "Reply worker: {} sent, {} to retry, {} dead-lettered",
+            summary.sent, summary.retried, summary.dead
+        );
+    }
+    Ok(Some(summary.sent as i64))
+}
+
+/// What a scheduled task returns: the number of things it handled, if it counts any.
+type TaskResult = Result<Option<i64>, ReputestError>;
+
+/// The scheduled tasks: name (in `job_runs` and the `CRON_<NAME>_*` variables) and default schedule.
+///
+/// The 5-minute tasks are staggered by a minute so that, as when they ran in one job, the
+/// views are refreshed after the searches and the stats snapshot is taken after the refresh.
+pub const CRON_TASKS: &[(&str, &str)] = &[
+    ("hashtag_search", "0 0/5 * * * * *"),
+    ("mentions", "0 1/5 * * * * *"),
+    ("view_refresh", "0 2/5 * * * * *"),
+    ("graph_stats", "0 3/5 * * * * *"),
+    ("outbound_replies", "0 * * * * * *"),
+    ("follower_recount", "0 30 3 * * * *"),
+    ("cluster_detection", "0 15 * * * * *"),
+];
+
+/// The tasks that read and answer tweets: scheduled runs of these are skipped while the bot is paused.
+pub const BOT_TASKS: &[&str] = &["hashtag_search", "mentions", "outbound_replies"];
+
+/// Whether `name` is one of the [`CRON_TASKS`].
+pub fn is_cron_task(name: &str) -> bool {
+    CRON_TASKS.iter().any(|&(task, _)| task == name)
+}
+
+/// Runs the body of the scheduled task `name`.
+async fn run_task_body(name: &str, ctx: &JobContext) -> TaskResult {
+    let pool = &ctx.pool;
+    match name {
+        "hashtag_search" => process_hashtag_searches(pool).await,
+        "mentions" => process_mentions(pool).await,
+        "view_refresh" => process_materialized_view_refresh(pool).await,
+        "graph_stats" => process_graph_stats_snapshot(pool).await,
+        "outbound_replies" => process_outbound_replies(pool, &ctx.config).await,
+        "follower_recount" => process_follower_count_recompute(pool).await,
+        "cluster_detection" => process_cluster_detection(pool, ctx.config.cluster_seed).await,
+        other => Err(ReputestError::Config(format!(
+            "unknown scheduled task {}",
+            other
+        ))),
+    }
+}
+
+/// Clears a task's running flag when its run ends, even if the run panics.
+struct RunningGuard(Arc<AtomicBool>);
+
+impl Drop for RunningGuard {
+    fn drop(&mut self) {
+        self.0.store(false, Ordering::Release);
+    }
+}
+
+/// Runs one scheduled task, unless its previous run is still in progress, and records the run in `job_runs`.
+///
+/// `triggered_by` is the admin who started the run from `/admin`, or `None` for a
+/// scheduled run. Scheduled runs of the [`BOT_TASKS`] are skipped while the bot is paused;
+/// manual runs are not. Failing to write `job_runs` is logged but does not stop the task.
+pub(crate) async fn run_task(ctx: &JobContext, name: &str, triggered_by: Option<&str>) {
+    if triggered_by.is_none() && BOT_TASKS.contains(&name) {
+        match get_bot_paused(&ctx.pool).await {
+            Ok(true) => {
+                info!("Skipping scheduled task {}: the bot is paused", name);
+                if let Err(e) =
+                    record_skipped_job_run(&ctx.pool, name, JobOutcome::Paused, None).await
+                {
+                    error!("Failed to record paused run of {}: {}", name, e);
+                }
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => error!("Failed to check whether the bot is paused: {}", e),
+        }
+    }
+
+    let running = ctx.running_flag(name).unwrap_or_default();
+    if running.swap(true, Ordering::AcqRel) {
+        warn!(
+            "Skipping task {}: the previous run is still in progress",
+            name
+        );
+        if let Err(e) =
+            record_skipped_job_run(&ctx.pool, name, JobOutcome::Skipped, triggered_by).await
+        {
+            error!("Failed to record skipped run of {}: {}", name, e);
+        }
+        return;
+    }
+    let _guard = RunningGuard(running);
+
+    let run_id = match start_job_run(&ctx.pool, name, triggered_by).await {
+        Ok(id) => Some(id),
+        Err(e) => {
+            error!("Failed to record start of {}: {}", name, e);
+            None
+        }
+    };
+    match triggered_by {
+        Some(username) => info!("Starting task {} (triggered by @{})", name, username),
+        None => info!("Starting scheduled task {}", name),
+    }
+
+    let (outcome, items, error_text) = match run_task_body(name, ctx).await {
+        Ok(items) => {
+            info!(
+                "Scheduled task {} completed successfully (items: {:?})",
+                name, items
+            );
+            (JobOutcome::Succeeded, items, None)
+        }
+        Err(e) => {
+            error!("Scheduled task {} failed: {}", name, e);
+            (JobOutcome::Failed, None, Some(e.to_string()))
+        }
+    };
+    if let Some(id) = run_id {
+        if let Err(e) = finish_job_run(&ctx.pool, id, outcome, items, error_text.as_deref()).await {
+            error!("Failed to record end of {}: {}", name, e);
+        }
+    }
+}
+
+/// Creates the job scheduler with one job per enabled task of [`CRON_TASKS`].
+///
+/// Each task has its own cron expression (with seconds) and enable flag, from
+/// `CRON_<TASK>_SCHEDULE` and `CRON_<TASK>_ENABLED` in the configuration of `ctx` (see
+/// [`crate::config::CronTaskConfig`]):
+///
+/// - `hashtag_search`: searches the enabled hashtags of the registry (every 5 minutes)
+/// - `mentions`: answers @reputest queries (every 5 minutes), such as:
+///    - Specific vibe score queries (e.g., "@reputest @username?")
+///    - Trusted vibe score queries through followed accounts (e.g., "@reputest @username trusted?")
+///    - Vibe score queries within a category (e.g., "@reputest vibes for @username in #dev")
+///    - Requests for mutual follows without vibes ("@reputest mutuals?")
+///    - Requests for accounts to vibe ("@reputest suggest")
+///    - General requests for the total vibes count (messages containing "vibecount")
+/// - `view_refresh`: refreshes the materialized views and records timing metrics (every 5 minutes)
+/// - `graph_stats`: appends a graph statistics snapshot (every 5 minutes, see `crate::stats`)
+/// - `outbound_replies`: sends due replies from the reply queue (every minute, see `crate::replies`)
+/// - `follower_recount`: recounts known followers to repair drift (daily at 03:30)
+/// - `cluster_detection`: detects communities (hourly)
+///
+/// A run is skipped while the previous run of the same task (scheduled or started from
+/// `/admin`) is still in progress, and runs of `hashtag_search`, `mentions` and
+/// `outbound_replies` are skipped while the bot is paused. Every run, including skipped
+/// ones, is recorded in `job_runs`.
+///
+/// # Parameters
+///
+/// - `ctx`: Shared state handed to every job (the application's connection pool and
+///   configuration, and the running flags of the tasks)
+///
+/// # Returns
+///
+/// - `Ok(JobScheduler)`: The configured job scheduler
+/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If there's an error creating or configuring the scheduler
+///
+/// # Example
+///
+/// ```rust,no_run
+/// use reputest::{config::AppConfig, db::get_db_pool, start_gmgv_cronjob, JobContext};
+///
+/// #[tokio::main]
+/// async fn main() {
+///     let pool = get_db_pool().await.unwrap();
+///     let ctx = JobContext::new(pool, AppConfig::current());
+///     let scheduler = start_gmgv_cronjob(ctx).await.unwrap();
+///     scheduler.start().await.unwrap();
+///     
+///     // Keep the scheduler running
+///     tokio::signal::ctrl_c().await.unwrap();
+/// }
+/// ```
+///
+/// # Errors
+///
+/// This function can fail if the job scheduler cannot be created or a job cannot be
+/// added. A task with an invalid cron expression is logged and left out.
+pub async fn start_gmgv_cronjob(
+    ctx: JobContext,
+) -> Result<JobScheduler, Box<dyn std::error::Error + Send + Sync>> {
     let sched = JobScheduler::new().await?;
 
-    // Create a job that runs every 5 minutes
-    sched
-        .add(Job::new_async("0 0/5 * * * * *", |_uuid, _l| {
-            Box::pin(async {
-                process_hashtag_search().await; // #gmgv
-                process_megajoule_search().await; // #megajoules
-                process_mentions().await;
-                process_materialized_view_refresh().await;
+    for (name, config) in ctx.config.cron.clone() {
+        if !config.enabled {
+            info!("Scheduled task {} is disabled", name);
+            continue;
+        }
+
+        let job_ctx = ctx.clone();
+        let job = Job::new_async(config.schedule.as_str(), move |_uuid, _l| {
+            let ctx = job_ctx.clone();
+            Box::pin(async move {
+                run_task(&ctx, name, None).await;
             })
-        })?)
-        .await?;
+        });
+        match job {
+            Ok(job) => {
+                sched.add(job).await?;
+                info!(
+                    "Scheduled task {} registered with schedule \"{}\"",
+                    name, config.schedule
+                );
+            }
+            Err(e) => {
+                error!(
+                    "Invalid schedule \"{}\" for task {} (CRON_{}_SCHEDULE), task not registered: {}",
+                    config.schedule,
+                    name,
+                    name.to_ascii_uppercase(),
+                    e
+                );
+            }
+        }
+    }
 
-    info!("Cronjob scheduler configured to search for #gmgv and #megajoules tweets (megajoules require @reputest), process vibe queries, and refresh materialized views every 5 minutes");
     Ok(sched)
 }
 
@@ -570,18 +966,21 @@ pub async fn start_gmgv_cronjob() -> Result<JobScheduler, Box<dyn std::error::Er
 /// # Example
 ///
 /// ```rust,no_run
-/// use reputest::run_gmgv_cronjob;
+/// use reputest::{config::AppConfig, db::get_db_pool, run_gmgv_cronjob, JobContext};
 ///
 /// #[tokio::main]
 /// async fn main() {
-///     if let Err(e) = run_gmgv_cronjob().await {
+///     let pool = get_db_pool().await.unwrap();
+///     if let Err(e) = run_gmgv_cronjob(JobContext::new(pool, AppConfig::current())).await {
 ///         eprintln!("Cronjob failed: {}", e);
 ///     }
 /// }
 /// ```
 #[allow(dead_code)]
-pub async fn run_gmgv_cronjob() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    let mut sched = start_gmgv_cronjob().await?;
+pub async fn run_gmgv_cronjob(
+    ctx: JobContext,
+) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    let mut sched = start_gmgv_cronjob(ctx).await?;
     sched.start().await?;
 
     info!("Cronjob scheduler started successfully");
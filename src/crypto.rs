@@ -2,170 +2,807 @@
 //!
 //! This module provides encryption and decryption functions for sensitive tokens
 //! stored in the database using AES-256-GCM authenticated encryption.
+//!
+//! Tokens are stored in a versioned envelope, `v2:<key id>:<hex nonce || ciphertext>`,
+//! where the key id is a fingerprint of the key that sealed them. Several keys can be
+//! configured at once (see [`Keyring`]), so the key can be rotated without losing access
+//! to stored tokens: the `rotate_keys` binary re-encrypts them with the current key.
+//!
+//! Each token is sealed with the place it is stored in (table, row and token kind, see
+//! [`TokenContext`]) as associated data, so a ciphertext copied into another row or column
+//! fails to decrypt. Tokens written before that (`v1` envelopes and bare hex) are bound to
+//! their row by the same re-encryption, which the server also runs at startup.
+//!
+//! The keys come from a [`KeyProvider`], chosen with `TOKEN_ENCRYPTION_KEY_PROVIDER`: the
+//! environment (the default), a key file readable only by its owner, or a passphrase
+//! stretched with Argon2id. The server loads them once at startup (see
+//! [`validate_encryption_config`]).
 
 use aes_gcm::{
-    aead::{generic_array::typenum::U12, Aead, KeyInit},
+    aead::{generic_array::typenum::U12, Aead, KeyInit, Payload},
     Aes256Gcm, Nonce,
 };
-use log::debug;
-use std::env;
+use argon2::{Algorithm, Argon2, Params, Version};
+use log::{debug, info};
+use sha2::{Digest, Sha256};
+use std::path::PathBuf;
+use std::sync::OnceLock;
+
+use crate::config::setting;
+use crate::error::ReputestError;
 
 /// The length of the nonce in bytes (96 bits for AES-GCM)
 const NONCE_LENGTH: usize = 12;
 
-/// Gets the encryption key from environment variable.
-///
-/// The key must be exactly 32 bytes (256 bits) encoded as a 64-character hex string.
-///
-/// # Returns
-///
-/// - `Ok([u8; 32])`: The 32-byte encryption key
-/// - `Err`: If the key is missing, invalid hex, or wrong length
-fn get_encryption_key() -> Result<[u8; 32], Box<dyn std::error::Error + Send + Sync>> {
-    let key_hex = env::var("TOKEN_ENCRYPTION_KEY").map_err(|_| {
-        "TOKEN_ENCRYPTION_KEY environment variable is not set. Generate a 32-byte key with: openssl rand -hex 32"
-    })?;
+/// Version prefix of the ciphertext envelope
+const ENVELOPE_VERSION: &str = "v2";
+
+/// Version prefix of envelopes written before tokens were bound to their row
+const UNBOUND_ENVELOPE_VERSION: &str = "v1";
+
+/// Memory cost of the passphrase key derivation, in KiB
+const KDF_MEMORY_KIB: u32 = 64 * 1024;
+
+/// Iterations of the passphrase key derivation
+const KDF_ITERATIONS: u32 = 3;
+
+/// Shortest accepted `TOKEN_ENCRYPTION_KDF_SALT`, in bytes
+const MIN_KDF_SALT_LENGTH: usize = 16;
+
+/// Shortest accepted passphrase, in characters
+const MIN_PASSPHRASE_LENGTH: usize = 12;
+
+/// The keyring loaded by [`validate_encryption_config`] at startup
+static KEYRING: OnceLock<Keyring> = OnceLock::new();
 
-    let key_bytes = hex::decode(&key_hex).map_err(|e| {
+/// Which of a user's OAuth tokens a ciphertext holds.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenKind {
+    Access,
+    Refresh,
+}
+
+impl TokenKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenKind::Access => "access_token",
+            TokenKind::Refresh => "refresh_token",
+        }
+    }
+}
+
+/// Where a token is stored: the associated data its ciphertext is bound to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TokenContext {
+    table: &'static str,
+    row_id: String,
+    kind: TokenKind,
+}
+
+impl TokenContext {
+    /// The token of `kind` in row `row_id` of `table`.
+    pub fn new(table: &'static str, row_id: impl ToString, kind: TokenKind) -> Self {
+        TokenContext {
+            table,
+            row_id: row_id.to_string(),
+            kind,
+        }
+    }
+
+    fn aad(&self) -> String {
         format!(
-            "TOKEN_ENCRYPTION_KEY is not valid hex: {}. Generate a key with: openssl rand -hex 32",
-            e
+            "reputest/{}/{}/{}",
+            self.table,
+            self.row_id,
+            self.kind.as_str()
         )
-    })?;
+    }
+}
 
-    if key_bytes.len() != 32 {
-        return Err(format!(
-            "TOKEN_ENCRYPTION_KEY must be exactly 32 bytes (64 hex chars), got {} bytes",
-            key_bytes.len()
-        )
-        .into());
+/// An AES-256 key and its id.
+#[derive(Clone)]
+struct EncryptionKey {
+    /// First 8 hex characters of the SHA-256 of the key
+    id: String,
+    bytes: [u8; 32],
+}
+
+impl EncryptionKey {
+    fn new(bytes: [u8; 32]) -> Self {
+        let id = hex::encode(Sha256::digest(bytes))[..8].to_string();
+        EncryptionKey { id, bytes }
     }
 
-    let mut key: [u8; 32] = [0u8; 32];
-    key.copy_from_slice(&key_bytes);
-    Ok(key)
+    fn cipher(&self) -> Result<Aes256Gcm, ReputestError> {
+        Aes256Gcm::new_from_slice(&self.bytes)
+            .map_err(|e| ReputestError::Crypto(format!("Invalid key length: {}", e)))
+    }
 }
 
-/// Encrypts a token using AES-256-GCM.
-///
-/// The function generates a random nonce and prepends it to the ciphertext.
-/// The output format is: nonce (12 bytes) || ciphertext || auth_tag
+/// The keys tokens are encrypted and decrypted with.
 ///
-/// # Parameters
-///
-/// - `plaintext`: The token to encrypt
-///
-/// # Returns
-///
-/// - `Ok(String)`: The hex-encoded encrypted token (nonce + ciphertext)
-/// - `Err`: If encryption fails or the key is not configured
-pub fn encrypt_token(plaintext: &str) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let key = get_encryption_key()?;
-    let cipher = Aes256Gcm::new_from_slice(&key)?;
+/// New ciphertexts are always sealed with the current key. Ciphertexts sealed with one of
+/// the previous keys still decrypt, until `rotate_keys` has re-encrypted them. Where the
+/// keys come from is up to the [`KeyProvider`].
+#[derive(Clone)]
+pub struct Keyring {
+    current: EncryptionKey,
+    previous: Vec<EncryptionKey>,
+}
+
+impl std::fmt::Debug for Keyring {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let previous: Vec<&str> = self.previous.iter().map(|key| key.id.as_str()).collect();
+        f.debug_struct("Keyring")
+            .field("current", &self.current.id)
+            .field("previous", &previous)
+            .finish()
+    }
+}
+
+impl Keyring {
+    /// Creates a keyring from raw 32-byte keys.
+    pub fn new(current: [u8; 32], previous: Vec<[u8; 32]>) -> Self {
+        Keyring {
+            current: EncryptionKey::new(current),
+            previous: previous.into_iter().map(EncryptionKey::new).collect(),
+        }
+    }
+
+    /// Id of the key new ciphertexts are sealed with.
+    pub fn current_key_id(&self) -> &str {
+        &self.current.id
+    }
+
+    /// Encrypts a token with the current key, bound to where it will be stored.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(String)`: The envelope `v2:<key id>:<hex nonce || ciphertext || auth_tag>`
+    /// - `Err(ReputestError::Crypto)`: If encryption fails
+    pub fn encrypt(
+        &self,
+        plaintext: &str,
+        context: &TokenContext,
+    ) -> Result<String, ReputestError> {
+        let payload = encrypt_with(&self.current, plaintext, context.aad().as_bytes())?;
+        Ok(format!(
+            "{}:{}:{}",
+            ENVELOPE_VERSION, self.current.id, payload
+        ))
+    }
+
+    /// Encrypts a token without binding it to a row, in a `v1` envelope.
+    ///
+    /// For tokens inserted by hand (see the `encrypt_token` binary), whose row id is not
+    /// known yet. [`Keyring::decrypt`] rejects them until [`Keyring::reencrypt`] has bound
+    /// them to their row.
+    #[allow(dead_code)] // Used by the encrypt_token binary
+    pub fn encrypt_unbound(&self, plaintext: &str) -> Result<String, ReputestError> {
+        let payload = encrypt_with(&self.current, plaintext, b"")?;
+        Ok(format!(
+            "{}:{}:{}",
+            UNBOUND_ENVELOPE_VERSION, self.current.id, payload
+        ))
+    }
+
+    /// Decrypts a token sealed with any key of the keyring for `context`.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(String)`: The decrypted token
+    /// - `Err(ReputestError::Crypto)`: If the key id is unknown, the token was sealed for
+    ///   another row or token kind, the data is corrupted, or the token is not bound yet
+    pub fn decrypt(&self, stored: &str, context: &TokenContext) -> Result<String, ReputestError> {
+        match parse_envelope(stored)? {
+            Envelope::Bound { key_id, payload } => {
+                decrypt_with(self.key(key_id)?, payload, context.aad().as_bytes())
+            }
+            Envelope::Unbound { .. } | Envelope::Legacy(_) => Err(ReputestError::Crypto(
+                "Token is not bound to its row yet - run rotate_keys or restart the server"
+                    .to_string(),
+            )),
+        }
+    }
+
+    /// Re-encrypts a stored token with the current key, bound to `context`.
+    ///
+    /// Unlike [`Keyring::decrypt`], this also accepts tokens that are not bound yet (`v1`
+    /// envelopes and bare hex, which is tried with every key), so it is how they are
+    /// migrated.
+    pub fn reencrypt(&self, stored: &str, context: &TokenContext) -> Result<String, ReputestError> {
+        let token = match parse_envelope(stored)? {
+            Envelope::Bound { .. } => self.decrypt(stored, context)?,
+            Envelope::Unbound { key_id, payload } => decrypt_with(self.key(key_id)?, payload, b"")?,
+            Envelope::Legacy(payload) => std::iter::once(&self.current)
+                .chain(&self.previous)
+                .find_map(|key| decrypt_with(key, payload, b"").ok())
+                .ok_or_else(|| {
+                    ReputestError::Crypto(
+                        "Decryption failed - wrong key or corrupted data".to_string(),
+                    )
+                })?,
+        };
+        self.encrypt(&token, context)
+    }
+
+    /// Whether a stored token is not sealed with the current key, or not bound to its row,
+    /// so `rotate_keys` should re-encrypt it.
+    pub fn needs_reencryption(&self, stored: &str) -> bool {
+        !matches!(
+            parse_envelope(stored),
+            Ok(Envelope::Bound { key_id, .. }) if key_id == self.current.id
+        )
+    }
+
+    fn key(&self, id: &str) -> Result<&EncryptionKey, ReputestError> {
+        std::iter::once(&self.current)
+            .chain(&self.previous)
+            .find(|key| key.id == id)
+            .ok_or_else(|| {
+                ReputestError::Crypto(format!(
+                    "Token was encrypted with unknown key {} - add it to TOKEN_ENCRYPTION_PREVIOUS_KEYS",
+                    id
+                ))
+            })
+    }
+}
+
+/// A stored token, split into its parts.
+enum Envelope<'a> {
+    /// `v2:<key id>:<hex>`, sealed with its [`TokenContext`] as associated data
+    Bound { key_id: &'a str, payload: &'a str },
+    /// `v1:<key id>:<hex>`, written before tokens were bound to their row
+    Unbound { key_id: &'a str, payload: &'a str },
+    /// Bare hex, written before the envelope was versioned
+    Legacy(&'a str),
+}
+
+fn parse_envelope(stored: &str) -> Result<Envelope<'_>, ReputestError> {
+    let mut parts = stored.splitn(3, ':');
+    match (parts.next(), parts.next(), parts.next()) {
+        (Some(payload), None, None) => Ok(Envelope::Legacy(payload)),
+        (Some(ENVELOPE_VERSION), Some(key_id), Some(payload)) => {
+            Ok(Envelope::Bound { key_id, payload })
+        }
+        (Some(UNBOUND_ENVELOPE_VERSION), Some(key_id), Some(payload)) => {
+            Ok(Envelope::Unbound { key_id, payload })
+        }
+        (Some(version), _, _) => Err(ReputestError::Crypto(format!(
+            "Unsupported encrypted token version '{}'",
+            version
+        ))),
+        _ => Err(ReputestError::Crypto(
+            "Malformed encrypted token".to_string(),
+        )),
+    }
+}
+
+/// Encrypts a token with one key, returning `hex(nonce || ciphertext || auth_tag)`.
+fn encrypt_with(key: &EncryptionKey, plaintext: &str, aad: &[u8]) -> Result<String, ReputestError> {
+    let cipher = key.cipher()?;
 
     // Generate a random nonce
     let mut nonce_bytes = [0u8; NONCE_LENGTH];
     getrandom::getrandom(&mut nonce_bytes)
-        .map_err(|e| format!("Failed to generate random nonce: {}", e))?;
+        .map_err(|e| ReputestError::Crypto(format!("Failed to generate random nonce: {}", e)))?;
     let nonce: Nonce<U12> = nonce_bytes.into();
 
     // Encrypt the plaintext
     let ciphertext = cipher
-        .encrypt(&nonce, plaintext.as_bytes())
-        .map_err(|e| format!("Encryption failed: {}", e))?;
+        .encrypt(
+            &nonce,
+            Payload {
+                msg: plaintext.as_bytes(),
+                aad,
+            },
+        )
+        .map_err(|e| ReputestError::Crypto(format!("Encryption failed: {}", e)))?;
 
     // Prepend nonce to ciphertext and encode as hex
     let mut result = Vec::with_capacity(NONCE_LENGTH + ciphertext.len());
     result.extend_from_slice(&nonce_bytes);
     result.extend_from_slice(&ciphertext);
 
-    debug!("Token encrypted successfully");
+    debug!("Token encrypted successfully with key {}", key.id);
     Ok(hex::encode(result))
 }
 
-/// Decrypts a token that was encrypted with `encrypt_token`.
-///
-/// Expects the input to be a hex-encoded string containing: nonce (12 bytes) || ciphertext || auth_tag
-///
-/// # Parameters
-///
-/// - `encrypted_hex`: The hex-encoded encrypted token
-///
-/// # Returns
-///
-/// - `Ok(String)`: The decrypted token
-/// - `Err`: If decryption fails, the key is wrong, or the data is corrupted
-pub fn decrypt_token(
-    encrypted_hex: &str,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
-    let key = get_encryption_key()?;
-    let cipher = Aes256Gcm::new_from_slice(&key)?;
+/// Decrypts `hex(nonce || ciphertext || auth_tag)` with one key.
+fn decrypt_with(key: &EncryptionKey, payload: &str, aad: &[u8]) -> Result<String, ReputestError> {
+    let cipher = key.cipher()?;
 
     // Decode from hex
-    let encrypted_bytes =
-        hex::decode(encrypted_hex).map_err(|e| format!("Invalid hex in encrypted token: {}", e))?;
+    let encrypted_bytes = hex::decode(payload)
+        .map_err(|e| ReputestError::Crypto(format!("Invalid hex in encrypted token: {}", e)))?;
 
     if encrypted_bytes.len() < NONCE_LENGTH {
-        return Err("Encrypted token is too short".into());
+        return Err(ReputestError::Crypto(
+            "Encrypted token is too short".to_string(),
+        ));
     }
 
     // Extract nonce and ciphertext
     let (nonce_bytes, ciphertext) = encrypted_bytes.split_at(NONCE_LENGTH);
-    let nonce_array: [u8; NONCE_LENGTH] =
-        nonce_bytes.try_into().map_err(|_| "Invalid nonce length")?;
+    let nonce_array: [u8; NONCE_LENGTH] = nonce_bytes
+        .try_into()
+        .map_err(|_| ReputestError::Crypto("Invalid nonce length".to_string()))?;
     let nonce: Nonce<U12> = nonce_array.into();
 
     // Decrypt
     let plaintext = cipher
-        .decrypt(&nonce, ciphertext)
-        .map_err(|_| "Decryption failed - wrong key or corrupted data")?;
+        .decrypt(
+            &nonce,
+            Payload {
+                msg: ciphertext,
+                aad,
+            },
+        )
+        .map_err(|_| {
+            ReputestError::Crypto(
+                "Decryption failed - wrong key, another row's token or corrupted data".to_string(),
+            )
+        })?;
 
     let token = String::from_utf8(plaintext)
-        .map_err(|e| format!("Decrypted token is not valid UTF-8: {}", e))?;
+        .map_err(|e| ReputestError::Crypto(format!("Decrypted token is not valid UTF-8: {}", e)))?;
 
-    debug!("Token decrypted successfully");
+    debug!("Token decrypted successfully with key {}", key.id);
     Ok(token)
 }
 
+/// A source of the token encryption keys.
+///
+/// [`key_provider`] picks one from `TOKEN_ENCRYPTION_KEY_PROVIDER`. Only
+/// [`EnvKeyProvider`] keeps the key in the process environment, where child processes
+/// inherit it and crash dumps capture it.
+pub trait KeyProvider: Send + Sync {
+    /// Where the keys come from, for logs. Never includes key material.
+    fn describe(&self) -> String;
+
+    /// Whether the settings the provider needs are present, without loading the keys.
+    fn is_configured(&self) -> bool;
+
+    /// Loads the current key and the previous keys.
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Keyring)`: The keys
+    /// - `Err(ReputestError::Config)`: If a key is missing or invalid, or a secret file is
+    ///   readable by other users
+    fn load(&self) -> Result<Keyring, ReputestError>;
+}
+
+/// Reads `TOKEN_ENCRYPTION_KEY` and the comma-separated `TOKEN_ENCRYPTION_PREVIOUS_KEYS`,
+/// both 64 hex characters per key.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct EnvKeyProvider;
+
+impl KeyProvider for EnvKeyProvider {
+    fn describe(&self) -> String {
+        "environment variable TOKEN_ENCRYPTION_KEY".to_string()
+    }
+
+    fn is_configured(&self) -> bool {
+        setting("TOKEN_ENCRYPTION_KEY").is_some()
+    }
+
+    fn load(&self) -> Result<Keyring, ReputestError> {
+        let key_hex = setting("TOKEN_ENCRYPTION_KEY").ok_or_else(|| {
+            ReputestError::Config("TOKEN_ENCRYPTION_KEY environment variable is not set. Generate a 32-byte key with: openssl rand -hex 32".to_string())
+        })?;
+        let current = parse_key("TOKEN_ENCRYPTION_KEY", &key_hex)?;
+        Ok(Keyring::new(current, previous_keys_from_env()?))
+    }
+}
+
+/// Reads the keys from a file, such as a mounted secret, one hex key per line.
+///
+/// The first key is the current one and any further keys are previous keys. Blank lines
+/// and lines starting with `#` are ignored. On Unix the file must not be accessible by
+/// group or other users.
+#[derive(Debug, Clone)]
+pub struct FileKeyProvider {
+    pub path: PathBuf,
+}
+
+impl FileKeyProvider {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        FileKeyProvider { path: path.into() }
+    }
+
+    /// Reads the path from `TOKEN_ENCRYPTION_KEY_FILE`.
+    pub fn from_env() -> Result<Self, ReputestError> {
+        setting("TOKEN_ENCRYPTION_KEY_FILE")
+            .map(FileKeyProvider::new)
+            .ok_or_else(|| {
+                ReputestError::Config(
+                    "TOKEN_ENCRYPTION_KEY_FILE must be set when TOKEN_ENCRYPTION_KEY_PROVIDER is \"file\""
+                        .to_string(),
+                )
+            })
+    }
+}
+
+impl KeyProvider for FileKeyProvider {
+    fn describe(&self) -> String {
+        format!("key file {}", self.path.display())
+    }
+
+    fn is_configured(&self) -> bool {
+        true
+    }
+
+    fn load(&self) -> Result<Keyring, ReputestError> {
+        let contents = read_secret_file(&self.path)?;
+        let name = self.describe();
+        let mut keys = contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|key_hex| parse_key(&name, key_hex));
+        let current = keys.next().ok_or_else(|| {
+            ReputestError::Config(format!(
+                "{} contains no key. Generate one with: openssl rand -hex 32",
+                name
+            ))
+        })??;
+        Ok(Keyring::new(current, keys.collect::<Result<_, _>>()?))
+    }
+}
+
+/// Where [`PassphraseKeyProvider`] reads the passphrase from.
+pub enum PassphraseSource {
+    /// A file that, on Unix, must not be accessible by group or other users
+    File(PathBuf),
+    /// The `TOKEN_ENCRYPTION_PASSPHRASE` environment variable
+    Env,
+}
+
+/// Derives the current key from a passphrase with Argon2id (64 MiB, 3 iterations).
+///
+/// The salt (`TOKEN_ENCRYPTION_KDF_SALT`, at least 16 bytes) is not secret but must never
+/// change: another salt or passphrase derives another key, which no longer decrypts the
+/// stored tokens.
+///
+/// Previous keys come from the optional `previous_file` (`TOKEN_ENCRYPTION_PREVIOUS_FILE`),
+/// which is permission-checked like the passphrase file. It holds one previous secret per
+/// line: a 64-character hex key is used as is, any other line is a previous passphrase and
+/// derived with the same salt. Blank lines and lines starting with `#` are ignored.
+pub struct PassphraseKeyProvider {
+    pub source: PassphraseSource,
+    pub salt: String,
+    pub previous_file: Option<PathBuf>,
+}
+
+impl PassphraseKeyProvider {
+    pub fn new(source: PassphraseSource, salt: impl Into<String>) -> Self {
+        PassphraseKeyProvider {
+            source,
+            salt: salt.into(),
+            previous_file: None,
+        }
+    }
+
+    /// Reads the previous keys and passphrases from `path`.
+    pub fn with_previous_file(mut self, path: impl Into<PathBuf>) -> Self {
+        self.previous_file = Some(path.into());
+        self
+    }
+
+    /// Reads the passphrase from `TOKEN_ENCRYPTION_PASSPHRASE_FILE` (or, when that is not
+    /// set, `TOKEN_ENCRYPTION_PASSPHRASE`), the salt from `TOKEN_ENCRYPTION_KDF_SALT` and
+    /// the previous secrets from `TOKEN_ENCRYPTION_PREVIOUS_FILE`.
+    pub fn from_env() -> Result<Self, ReputestError> {
+        let salt = setting("TOKEN_ENCRYPTION_KDF_SALT").ok_or_else(|| {
+            ReputestError::Config(
+                "TOKEN_ENCRYPTION_KDF_SALT must be set when TOKEN_ENCRYPTION_KEY_PROVIDER is \"passphrase\". Generate one with: openssl rand -hex 16"
+                    .to_string(),
+            )
+        })?;
+        if setting("TOKEN_ENCRYPTION_PREVIOUS_KEYS").is_some() {
+            return Err(ReputestError::Config(
+                "TOKEN_ENCRYPTION_PREVIOUS_KEYS is not read by the passphrase provider: move the previous keys to the file named by TOKEN_ENCRYPTION_PREVIOUS_FILE"
+                    .to_string(),
+            ));
+        }
+        let source = match setting("TOKEN_ENCRYPTION_PASSPHRASE_FILE") {
+            Some(path) => PassphraseSource::File(path.into()),
+            None => PassphraseSource::Env,
+        };
+        let provider = PassphraseKeyProvider::new(source, salt);
+        Ok(match setting("TOKEN_ENCRYPTION_PREVIOUS_FILE") {
+            Some(path) => provider.with_previous_file(path),
+            None => provider,
+        })
+    }
+
+    fn passphrase(&self) -> Result<String, ReputestError> {
+        let passphrase = match &self.source {
+            PassphraseSource::File(path) => read_secret_file(path)?
+                .trim_end_matches(['\r', '\n'])
+                .to_string(),
+            PassphraseSource::Env => setting("TOKEN_ENCRYPTION_PASSPHRASE").ok_or_else(|| {
+                ReputestError::Config(
+                    "Set TOKEN_ENCRYPTION_PASSPHRASE_FILE or TOKEN_ENCRYPTION_PASSPHRASE when TOKEN_ENCRYPTION_KEY_PROVIDER is \"passphrase\""
+                        .to_string(),
+                )
+            })?,
+        };
+        check_passphrase_length(&passphrase)?;
+        Ok(passphrase)
+    }
+
+    /// Reads the previous keys of `previous_file`, deriving the previous passphrases.
+    fn previous_keys(&self) -> Result<Vec<[u8; 32]>, ReputestError> {
+        let Some(path) = &self.previous_file else {
+            return Ok(Vec::new());
+        };
+        let contents = read_secret_file(path)?;
+        let name = format!("previous key file {}", path.display());
+        contents
+            .lines()
+            .map(str::trim)
+            .filter(|line| !line.is_empty() && !line.starts_with('#'))
+            .map(|secret| {
+                if secret.len() == 64 && secret.bytes().all(|b| b.is_ascii_hexdigit()) {
+                    parse_key(&name, secret)
+                } else {
+                    check_passphrase_length(secret)?;
+                    self.derive_key(secret)
+                }
+            })
+            .collect()
+    }
+
+    /// Stretches the passphrase into a 32-byte key.
+    fn derive_key(&self, passphrase: &str) -> Result<[u8; 32], ReputestError> {
+        if self.salt.len() < MIN_KDF_SALT_LENGTH {
+            return Err(ReputestError::Config(format!(
+                "TOKEN_ENCRYPTION_KDF_SALT must be at least {} bytes, got {}",
+                MIN_KDF_SALT_LENGTH,
+                self.salt.len()
+            )));
+        }
+        let params = Params::new(KDF_MEMORY_KIB, KDF_ITERATIONS, 1, Some(32)).map_err(|e| {
+            ReputestError::Crypto(format!("Invalid key derivation parameters: {}", e))
+        })?;
+        let mut key = [0u8; 32];
+        Argon2::new(Algorithm::Argon2id, Version::V0x13, params)
+            .hash_password_into(passphrase.as_bytes(), self.salt.as_bytes(), &mut key)
+            .map_err(|e| ReputestError::Crypto(format!("Key derivation failed: {}", e)))?;
+        Ok(key)
+    }
+}
+
+impl KeyProvider for PassphraseKeyProvider {
+    fn describe(&self) -> String {
+        match &self.source {
+            PassphraseSource::File(path) => format!("passphrase file {}", path.display()),
+            PassphraseSource::Env => "environment variable TOKEN_ENCRYPTION_PASSPHRASE".to_string(),
+        }
+    }
+
+    fn is_configured(&self) -> bool {
+        match &self.source {
+            PassphraseSource::Env => setting("TOKEN_ENCRYPTION_PASSPHRASE").is_some(),
+            PassphraseSource::File(_) => true,
+        }
+    }
+
+    fn load(&self) -> Result<Keyring, ReputestError> {
+        let current = self.derive_key(&self.passphrase()?)?;
+        Ok(Keyring::new(current, self.previous_keys()?))
+    }
+}
+
+/// Picks the key provider named by `TOKEN_ENCRYPTION_KEY_PROVIDER`: `env` (the default),
+/// `file` or `passphrase`.
+///
+/// # Returns
+///
+/// - `Ok(Box<dyn KeyProvider>)`: The provider, which has not loaded any key yet
+/// - `Err(ReputestError::Config)`: If the provider is unknown or a setting it needs is
+///   missing
+pub fn key_provider() -> Result<Box<dyn KeyProvider>, ReputestError> {
+    match setting("TOKEN_ENCRYPTION_KEY_PROVIDER")
+        .as_deref()
+        .map(str::trim)
+    {
+        None | Some("env") => Ok(Box::new(EnvKeyProvider)),
+        Some("file") => Ok(Box::new(FileKeyProvider::from_env()?)),
+        Some("passphrase") => Ok(Box::new(PassphraseKeyProvider::from_env()?)),
+        Some(other) => Err(ReputestError::Config(format!(
+            "Invalid TOKEN_ENCRYPTION_KEY_PROVIDER value '{}': expected env, file or passphrase",
+            other
+        ))),
+    }
+}
+
+/// The keyring loaded at startup, or else the keys of the configured provider.
+#[allow(dead_code)] // Used by the encrypt_token and rotate_keys binaries
+pub fn load_keyring() -> Result<Keyring, ReputestError> {
+    match KEYRING.get() {
+        Some(keyring) => Ok(keyring.clone()),
+        None => key_provider()?.load(),
+    }
+}
+
+/// Runs `f` with the keyring loaded at startup, loading the keys only if there is none.
+fn with_keyring<T>(
+    f: impl FnOnce(&Keyring) -> Result<T, ReputestError>,
+) -> Result<T, ReputestError> {
+    match KEYRING.get() {
+        Some(keyring) => f(keyring),
+        None => f(&key_provider()?.load()?),
+    }
+}
+
+/// Decodes the comma-separated `TOKEN_ENCRYPTION_PREVIOUS_KEYS`.
+fn previous_keys_from_env() -> Result<Vec<[u8; 32]>, ReputestError> {
+    let mut previous = Vec::new();
+    if let Some(keys) = setting("TOKEN_ENCRYPTION_PREVIOUS_KEYS") {
+        for key_hex in keys.split(',').map(str::trim).filter(|k| !k.is_empty()) {
+            previous.push(parse_key("TOKEN_ENCRYPTION_PREVIOUS_KEYS", key_hex)?);
+        }
+    }
+    Ok(previous)
+}
+
+/// Refuses passphrases shorter than [`MIN_PASSPHRASE_LENGTH`].
+fn check_passphrase_length(passphrase: &str) -> Result<(), ReputestError> {
+    if passphrase.chars().count() < MIN_PASSPHRASE_LENGTH {
+        return Err(ReputestError::Config(format!(
+            "The token encryption passphrase must be at least {} characters",
+            MIN_PASSPHRASE_LENGTH
+        )));
+    }
+    Ok(())
+}
+
+/// Reads a file holding a secret, refusing it unless it is a regular file that (on Unix)
+/// only its owner can access.
+fn read_secret_file(path: &std::path::Path) -> Result<String, ReputestError> {
+    let metadata = std::fs::metadata(path).map_err(|e| {
+        ReputestError::Config(format!("Cannot read secret file {}: {}", path.display(), e))
+    })?;
+    if !metadata.is_file() {
+        return Err(ReputestError::Config(format!(
+            "Secret file {} is not a regular file",
+            path.display()
+        )));
+    }
+    #[cfg(unix)]
+    {
+        use std::os::unix::fs::PermissionsExt;
+        let mode = metadata.permissions().mode() & 0o777;
+        if mode & 0o077 != 0 {
+            return Err(ReputestError::Config(format!(
+                "Secret file {} is accessible by other users (mode {:o}); restrict it with: chmod 600 {}",
+                path.display(),
+                mode,
+                path.display()
+            )));
+        }
+    }
+    std::fs::read_to_string(path).map_err(|e| {
+        ReputestError::Config(format!("Cannot read secret file {}: {}", path.display(), e))
+    })
+}
+
+/// Decodes a 64-character hex key read from the variable `name`.
+fn parse_key(name: &str, key_hex: &str) -> Result<[u8; 32], ReputestError> {
+    let key_bytes = hex::decode(key_hex).map_err(|e| {
+        ReputestError::Config(format!(
+            "{} is not valid hex: {}. Generate a key with: openssl rand -hex 32",
+            name, e
+        ))
+    })?;
+
+    if key_bytes.len() != 32 {
+        return Err(ReputestError::Config(format!(
+            "{} must be exactly 32 bytes (64 hex chars), got {} bytes",
+            name,
+            key_bytes.len()
+        )));
+    }
+
+    let mut key: [u8; 32] = [0u8; 32];
+    key.copy_from_slice(&key_bytes);
+    Ok(key)
+}
+
+/// Encrypts a token using AES-256-GCM with the current key of [`load_keyring`].
+///
+/// The function generates a random nonce and prepends it to the ciphertext.
+/// The output format is: `v2:<key id>:` followed by hex of nonce (12 bytes) || ciphertext || auth_tag
+///
+/// # Parameters
+///
+/// - `plaintext`: The token to encrypt
+/// - `context`: Where the token will be stored; it only decrypts there
+///
+/// # Returns
+///
+/// - `Ok(String)`: The encrypted token envelope
+/// - `Err`: If encryption fails or the key is not configured
+pub fn encrypt_token(plaintext: &str, context: &TokenContext) -> Result<String, ReputestError> {
+    with_keyring(|keyring| keyring.encrypt(plaintext, context))
+}
+
+/// Decrypts a token that was encrypted with `encrypt_token` for the same `context`, with
+/// the current or a previous key.
+///
+/// # Parameters
+///
+/// - `encrypted`: The encrypted token envelope
+/// - `context`: Where the token is stored
+///
+/// # Returns
+///
+/// - `Ok(String)`: The decrypted token
+/// - `Err`: If decryption fails, the key is wrong, the token belongs to another row or
+///   token kind, or the data is corrupted
+pub fn decrypt_token(encrypted: &str, context: &TokenContext) -> Result<String, ReputestError> {
+    with_keyring(|keyring| keyring.decrypt(encrypted, context))
+}
+
 /// Checks if token encryption is configured.
 ///
 /// # Returns
 ///
-/// `true` if TOKEN_ENCRYPTION_KEY is set, `false` otherwise
+/// `true` if keys were loaded at startup or the key provider has its settings, `false`
+/// otherwise
 pub fn is_encryption_configured() -> bool {
-    env::var("TOKEN_ENCRYPTION_KEY").is_ok()
+    KEYRING.get().is_some()
+        || key_provider()
+            .map(|provider| provider.is_configured())
+            .unwrap_or(false)
 }
 
 /// Validates that encryption is properly configured.
 ///
 /// This function should be called at application startup to ensure
 /// that token encryption is properly configured before processing any tokens.
+/// The keys it loads are kept for the life of the process, so the key file and
+/// passphrase are read (and the passphrase is stretched) only once.
 ///
 /// # Returns
 ///
-/// - `Ok(())`: If encryption is properly configured
-/// - `Err`: If TOKEN_ENCRYPTION_KEY is missing or invalid
+/// - `Ok(Keyring)`: The configured keys, if encryption is properly configured
+/// - `Err`: If the key provider is misconfigured or a key is missing or invalid
 ///
 /// # Errors
 ///
 /// Returns an error if:
-/// - TOKEN_ENCRYPTION_KEY environment variable is not set
-/// - The key is not valid hexadecimal
-/// - The key is not exactly 32 bytes (64 hex characters)
-pub fn validate_encryption_config() -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    get_encryption_key()?;
-    debug!("Token encryption configuration validated successfully");
-    Ok(())
+/// - TOKEN_ENCRYPTION_KEY_PROVIDER is unknown or a setting it needs is not set
+/// - A key is not valid hexadecimal or not exactly 32 bytes (64 hex characters)
+/// - The key or passphrase file is accessible by other users
+/// - The passphrase or its salt is too short
+pub fn validate_encryption_config() -> Result<Keyring, ReputestError> {
+    let provider = key_provider()?;
+    let keyring = provider.load()?;
+    info!(
+        "Loaded token encryption keys from {} ({} previous keys)",
+        provider.describe(),
+        keyring.previous.len()
+    );
+    Ok(KEYRING.get_or_init(|| keyring).clone())
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use std::env;
     use std::sync::Mutex;
 
     // Mutex to prevent parallel test execution that manipulates TOKEN_ENCRYPTION_KEY
     static ENV_LOCK: Mutex<()> = Mutex::new(());
 
+    fn context(table: &'static str, row_id: i32, kind: TokenKind) -> TokenContext {
+        TokenContext::new(table, row_id, kind)
+    }
+
     #[test]
     fn test_encrypt_decrypt_roundtrip() {
         let _guard = ENV_LOCK.lock().unwrap();
@@ -176,13 +813,14 @@ mod tests {
         );
 
         let original = "test_token_12345";
-        let encrypted = encrypt_token(original).unwrap();
+        let context = context("access_tokens", 1, TokenKind::Access);
+        let encrypted = encrypt_token(original, &context).unwrap();
 
         // Encrypted should be different from original
         assert_ne!(encrypted, original);
 
         // Decrypt should recover original
-        let decrypted = decrypt_token(&encrypted).unwrap();
+        let decrypted = decrypt_token(&encrypted, &context).unwrap();
         assert_eq!(decrypted, original);
 
         // Clean up
@@ -198,16 +836,254 @@ mod tests {
         );
 
         let original = "test_token";
-        let encrypted1 = encrypt_token(original).unwrap();
-        let encrypted2 = encrypt_token(original).unwrap();
+        let context = context("access_tokens", 1, TokenKind::Access);
+        let encrypted1 = encrypt_token(original, &context).unwrap();
+        let encrypted2 = encrypt_token(original, &context).unwrap();
 
         // Due to random nonce, same plaintext should produce different ciphertext
         assert_ne!(encrypted1, encrypted2);
 
         // Both should decrypt to the same value
-        assert_eq!(decrypt_token(&encrypted1).unwrap(), original);
-        assert_eq!(decrypt_token(&encrypted2).unwrap(), original);
+        assert_eq!(decrypt_token(&encrypted1, &context).unwrap(), original);
+        assert_eq!(decrypt_token(&encrypted2, &context).unwrap(), original);
 
         env::remove_var("TOKEN_ENCRYPTION_KEY");
     }
+
+    #[test]
+    fn test_swapped_ciphertexts_fail_to_decrypt() {
+        let keyring = Keyring::new([1u8; 32], vec![]);
+        let refresh = context("refresh_tokens", 7, TokenKind::Refresh);
+        let encrypted = keyring.encrypt("refresh_token_value", &refresh).unwrap();
+        assert_eq!(
+            keyring.decrypt(&encrypted, &refresh).unwrap(),
+            "refresh_token_value"
+        );
+
+        // Copied into another table, another row, or the other token column
+        for other in [
+            context("access_tokens", 7, TokenKind::Refresh),
+            context("access_tokens", 7, TokenKind::Access),
+            context("refresh_tokens", 8, TokenKind::Refresh),
+            context("refresh_tokens", 7, TokenKind::Access),
+        ] {
+            assert!(
+                matches!(
+                    keyring.decrypt(&encrypted, &other),
+                    Err(ReputestError::Crypto(_))
+                ),
+                "{:?}",
+                other
+            );
+        }
+
+        let session = TokenContext::new(
+            "sessions",
+            "7b0b6e2e-6a44-4a35-9d1c-0d51a1c7f6a1",
+            TokenKind::Access,
+        );
+        let encrypted = keyring.encrypt("session_access", &session).unwrap();
+        let other_session = TokenContext::new(
+            "sessions",
+            "0f7e0a4c-55b9-4d5e-8a41-3bb0d7f3c2e9",
+            TokenKind::Access,
+        );
+        assert!(keyring.decrypt(&encrypted, &other_session).is_err());
+        assert!(keyring
+            .decrypt(
+                &encrypted,
+                &TokenContext::new(
+                    "sessions",
+                    "7b0b6e2e-6a44-4a35-9d1c-0d51a1c7f6a1",
+                    TokenKind::Refresh
+                )
+            )
+            .is_err());
+    }
+
+    #[test]
+    fn test_keyring_rotation() {
+        let old_key = [1u8; 32];
+        let new_key = [2u8; 32];
+        let old = Keyring::new(old_key, vec![]);
+        let rotated = Keyring::new(new_key, vec![old_key]);
+        assert_ne!(old.current_key_id(), rotated.current_key_id());
+        let context = context("access_tokens", 1, TokenKind::Access);
+
+        let encrypted = old.encrypt("test_token", &context).unwrap();
+        assert!(encrypted.starts_with(&format!("v2:{}:", old.current_key_id())));
+        assert!(!old.needs_reencryption(&encrypted));
+
+        // A previous key still decrypts, but the token should be re-encrypted
+        assert_eq!(rotated.decrypt(&encrypted, &context).unwrap(), "test_token");
+        assert!(rotated.needs_reencryption(&encrypted));
+        let reencrypted = rotated.reencrypt(&encrypted, &context).unwrap();
+        assert!(!rotated.needs_reencryption(&reencrypted));
+        assert_eq!(
+            rotated.decrypt(&reencrypted, &context).unwrap(),
+            "test_token"
+        );
+
+        // Re-encryption keeps the binding: it cannot move a token to another row
+        assert!(rotated
+            .reencrypt(
+                &encrypted,
+                &TokenContext::new("access_tokens", 2, TokenKind::Access)
+            )
+            .is_err());
+
+        // Without the previous key the token is rejected by key id
+        let new_only = Keyring::new(new_key, vec![]);
+        assert!(matches!(
+            new_only.decrypt(&encrypted, &context),
+            Err(ReputestError::Crypto(_))
+        ));
+        assert!(old.decrypt(&reencrypted, &context).is_err());
+    }
+
+    #[test]
+    fn test_keyring_unbound_legacy_and_malformed_tokens() {
+        let old_key = [1u8; 32];
+        let old = Keyring::new(old_key, vec![]);
+        let rotated = Keyring::new([2u8; 32], vec![old_key]);
+        let context = context("refresh_tokens", 3, TokenKind::Refresh);
+
+        // Tokens written before they were bound to their row only decrypt by re-encryption
+        let unbound = old.encrypt_unbound("unbound_token").unwrap();
+        assert!(unbound.starts_with(&format!("v1:{}:", old.current_key_id())));
+        assert!(old.decrypt(&unbound, &context).is_err());
+        assert!(old.needs_reencryption(&unbound));
+        let bound = rotated.reencrypt(&unbound, &context).unwrap();
+        assert_eq!(rotated.decrypt(&bound, &context).unwrap(), "unbound_token");
+
+        // Tokens stored before the envelope was versioned are bare hex
+        let legacy = unbound.rsplit(':').next().unwrap();
+        assert!(rotated.decrypt(legacy, &context).is_err());
+        assert!(rotated.needs_reencryption(legacy));
+        let bound = rotated.reencrypt(legacy, &context).unwrap();
+        assert_eq!(rotated.decrypt(&bound, &context).unwrap(), "unbound_token");
+
+        let payload = unbound.splitn(3, ':').nth(2).unwrap();
+        let tampered = format!("v1:{}:{}ff", old.current_key_id(), payload);
+        assert!(old.reencrypt(&tampered, &context).is_err());
+        for malformed in [
+            format!("v9:{}:{}", old.current_key_id(), payload),
+            format!("v2:{}", old.current_key_id()),
+            "not hex".to_string(),
+        ] {
+            assert!(
+                old.reencrypt(&malformed, &context).is_err(),
+                "{}",
+                malformed
+            );
+            assert!(old.needs_reencryption(&malformed), "{}", malformed);
+        }
+    }
+
+    /// Writes `contents` to a fresh file in the temp directory with the given mode.
+    fn secret_file(name: &str, contents: &str, mode: u32) -> PathBuf {
+        let path = env::temp_dir().join(format!("reputest-{}-{}", std::process::id(), name));
+        std::fs::write(&path, contents).unwrap();
+        #[cfg(unix)]
+        {
+            use std::os::unix::fs::PermissionsExt;
+            std::fs::set_permissions(&path, std::fs::Permissions::from_mode(mode)).unwrap();
+        }
+        #[cfg(not(unix))]
+        let _ = mode;
+        path
+    }
+
+    #[test]
+    fn test_file_key_provider() {
+        let current = "2".repeat(64);
+        let previous = "1".repeat(64);
+        let path = secret_file(
+            "keys",
+            &format!("# rotated in October\n{}\n\n{}\n", current, previous),
+            0o600,
+        );
+        let keyring = FileKeyProvider::new(&path).load().unwrap();
+        let expected = Keyring::new([0x22; 32], vec![[0x11; 32]]);
+        assert_eq!(keyring.current_key_id(), expected.current_key_id());
+        let context = context("access_tokens", 1, TokenKind::Access);
+        let encrypted = Keyring::new([0x11; 32], vec![])
+            .encrypt("test_token", &context)
+            .unwrap();
+        assert_eq!(keyring.decrypt(&encrypted, &context).unwrap(), "test_token");
+        std::fs::remove_file(&path).unwrap();
+
+        for (name, contents) in [("empty", "# no key yet\n"), ("short", "abcd\n")] {
+            let path = secret_file(name, contents, 0o600);
+            assert!(matches!(
+                FileKeyProvider::new(&path).load(),
+                Err(ReputestError::Config(_))
+            ));
+            std::fs::remove_file(&path).unwrap();
+        }
+        assert!(
+            FileKeyProvider::new(env::temp_dir().join("reputest-missing-key"))
+                .load()
+                .is_err()
+        );
+        assert!(FileKeyProvider::new(env::temp_dir()).load().is_err());
+    }
+
+    #[cfg(unix)]
+    #[test]
+    fn test_secret_files_readable_by_others_are_rejected() {
+        for mode in [0o640, 0o604, 0o660] {
+            let path = secret_file(&format!("open-{:o}", mode), &"2".repeat(64), mode);
+            let err = FileKeyProvider::new(&path).load().unwrap_err();
+            assert!(
+                err.to_string().contains("accessible by other users"),
+                "{}",
+                err
+            );
+            let err = PassphraseKeyProvider::new(
+                PassphraseSource::File(path.clone()),
+                "0123456789abcdef",
+            )
+            .load()
+            .unwrap_err();
+            assert!(
+                err.to_string().contains("accessible by other users"),
+                "{}",
+                err
+            );
+            std::fs::remove_file(&path).unwrap();
+        }
+        let path = secret_file("owner-only", &"2".repeat(64), 0o400);
+        assert!(FileKeyProvider::new(&path).load().is_ok());
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn test_passphrase_key_provider() {
+        let path = secret_file("passphrase", "correct horse battery staple\n", 0o600);
+        let provider = |salt: &str| {
+            PassphraseKeyProvider::new(PassphraseSource::File(path.clone()), salt).load()
+        };
+
+        // The same passphrase and salt always derive the same key; another salt does not
+        let keyring = provider("reputest-salt-0001").unwrap();
+        assert_eq!(
+            keyring.current_key_id(),
+            provider("reputest-salt-0001").unwrap().current_key_id()
+        );
+        assert_ne!(
+            keyring.current_key_id(),
+            provider("reputest-salt-0002").unwrap().current_key_id()
+        );
+        assert!(matches!(provider("short"), Err(ReputestError::Config(_))));
+        std::fs::remove_file(&path).unwrap();
+
+        let path = secret_file("weak-passphrase", "hunter2\n", 0o600);
+        assert!(matches!(
+            PassphraseKeyProvider::new(PassphraseSource::File(path.clone()), "reputest-salt-0001")
+                .load(),
+            Err(ReputestError::Config(_))
+        ));
+        std::fs::remove_file(&path).unwrap();
+    }
 }
@@ -5,25 +5,56 @@
 //! access_tokens tables which store tokens along with their creation timestamps.
 
 use log::{debug, info, warn};
-use sqlx::{PgPool, Row};
+use sqlx::pool::PoolConnection;
+use sqlx::postgres::{PgConnectOptions, PgPoolOptions};
+use sqlx::{PgConnection, PgPool, Postgres, Row, Transaction};
 use std::collections::{HashMap, HashSet, VecDeque};
-use std::env;
+use std::str::FromStr;
 
-use crate::crypto::{decrypt_token, encrypt_token, is_encryption_configured};
+use crate::config::{get_shadow_schema, setting, AppConfig, DbPoolConfig};
+use crate::crypto::{
+    decrypt_token, encrypt_token, is_encryption_configured, Keyring, TokenContext, TokenKind,
+};
+use crate::error::ReputestError;
 
-/// Establishes a connection to the PostgreSQL database using DATABASE_URL.
+/// Creates the PostgreSQL connection pool from DATABASE_URL.
+///
+/// Pool size, acquire timeout and statement timeout come from [`DbPoolConfig`]. The
+/// application calls this once at startup and shares the pool (cloning a `PgPool` is
+/// cheap) with the HTTP handlers and scheduled jobs.
 ///
 /// # Returns
 ///
 /// - `Ok(PgPool)`: A connection pool to the database
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the connection fails or DATABASE_URL is missing
-pub async fn get_db_pool() -> Result<PgPool, Box<dyn std::error::Error + Send + Sync>> {
-    let database_url =
-        env::var("DATABASE_URL").map_err(|_| "DATABASE_URL environment variable is not set")?;
-
-    debug!("Connecting to PostgreSQL database");
+/// - `Err(ReputestError)`: If the connection fails or DATABASE_URL is missing
+pub async fn get_db_pool() -> Result<PgPool, ReputestError> {
+    let database_url = setting("DATABASE_URL").ok_or_else(|| {
+        ReputestError::Config(
+            "DATABASE_URL is not set in the environment or the configuration file".to_string(),
+        )
+    })?;
+    let config = DbPoolConfig::from_env();
+    config.validate()?;
+
+    debug!("Connecting to PostgreSQL database with {:?}", config);
+
+    let mut connect_options = PgConnectOptions::from_str(&database_url)?;
+    if !config.statement_timeout.is_zero() {
+        connect_options = connect_options.options([(
+            "statement_timeout",
+            config.statement_timeout.as_millis().to_string(),
+        )]);
+    }
+    if let Some(schema) = get_shadow_schema() {
+        info!("Shadow mode: writing to schema '{}' before public", schema);
+        connect_options = connect_options.options([("search_path", format!("{},public", schema))]);
+    }
 
-    let pool = PgPool::connect(&database_url).await?;
+    let pool = PgPoolOptions::new()
+        .max_connections(config.max_connections)
+        .acquire_timeout(config.acquire_timeout)
+        .connect_with(connect_options)
+        .await?;
     debug!("Successfully connected to PostgreSQL database");
 
     Ok(pool)
@@ -36,26 +67,26 @@ pub async fn get_db_pool() -> Result<PgPool, Box<dyn std::error::Error + Send +
 ///
 /// # Parameters
 ///
-/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
 ///
 /// # Returns
 ///
 /// - `Ok(Option<String>)`: The latest refresh token if one exists, None otherwise
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_latest_refresh_token(
-    pool: &PgPool,
-) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    conn: &mut PgConnection,
+) -> Result<Option<String>, ReputestError> {
     info!("Querying database for latest refresh token");
 
     let row = sqlx::query(
         r#"
-        SELECT token, created_at
+        SELECT id, token, created_at
         FROM refresh_tokens
         ORDER BY created_at DESC
         LIMIT 1
         "#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
     match row {
@@ -67,9 +98,14 @@ pub async fn get_latest_refresh_token(
 
             // SECURITY: Always require decryption - tokens must be stored encrypted
             if !is_encryption_configured() {
-                return Err("TOKEN_ENCRYPTION_KEY is required to read tokens - refusing to read potentially unencrypted data".into());
+                return Err(ReputestError::Config("TOKEN_ENCRYPTION_KEY is required to read tokens - refusing to read potentially unencrypted data".to_string()));
             }
-            let token = decrypt_token(&stored_token)?;
+            let context = TokenContext::new(
+                "refresh_tokens",
+                row.get::<i32, _>("id"),
+                TokenKind::Refresh,
+            );
+            let token = decrypt_token(&stored_token, &context)?;
 
             Ok(Some(token))
         }
@@ -83,69 +119,77 @@ pub async fn get_latest_refresh_token(
 /// Stores a new refresh token in the database.
 ///
 /// This function inserts a new refresh token into the refresh_tokens table
-/// with the current timestamp. Old tokens are automatically deleted to prevent
-/// accumulation of stale credentials.
+/// with the current timestamp. Only the newest `TOKEN_RETENTION_COUNT` tokens are kept
+/// (see [`prune_tokens`]) to prevent accumulation of stale credentials.
 ///
 /// # Parameters
 ///
-/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
 /// - `token`: The refresh token to store
 ///
 /// # Returns
 ///
 /// - `Ok(())`: If the token was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert fails or encryption is not configured
+/// - `Err(ReputestError)`: If the insert fails or encryption is not configured
 ///
 /// # Security
 ///
 /// This function requires TOKEN_ENCRYPTION_KEY to be configured. Tokens are always
 /// encrypted before storage to protect against database breaches.
-pub async fn save_refresh_token(
-    pool: &PgPool,
-    token: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+pub async fn save_refresh_token(conn: &mut PgConnection, token: &str) -> Result<(), ReputestError> {
     info!("Storing new refresh token in database");
 
     // Encryption is mandatory - fail if not configured
     if !is_encryption_configured() {
-        return Err(
-            "TOKEN_ENCRYPTION_KEY must be set - refusing to store tokens in plaintext".into(),
-        );
+        return Err(ReputestError::Config(
+            "TOKEN_ENCRYPTION_KEY must be set - refusing to store tokens in plaintext".to_string(),
+        ));
     }
 
-    let token_to_store = encrypt_token(token)?;
-
-    // Delete old tokens before inserting new one to prevent accumulation
-    let deleted = sqlx::query(
-        r#"
-        DELETE FROM refresh_tokens
-        WHERE created_at < NOW() - INTERVAL '24 hours'
-        "#,
+    // The ciphertext is bound to the row, so its id is taken before encrypting
+    let id: i32 = sqlx::query_scalar(
+        "SELECT nextval(pg_get_serial_sequence('refresh_tokens', 'id'))::INTEGER",
     )
-    .execute(pool)
+    .fetch_one(&mut *conn)
     .await?;
-
-    if deleted.rows_affected() > 0 {
-        info!(
-            "Cleaned up {} old refresh token(s)",
-            deleted.rows_affected()
-        );
-    }
+    let token_to_store = encrypt_token(
+        token,
+        &TokenContext::new("refresh_tokens", id, TokenKind::Refresh),
+    )?;
 
     sqlx::query(
         r#"
-        INSERT INTO refresh_tokens (token, created_at)
-        VALUES ($1, NOW())
+        INSERT INTO refresh_tokens (id, token, created_at)
+        VALUES ($1, $2, NOW())
         "#,
     )
+    .bind(id)
     .bind(&token_to_store)
-    .execute(pool)
+    .execute(&mut *conn)
     .await?;
 
+    let pruned = prune_tokens(
+        &mut *conn,
+        TokenKind::Refresh,
+        AppConfig::current().token_retention,
+    )
+    .await?;
+    if pruned > 0 {
+        info!("Deleted {} old refresh token(s)", pruned);
+    }
+
     info!("Successfully stored new refresh token in database");
     Ok(())
 }
 
+/// The bot's access token as stored in `access_tokens`.
+#[derive(Debug, Clone)]
+pub struct StoredAccessToken {
+    pub token: String,
+    /// When the token expires, if X told us
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
 /// Retrieves the most recent access token from the database.
 ///
 /// This function queries the access_tokens table and returns the token
@@ -153,26 +197,27 @@ pub async fn save_refresh_token(
 ///
 /// # Parameters
 ///
-/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
 ///
 /// # Returns
 ///
-/// - `Ok(Option<String>)`: The latest access token if one exists, None otherwise
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Ok(Option<StoredAccessToken>)`: The latest access token and its expiry if one exists,
+///   None otherwise
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_latest_access_token(
-    pool: &PgPool,
-) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+    conn: &mut PgConnection,
+) -> Result<Option<StoredAccessToken>, ReputestError> {
     debug!("Querying database for latest access token");
 
     let row = sqlx::query(
         r#"
-        SELECT token, created_at
+        SELECT id, token, created_at, expires_at
         FROM access_tokens
         ORDER BY created_at DESC
         LIMIT 1
         "#,
     )
-    .fetch_optional(pool)
+    .fetch_optional(&mut *conn)
     .await?;
 
     match row {
@@ -184,11 +229,16 @@ pub async fn get_latest_access_token(
 
             // SECURITY: Always require decryption - tokens must be stored encrypted
             if !is_encryption_configured() {
-                return Err("TOKEN_ENCRYPTION_KEY is required to read tokens - refusing to read potentially unencrypted data".into());
+                return Err(ReputestError::Config("TOKEN_ENCRYPTION_KEY is required to read tokens - refusing to read potentially unencrypted data".to_string()));
             }
-            let token = decrypt_token(&stored_token)?;
+            let context =
+                TokenContext::new("access_tokens", row.get::<i32, _>("id"), TokenKind::Access);
+            let token = decrypt_token(&stored_token, &context)?;
 
-            Ok(Some(token))
+            Ok(Some(StoredAccessToken {
+                token,
+                expires_at: row.get("expires_at"),
+            }))
         }
         None => {
             warn!("No access tokens found in database");
@@ -200,66 +250,286 @@ pub async fn get_latest_access_token(
 /// Stores a new access token in the database.
 ///
 /// This function inserts a new access token into the access_tokens table
-/// with the current timestamp. Old tokens are automatically deleted to prevent
-/// accumulation of stale credentials.
+/// with the current timestamp. Only the newest `TOKEN_RETENTION_COUNT` tokens are kept
+/// (see [`prune_tokens`]) to prevent accumulation of stale credentials.
 ///
 /// # Parameters
 ///
-/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
 /// - `token`: The access token to store
+/// - `expires_at`: When the token expires, if known
 ///
 /// # Returns
 ///
 /// - `Ok(())`: If the token was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert fails or encryption is not configured
+/// - `Err(ReputestError)`: If the insert fails or encryption is not configured
 ///
 /// # Security
 ///
 /// This function requires TOKEN_ENCRYPTION_KEY to be configured. Tokens are always
 /// encrypted before storage to protect against database breaches.
 pub async fn save_access_token(
-    pool: &PgPool,
+    conn: &mut PgConnection,
     token: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    expires_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), ReputestError> {
     debug!("Storing new access token in database");
 
     // Encryption is mandatory - fail if not configured
     if !is_encryption_configured() {
-        return Err(
-            "TOKEN_ENCRYPTION_KEY must be set - refusing to store tokens in plaintext".into(),
-        );
+        return Err(ReputestError::Config(
+            "TOKEN_ENCRYPTION_KEY must be set - refusing to store tokens in plaintext".to_string(),
+        ));
     }
 
-    let token_to_store = encrypt_token(token)?;
+    // The ciphertext is bound to the row, so its id is taken before encrypting
+    let id: i32 = sqlx::query_scalar(
+        "SELECT nextval(pg_get_serial_sequence('access_tokens', 'id'))::INTEGER",
+    )
+    .fetch_one(&mut *conn)
+    .await?;
+    let token_to_store = encrypt_token(
+        token,
+        &TokenContext::new("access_tokens", id, TokenKind::Access),
+    )?;
 
-    // Delete old tokens before inserting new one to prevent accumulation
-    let deleted = sqlx::query(
+    sqlx::query(
         r#"
-        DELETE FROM access_tokens
-        WHERE created_at < NOW() - INTERVAL '24 hours'
+        INSERT INTO access_tokens (id, token, created_at, expires_at)
+        VALUES ($1, $2, NOW(), $3)
         "#,
     )
-    .execute(pool)
+    .bind(id)
+    .bind(&token_to_store)
+    .bind(expires_at)
+    .execute(&mut *conn)
+    .await?;
+
+    let pruned = prune_tokens(
+        &mut *conn,
+        TokenKind::Access,
+        AppConfig::current().token_retention,
+    )
+    .await?;
+    if pruned > 0 {
+        debug!("Deleted {} old access token(s)", pruned);
+    }
+
+    debug!("Successfully stored new access token in database");
+    Ok(())
+}
+
+/// Deletes all but the newest `keep` bot tokens of one kind.
+///
+/// Deleting a row does not erase its ciphertext: PostgreSQL keeps the dead row version
+/// until vacuum reclaims it, and the WAL and backups keep it longer. Rotating the
+/// encryption key and retiring the old one is what makes pruned tokens unreadable.
+///
+/// # Parameters
+///
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
+/// - `kind`: Prune `access_tokens` or `refresh_tokens`
+/// - `keep`: How many of the newest tokens to keep (at least 1)
+///
+/// # Returns
+///
+/// - `Ok(u64)`: The number of tokens deleted
+/// - `Err(ReputestError)`: If the delete fails
+pub async fn prune_tokens(
+    conn: &mut PgConnection,
+    kind: TokenKind,
+    keep: i64,
+) -> Result<u64, ReputestError> {
+    let table = match kind {
+        TokenKind::Access => "access_tokens",
+        TokenKind::Refresh => "refresh_tokens",
+    };
+
+    let result = sqlx::query(&format!(
+        r#"
+        DELETE FROM {table}
+        WHERE id IN (
+            SELECT id FROM {table}
+            ORDER BY created_at DESC, id DESC
+            OFFSET $1
+        )
+        "#
+    ))
+    .bind(keep.max(1))
+    .execute(&mut *conn)
     .await?;
 
-    if deleted.rows_affected() > 0 {
-        debug!("Cleaned up {} old access token(s)", deleted.rows_affected());
+    Ok(result.rows_affected())
+}
+
+/// Advisory lock key held while the bot's tokens are refreshed and saved, so processes
+/// sharing the database (the server, a second machine, the `refresh_token` binary) never
+/// refresh with the same refresh token at once.
+const TOKEN_REFRESH_LOCK_KEY: i64 = 0x7265_6672_6573_6801;
+
+/// The token refresh lock: a session-level advisory lock on a connection of its own.
+///
+/// The holder reloads and saves the tokens on that connection ([`TokenRefreshLock::conn`]),
+/// so a refresh needs no second connection from the pool, and no transaction is open while
+/// the token endpoint is called. A lock dropped without [`TokenRefreshLock::release`]
+/// closes its connection instead of returning it to the pool, which ends the database
+/// session and so releases the lock.
+pub struct TokenRefreshLock {
+    conn: Option<PoolConnection<Postgres>>,
+}
+
+impl TokenRefreshLock {
+    /// The connection holding the lock.
+    pub fn conn(&mut self) -> &mut PgConnection {
+        self.conn
+            .as_deref_mut()
+            .expect("the connection is only taken on release or drop")
+    }
+
+    /// Releases the lock and returns its connection to the pool.
+    pub async fn release(mut self) -> Result<(), ReputestError> {
+        sqlx::query("SELECT pg_advisory_unlock($1)")
+            .bind(TOKEN_REFRESH_LOCK_KEY)
+            .execute(self.conn())
+            .await?;
+        self.conn.take();
+        Ok(())
+    }
+}
+
+impl Drop for TokenRefreshLock {
+    fn drop(&mut self) {
+        if let Some(conn) = self.conn.take() {
+            warn!("Token refresh lock dropped while held, closing its connection");
+            drop(conn.detach());
+        }
+    }
+}
+
+/// Waits for the token refresh lock.
+///
+/// Whoever holds it reloads the latest tokens before refreshing, since another process
+/// may have refreshed them while it waited.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+///
+/// # Returns
+///
+/// - `Ok(TokenRefreshLock)`: The lock, held on one connection of the pool
+/// - `Err(ReputestError)`: If the lock cannot be taken
+pub async fn lock_token_refresh(pool: &PgPool) -> Result<TokenRefreshLock, ReputestError> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(TOKEN_REFRESH_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    Ok(TokenRefreshLock { conn: Some(conn) })
+}
+
+/// Why the bot's access token was refreshed, stored in `token_refreshes.reason`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TokenRefreshReason {
+    /// X answered 401 Unauthorized with the stored access token
+    Unauthorized,
+    /// The access token was about to expire
+    Expiring,
+    /// Someone ran the `refresh_token` binary
+    #[allow(dead_code)] // Used by the refresh_token binary
+    Manual,
+}
+
+impl TokenRefreshReason {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            TokenRefreshReason::Unauthorized => "unauthorized",
+            TokenRefreshReason::Expiring => "expiring",
+            TokenRefreshReason::Manual => "manual",
+        }
     }
+}
 
+/// Records an attempt to refresh the bot's access token in `token_refreshes`.
+///
+/// # Parameters
+///
+/// - `conn`: A database connection, e.g. the one holding the [`TokenRefreshLock`]
+/// - `reason`: Why the token was refreshed
+/// - `triggered_by`: The X API operation that refreshed it, e.g. `search_mentions`
+/// - `succeeded`: Whether X issued a new access token
+/// - `refresh_rotated`: Whether X also issued a new refresh token
+/// - `error`: Why the refresh failed, or which of the new tokens could not be saved
+pub async fn record_token_refresh(
+    conn: &mut PgConnection,
+    reason: TokenRefreshReason,
+    triggered_by: &str,
+    succeeded: bool,
+    refresh_rotated: bool,
+    error: Option<&str>,
+) -> Result<(), ReputestError> {
     sqlx::query(
         r#"
-        INSERT INTO access_tokens (token, created_at)
-        VALUES ($1, NOW())
+        INSERT INTO token_refreshes (reason, triggered_by, outcome, refresh_rotated, error)
+        VALUES ($1, $2, $3, $4, $5)
         "#,
     )
-    .bind(&token_to_store)
-    .execute(pool)
+    .bind(reason.as_str())
+    .bind(triggered_by)
+    .bind(if succeeded { "succeeded" } else { "failed" })
+    .bind(refresh_rotated)
+    .bind(error)
+    .execute(&mut *conn)
     .await?;
-
-    debug!("Successfully stored new access token in database");
     Ok(())
 }
 
+/// A recorded refresh of the bot's access token, for the admin console.
+#[derive(Debug)]
+pub struct TokenRefreshRow {
+    pub refreshed_at: chrono::DateTime<chrono::Utc>,
+    pub reason: String,
+    pub triggered_by: String,
+    pub outcome: String,
+    pub refresh_rotated: bool,
+    pub error: Option<String>,
+}
+
+/// Retrieves the most recent refreshes of the bot's access token, newest first.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `limit`: The maximum number of refreshes to return
+pub async fn get_recent_token_refreshes(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<TokenRefreshRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT refreshed_at, reason, triggered_by, outcome, refresh_rotated, error
+        FROM token_refreshes
+        ORDER BY refreshed_at DESC, id DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| TokenRefreshRow {
+            refreshed_at: row.get("refreshed_at"),
+            reason: row.get("reason"),
+            triggered_by: row.get("triggered_by"),
+            outcome: row.get("outcome"),
+            refresh_rotated: row.get("refresh_rotated"),
+            error: row.get("error"),
+        })
+        .collect())
+}
+
 /// Data for a web login session (decrypted tokens).
 #[allow(dead_code)] // Fields are part of the session data model; some may not be read yet
 #[derive(Clone, Debug)]
@@ -269,32 +539,47 @@ pub struct WebSession {
     pub username: String,
     pub access_token: String,
     pub refresh_token: Option<String>,
+    /// When the access token expires, if X told us
+    pub token_expires_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// OAuth 2.0 tokens of a web session, as issued by X.
+#[derive(Clone, Debug)]
+pub struct SessionTokens {
+    pub access_token: String,
+    pub refresh_token: Option<String>,
+    /// When the access token expires, if known
+    pub expires_at: Option<chrono::DateTime<chrono::Utc>>,
 }
 
 /// Creates a new web session and returns its ID.
+///
+/// `expires_at` is when the session itself ends; `user_agent` identifies the browser on
+/// the sessions page.
 pub async fn create_session(
     pool: &PgPool,
     user_id: &str,
     username: &str,
-    access_token: &str,
-    refresh_token: Option<&str>,
+    tokens: &SessionTokens,
     expires_at: chrono::DateTime<chrono::Utc>,
-) -> Result<sqlx::types::Uuid, Box<dyn std::error::Error + Send + Sync>> {
+    user_agent: Option<&str>,
+) -> Result<sqlx::types::Uuid, ReputestError> {
     if !is_encryption_configured() {
-        return Err(
+        return Err(ReputestError::Config(
             "TOKEN_ENCRYPTION_KEY must be set - refusing to store session tokens in plaintext"
-                .into(),
-        );
+                .to_string(),
+        ));
     }
 
     let id = sqlx::types::Uuid::new_v4();
-    let access_enc = encrypt_token(access_token)?;
-    let refresh_enc: Option<String> = refresh_token.map(encrypt_token).transpose()?;
+    let (access_enc, refresh_enc) = encrypt_session_tokens(id, tokens)?;
 
     sqlx::query(
         r#"
-        INSERT INTO sessions (id, user_id, username, access_token, refresh_token, expires_at)
-        VALUES ($1, $2, $3, $4, $5, $6)
+        INSERT INTO sessions
+            (id, user_id, username, access_token, refresh_token, token_expires_at, expires_at,
+             user_agent)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
         "#,
     )
     .bind(id)
@@ -302,7 +587,9 @@ pub async fn create_session(
     .bind(username)
     .bind(&access_enc)
     .bind(refresh_enc.as_deref())
+    .bind(tokens.expires_at)
     .bind(expires_at)
+    .bind(user_agent)
     .execute(pool)
     .await?;
 
@@ -310,51 +597,332 @@ pub async fn create_session(
     Ok(id)
 }
 
-/// Retrieves a session by ID if it exists and is not expired. Tokens are decrypted.
-pub async fn get_session_by_id(
+/// Encrypts a session's tokens, bound to the session.
+fn encrypt_session_tokens(
+    id: sqlx::types::Uuid,
+    tokens: &SessionTokens,
+) -> Result<(String, Option<String>), ReputestError> {
+    let access_enc = encrypt_token(
+        &tokens.access_token,
+        &TokenContext::new("sessions", id, TokenKind::Access),
+    )?;
+    let refresh_enc = tokens
+        .refresh_token
+        .as_deref()
+        .map(|token| {
+            encrypt_token(
+                token,
+                &TokenContext::new("sessions", id, TokenKind::Refresh),
+            )
+        })
+        .transpose()?;
+    Ok((access_enc, refresh_enc))
+}
+
+/// A session row locked for a token refresh (`SELECT ... FOR UPDATE`).
+///
+/// Other requests of the same session wait for the lock, whether they refresh or only load
+/// the session, and then see the new tokens; other sessions are not affected. The lock is
+/// held until [`SessionLock::save_tokens`] or [`SessionLock::release`], or until it is
+/// dropped.
+pub struct SessionLock {
+    tx: Transaction<'static, Postgres>,
+    /// The session as stored when the lock was taken
+    pub session: WebSession,
+}
+
+impl SessionLock {
+    /// Stores the session's refreshed tokens and releases the lock.
+    ///
+    /// The stored refresh token is kept when `tokens` has none (X did not rotate it).
+    pub async fn save_tokens(mut self, tokens: &SessionTokens) -> Result<(), ReputestError> {
+        let id = self.session.id;
+        let (access_enc, refresh_enc) = encrypt_session_tokens(id, tokens)?;
+        sqlx::query(
+            r#"
+            UPDATE sessions
+            SET access_token = $2,
+                refresh_token = COALESCE($3, refresh_token),
+                token_expires_at = $4
+            WHERE id = $1
+            "#,
+        )
+        .bind(id)
+        .bind(&access_enc)
+        .bind(refresh_enc.as_deref())
+        .bind(tokens.expires_at)
+        .execute(&mut *self.tx)
+        .await?;
+        self.tx.commit().await?;
+        debug!("Updated tokens of web session {}", id);
+        Ok(())
+    }
+
+    /// Releases the lock without changing the session.
+    pub async fn release(self) -> Result<(), ReputestError> {
+        self.tx.commit().await?;
+        Ok(())
+    }
+}
+
+/// Locks an unexpired session for a token refresh and loads it, waiting for any refresh
+/// of the same session in progress.
+///
+/// # Returns
+///
+/// - `Ok(Some(SessionLock))`: The lock, with the session's current (decrypted) tokens
+/// - `Ok(None)`: If the session does not exist or has expired
+/// - `Err(ReputestError)`: If encryption is not configured, or the query or decryption fails
+pub async fn lock_session(
     pool: &PgPool,
     id: sqlx::types::Uuid,
-) -> Result<Option<WebSession>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Option<SessionLock>, ReputestError> {
     if !is_encryption_configured() {
-        return Err("TOKEN_ENCRYPTION_KEY is required to read session tokens".into());
+        return Err(ReputestError::Config(
+            "TOKEN_ENCRYPTION_KEY is required to read session tokens".to_string(),
+        ));
     }
 
+    let mut tx = pool.begin().await?;
     let row = sqlx::query(
         r#"
-        SELECT id, user_id, username, access_token, refresh_token
+        SELECT id, user_id, username, access_token, refresh_token, token_expires_at
         FROM sessions
         WHERE id = $1 AND expires_at > NOW()
+        FOR UPDATE
         "#,
     )
     .bind(id)
-    .fetch_optional(pool)
+    .fetch_optional(&mut *tx)
     .await?;
 
     match row {
-        Some(row) => {
-            let access_token = decrypt_token(&row.get::<String, _>("access_token"))?;
-            let refresh_token = row
-                .get::<Option<String>, _>("refresh_token")
-                .filter(|s| !s.is_empty())
-                .map(|s| decrypt_token(&s))
-                .transpose()?;
-            Ok(Some(WebSession {
-                id: row.get("id"),
-                user_id: row.get("user_id"),
-                username: row.get("username"),
-                access_token,
-                refresh_token,
-            }))
-        }
+        Some(row) => Ok(Some(SessionLock {
+            tx,
+            session: session_from_row(&row)?,
+        })),
         None => Ok(None),
     }
 }
 
-/// Deletes a session by ID (e.g. on logout).
-pub async fn delete_session(
+/// Builds a [`WebSession`] from a `sessions` row, decrypting its tokens.
+fn session_from_row(row: &sqlx::postgres::PgRow) -> Result<WebSession, ReputestError> {
+    let id: sqlx::types::Uuid = row.get("id");
+    let access_token = decrypt_token(
+        &row.get::<String, _>("access_token"),
+        &TokenContext::new("sessions", id, TokenKind::Access),
+    )?;
+    let refresh_token = row
+        .get::<Option<String>, _>("refresh_token")
+        .filter(|s| !s.is_empty())
+        .map(|s| decrypt_token(&s, &TokenContext::new("sessions", id, TokenKind::Refresh)))
+        .transpose()?;
+    Ok(WebSession {
+        id,
+        user_id: row.get("user_id"),
+        username: row.get("username"),
+        access_token,
+        refresh_token,
+        token_expires_at: row.get("token_expires_at"),
+    })
+}
+
+/// Retrieves a session by ID if it exists and is not expired, and marks it as used now.
+/// Tokens are decrypted.
+pub async fn get_session_by_id(
     pool: &PgPool,
     id: sqlx::types::Uuid,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Option<WebSession>, ReputestError> {
+    if !is_encryption_configured() {
+        return Err(ReputestError::Config(
+            "TOKEN_ENCRYPTION_KEY is required to read session tokens".to_string(),
+        ));
+    }
+
+    let row = sqlx::query(
+        r#"
+        UPDATE sessions
+        SET last_seen_at = NOW()
+        WHERE id = $1 AND expires_at > NOW()
+        RETURNING id, user_id, username, access_token, refresh_token, token_expires_at
+        "#,
+    )
+    .bind(id)
+    .fetch_optional(pool)
+    .await?;
+
+    row.as_ref().map(session_from_row).transpose()
+}
+
+/// An active web session, as listed on the sessions page (without its tokens).
+#[derive(Debug)]
+pub struct SessionSummary {
+    pub id: sqlx::types::Uuid,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub last_seen_at: chrono::DateTime<chrono::Utc>,
+    pub expires_at: chrono::DateTime<chrono::Utc>,
+    pub user_agent: Option<String>,
+}
+
+/// Retrieves the unexpired sessions of a user, most recently used first.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `user_id`: The X user ID whose sessions to list
+pub async fn get_user_sessions(
+    pool: &PgPool,
+    user_id: &str,
+) -> Result<Vec<SessionSummary>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, created_at, last_seen_at, expires_at, user_agent
+        FROM sessions
+        WHERE user_id = $1 AND expires_at > NOW()
+        ORDER BY last_seen_at DESC, created_at DESC
+        "#,
+    )
+    .bind(user_id)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SessionSummary {
+            id: row.get("id"),
+            created_at: row.get("created_at"),
+            last_seen_at: row.get("last_seen_at"),
+            expires_at: row.get("expires_at"),
+            user_agent: row.get("user_agent"),
+        })
+        .collect())
+}
+
+/// Rows re-encrypted by [`reencrypt_tokens`], per table.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct KeyRotationReport {
+    pub access_tokens: u64,
+    pub refresh_tokens: u64,
+    pub sessions: u64,
+    /// Rows left alone because no key of the keyring decrypts them
+    pub failed: u64,
+}
+
+/// Re-encrypts every stored token that is not sealed with the keyring's current key or
+/// not bound to its row yet.
+///
+/// Covers `access_tokens`, `refresh_tokens` and both tokens of `sessions`. This is both
+/// the second step of a key rotation and the migration of tokens written before they were
+/// bound to their row (see [`TokenContext`]); the server runs it at startup. Each row is
+/// updated only if it still holds the ciphertext that was read, so tokens written
+/// concurrently are never overwritten. Rows that no key decrypts are logged, counted in
+/// `failed` and left unchanged; run again once the missing key is configured.
+///
+/// # Returns
+///
+/// - `Ok(KeyRotationReport)`: How many rows were re-encrypted or could not be
+/// - `Err(ReputestError)`: If a query fails
+pub async fn reencrypt_tokens(
+    pool: &PgPool,
+    keyring: &Keyring,
+) -> Result<KeyRotationReport, ReputestError> {
+    let mut report = KeyRotationReport::default();
+
+    for (table, kind) in [
+        ("access_tokens", TokenKind::Access),
+        ("refresh_tokens", TokenKind::Refresh),
+    ] {
+        let rows = sqlx::query(&format!("SELECT id, token FROM {} ORDER BY id", table))
+            .fetch_all(pool)
+            .await?;
+        for row in rows {
+            let id: i32 = row.get("id");
+            let stored: String = row.get("token");
+            if !keyring.needs_reencryption(&stored) {
+                continue;
+            }
+            let token = match keyring.reencrypt(&stored, &TokenContext::new(table, id, kind)) {
+                Ok(token) => token,
+                Err(e) => {
+                    warn!("Cannot re-encrypt {} row {}: {}", table, id, e);
+                    report.failed += 1;
+                    continue;
+                }
+            };
+            let result = sqlx::query(&format!(
+                "UPDATE {} SET token = $1 WHERE id = $2 AND token = $3",
+                table
+            ))
+            .bind(token)
+            .bind(id)
+            .bind(&stored)
+            .execute(pool)
+            .await?;
+            match kind {
+                TokenKind::Access => report.access_tokens += result.rows_affected(),
+                TokenKind::Refresh => report.refresh_tokens += result.rows_affected(),
+            }
+        }
+    }
+
+    let rows = sqlx::query("SELECT id, access_token, refresh_token FROM sessions")
+        .fetch_all(pool)
+        .await?;
+    for row in rows {
+        let id: sqlx::types::Uuid = row.get("id");
+        let access_stored: String = row.get("access_token");
+        let refresh_stored: Option<String> = row
+            .get::<Option<String>, _>("refresh_token")
+            .filter(|s| !s.is_empty());
+        if !keyring.needs_reencryption(&access_stored)
+            && !refresh_stored
+                .as_deref()
+                .is_some_and(|s| keyring.needs_reencryption(s))
+        {
+            continue;
+        }
+        let reencrypted = keyring
+            .reencrypt(
+                &access_stored,
+                &TokenContext::new("sessions", id, TokenKind::Access),
+            )
+            .and_then(|access| {
+                let refresh = refresh_stored
+                    .as_deref()
+                    .map(|s| {
+                        keyring.reencrypt(s, &TokenContext::new("sessions", id, TokenKind::Refresh))
+                    })
+                    .transpose()?;
+                Ok((access, refresh))
+            });
+        let (access, refresh) = match reencrypted {
+            Ok(tokens) => tokens,
+            Err(e) => {
+                warn!("Cannot re-encrypt session {}: {}", id, e);
+                report.failed += 1;
+                continue;
+            }
+        };
+        let result = sqlx::query(
+            r#"
+            UPDATE sessions SET access_token = $1, refresh_token = $2
+            WHERE id = $3 AND access_token = $4
+            "#,
+        )
+        .bind(access)
+        .bind(refresh)
+        .bind(id)
+        .bind(&access_stored)
+        .execute(pool)
+        .await?;
+        report.sessions += result.rows_affected();
+    }
+
+    Ok(report)
+}
+
+/// Deletes a session by ID (e.g. on logout).
+pub async fn delete_session(pool: &PgPool, id: sqlx::types::Uuid) -> Result<(), ReputestError> {
     sqlx::query("DELETE FROM sessions WHERE id = $1")
         .bind(id)
         .execute(pool)
@@ -365,9 +933,7 @@ pub async fn delete_session(
 
 /// Deletes expired sessions (optional cleanup).
 #[allow(dead_code)] // Infrastructure for future cronjob/maintenance task
-pub async fn delete_expired_sessions(
-    pool: &PgPool,
-) -> Result<u64, Box<dyn std::error::Error + Send + Sync>> {
+pub async fn delete_expired_sessions(pool: &PgPool) -> Result<u64, ReputestError> {
     let result = sqlx::query("DELETE FROM sessions WHERE expires_at <= NOW()")
         .execute(pool)
         .await?;
@@ -395,14 +961,14 @@ pub async fn delete_expired_sessions(
 /// # Returns
 ///
 /// - `Ok(())`: If the vibes data was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert fails
+/// - `Err(ReputestError)`: If the insert fails
 pub async fn save_good_vibes(
     pool: &PgPool,
     tweet_id: &str,
     emitter_id: &str,
     sensor_id: &str,
     created_at: chrono::DateTime<chrono::Utc>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<(), ReputestError> {
     info!(
         "Storing good vibes data in database: tweet {} from {} to {} at {}",
         tweet_id, emitter_id, sensor_id, created_at
@@ -434,10 +1000,10 @@ pub async fn save_good_vibes(
                 );
                 Ok(())
             } else {
-                Err(Box::new(sqlx::Error::Database(db_err)))
+                Err(sqlx::Error::Database(db_err).into())
             }
         }
-        Err(e) => Err(Box::new(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -445,9 +1011,10 @@ pub async fn save_good_vibes(
 ///
 /// This function inserts or updates user information in the users table.
 /// It uses ON CONFLICT to handle cases where the user already exists.
-/// When `follower_count` is provided (e.g. from Twitter API public_metrics),
-/// it is used for both insert and update; otherwise the column default (0) is used
-/// on insert, and the existing value is preserved on update.
+/// When `api_follower_count` is provided (e.g. from Twitter API public_metrics),
+/// it is stored in `api_follower_count` together with the fetch time; otherwise the
+/// existing API count is preserved. `known_follower_count` is never written here: it is
+/// maintained by a trigger on the following table.
 ///
 /// # Parameters
 ///
@@ -456,44 +1023,49 @@ pub async fn save_good_vibes(
 /// - `username`: The Twitter username
 /// - `name`: The Twitter display name
 /// - `created_at`: The timestamp when the user account was created
-/// - `follower_count`: Optional follower count from Twitter API public_metrics
+/// - `api_follower_count`: Optional follower count from Twitter API public_metrics
 ///
 /// # Returns
 ///
 /// - `Ok(())`: If the user data was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert/update fails
+/// - `Err(ReputestError)`: If the insert/update fails
 pub async fn save_user(
     pool: &PgPool,
     user_id: &str,
     username: &str,
     name: &str,
     created_at: chrono::DateTime<chrono::Utc>,
-    follower_count: Option<i32>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+    api_follower_count: Option<i32>,
+) -> Result<(), ReputestError> {
     debug!(
-        "Upserting user {} (@{}) created at {}, follower_count: {:?}",
-        name, username, created_at, follower_count
+        "Upserting user {} (@{}) created at {}, api_follower_count: {:?}",
+        name, username, created_at, api_follower_count
     );
 
     // RETURNING (xmax = 0) AS inserted lets us distinguish insert vs update.
     // The WHERE clause on the UPDATE branch makes the upsert a no-op (returns no row)
-    // when all of (username, name, created_at, follower_count) already match, which
+    // when all of (username, name, created_at, api_follower_count) already match, which
     // avoids touching the row and avoids verbose logs for the common steady-state case.
-    let row = match follower_count {
+    // An unchanged API count still bumps api_follower_count_fetched_at once it is a day old,
+    // so the timestamp stays meaningful without rewriting the row on every fetch.
+    let row = match api_follower_count {
         Some(count) => {
             sqlx::query(
                 r#"
-                INSERT INTO users (id, username, name, created_at, follower_count)
-                VALUES ($1, $2, $3, $4, $5)
+                INSERT INTO users (id, username, name, created_at, api_follower_count, api_follower_count_fetched_at)
+                VALUES ($1, $2, $3, $4, $5, NOW())
                 ON CONFLICT (id) DO UPDATE SET
                     username = EXCLUDED.username,
                     name = EXCLUDED.name,
                     created_at = EXCLUDED.created_at,
-                    follower_count = EXCLUDED.follower_count
-                WHERE users.username     IS DISTINCT FROM EXCLUDED.username
-                   OR users.name         IS DISTINCT FROM EXCLUDED.name
-                   OR users.created_at   IS DISTINCT FROM EXCLUDED.created_at
-                   OR users.follower_count IS DISTINCT FROM EXCLUDED.follower_count
+                    api_follower_count = EXCLUDED.api_follower_count,
+                    api_follower_count_fetched_at = EXCLUDED.api_follower_count_fetched_at
+                WHERE users.username           IS DISTINCT FROM EXCLUDED.username
+                   OR users.name               IS DISTINCT FROM EXCLUDED.name
+                   OR users.created_at         IS DISTINCT FROM EXCLUDED.created_at
+                   OR users.api_follower_count IS DISTINCT FROM EXCLUDED.api_follower_count
+                   OR users.api_follower_count_fetched_at IS NULL
+                   OR users.api_follower_count_fetched_at < NOW() - INTERVAL '1 day'
                 RETURNING (xmax = 0) AS inserted
                 "#,
             )
@@ -534,13 +1106,13 @@ pub async fn save_user(
             let inserted: bool = r.try_get("inserted").unwrap_or(false);
             if inserted {
                 info!(
-                    "Inserted new user row: {} (@{}, id={}) created at {}, follower_count: {:?}",
-                    name, username, user_id, created_at, follower_count
+                    "Inserted new user row: {} (@{}, id={}) created at {}, api_follower_count: {:?}",
+                    name, username, user_id, created_at, api_follower_count
                 );
             } else {
                 info!(
-                    "Updated user row: {} (@{}, id={}) created at {}, follower_count: {:?}",
-                    name, username, user_id, created_at, follower_count
+                    "Updated user row: {} (@{}, id={}) created at {}, api_follower_count: {:?}",
+                    name, username, user_id, created_at, api_follower_count
                 );
             }
         }
@@ -566,10 +1138,8 @@ pub async fn save_user(
 /// # Returns
 ///
 /// - `Ok(i64)`: The count of good vibes records
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
-pub async fn get_good_vibes_count(
-    pool: &PgPool,
-) -> Result<i64, Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_good_vibes_count(pool: &PgPool) -> Result<i64, ReputestError> {
     info!("Querying database for good vibes count");
 
     let count: i64 = sqlx::query_scalar(
@@ -600,12 +1170,12 @@ pub async fn get_good_vibes_count(
 ///
 /// - `Ok(true)`: If a good vibes record exists between the sensor and emitter
 /// - `Ok(false)`: If no good vibes record exists between the sensor and emitter
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn has_good_vibes_record(
     pool: &PgPool,
     sensor_id: &str,
     emitter_id: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<bool, ReputestError> {
     info!(
         "Checking for good vibes record between sensor {} and emitter {}",
         sensor_id, emitter_id
@@ -646,12 +1216,12 @@ pub async fn has_good_vibes_record(
 ///
 /// - `Ok(Some(tweet_id))`: If a good vibes record exists, returns the tweet_id
 /// - `Ok(None)`: If no good vibes record exists between these users
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_good_vibes_tweet_id(
     pool: &PgPool,
     emitter_id: &str,
     sensor_id: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Option<String>, ReputestError> {
     info!(
         "Getting tweet ID for good vibes record between emitter {} and sensor {}",
         emitter_id, sensor_id
@@ -690,11 +1260,11 @@ pub async fn get_good_vibes_tweet_id(
 ///
 /// - `Ok(Some(user_id))`: The user ID if the username exists
 /// - `Ok(None)`: If the username is not found
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_user_id_by_username(
     pool: &PgPool,
     username: &str,
-) -> Result<Option<String>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Option<String>, ReputestError> {
     info!("Looking up user ID for username: {}", username);
 
     let user_id: Option<String> = sqlx::query_scalar(
@@ -728,14 +1298,11 @@ pub async fn get_user_id_by_username(
 ///
 /// - `Ok(Some((user_id, name, created_at)))`: Complete user information if found
 /// - `Ok(None)`: If the username is not found in the database
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_user_info_by_username(
     pool: &PgPool,
     username: &str,
-) -> Result<
-    Option<(String, String, chrono::DateTime<chrono::Utc>)>,
-    Box<dyn std::error::Error + Send + Sync>,
-> {
+) -> Result<Option<(String, String, chrono::DateTime<chrono::Utc>)>, ReputestError> {
     info!("Looking up complete user info for username: {}", username);
 
     let result: Option<(String, String, chrono::DateTime<chrono::Utc>)> = sqlx::query_as(
@@ -772,11 +1339,8 @@ pub async fn get_user_info_by_username(
 ///
 /// - `Ok(true)`: If the tweet ID exists in the vibe_requests table
 /// - `Ok(false)`: If the tweet ID does not exist in the vibe_requests table
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
-pub async fn has_vibe_request(
-    pool: &PgPool,
-    tweet_id: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If the query fails
+pub async fn has_vibe_request(pool: &PgPool, tweet_id: &str) -> Result<bool, ReputestError> {
     info!(
         "Checking if tweet {} has been processed for vibe request",
         tweet_id
@@ -803,8 +1367,9 @@ pub async fn has_vibe_request(
 
 /// Checks if a tweet ID exists in the good_vibes table.
 ///
-/// This function queries the good_vibes table to see if the given tweet_id
-/// has already been processed for good vibes data.
+/// This function queries the good_vibes table (and the edge categories a tweet may have
+/// added to an existing edge) to see if the given tweet_id has already been processed
+/// for good vibes data.
 ///
 /// # Parameters
 ///
@@ -815,11 +1380,8 @@ pub async fn has_vibe_request(
 ///
 /// - `Ok(true)`: If the tweet ID exists in the good_vibes table
 /// - `Ok(false)`: If the tweet ID does not exist in the good_vibes table
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
-pub async fn has_good_vibes_tweet(
-    pool: &PgPool,
-    tweet_id: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If the query fails
+pub async fn has_good_vibes_tweet(pool: &PgPool, tweet_id: &str) -> Result<bool, ReputestError> {
     info!(
         "Checking if tweet {} has already been processed for good vibes",
         tweet_id
@@ -830,6 +1392,9 @@ pub async fn has_good_vibes_tweet(
         SELECT EXISTS(
             SELECT 1 FROM good_vibes
             WHERE tweet_id = $1
+        ) OR EXISTS(
+            SELECT 1 FROM good_vibes_categories
+            WHERE tweet_id = $1
         ) as exists
         "#,
     )
@@ -857,11 +1422,8 @@ pub async fn has_good_vibes_tweet(
 /// # Returns
 ///
 /// - `Ok(())`: If the vibe request was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert fails
-pub async fn save_vibe_request(
-    pool: &PgPool,
-    tweet_id: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn save_vibe_request(pool: &PgPool, tweet_id: &str) -> Result<(), ReputestError> {
     info!("Storing vibe request for tweet {} in database", tweet_id);
 
     sqlx::query(
@@ -881,6 +1443,43 @@ pub async fn save_vibe_request(
     Ok(())
 }
 
+/// Removes a tweet from the vibe_requests table so that it is processed again.
+///
+/// Used to reprocess a tweet from the admin console. A declaration that was revoked keeps
+/// its row (see [`get_vibe_request_revocation`]): the row is what stops the pipeline from
+/// recording the revoked edge again.
+///
+/// # Returns
+///
+/// - `Ok(bool)`: Whether a row was removed
+/// - `Err(ReputestError)`: If the delete fails
+pub async fn clear_vibe_request(pool: &PgPool, tweet_id: &str) -> Result<bool, ReputestError> {
+    let result =
+        sqlx::query("DELETE FROM vibe_requests WHERE tweet_id = $1 AND revoked_by IS NULL")
+            .bind(tweet_id)
+            .execute(pool)
+            .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns the revocation tweet that revoked what a declaration tweet recorded, if any.
+///
+/// # Returns
+///
+/// - `Ok(Some(String))`: The ID of the revocation tweet
+/// - `Ok(None)`: If the tweet is not a revoked declaration
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_vibe_request_revocation(
+    pool: &PgPool,
+    tweet_id: &str,
+) -> Result<Option<String>, ReputestError> {
+    let revoked_by = sqlx::query_scalar("SELECT revoked_by FROM vibe_requests WHERE tweet_id = $1")
+        .bind(tweet_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(revoked_by.flatten())
+}
+
 /// Retrieves all good vibes relationships as an adjacency list.
 ///
 /// This function queries the good_vibes table and returns a HashMap where
@@ -895,11 +1494,11 @@ pub async fn save_vibe_request(
 /// # Returns
 ///
 /// - `Ok(HashMap<String, Vec<String>>)`: Adjacency list representation of the graph
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 #[allow(dead_code)]
 pub async fn get_good_vibes_graph(
     pool: &PgPool,
-) -> Result<HashMap<String, Vec<String>>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<HashMap<String, Vec<String>>, ReputestError> {
     info!("Building good vibes graph from database");
 
     let rows = sqlx::query(
@@ -945,7 +1544,7 @@ pub async fn get_good_vibes_graph(
 ///
 /// - `Ok(Some(distance))`: The shortest path distance if a path exists
 /// - `Ok(None)`: If no path exists between the users
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 ///
 /// # Example
 ///
@@ -959,7 +1558,7 @@ pub async fn get_vibe_distance(
     source_user_id: &str,
     target_user_id: &str,
     max_depth: usize,
-) -> Result<Option<usize>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Option<usize>, ReputestError> {
     info!(
         "Calculating vibe distance from {} to {} (max depth: {})",
         source_user_id, target_user_id, max_depth
@@ -1028,12 +1627,12 @@ pub async fn get_vibe_distance(
 ///
 /// - `Ok(1)`: Direct connection exists
 /// - `Ok(0)`: No direct connection
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_one(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating first-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1075,12 +1674,12 @@ pub async fn get_vibe_score_one(
 /// # Returns
 ///
 /// - `Ok(count)`: Number of paths of length 2
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_two(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating second-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1121,12 +1720,12 @@ pub async fn get_vibe_score_two(
 /// # Returns
 ///
 /// - `Ok(count)`: Number of paths of length 3
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_three(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating third-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1167,12 +1766,12 @@ pub async fn get_vibe_score_three(
 /// # Returns
 ///
 /// - `Ok(count)`: Number of paths of length 4
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_four(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating fourth-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1213,12 +1812,12 @@ pub async fn get_vibe_score_four(
 /// # Returns
 ///
 /// - `Ok(count)`: Number of paths of length 5
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_five(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating fifth-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1259,12 +1858,12 @@ pub async fn get_vibe_score_five(
 /// # Returns
 ///
 /// - `Ok(count)`: Number of paths of length 6
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_vibe_score_six(
     pool: &PgPool,
     sensor_user_id: &str,
     emitter_user_id: &str,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     info!(
         "Calculating sixth-degree vibe score for sensor {} from emitter {}",
         sensor_user_id, emitter_user_id
@@ -1306,7 +1905,7 @@ pub async fn get_vibe_score_six(
 /// # Returns
 ///
 /// - `Ok(score)`: The second-degree vibe score (for backward compatibility)
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the calculation fails
+/// - `Err(ReputestError)`: If the calculation fails
 #[allow(dead_code)]
 #[deprecated(note = "Use get_vibe_score_one, get_vibe_score_two, and get_vibe_score_three instead")]
 pub async fn get_vibe_score(
@@ -1314,7 +1913,7 @@ pub async fn get_vibe_score(
     sensor_user_id: &str,
     emitter_user_id: &str,
     _max_depth: usize,
-) -> Result<usize, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<usize, ReputestError> {
     get_vibe_score_two(pool, sensor_user_id, emitter_user_id).await
 }
 
@@ -1345,10 +1944,10 @@ pub struct AllGoodVibesDegrees {
 /// # Returns
 ///
 /// - `Ok(Vec<AllGoodVibesDegrees>)`: Vector of all rows from the view
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
+/// - `Err(ReputestError)`: If the query fails
 pub async fn get_all_good_vibes_degrees(
     pool: &PgPool,
-) -> Result<Vec<AllGoodVibesDegrees>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Vec<AllGoodVibesDegrees>, ReputestError> {
     info!("Querying view_all_good_vibes_degrees");
 
     let rows = sqlx::query(
@@ -1399,10 +1998,8 @@ pub async fn get_all_good_vibes_degrees(
 /// # Returns
 ///
 /// - `Ok(())`: If all refreshes completed successfully
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If any refresh fails
-pub async fn refresh_materialized_views(
-    pool: &PgPool,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If any refresh fails
+pub async fn refresh_materialized_views(pool: &PgPool) -> Result<(), ReputestError> {
     info!("Starting materialized view refresh");
 
     let start = std::time::Instant::now();
@@ -1437,7 +2034,7 @@ pub async fn refresh_materialized_views(
 /// # Returns
 ///
 /// - `Ok(())`: If the megajoule transfer was successfully stored
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the insert fails
+/// - `Err(ReputestError)`: If the insert fails
 pub async fn save_megajoule(
     pool: &PgPool,
     tweet_id: &str,
@@ -1445,7 +2042,7 @@ pub async fn save_megajoule(
     receiver_id: &str,
     amount: i32,
     created_at: chrono::DateTime<chrono::Utc>,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<(), ReputestError> {
     info!(
         "Megajoule INSERT executing: tweet_id={} sender_id={} receiver_id={} amount={} created_at={}",
         tweet_id, sender_id, receiver_id, amount, created_at
@@ -1487,10 +2084,10 @@ pub async fn save_megajoule(
                 );
                 Ok(())
             } else {
-                Err(Box::new(sqlx::Error::Database(db_err)))
+                Err(sqlx::Error::Database(db_err).into())
             }
         }
-        Err(e) => Err(Box::new(e)),
+        Err(e) => Err(e.into()),
     }
 }
 
@@ -1508,11 +2105,8 @@ pub async fn save_megajoule(
 ///
 /// - `Ok(true)`: If the tweet ID exists in the megajoule table
 /// - `Ok(false)`: If the tweet ID does not exist in the megajoule table
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the query fails
-pub async fn has_megajoule_tweet(
-    pool: &PgPool,
-    tweet_id: &str,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+/// - `Err(ReputestError)`: If the query fails
+pub async fn has_megajoule_tweet(pool: &PgPool, tweet_id: &str) -> Result<bool, ReputestError> {
     info!(
         "Checking if tweet {} has already been processed for megajoules",
         tweet_id
@@ -1546,7 +2140,7 @@ pub async fn save_following(
     follower_id: &str,
     followed_id: &str,
     created_at: chrono::DateTime<chrono::Utc>,
-) -> Result<bool, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<bool, ReputestError> {
     let result = sqlx::query(
         r#"
         INSERT INTO following (follower, followed, created_at)
@@ -1563,40 +2157,47 @@ pub async fn save_following(
     Ok(result.rows_affected() > 0)
 }
 
-/// Increments the follower_count for a user (when they appear as followed).
-pub async fn increment_follower_count(
-    pool: &PgPool,
-    user_id: &str,
-) -> Result<(), Box<dyn std::error::Error + Send + Sync>> {
-    sqlx::query(
-        r#"
-        UPDATE users SET follower_count = follower_count + 1 WHERE id = $1
-        "#,
-    )
-    .bind(user_id)
-    .execute(pool)
-    .await?;
+/// Recomputes `known_follower_count` for all users from the following table.
+///
+/// The count is normally kept up to date by a trigger on the following table; this
+/// repairs any drift (e.g. rows changed while the trigger was disabled or by a manual fix).
+///
+/// # Returns
+///
+/// - `Ok(())`: If the recomputation completed
+/// - `Err(ReputestError)`: If the query fails
+pub async fn recompute_known_follower_counts(pool: &PgPool) -> Result<(), ReputestError> {
+    info!("Recomputing known follower counts from the following table");
+    sqlx::query("SELECT recompute_known_follower_counts()")
+        .execute(pool)
+        .await?;
     Ok(())
 }
 
 /// Row for the following page display.
+///
+/// `is_trusted` marks a trusted follow: the follower has also declared good vibes
+/// from the followed account (good_vibes row with sensor = follower, emitter = followed).
 #[derive(Debug)]
 pub struct FollowingRow {
     pub follower_username: String,
     pub followed_username: String,
     pub created_at: chrono::DateTime<chrono::Utc>,
+    pub is_trusted: bool,
 }
 
 /// Retrieves all following relationships with usernames for the /following page.
-pub async fn get_all_following(
-    pool: &PgPool,
-) -> Result<Vec<FollowingRow>, Box<dyn std::error::Error + Send + Sync>> {
+pub async fn get_all_following(pool: &PgPool) -> Result<Vec<FollowingRow>, ReputestError> {
     let rows = sqlx::query(
         r#"
         SELECT
             follower_u.username AS follower_username,
             followed_u.username AS followed_username,
-            f.created_at
+            f.created_at,
+            EXISTS (
+                SELECT 1 FROM good_vibes g
+                WHERE g.sensor_id = f.follower AND g.emitter_id = f.followed
+            ) AS is_trusted
         FROM following f
         JOIN users follower_u ON f.follower = follower_u.id
         JOIN users followed_u ON f.followed = followed_u.id
@@ -1612,6 +2213,1800 @@ pub async fn get_all_following(
             follower_username: row.get("follower_username"),
             followed_username: row.get("followed_username"),
             created_at: row.get("created_at"),
+            is_trusted: row.get("is_trusted"),
+        })
+        .collect())
+}
+
+/// Follower counts for a followed account, shown on the following page.
+#[derive(Debug)]
+pub struct FollowerCountRow {
+    pub username: String,
+    pub known_follower_count: i32,
+    pub api_follower_count: Option<i32>,
+    pub api_follower_count_fetched_at: Option<chrono::DateTime<chrono::Utc>>,
+}
+
+/// Retrieves known and API-reported follower counts for every account that appears
+/// as followed in the following table, ordered by known follower count (highest first).
+pub async fn get_follower_counts(pool: &PgPool) -> Result<Vec<FollowerCountRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT username, known_follower_count, api_follower_count, api_follower_count_fetched_at
+        FROM users
+        WHERE known_follower_count > 0
+        ORDER BY known_follower_count DESC, username ASC
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| FollowerCountRow {
+            username: row.get("username"),
+            known_follower_count: row.get("known_follower_count"),
+            api_follower_count: row.get("api_follower_count"),
+            api_follower_count_fetched_at: row.get("api_follower_count_fetched_at"),
+        })
+        .collect())
+}
+
+/// Retrieves the IDs of all accounts the given user is known to follow.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `follower_id`: The user ID whose following list to load
+///
+/// # Returns
+///
+/// - `Ok(HashSet<String>)`: User IDs followed by `follower_id` (empty if the following list was never fetched)
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_followed_ids(
+    pool: &PgPool,
+    follower_id: &str,
+) -> Result<HashSet<String>, ReputestError> {
+    let ids: Vec<String> = sqlx::query_scalar("SELECT followed FROM following WHERE follower = $1")
+        .bind(follower_id)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(ids.into_iter().collect())
+}
+
+/// Calculates trusted vibe scores (degrees 1-6) between two users.
+///
+/// A trusted score counts the same acyclic paths as the regular degree scores, but only
+/// paths whose intermediate accounts are all followed by the sensor (the querying user).
+/// The first-degree score has no intermediates and is therefore equal to the regular score.
+/// Counting is done in memory (see [`crate::graph::count_paths_by_degree`]) because the
+/// materialized views cannot be filtered by intermediate node.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `sensor_user_id`: The user ID of the querying user (sensor); their following list is the trust set
+/// - `emitter_user_id`: The user ID of the person being asked about (emitter)
+///
+/// # Returns
+///
+/// - `Ok(Vec<usize>)`: Six path counts, index 0 holding the first-degree score
+/// - `Err(ReputestError)`: If a query fails
+pub async fn get_trusted_vibe_scores(
+    pool: &PgPool,
+    sensor_user_id: &str,
+    emitter_user_id: &str,
+) -> Result<Vec<usize>, ReputestError> {
+    info!(
+        "Calculating trusted vibe scores for sensor {} from emitter {}",
+        sensor_user_id, emitter_user_id
+    );
+
+    let graph = get_good_vibes_graph(pool).await?;
+    let followed = get_followed_ids(pool, sensor_user_id).await?;
+
+    // The graph is emitter -> sensors, so walk from the emitter back to the sensor
+    let scores = crate::graph::count_paths_by_degree(
+        &graph,
+        emitter_user_id,
+        sensor_user_id,
+        crate::graph::MAX_DEGREE,
+        |id| followed.contains(id),
+    );
+
+    debug!(
+        "Trusted vibe scores for sensor {} from emitter {}: {:?} ({} followed accounts)",
+        sensor_user_id,
+        emitter_user_id,
+        scores,
+        followed.len()
+    );
+    Ok(scores)
+}
+
+/// Builds the good vibes graph restricted to the edges that carry a category.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `category`: The category label (e.g. `dev`)
+///
+/// # Returns
+///
+/// - `Ok(HashMap<String, Vec<String>>)`: Adjacency list (emitter -> sensors) of the category's edges
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_good_vibes_graph_in_category(
+    pool: &PgPool,
+    category: &str,
+) -> Result<HashMap<String, Vec<String>>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT emitter_id, sensor_id
+        FROM good_vibes_categories
+        WHERE category = $1
+        ORDER BY created_at
+        "#,
+    )
+    .bind(category)
+    .fetch_all(pool)
+    .await?;
+
+    let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        graph
+            .entry(row.get("emitter_id"))
+            .or_default()
+            .push(row.get("sensor_id"));
+    }
+    Ok(graph)
+}
+
+/// Calculates vibe scores for degrees 1 through 6 counting only edges in a category.
+///
+/// A category score counts the same acyclic paths as the regular degree scores, but every
+/// edge of a path must carry the category. Like [`get_trusted_vibe_scores`], counting is
+/// done in memory on the category's subgraph.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `sensor_user_id`: The user ID of the querying user (sensor)
+/// - `emitter_user_id`: The user ID of the person being asked about (emitter)
+/// - `category`: The category label
+///
+/// # Returns
+///
+/// - `Ok(Vec<usize>)`: Six path counts, index 0 holding the first-degree score
+/// - `Err(ReputestError)`: If a query fails
+pub async fn get_category_vibe_scores(
+    pool: &PgPool,
+    sensor_user_id: &str,
+    emitter_user_id: &str,
+    category: &str,
+) -> Result<Vec<usize>, ReputestError> {
+    info!(
+        "Calculating #{} vibe scores for sensor {} from emitter {}",
+        category, sensor_user_id, emitter_user_id
+    );
+
+    let graph = get_good_vibes_graph_in_category(pool, category).await?;
+    let scores = crate::graph::count_paths_by_degree(
+        &graph,
+        emitter_user_id,
+        sensor_user_id,
+        crate::graph::MAX_DEGREE,
+        |_| true,
+    );
+
+    debug!(
+        "#{} vibe scores for sensor {} from emitter {}: {:?}",
+        category, sensor_user_id, emitter_user_id, scores
+    );
+    Ok(scores)
+}
+
+/// Computes the dashboard rows (see [`get_all_good_vibes_degrees`]) for one category.
+///
+/// Path counts are computed in memory on the category's subgraph, so only pairs connected
+/// by edges in that category are returned. Rows are ordered like the view: by sensor
+/// username, then emitter username.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `category`: The category label
+///
+/// # Returns
+///
+/// - `Ok(Vec<AllGoodVibesDegrees>)`: One row per connected sensor-emitter pair
+/// - `Err(ReputestError)`: If a query fails
+pub async fn get_all_good_vibes_degrees_in_category(
+    pool: &PgPool,
+    category: &str,
+) -> Result<Vec<AllGoodVibesDegrees>, ReputestError> {
+    let graph = get_good_vibes_graph_in_category(pool, category).await?;
+
+    let mut user_ids: HashSet<&String> = graph.keys().collect();
+    user_ids.extend(graph.values().flatten());
+    let user_ids: Vec<&String> = user_ids.into_iter().collect();
+    let rows = sqlx::query("SELECT id, username, name FROM users WHERE id = ANY($1)")
+        .bind(&user_ids)
+        .fetch_all(pool)
+        .await?;
+    let users: HashMap<String, (String, String)> = rows
+        .into_iter()
+        .map(|row| (row.get("id"), (row.get("username"), row.get("name"))))
+        .collect();
+
+    let mut results = Vec::new();
+    for emitter_id in graph.keys() {
+        let Some((emitter_username, emitter_name)) = users.get(emitter_id) else {
+            continue;
+        };
+        let paths = crate::graph::count_paths_from(&graph, emitter_id, crate::graph::MAX_DEGREE);
+        for (sensor_id, counts) in paths {
+            let Some((sensor_username, sensor_name)) = users.get(&sensor_id) else {
+                continue;
+            };
+            results.push(AllGoodVibesDegrees {
+                sensor_username: sensor_username.clone(),
+                sensor_name: sensor_name.clone(),
+                emitter_username: emitter_username.clone(),
+                emitter_name: emitter_name.clone(),
+                degree_one_path_count: counts[0] as i64,
+                degree_two_path_count: counts[1] as i64,
+                degree_three_path_count: counts[2] as i64,
+                degree_four_path_count: counts[3] as i64,
+                degree_five_path_count: counts[4] as i64,
+                degree_six_path_count: counts[5] as i64,
+            });
+        }
+    }
+    results.sort_by(|a, b| {
+        (&a.sensor_username, &a.emitter_username).cmp(&(&b.sensor_username, &b.emitter_username))
+    });
+
+    info!("Found {} rows for category #{}", results.len(), category);
+    Ok(results)
+}
+
+/// Retrieves the categories in use with their edge counts, most used first.
+///
+/// # Returns
+///
+/// - `Ok(Vec<(String, i64)>)`: `(category, edge_count)` pairs
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_good_vibes_categories(pool: &PgPool) -> Result<Vec<(String, i64)>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT category, COUNT(*) AS edge_count
+        FROM good_vibes_categories
+        GROUP BY category
+        ORDER BY edge_count DESC, category
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("category"), row.get("edge_count")))
+        .collect())
+}
+
+/// A pair of accounts that follow each other but have no good vibes in either direction.
+#[derive(Debug)]
+pub struct MutualFollowPair {
+    pub user_a_username: String,
+    pub user_b_username: String,
+}
+
+/// Retrieves all mutual-follow pairs with no good vibes in either direction.
+///
+/// Each pair is reported once (ordered by user ID). These are suggested people to vibe.
+///
+/// # Returns
+///
+/// - `Ok(Vec<MutualFollowPair>)`: Pairs ordered by usernames
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_mutual_follows_without_vibes(
+    pool: &PgPool,
+) -> Result<Vec<MutualFollowPair>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT a.username AS user_a_username, b.username AS user_b_username
+        FROM following f1
+        JOIN following f2 ON f2.follower = f1.followed AND f2.followed = f1.follower
+        JOIN users a ON a.id = f1.follower
+        JOIN users b ON b.id = f1.followed
+        WHERE f1.follower < f1.followed
+          AND NOT EXISTS (
+              SELECT 1 FROM good_vibes g
+              WHERE (g.emitter_id = f1.follower AND g.sensor_id = f1.followed)
+                 OR (g.emitter_id = f1.followed AND g.sensor_id = f1.follower)
+          )
+        ORDER BY a.username, b.username
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MutualFollowPair {
+            user_a_username: row.get("user_a_username"),
+            user_b_username: row.get("user_b_username"),
+        })
+        .collect())
+}
+
+/// Retrieves usernames of accounts that mutually follow the given user but share no good vibes with them.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `user_id`: The user ID to find suggestions for
+/// - `limit`: Maximum number of usernames to return
+///
+/// # Returns
+///
+/// - `Ok(Vec<String>)`: Usernames ordered by most recently recorded follow first
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_mutual_follow_suggestions(
+    pool: &PgPool,
+    user_id: &str,
+    limit: i64,
+) -> Result<Vec<String>, ReputestError> {
+    let usernames: Vec<String> = sqlx::query_scalar(
+        r#"
+        SELECT u.username
+        FROM following f1
+        JOIN following f2 ON f2.follower = f1.followed AND f2.followed = f1.follower
+        JOIN users u ON u.id = f1.followed
+        WHERE f1.follower = $1
+          AND NOT EXISTS (
+              SELECT 1 FROM good_vibes g
+              WHERE (g.emitter_id = f1.follower AND g.sensor_id = f1.followed)
+                 OR (g.emitter_id = f1.followed AND g.sensor_id = f1.follower)
+          )
+        ORDER BY GREATEST(f1.created_at, f2.created_at) DESC, u.username
+        LIMIT $2
+        "#,
+    )
+    .bind(user_id)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(usernames)
+}
+
+/// Builds the follow graph (follower -> accounts they follow) from the following table.
+///
+/// # Returns
+///
+/// - `Ok(HashMap<String, Vec<String>>)`: Adjacency list keyed by follower user ID
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_following_graph(
+    pool: &PgPool,
+) -> Result<HashMap<String, Vec<String>>, ReputestError> {
+    let rows = sqlx::query("SELECT follower, followed FROM following")
+        .fetch_all(pool)
+        .await?;
+
+    let mut graph: HashMap<String, Vec<String>> = HashMap::new();
+    for row in rows {
+        let follower: String = row.get("follower");
+        let followed: String = row.get("followed");
+        graph.entry(follower).or_default().push(followed);
+    }
+    Ok(graph)
+}
+
+/// Retrieves, for every emitter, when good vibes from them were most recently declared.
+///
+/// # Returns
+///
+/// - `Ok(HashMap<String, DateTime<Utc>>)`: Latest good_vibes created_at keyed by emitter user ID
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_latest_vibe_times(
+    pool: &PgPool,
+) -> Result<HashMap<String, chrono::DateTime<chrono::Utc>>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT emitter_id, MAX(created_at) AS latest
+        FROM good_vibes
+        GROUP BY emitter_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("emitter_id"), row.get("latest")))
+        .collect())
+}
+
+/// Looks up usernames for a set of user IDs.
+///
+/// # Returns
+///
+/// - `Ok(HashMap<String, String>)`: Username keyed by user ID (IDs not in the users table are omitted)
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_usernames_by_ids(
+    pool: &PgPool,
+    user_ids: &[String],
+) -> Result<HashMap<String, String>, ReputestError> {
+    if user_ids.is_empty() {
+        return Ok(HashMap::new());
+    }
+
+    let rows = sqlx::query("SELECT id, username FROM users WHERE id = ANY($1)")
+        .bind(user_ids)
+        .fetch_all(pool)
+        .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("id"), row.get("username")))
+        .collect())
+}
+
+/// Records suggestions shown to a user so that conversion can be measured.
+///
+/// A suggestion of the same account from the same source is recorded at most once per day,
+/// so repeated page views or queries do not inflate the shown count.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `requester_id`: The user ID the suggestions were shown to
+/// - `suggestions`: `(suggested_id, score)` pairs
+/// - `source`: Where the suggestions were shown (`bot` or `profile`)
+///
+/// # Returns
+///
+/// - `Ok(u64)`: Number of suggestions recorded
+/// - `Err(ReputestError)`: If an insert fails
+pub async fn save_vibe_suggestions(
+    pool: &PgPool,
+    requester_id: &str,
+    suggestions: &[(String, f64)],
+    source: &str,
+) -> Result<u64, ReputestError> {
+    let mut recorded = 0;
+    for (suggested_id, score) in suggestions {
+        let result = sqlx::query(
+            r#"
+            INSERT INTO vibe_suggestions (requester_id, suggested_id, source, score)
+            SELECT $1, $2, $3, $4
+            WHERE NOT EXISTS (
+                SELECT 1 FROM vibe_suggestions
+                WHERE requester_id = $1 AND suggested_id = $2 AND source = $3
+                  AND shown_at > NOW() - INTERVAL '1 day'
+            )
+            "#,
+        )
+        .bind(requester_id)
+        .bind(suggested_id)
+        .bind(source)
+        .bind(score)
+        .execute(pool)
+        .await?;
+        recorded += result.rows_affected();
+    }
+
+    debug!(
+        "Recorded {} of {} {} suggestions for {}",
+        recorded,
+        suggestions.len(),
+        source,
+        requester_id
+    );
+    Ok(recorded)
+}
+
+/// Conversion of recommender suggestions for one source, from view_vibe_suggestion_conversion.
+#[derive(Debug)]
+pub struct SuggestionConversionRow {
+    pub source: String,
+    pub shown_count: i64,
+    pub converted_count: i64,
+}
+
+/// Retrieves how many suggestions were shown and converted into good vibes, per source.
+pub async fn get_suggestion_conversion(
+    pool: &PgPool,
+) -> Result<Vec<SuggestionConversionRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT source, shown_count, converted_count
+        FROM view_vibe_suggestion_conversion
+        ORDER BY source
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| SuggestionConversionRow {
+            source: row.get("source"),
+            shown_count: row.get("shown_count"),
+            converted_count: row.get("converted_count"),
         })
         .collect())
 }
+
+/// Good vibes totals for a user's profile page.
+#[derive(Debug)]
+pub struct UserProfile {
+    pub user_id: String,
+    pub username: String,
+    pub name: String,
+    pub known_follower_count: i32,
+    pub api_follower_count: Option<i32>,
+    /// Accounts this user declared good vibes from (user is the sensor)
+    pub vibes_declared: i64,
+    /// Accounts that declared good vibes from this user (user is the emitter)
+    pub vibes_received: i64,
+}
+
+/// Retrieves profile information and good vibes totals for a username.
+///
+/// # Returns
+///
+/// - `Ok(Some(UserProfile))`: If the user exists
+/// - `Ok(None)`: If the username is not in the users table
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_user_profile(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<UserProfile>, ReputestError> {
+    let row = sqlx::query(
+        r#"
+        SELECT
+            u.id, u.username, u.name, u.known_follower_count, u.api_follower_count,
+            (SELECT COUNT(*) FROM good_vibes g WHERE g.sensor_id = u.id) AS vibes_declared,
+            (SELECT COUNT(*) FROM good_vibes g WHERE g.emitter_id = u.id) AS vibes_received
+        FROM users u
+        WHERE u.username = $1
+        "#,
+    )
+    .bind(username)
+    .fetch_optional(pool)
+    .await?;
+
+    Ok(row.map(|row| UserProfile {
+        user_id: row.get("id"),
+        username: row.get("username"),
+        name: row.get("name"),
+        known_follower_count: row.get("known_follower_count"),
+        api_follower_count: row.get("api_follower_count"),
+        vibes_declared: row.get("vibes_declared"),
+        vibes_received: row.get("vibes_received"),
+    }))
+}
+
+/// Summary statistics for one detected cluster, as stored in the clusters table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ClusterStats {
+    pub cluster_id: i32,
+    pub member_count: i32,
+    pub internal_edge_count: i32,
+    pub external_edge_count: i32,
+    pub density: f64,
+    pub top_member_id: Option<String>,
+}
+
+/// Replaces the stored clustering with a new detection run.
+///
+/// In one transaction: clears every `users.cluster_id`, sets the new assignments and
+/// replaces the contents of the clusters table.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `assignments`: Cluster ID per user ID
+/// - `stats`: Summary statistics per cluster
+/// - `seed`: Seed used by the detection run
+///
+/// # Returns
+///
+/// - `Ok(())`: If the clustering was stored
+/// - `Err(ReputestError)`: If any statement fails (nothing is changed)
+pub async fn save_clusters(
+    pool: &PgPool,
+    assignments: &HashMap<String, i32>,
+    stats: &[ClusterStats],
+    seed: u64,
+) -> Result<(), ReputestError> {
+    let (user_ids, cluster_ids): (Vec<String>, Vec<i32>) = assignments
+        .iter()
+        .map(|(id, cluster)| (id.clone(), *cluster))
+        .unzip();
+
+    let mut tx = pool.begin().await?;
+
+    sqlx::query("UPDATE users SET cluster_id = NULL WHERE cluster_id IS NOT NULL")
+        .execute(&mut *tx)
+        .await?;
+
+    sqlx::query(
+        r#"
+        UPDATE users u
+        SET cluster_id = a.cluster_id
+        FROM UNNEST($1::text[], $2::int[]) AS a(id, cluster_id)
+        WHERE u.id = a.id
+        "#,
+    )
+    .bind(&user_ids)
+    .bind(&cluster_ids)
+    .execute(&mut *tx)
+    .await?;
+
+    sqlx::query("DELETE FROM clusters")
+        .execute(&mut *tx)
+        .await?;
+
+    for cluster in stats {
+        sqlx::query(
+            r#"
+            INSERT INTO clusters (cluster_id, member_count, internal_edge_count, external_edge_count,
+                                  density, top_member_id, seed)
+            VALUES ($1, $2, $3, $4, $5, $6, $7)
+            "#,
+        )
+        .bind(cluster.cluster_id)
+        .bind(cluster.member_count)
+        .bind(cluster.internal_edge_count)
+        .bind(cluster.external_edge_count)
+        .bind(cluster.density)
+        .bind(&cluster.top_member_id)
+        .bind(seed as i64)
+        .execute(&mut *tx)
+        .await?;
+    }
+
+    tx.commit().await?;
+    info!(
+        "Stored {} clusters covering {} users",
+        stats.len(),
+        assignments.len()
+    );
+    Ok(())
+}
+
+/// A stored cluster with member usernames, for the clusters page and JSON API.
+#[derive(Debug, serde::Serialize)]
+pub struct ClusterRow {
+    pub cluster_id: i32,
+    pub member_count: i32,
+    pub internal_edge_count: i32,
+    pub external_edge_count: i32,
+    pub density: f64,
+    pub top_member_username: Option<String>,
+    pub members: Vec<String>,
+    pub seed: i64,
+    pub computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Retrieves all stored clusters, largest first, with their member usernames.
+pub async fn get_clusters(pool: &PgPool) -> Result<Vec<ClusterRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT
+            c.cluster_id, c.member_count, c.internal_edge_count, c.external_edge_count,
+            c.density, top.username AS top_member_username, c.seed, c.computed_at,
+            ARRAY(
+                SELECT u.username FROM users u
+                WHERE u.cluster_id = c.cluster_id
+                ORDER BY u.username
+            ) AS members
+        FROM clusters c
+        LEFT JOIN users top ON top.id = c.top_member_id
+        ORDER BY c.cluster_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| ClusterRow {
+            cluster_id: row.get("cluster_id"),
+            member_count: row.get("member_count"),
+            internal_edge_count: row.get("internal_edge_count"),
+            external_edge_count: row.get("external_edge_count"),
+            density: row.get("density"),
+            top_member_username: row.get("top_member_username"),
+            members: row.get("members"),
+            seed: row.get("seed"),
+            computed_at: row.get("computed_at"),
+        })
+        .collect())
+}
+
+/// Counts good vibes edges whose tweet was created at or after `since`.
+pub async fn count_good_vibes_since(
+    pool: &PgPool,
+    since: chrono::DateTime<chrono::Utc>,
+) -> Result<i64, ReputestError> {
+    let count: i64 = sqlx::query_scalar(
+        "SELECT COUNT(*) FROM good_vibes WHERE created_at >= $1 AND emitter_id <> sensor_id",
+    )
+    .bind(since)
+    .fetch_one(pool)
+    .await?;
+    Ok(count)
+}
+
+/// Graph-wide statistics for one snapshot, as stored in the graph_stats table.
+#[derive(Debug, Clone, PartialEq)]
+pub struct GraphStats {
+    pub node_count: i32,
+    pub edge_count: i32,
+    pub reciprocal_edge_count: i32,
+    pub reciprocity_rate: f64,
+    pub largest_scc_size: i32,
+    pub in_degree_histogram: Vec<i32>,
+    pub out_degree_histogram: Vec<i32>,
+    pub new_edges_last_day: i32,
+}
+
+/// Appends a graph statistics snapshot.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `stats`: The snapshot to store
+///
+/// # Returns
+///
+/// - `Ok(i32)`: The ID of the new snapshot
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn save_graph_stats(pool: &PgPool, stats: &GraphStats) -> Result<i32, ReputestError> {
+    let id: i32 = sqlx::query_scalar(
+        r#"
+        INSERT INTO graph_stats (node_count, edge_count, reciprocal_edge_count, reciprocity_rate,
+                                 largest_scc_size, in_degree_histogram, out_degree_histogram,
+                                 new_edges_last_day)
+        VALUES ($1, $2, $3, $4, $5, $6, $7, $8)
+        RETURNING id
+        "#,
+    )
+    .bind(stats.node_count)
+    .bind(stats.edge_count)
+    .bind(stats.reciprocal_edge_count)
+    .bind(stats.reciprocity_rate)
+    .bind(stats.largest_scc_size)
+    .bind(&stats.in_degree_histogram)
+    .bind(&stats.out_degree_histogram)
+    .bind(stats.new_edges_last_day)
+    .fetch_one(pool)
+    .await?;
+
+    debug!("Stored graph stats snapshot {}", id);
+    Ok(id)
+}
+
+/// A stored graph statistics snapshot, for the stats page and JSON API.
+#[derive(Debug, serde::Serialize)]
+pub struct GraphStatsRow {
+    pub node_count: i32,
+    pub edge_count: i32,
+    pub reciprocal_edge_count: i32,
+    pub reciprocity_rate: f64,
+    pub largest_scc_size: i32,
+    pub in_degree_histogram: Vec<i32>,
+    pub out_degree_histogram: Vec<i32>,
+    pub new_edges_last_day: i32,
+    pub computed_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Retrieves graph statistics history: the last snapshot of each day, oldest first.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `days`: How many days back to include (the current day counts as one)
+///
+/// # Returns
+///
+/// - `Ok(Vec<GraphStatsRow>)`: At most one snapshot per UTC day, the most recent last
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_graph_stats_history(
+    pool: &PgPool,
+    days: i32,
+) -> Result<Vec<GraphStatsRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT DISTINCT ON (date_trunc('day', computed_at AT TIME ZONE 'UTC'))
+            node_count, edge_count, reciprocal_edge_count, reciprocity_rate, largest_scc_size,
+            in_degree_histogram, out_degree_histogram, new_edges_last_day, computed_at
+        FROM graph_stats
+        WHERE computed_at >= date_trunc('day', NOW() AT TIME ZONE 'UTC') AT TIME ZONE 'UTC'
+                             - make_interval(days => $1 - 1)
+        ORDER BY date_trunc('day', computed_at AT TIME ZONE 'UTC'), computed_at DESC
+        "#,
+    )
+    .bind(days)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| GraphStatsRow {
+            node_count: row.get("node_count"),
+            edge_count: row.get("edge_count"),
+            reciprocal_edge_count: row.get("reciprocal_edge_count"),
+            reciprocity_rate: row.get("reciprocity_rate"),
+            largest_scc_size: row.get("largest_scc_size"),
+            in_degree_histogram: row.get("in_degree_histogram"),
+            out_degree_histogram: row.get("out_degree_histogram"),
+            new_edges_last_day: row.get("new_edges_last_day"),
+            computed_at: row.get("computed_at"),
+        })
+        .collect())
+}
+
+/// A bot reply in the outbound_replies queue.
+#[derive(Debug, Clone, serde::Serialize)]
+pub struct OutboundReply {
+    pub id: i64,
+    pub in_reply_to_tweet_id: String,
+    pub reply_text: String,
+    /// "pending", "sent" or "dead"
+    pub status: String,
+    pub attempts: i32,
+    pub last_error: Option<String>,
+    pub next_attempt_at: chrono::DateTime<chrono::Utc>,
+    pub reply_tweet_id: Option<String>,
+    pub created_at: chrono::DateTime<chrono::Utc>,
+    pub updated_at: chrono::DateTime<chrono::Utc>,
+}
+
+fn outbound_reply_from_row(row: &sqlx::postgres::PgRow) -> OutboundReply {
+    OutboundReply {
+        id: row.get("id"),
+        in_reply_to_tweet_id: row.get("in_reply_to_tweet_id"),
+        reply_text: row.get("reply_text"),
+        status: row.get("status"),
+        attempts: row.get("attempts"),
+        last_error: row.get("last_error"),
+        next_attempt_at: row.get("next_attempt_at"),
+        reply_tweet_id: row.get("reply_tweet_id"),
+        created_at: row.get("created_at"),
+        updated_at: row.get("updated_at"),
+    }
+}
+
+/// Queues a reply to a tweet for the reply worker.
+///
+/// A tweet is answered at most once: queueing a second reply to the same tweet is a no-op.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `in_reply_to_tweet_id`: The tweet to reply to
+/// - `reply_text`: The text of the reply
+///
+/// # Returns
+///
+/// - `Ok(true)`: If the reply was queued
+/// - `Ok(false)`: If a reply to this tweet was already queued
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn enqueue_reply(
+    pool: &PgPool,
+    in_reply_to_tweet_id: &str,
+    reply_text: &str,
+) -> Result<bool, ReputestError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO outbound_replies (in_reply_to_tweet_id, reply_text)
+        VALUES ($1, $2)
+        ON CONFLICT (in_reply_to_tweet_id) DO NOTHING
+        "#,
+    )
+    .bind(in_reply_to_tweet_id)
+    .bind(reply_text)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Returns the status (`pending`, `sent` or `dead`) of the reply queued to a tweet, if any.
+///
+/// A tweet is answered at most once (see [`enqueue_reply`]), so while this is `Some` no
+/// other reply to the tweet can be queued.
+pub async fn get_reply_status(
+    pool: &PgPool,
+    in_reply_to_tweet_id: &str,
+) -> Result<Option<String>, ReputestError> {
+    let status =
+        sqlx::query_scalar("SELECT status FROM outbound_replies WHERE in_reply_to_tweet_id = $1")
+            .bind(in_reply_to_tweet_id)
+            .fetch_optional(pool)
+            .await?;
+    Ok(status)
+}
+
+/// Claims up to `limit` due pending replies for sending.
+///
+/// Each claimed reply has its attempt counter incremented and its next attempt pushed
+/// `lease` into the future, so a concurrent worker will not pick it up while it is being
+/// sent. If the worker dies mid-send the reply becomes due again once the lease expires.
+///
+/// # Returns
+///
+/// - `Ok(Vec<OutboundReply>)`: The claimed replies, oldest due first
+/// - `Err(ReputestError)`: If the update fails
+pub async fn claim_due_replies(
+    pool: &PgPool,
+    limit: i64,
+    lease: chrono::Duration,
+) -> Result<Vec<OutboundReply>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        UPDATE outbound_replies
+        SET attempts = attempts + 1,
+            next_attempt_at = NOW() + make_interval(secs => $2),
+            updated_at = NOW()
+        WHERE id IN (
+            SELECT id FROM outbound_replies
+            WHERE status = 'pending' AND next_attempt_at <= NOW()
+            ORDER BY next_attempt_at
+            LIMIT $1
+            FOR UPDATE SKIP LOCKED
+        )
+        RETURNING id, in_reply_to_tweet_id, reply_text, status, attempts, last_error,
+                  next_attempt_at, reply_tweet_id, created_at, updated_at
+        "#,
+    )
+    .bind(limit)
+    .bind(lease.num_seconds() as f64)
+    .fetch_all(pool)
+    .await?;
+
+    let mut replies: Vec<OutboundReply> = rows.iter().map(outbound_reply_from_row).collect();
+    replies.sort_by_key(|r| r.id);
+    Ok(replies)
+}
+
+/// Extends the lease of a claimed reply just before it is sent.
+///
+/// The lease is only renewed while the reply is still held by the claim that made
+/// `attempts` attempts. If the lease expired and another worker claimed the reply again
+/// (incrementing `attempts`) or already sent it, nothing is updated and the caller must
+/// not send it.
+///
+/// # Returns
+///
+/// - `Ok(true)`: If the lease was renewed and the reply may be sent
+/// - `Ok(false)`: If the reply is no longer held by this claim
+/// - `Err(ReputestError)`: If the update fails
+pub async fn renew_reply_lease(
+    pool: &PgPool,
+    id: i64,
+    attempts: i32,
+    lease: chrono::Duration,
+) -> Result<bool, ReputestError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE outbound_replies
+        SET next_attempt_at = NOW() + make_interval(secs => $3), updated_at = NOW()
+        WHERE id = $1 AND attempts = $2 AND status = 'pending'
+        "#,
+    )
+    .bind(id)
+    .bind(attempts)
+    .bind(lease.num_seconds() as f64)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Marks a queued reply as sent.
+pub async fn mark_reply_sent(
+    pool: &PgPool,
+    id: i64,
+    reply_tweet_id: Option<&str>,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        UPDATE outbound_replies
+        SET status = 'sent', reply_tweet_id = $2, last_error = NULL, updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(reply_tweet_id)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records a failed send attempt.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `id`: The queued reply
+/// - `error`: Error of the attempt (stored in `last_error`)
+/// - `retry_at`: When to try again, or `None` to dead-letter the reply
+pub async fn mark_reply_failed(
+    pool: &PgPool,
+    id: i64,
+    error: &str,
+    retry_at: Option<chrono::DateTime<chrono::Utc>>,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        UPDATE outbound_replies
+        SET status = CASE WHEN $3::timestamptz IS NULL THEN 'dead' ELSE 'pending' END,
+            last_error = $2,
+            next_attempt_at = COALESCE($3, next_attempt_at),
+            updated_at = NOW()
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(error)
+    .bind(retry_at)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Puts a dead-lettered reply back in the queue, due immediately with a fresh attempt count.
+///
+/// # Returns
+///
+/// - `Ok(true)`: If the reply was dead and has been requeued
+/// - `Ok(false)`: If there is no dead reply with this ID
+/// - `Err(ReputestError)`: If the update fails
+pub async fn requeue_reply(pool: &PgPool, id: i64) -> Result<bool, ReputestError> {
+    let result = sqlx::query(
+        r#"
+        UPDATE outbound_replies
+        SET status = 'pending', attempts = 0, next_attempt_at = NOW(), updated_at = NOW()
+        WHERE id = $1 AND status = 'dead'
+        "#,
+    )
+    .bind(id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Retrieves replies that need attention: dead-lettered replies and pending replies
+/// that have already failed at least once, most recently updated first.
+pub async fn get_stuck_replies(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<OutboundReply>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT id, in_reply_to_tweet_id, reply_text, status, attempts, last_error,
+               next_attempt_at, reply_tweet_id, created_at, updated_at
+        FROM outbound_replies
+        WHERE status = 'dead' OR (status = 'pending' AND last_error IS NOT NULL)
+        ORDER BY updated_at DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+    Ok(rows.iter().map(outbound_reply_from_row).collect())
+}
+
+/// Counts queued replies by status ("pending", "sent", "dead").
+pub async fn count_outbound_replies_by_status(
+    pool: &PgPool,
+) -> Result<Vec<(String, i64)>, ReputestError> {
+    let rows = sqlx::query(
+        "SELECT status, COUNT(*) AS count FROM outbound_replies GROUP BY status ORDER BY status",
+    )
+    .fetch_all(pool)
+    .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("status"), row.get("count")))
+        .collect())
+}
+
+/// Records a reply that would have been posted, for `BOT_MODE=shadow`.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `in_reply_to_tweet_id`: The tweet that would have been answered
+/// - `reply_text`: The text that would have been posted
+///
+/// # Returns
+///
+/// - `Ok(i64)`: The ID of the `shadow_replies` row
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn save_shadow_reply(
+    pool: &PgPool,
+    in_reply_to_tweet_id: &str,
+    reply_text: &str,
+) -> Result<i64, ReputestError> {
+    let row = sqlx::query(
+        "INSERT INTO shadow_replies (in_reply_to_tweet_id, reply_text) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(in_reply_to_tweet_id)
+    .bind(reply_text)
+    .fetch_one(pool)
+    .await?;
+    Ok(row.get("id"))
+}
+
+/// What a tweet using a registered hashtag means, stored in `hashtags.action`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HashtagAction {
+    /// "emitter #tag": the poster declares good vibes from the emitter
+    Vibe,
+    /// "emitter #tag": the poster withdraws good vibes from the emitter
+    Revoke,
+    /// "N #tag to receiver": the poster sends N megajoules to the receiver
+    Transfer,
+}
+
+impl HashtagAction {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            HashtagAction::Vibe => "vibe",
+            HashtagAction::Revoke => "revoke",
+            HashtagAction::Transfer => "transfer",
+        }
+    }
+
+    /// Parses an action as stored in `hashtags.action`.
+    pub fn parse(value: &str) -> Option<Self> {
+        match value {
+            "vibe" => Some(HashtagAction::Vibe),
+            "revoke" => Some(HashtagAction::Revoke),
+            "transfer" => Some(HashtagAction::Transfer),
+            _ => None,
+        }
+    }
+}
+
+/// A row of the `hashtags` registry.
+#[derive(Debug, Clone, PartialEq)]
+pub struct HashtagRule {
+    /// Hashtag without the leading `#`, lowercase
+    pub tag: String,
+    pub action: HashtagAction,
+    /// Edge category a vibe hashtag records (or a revoke hashtag removes)
+    pub category: Option<String>,
+}
+
+impl HashtagRule {
+    /// Creates a registry entry.
+    pub fn new(tag: &str, action: HashtagAction, category: Option<&str>) -> Self {
+        HashtagRule {
+            tag: tag.to_string(),
+            action,
+            category: category.map(String::from),
+        }
+    }
+}
+
+/// Retrieves the enabled hashtags of the registry, ordered by tag.
+///
+/// # Returns
+///
+/// - `Ok(Vec<HashtagRule>)`: The enabled hashtags
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_hashtag_rules(pool: &PgPool) -> Result<Vec<HashtagRule>, ReputestError> {
+    let rows = sqlx::query("SELECT tag, action, category FROM hashtags WHERE enabled ORDER BY tag")
+        .fetch_all(pool)
+        .await?;
+
+    let mut rules = Vec::with_capacity(rows.len());
+    for row in rows {
+        let tag: String = row.get("tag");
+        let action: String = row.get("action");
+        match HashtagAction::parse(&action) {
+            Some(action) => rules.push(HashtagRule::new(&tag, action, row.get("category"))),
+            None => warn!("Ignoring hashtag #{} with unknown action '{}'", tag, action),
+        }
+    }
+    Ok(rules)
+}
+
+/// Creates or updates a hashtag of the registry.
+///
+/// Used at startup to apply the `[hashtags]` table of the configuration file.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `rule`: The hashtag, its action and optional category
+/// - `enabled`: Whether the scheduled search includes the hashtag
+///
+/// # Returns
+///
+/// - `Ok(())`: If the hashtag was saved
+/// - `Err(ReputestError)`: If the upsert fails
+pub async fn upsert_hashtag_rule(
+    pool: &PgPool,
+    rule: &HashtagRule,
+    enabled: bool,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        INSERT INTO hashtags (tag, action, category, enabled)
+        VALUES ($1, $2, $3, $4)
+        ON CONFLICT (tag) DO UPDATE
+        SET action = EXCLUDED.action, category = EXCLUDED.category, enabled = EXCLUDED.enabled
+        "#,
+    )
+    .bind(&rule.tag)
+    .bind(rule.action.as_str())
+    .bind(&rule.category)
+    .bind(enabled)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Adds a category to an existing good vibes edge.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `tweet_id`: The tweet that declared the category
+/// - `emitter_id`: The emitter of the edge
+/// - `sensor_id`: The sensor of the edge
+/// - `category`: The category label
+/// - `created_at`: When the tweet was created
+///
+/// # Returns
+///
+/// - `Ok(true)`: If the category was added
+/// - `Ok(false)`: If the edge already had this category
+/// - `Err(ReputestError)`: If the insert fails (e.g. the edge does not exist)
+pub async fn save_good_vibes_category(
+    pool: &PgPool,
+    tweet_id: &str,
+    emitter_id: &str,
+    sensor_id: &str,
+    category: &str,
+    created_at: chrono::DateTime<chrono::Utc>,
+) -> Result<bool, ReputestError> {
+    let result = sqlx::query(
+        r#"
+        INSERT INTO good_vibes_categories (emitter_id, sensor_id, category, tweet_id, created_at)
+        VALUES ($1, $2, $3, $4, $5)
+        ON CONFLICT (emitter_id, sensor_id, category) DO NOTHING
+        "#,
+    )
+    .bind(emitter_id)
+    .bind(sensor_id)
+    .bind(category)
+    .bind(tweet_id)
+    .bind(created_at)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Revokes good vibes from an emitter to a sensor.
+///
+/// Without a category the whole edge (and its categories) is deleted; with one, only
+/// that category is removed from the edge. In the same transaction the revocation
+/// tweet and the tweets that declared what was removed are stored in `vibe_requests`,
+/// so later searches neither repeat the revocation nor record the declarations again.
+/// The declarations are marked `revoked_by` the revocation tweet, which keeps them
+/// revoked when they are reprocessed (see [`clear_vibe_request`]).
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `revocation_tweet_id`: The tweet asking for the revocation
+/// - `emitter_id`: The emitter of the edge
+/// - `sensor_id`: The sensor of the edge (the poster of the revocation)
+/// - `category`: The category to remove, or `None` for the whole edge
+///
+/// # Returns
+///
+/// - `Ok(true)`: If something was revoked
+/// - `Ok(false)`: If there was nothing to revoke
+/// - `Err(ReputestError)`: If the transaction fails
+pub async fn revoke_good_vibes(
+    pool: &PgPool,
+    revocation_tweet_id: &str,
+    emitter_id: &str,
+    sensor_id: &str,
+    category: Option<&str>,
+) -> Result<bool, ReputestError> {
+    let mut tx = pool.begin().await?;
+
+    let declaring_tweets: Vec<Option<String>> = match category {
+        Some(category) => {
+            sqlx::query_scalar(
+                r#"
+                DELETE FROM good_vibes_categories
+                WHERE emitter_id = $1 AND sensor_id = $2 AND category = $3
+                RETURNING tweet_id
+                "#,
+            )
+            .bind(emitter_id)
+            .bind(sensor_id)
+            .bind(category)
+            .fetch_all(&mut *tx)
+            .await?
+        }
+        None => {
+            let mut tweets: Vec<Option<String>> = sqlx::query_scalar(
+                "SELECT tweet_id FROM good_vibes_categories WHERE emitter_id = $1 AND sensor_id = $2",
+            )
+            .bind(emitter_id)
+            .bind(sensor_id)
+            .fetch_all(&mut *tx)
+            .await?;
+            // Categories only exist on an edge, so they are empty if the edge is
+            let edge: Vec<Option<String>> = sqlx::query_scalar(
+                "DELETE FROM good_vibes WHERE emitter_id = $1 AND sensor_id = $2 RETURNING tweet_id",
+            )
+            .bind(emitter_id)
+            .bind(sensor_id)
+            .fetch_all(&mut *tx)
+            .await?;
+            tweets.extend(edge);
+            tweets
+        }
+    };
+    let revoked = !declaring_tweets.is_empty();
+
+    let declaring_tweets: Vec<String> = declaring_tweets.into_iter().flatten().collect();
+    sqlx::query(
+        r#"
+        INSERT INTO vibe_requests (tweet_id, revoked_by)
+        SELECT UNNEST($1::text[]), $2
+        ON CONFLICT (tweet_id) DO UPDATE SET revoked_by = EXCLUDED.revoked_by
+        "#,
+    )
+    .bind(&declaring_tweets)
+    .bind(revocation_tweet_id)
+    .execute(&mut *tx)
+    .await?;
+    sqlx::query("INSERT INTO vibe_requests (tweet_id) VALUES ($1) ON CONFLICT DO NOTHING")
+        .bind(revocation_tweet_id)
+        .execute(&mut *tx)
+        .await?;
+
+    tx.commit().await?;
+    info!(
+        "Revocation tweet {}: good vibes from {} to {} (category {:?}) {}",
+        revocation_tweet_id,
+        emitter_id,
+        sensor_id,
+        category,
+        if revoked { "revoked" } else { "not found" }
+    );
+    Ok(revoked)
+}
+
+/// How a run of a scheduled task ended, stored in `job_runs.outcome`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobOutcome {
+    Succeeded,
+    Failed,
+    /// Not run because the previous run of the task was still in progress
+    Skipped,
+    /// Not run because the bot is paused (see [`get_bot_paused`])
+    Paused,
+}
+
+impl JobOutcome {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            JobOutcome::Succeeded => "succeeded",
+            JobOutcome::Failed => "failed",
+            JobOutcome::Skipped => "skipped",
+            JobOutcome::Paused => "paused",
+        }
+    }
+}
+
+/// Records the start of a scheduled task run.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `task`: The task name
+/// - `triggered_by`: The admin who started the run from `/admin`, or `None` for a scheduled run
+///
+/// # Returns
+///
+/// - `Ok(i64)`: The ID of the `job_runs` row (outcome `running`)
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn start_job_run(
+    pool: &PgPool,
+    task: &str,
+    triggered_by: Option<&str>,
+) -> Result<i64, ReputestError> {
+    let id = sqlx::query_scalar(
+        "INSERT INTO job_runs (task, triggered_by) VALUES ($1, $2) RETURNING id",
+    )
+    .bind(task)
+    .bind(triggered_by)
+    .fetch_one(pool)
+    .await?;
+    Ok(id)
+}
+
+/// Records the end of a scheduled task run started with [`start_job_run`].
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `id`: The ID returned by [`start_job_run`]
+/// - `outcome`: How the run ended
+/// - `items`: What the run handled, if the task counts anything
+/// - `error`: The error message of a failed run
+pub async fn finish_job_run(
+    pool: &PgPool,
+    id: i64,
+    outcome: JobOutcome,
+    items: Option<i64>,
+    error: Option<&str>,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        UPDATE job_runs
+        SET finished_at = NOW(), outcome = $2, items = $3, error = $4
+        WHERE id = $1
+        "#,
+    )
+    .bind(id)
+    .bind(outcome.as_str())
+    .bind(items)
+    .bind(error)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Records a run of a scheduled task that did not happen.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `task`: The task name
+/// - `outcome`: Why the run did not happen ([`JobOutcome::Skipped`] or [`JobOutcome::Paused`])
+/// - `triggered_by`: The admin who started the run from `/admin`, or `None` for a scheduled run
+pub async fn record_skipped_job_run(
+    pool: &PgPool,
+    task: &str,
+    outcome: JobOutcome,
+    triggered_by: Option<&str>,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        "INSERT INTO job_runs (task, finished_at, outcome, triggered_by) VALUES ($1, NOW(), $2, $3)",
+    )
+    .bind(task)
+    .bind(outcome.as_str())
+    .bind(triggered_by)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// A recorded run of a scheduled task, for the admin console.
+#[derive(Debug)]
+pub struct JobRunRow {
+    pub task: String,
+    pub started_at: chrono::DateTime<chrono::Utc>,
+    pub finished_at: Option<chrono::DateTime<chrono::Utc>>,
+    pub outcome: String,
+    pub items: Option<i64>,
+    pub error: Option<String>,
+    pub triggered_by: Option<String>,
+}
+
+/// Retrieves the most recent runs of the scheduled tasks, newest first.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `task`: Only runs of this task, or `None` for all tasks
+/// - `limit`: The maximum number of runs to return
+pub async fn get_recent_job_runs(
+    pool: &PgPool,
+    task: Option<&str>,
+    limit: i64,
+) -> Result<Vec<JobRunRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT task, started_at, finished_at, outcome, items, error, triggered_by
+        FROM job_runs
+        WHERE $1::TEXT IS NULL OR task = $1
+        ORDER BY started_at DESC, id DESC
+        LIMIT $2
+        "#,
+    )
+    .bind(task)
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| JobRunRow {
+            task: row.get("task"),
+            started_at: row.get("started_at"),
+            finished_at: row.get("finished_at"),
+            outcome: row.get("outcome"),
+            items: row.get("items"),
+            error: row.get("error"),
+            triggered_by: row.get("triggered_by"),
+        })
+        .collect())
+}
+
+/// A recorded materialized view refresh, for the admin console.
+#[derive(Debug)]
+pub struct MaterializeTimeRow {
+    /// The degree of the refreshed view, or `None` for the combined view
+    pub degree: Option<i32>,
+    pub refresh_time: chrono::DateTime<chrono::Utc>,
+    pub time_taken_ms: i32,
+}
+
+/// Retrieves the most recent materialized view refreshes from `vibe_materialize_time`, newest first.
+pub async fn get_recent_materialize_times(
+    pool: &PgPool,
+    limit: i64,
+) -> Result<Vec<MaterializeTimeRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT degree, refresh_time, time_taken_ms
+        FROM vibe_materialize_time
+        ORDER BY refresh_time DESC, id DESC
+        LIMIT $1
+        "#,
+    )
+    .bind(limit)
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .map(|row| MaterializeTimeRow {
+            degree: row.get("degree"),
+            refresh_time: row.get("refresh_time"),
+            time_taken_ms: row.get("time_taken_ms"),
+        })
+        .collect())
+}
+
+/// Checks whether the bot is paused from the admin console.
+///
+/// While paused, scheduled runs of the tasks that read and answer tweets are skipped.
+pub async fn get_bot_paused(pool: &PgPool) -> Result<bool, ReputestError> {
+    let paused: Option<bool> = sqlx::query_scalar("SELECT paused FROM bot_control")
+        .fetch_optional(pool)
+        .await?;
+    Ok(paused.unwrap_or(false))
+}
+
+/// Pauses or resumes the bot.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `paused`: Whether the bot should be paused
+/// - `username`: The admin making the change
+pub async fn set_bot_paused(
+    pool: &PgPool,
+    paused: bool,
+    username: &str,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        INSERT INTO bot_control (id, paused, updated_by, updated_at)
+        VALUES (TRUE, $1, $2, NOW())
+        ON CONFLICT (id) DO UPDATE
+        SET paused = EXCLUDED.paused, updated_by = EXCLUDED.updated_by, updated_at = NOW()
+        "#,
+    )
+    .bind(paused)
+    .bind(username)
+    .execute(pool)
+    .await?;
+    info!(
+        "Bot {} by @{}",
+        if paused { "paused" } else { "resumed" },
+        username
+    );
+    Ok(())
+}
+
+/// What a logged-in account may do on the web pages, stored in `user_roles.role`.
+///
+/// Each role includes the ones before it: an operator can also view, an admin can also operate.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Role {
+    /// Read-only pages
+    Viewer,
+    /// Also runs tasks, pauses the bot, reprocesses tweets and requeues replies
+    Operator,
+    /// Also grants and revokes roles
+    Admin,
+}
+
+impl Role {
+    pub const ALL: [Role; 3] = [Role::Viewer, Role::Operator, Role::Admin];
+
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Role::Viewer => "viewer",
+            Role::Operator => "operator",
+            Role::Admin => "admin",
+        }
+    }
+
+    /// Parses a role name as stored in `user_roles.role`.
+    pub fn parse(value: &str) -> Option<Self> {
+        Role::ALL.into_iter().find(|role| role.as_str() == value)
+    }
+}
+
+/// A row of `user_roles`.
+#[derive(Debug)]
+pub struct UserRoleRow {
+    pub user_id: String,
+    /// Username at the last login or grant, `None` for an admin seeded by ID who has not
+    /// logged in yet
+    pub username: Option<String>,
+    pub role: Role,
+    pub granted_by: Option<String>,
+    pub granted_at: chrono::DateTime<chrono::Utc>,
+}
+
+/// Gets the role of an account.
+///
+/// Roles are keyed on the X user ID, never on the username: usernames can be changed
+/// and then taken by another account.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `user_id`: X user ID
+///
+/// # Returns
+///
+/// - `Ok(Some(Role))`: The account's role
+/// - `Ok(None)`: If the account has no role (and may not log in)
+/// - `Err(ReputestError)`: If the query fails
+pub async fn get_user_role(pool: &PgPool, user_id: &str) -> Result<Option<Role>, ReputestError> {
+    let role: Option<String> = sqlx::query_scalar("SELECT role FROM user_roles WHERE user_id = $1")
+        .bind(user_id)
+        .fetch_optional(pool)
+        .await?;
+    Ok(role.as_deref().and_then(Role::parse))
+}
+
+/// Lists all role grants, admins first.
+pub async fn get_user_roles(pool: &PgPool) -> Result<Vec<UserRoleRow>, ReputestError> {
+    let rows = sqlx::query(
+        r#"
+        SELECT user_id, username, role, granted_by, granted_at
+        FROM user_roles
+        ORDER BY CASE role WHEN 'admin' THEN 0 WHEN 'operator' THEN 1 ELSE 2 END,
+                 lower(username), user_id
+        "#,
+    )
+    .fetch_all(pool)
+    .await?;
+
+    Ok(rows
+        .into_iter()
+        .filter_map(|row| {
+            let role: String = row.get("role");
+            Some(UserRoleRow {
+                user_id: row.get("user_id"),
+                username: row.get("username"),
+                role: Role::parse(&role)?,
+                granted_by: row.get("granted_by"),
+                granted_at: row.get("granted_at"),
+            })
+        })
+        .collect())
+}
+
+/// Grants a role to an account, replacing the role it had.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `user_id`: X user ID of the account
+/// - `username`: Its current username, for display
+/// - `role`: The role to grant
+/// - `granted_by`: Username of the admin granting the role
+pub async fn grant_user_role(
+    pool: &PgPool,
+    user_id: &str,
+    username: &str,
+    role: Role,
+    granted_by: &str,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        r#"
+        INSERT INTO user_roles (user_id, username, role, granted_by, granted_at)
+        VALUES ($1, $2, $3, $4, NOW())
+        ON CONFLICT (user_id) DO UPDATE
+        SET username = EXCLUDED.username, role = EXCLUDED.role,
+            granted_by = EXCLUDED.granted_by, granted_at = NOW()
+        "#,
+    )
+    .bind(user_id)
+    .bind(username)
+    .bind(role.as_str())
+    .bind(granted_by)
+    .execute(pool)
+    .await?;
+    info!(
+        "Role {} granted to @{} ({}) by @{}",
+        role.as_str(),
+        username,
+        user_id,
+        granted_by
+    );
+    Ok(())
+}
+
+/// Makes an account admin unless it already has a role (the initial admin of
+/// `ADMIN_USER_ID`).
+///
+/// # Returns
+///
+/// - `Ok(bool)`: Whether the role was granted (false if the account already had one)
+/// - `Err(ReputestError)`: If the insert fails
+pub async fn seed_admin_role(pool: &PgPool, user_id: &str) -> Result<bool, ReputestError> {
+    let result = sqlx::query(
+        "INSERT INTO user_roles (user_id, role) VALUES ($1, 'admin') ON CONFLICT (user_id) DO NOTHING",
+    )
+    .bind(user_id)
+    .execute(pool)
+    .await?;
+    Ok(result.rows_affected() > 0)
+}
+
+/// Records the username an account logged in with, for display on `/admin/roles`.
+pub async fn update_role_username(
+    pool: &PgPool,
+    user_id: &str,
+    username: &str,
+) -> Result<(), ReputestError> {
+    sqlx::query(
+        "UPDATE user_roles SET username = $2 WHERE user_id = $1 AND username IS DISTINCT FROM $2",
+    )
+    .bind(user_id)
+    .bind(username)
+    .execute(pool)
+    .await?;
+    Ok(())
+}
+
+/// Revokes an account's role, so it can no longer use the web pages.
+///
+/// # Returns
+///
+/// - `Ok(bool)`: Whether the account had a role
+/// - `Err(ReputestError)`: If the delete fails
+pub async fn revoke_user_role(pool: &PgPool, user_id: &str) -> Result<bool, ReputestError> {
+    let result = sqlx::query("DELETE FROM user_roles WHERE user_id = $1")
+        .bind(user_id)
+        .execute(pool)
+        .await?;
+    Ok(result.rows_affected() > 0)
+}
@@ -0,0 +1,184 @@
+//! Crate-wide error type.
+//!
+//! Library functions return [`ReputestError`] so callers can branch on the kind of
+//! failure (for example a 403 from the X API versus a database outage) instead of
+//! matching on message strings. HTTP handlers return it directly: the `IntoResponse`
+//! implementation picks the status code, logs server-side failures and keeps internal
+//! details out of the response body. JSON endpoints wrap it in [`JsonError`].
+
+use axum::{
+    http::{header, StatusCode},
+    response::{IntoResponse, Json, Response},
+};
+use chrono::{DateTime, Utc};
+use log::error;
+use serde_json::json;
+
+/// Message returned to clients for server-side failures.
+const INTERNAL_ERROR_MESSAGE: &str = "An internal error occurred. Please try again later.";
+
+/// Message returned to clients when required configuration is missing or invalid.
+const CONFIG_ERROR_MESSAGE: &str = "Service misconfigured";
+
+/// Errors returned by the reputest library.
+#[derive(Debug, thiserror::Error)]
+pub enum ReputestError {
+    /// A database query or connection failed.
+    #[error("database error: {0}")]
+    Database(#[from] sqlx::Error),
+
+    /// The X API answered with a non-success status.
+    #[error("X API error ({status}): {title}: {detail}")]
+    TwitterApi {
+        /// HTTP status returned by the API
+        status: u16,
+        /// `title` from the API error body (or the status reason when missing)
+        title: String,
+        /// `detail` from the API error body (sanitized and truncated)
+        detail: String,
+        /// When the rate limit window resets (`x-rate-limit-reset` header), if sent
+        rate_limit_reset: Option<DateTime<Utc>>,
+    },
+
+    /// An HTTP request could not be sent or its body could not be read.
+    #[error("HTTP request failed: {0}")]
+    Http(#[from] reqwest::Error),
+
+    /// Token refresh or authorization code exchange failed.
+    #[error("OAuth error: {0}")]
+    OAuth(String),
+
+    /// Token encryption or decryption failed, or the key is invalid.
+    #[error("crypto error: {0}")]
+    Crypto(String),
+
+    /// Required configuration is missing or invalid.
+    #[error("configuration error: {0}")]
+    Config(String),
+
+    /// The client's request could not be parsed.
+    #[error("parse error: {0}")]
+    Parse(String),
+
+    /// A response from the X API or the OAuth token endpoint could not be decoded.
+    #[error("invalid upstream response: {0}")]
+    UpstreamResponse(String),
+
+    /// The database schema does not match this binary (pending migrations or drift).
+    #[error("schema error: {0}")]
+    Schema(String),
+
+    /// The requested resource does not exist.
+    #[error("{0} not found")]
+    NotFound(String),
+}
+
+/// JSON decoded with `?` is always an upstream response body; request bodies go
+/// through axum's extractors, and handlers report bad input as [`ReputestError::Parse`].
+impl From<serde_json::Error> for ReputestError {
+    fn from(e: serde_json::Error) -> Self {
+        ReputestError::UpstreamResponse(e.to_string())
+    }
+}
+
+impl ReputestError {
+    /// Returns true if this is an X API error with the given HTTP status.
+    pub fn is_twitter_status(&self, expected: u16) -> bool {
+        matches!(self, ReputestError::TwitterApi { status, .. } if *status == expected)
+    }
+
+    /// HTTP status code a handler should respond with for this error.
+    ///
+    /// Upstream X API failures, including responses that cannot be decoded, map to
+    /// 502 Bad Gateway, except rate limiting which is passed through as 429.
+    pub fn status_code(&self) -> StatusCode {
+        match self {
+            ReputestError::Database(sqlx::Error::RowNotFound) | ReputestError::NotFound(_) => {
+                StatusCode::NOT_FOUND
+            }
+            ReputestError::Database(_) | ReputestError::Crypto(_) | ReputestError::Schema(_) => {
+                StatusCode::INTERNAL_SERVER_ERROR
+            }
+            ReputestError::TwitterApi { status: 429, .. } => StatusCode::TOO_MANY_REQUESTS,
+            ReputestError::TwitterApi { .. }
+            | ReputestError::Http(_)
+            | ReputestError::UpstreamResponse(_) => StatusCode::BAD_GATEWAY,
+            ReputestError::OAuth(_) => StatusCode::UNAUTHORIZED,
+            ReputestError::Config(_) => StatusCode::SERVICE_UNAVAILABLE,
+            ReputestError::Parse(_) => StatusCode::BAD_REQUEST,
+        }
+    }
+
+    /// Message that is safe to show to clients.
+    ///
+    /// Server-side failures, including missing configuration, get a generic message;
+    /// details only go to the log.
+    pub fn public_message(&self) -> String {
+        match self {
+            ReputestError::NotFound(what) => format!("{} not found.", what),
+            ReputestError::Database(sqlx::Error::RowNotFound) => "Not found.".to_string(),
+            ReputestError::TwitterApi { status: 429, .. } => {
+                "Rate limited by the X API. Please try again later.".to_string()
+            }
+            ReputestError::Parse(msg) => format!("Invalid request: {}", msg),
+            ReputestError::Config(_) => CONFIG_ERROR_MESSAGE.to_string(),
+            _ if self.status_code().is_server_error() => INTERNAL_ERROR_MESSAGE.to_string(),
+            _ => self.to_string(),
+        }
+    }
+
+    /// Seconds until the X API rate limit resets, for the `Retry-After` header.
+    fn retry_after_secs(&self) -> Option<i64> {
+        match self {
+            ReputestError::TwitterApi {
+                rate_limit_reset: Some(reset),
+                ..
+            } => Some((*reset - Utc::now()).num_seconds().max(0)),
+            _ => None,
+        }
+    }
+
+    /// Logs server-side failures and builds the response around `body`.
+    fn respond<B: IntoResponse>(&self, body: B) -> Response {
+        let status = self.status_code();
+        if status.is_server_error() {
+            error!("Request failed ({}): {}", status, self);
+        }
+        let mut response = (status, body).into_response();
+        if let Some(secs) = self.retry_after_secs() {
+            if let Ok(value) = secs.to_string().parse() {
+                response.headers_mut().insert(header::RETRY_AFTER, value);
+            }
+        }
+        response
+    }
+}
+
+impl IntoResponse for ReputestError {
+    fn into_response(self) -> Response {
+        self.respond(self.public_message())
+    }
+}
+
+/// Wrapper that renders a [`ReputestError`] as `{"error": "..."}` for JSON endpoints.
+#[derive(Debug)]
+pub struct JsonError(pub ReputestError);
+
+impl From<ReputestError> for JsonError {
+    fn from(e: ReputestError) -> Self {
+        JsonError(e)
+    }
+}
+
+impl From<sqlx::Error> for JsonError {
+    fn from(e: sqlx::Error) -> Self {
+        JsonError(e.into())
+    }
+}
+
+impl IntoResponse for JsonError {
+    fn into_response(self) -> Response {
+        self.0
+            .respond(Json(json!({ "error": self.0.public_message() })))
+    }
+}
@@ -0,0 +1,348 @@
+//! In-memory graph algorithms over the good vibes and following graphs.
+//!
+//! The materialized views answer the plain degree 1-6 path counts. Queries that
+//! need to restrict which accounts a path may pass through (such as trusted
+//! follow scoring) load the graph with [`crate::db::get_good_vibes_graph`] and
+//! count paths here instead. Community detection (label propagation) and the strongly
+//! connected component search used by the graph statistics also live here.
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use std::collections::{BTreeMap, HashMap, HashSet};
+
+/// Maximum degree reported by the bot and the dashboard (matches the materialized views).
+pub const MAX_DEGREE: usize = 6;
+
+/// Counts acyclic paths from `from` to `to` for each degree 1..=`max_degree`.
+///
+/// Paths follow the edges of `graph` (an adjacency list such as the emitter -> sensors
+/// map returned by `get_good_vibes_graph`). A path never visits the same node twice and
+/// self-loops are ignored, matching the materialized views. Every intermediate node
+/// (not `from` or `to`) must satisfy `allow_intermediate`.
+///
+/// # Parameters
+///
+/// - `graph`: Adjacency list of the directed graph
+/// - `from`: Node the paths start from
+/// - `to`: Node the paths end at
+/// - `max_degree`: Longest path length (in edges) to count
+/// - `allow_intermediate`: Predicate every intermediate node must satisfy
+///
+/// # Returns
+///
+/// A vector of length `max_degree` where index `k` holds the number of paths of length `k + 1`.
+/// All counts are zero when `from == to`.
+pub fn count_paths_by_degree<'a, F>(
+    graph: &'a HashMap<String, Vec<String>>,
+    from: &'a str,
+    to: &str,
+    max_degree: usize,
+    allow_intermediate: F,
+) -> Vec<usize>
+where
+    F: Fn(&str) -> bool,
+{
+    let mut counts = vec![0usize; max_degree];
+    if from == to || max_degree == 0 {
+        return counts;
+    }
+
+    let mut visited: HashSet<&str> = HashSet::new();
+    visited.insert(from);
+    walk(
+        graph,
+        from,
+        to,
+        1,
+        max_degree,
+        &allow_intermediate,
+        &mut visited,
+        &mut counts,
+    );
+    counts
+}
+
+#[allow(clippy::too_many_arguments)]
+fn walk<'a, F>(
+    graph: &'a HashMap<String, Vec<String>>,
+    current: &str,
+    to: &str,
+    depth: usize,
+    max_degree: usize,
+    allow_intermediate: &F,
+    visited: &mut HashSet<&'a str>,
+    counts: &mut [usize],
+) where
+    F: Fn(&str) -> bool,
+{
+    let Some(neighbors) = graph.get(current) else {
+        return;
+    };
+
+    for next in neighbors {
+        let next = next.as_str();
+        if next == current || visited.contains(next) {
+            continue;
+        }
+        if next == to {
+            counts[depth - 1] += 1;
+            continue;
+        }
+        if depth < max_degree && allow_intermediate(next) {
+            visited.insert(next);
+            walk(
+                graph,
+                next,
+                to,
+                depth + 1,
+                max_degree,
+                allow_intermediate,
+                visited,
+                counts,
+            );
+            visited.remove(next);
+        }
+    }
+}
+
+/// Counts acyclic paths from `from` to every reachable node for each degree 1..=`max_degree`.
+///
+/// Same path rules as [`count_paths_by_degree`] (no repeated nodes, self-loops ignored),
+/// but without a fixed target and without restricting intermediates. `from` itself is
+/// never included in the result.
+///
+/// # Returns
+///
+/// A map from node to a vector of length `max_degree` where index `k` holds the number
+/// of paths of length `k + 1` ending at that node.
+pub fn count_paths_from<'a>(
+    graph: &'a HashMap<String, Vec<String>>,
+    from: &'a str,
+    max_degree: usize,
+) -> HashMap<String, Vec<usize>> {
+    let mut counts: HashMap<String, Vec<usize>> = HashMap::new();
+    if max_degree == 0 {
+        return counts;
+    }
+
+    let mut visited: HashSet<&str> = HashSet::new();
+    visited.insert(from);
+    walk_all(graph, from, 1, max_degree, &mut visited, &mut counts);
+    counts
+}
+
+fn walk_all<'a>(
+    graph: &'a HashMap<String, Vec<String>>,
+    current: &str,
+    depth: usize,
+    max_degree: usize,
+    visited: &mut HashSet<&'a str>,
+    counts: &mut HashMap<String, Vec<usize>>,
+) {
+    let Some(neighbors) = graph.get(current) else {
+        return;
+    };
+
+    for next in neighbors {
+        let next = next.as_str();
+        if next == current || visited.contains(next) {
+            continue;
+        }
+        counts
+            .entry(next.to_string())
+            .or_insert_with(|| vec![0; max_degree])[depth - 1] += 1;
+        if depth < max_degree {
+            visited.insert(next);
+            walk_all(graph, next, depth + 1, max_degree, visited, counts);
+            visited.remove(next);
+        }
+    }
+}
+
+/// Returns the graph with every edge reversed (e.g. emitter -> sensors becomes sensor -> emitters).
+pub fn reverse_graph(graph: &HashMap<String, Vec<String>>) -> HashMap<String, Vec<String>> {
+    let mut reversed: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, targets) in graph {
+        for to in targets {
+            reversed.entry(to.clone()).or_default().push(from.clone());
+        }
+    }
+    reversed
+}
+
+/// Detects communities with label propagation, treating the graph as undirected.
+///
+/// Every node starts with its own label; in each round nodes are visited in a random
+/// order and adopt the label most common among their neighbours (keeping their current
+/// label when it is among the most common, otherwise breaking ties randomly). Rounds
+/// stop when no label changes or after `max_iterations`.
+///
+/// The result is deterministic for a given graph and `seed`: nodes are indexed in sorted
+/// order and all randomness comes from a `StdRng` seeded with `seed`.
+///
+/// # Parameters
+///
+/// - `graph`: Adjacency list of the directed graph (edge direction is ignored)
+/// - `seed`: Seed for the visiting order and tie-breaking
+/// - `max_iterations`: Upper bound on the number of rounds
+///
+/// # Returns
+///
+/// A map from node to cluster ID. Cluster IDs start at 1 and are ordered by cluster size
+/// (largest first), then by the smallest node ID in the cluster.
+pub fn label_propagation(
+    graph: &HashMap<String, Vec<String>>,
+    seed: u64,
+    max_iterations: usize,
+) -> HashMap<String, usize> {
+    let mut nodes: Vec<&str> = graph
+        .iter()
+        .flat_map(|(from, targets)| {
+            std::iter::once(from.as_str()).chain(targets.iter().map(String::as_str))
+        })
+        .collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    let index: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+    let mut neighbors: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (from, targets) in graph {
+        let a = index[from.as_str()];
+        for to in targets {
+            let b = index[to.as_str()];
+            if a != b {
+                neighbors[a].push(b);
+                neighbors[b].push(a);
+            }
+        }
+    }
+    for list in &mut neighbors {
+        list.sort_unstable();
+        list.dedup();
+    }
+
+    let mut labels: Vec<usize> = (0..nodes.len()).collect();
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut order: Vec<usize> = (0..nodes.len()).collect();
+
+    for _ in 0..max_iterations {
+        order.shuffle(&mut rng);
+        let mut changed = false;
+
+        for &node in &order {
+            if neighbors[node].is_empty() {
+                continue;
+            }
+            let mut counts: BTreeMap<usize, usize> = BTreeMap::new();
+            for &n in &neighbors[node] {
+                *counts.entry(labels[n]).or_default() += 1;
+            }
+            let best = counts.values().copied().max().unwrap_or(0);
+            let candidates: Vec<usize> = counts
+                .into_iter()
+                .filter(|(_, c)| *c == best)
+                .map(|(label, _)| label)
+                .collect();
+            if candidates.contains(&labels[node]) {
+                continue;
+            }
+            labels[node] = candidates[rng.gen_range(0..candidates.len())];
+            changed = true;
+        }
+
+        if !changed {
+            break;
+        }
+    }
+
+    // Renumber labels: largest cluster first, then by smallest member (nodes are sorted)
+    let mut members: BTreeMap<usize, Vec<usize>> = BTreeMap::new();
+    for (node, label) in labels.iter().enumerate() {
+        members.entry(*label).or_default().push(node);
+    }
+    let mut clusters: Vec<Vec<usize>> = members.into_values().collect();
+    clusters.sort_by(|a, b| b.len().cmp(&a.len()).then_with(|| a[0].cmp(&b[0])));
+
+    let mut assignments = HashMap::new();
+    for (i, cluster) in clusters.iter().enumerate() {
+        for &node in cluster {
+            assignments.insert(nodes[node].to_string(), i + 1);
+        }
+    }
+    assignments
+}
+
+/// Returns the size of the largest strongly connected component of a directed graph.
+///
+/// Uses Kosaraju's algorithm with explicit stacks (no recursion), so deep graphs cannot
+/// overflow the call stack. Self-loops do not affect the result; an isolated node is a
+/// component of size 1. Returns 0 for an empty graph.
+pub fn largest_scc_size(graph: &HashMap<String, Vec<String>>) -> usize {
+    let mut nodes: Vec<&str> = graph
+        .iter()
+        .flat_map(|(from, targets)| {
+            std::iter::once(from.as_str()).chain(targets.iter().map(String::as_str))
+        })
+        .collect();
+    nodes.sort_unstable();
+    nodes.dedup();
+    let index: HashMap<&str, usize> = nodes.iter().enumerate().map(|(i, n)| (*n, i)).collect();
+
+    let mut adj: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    let mut radj: Vec<Vec<usize>> = vec![Vec::new(); nodes.len()];
+    for (from, targets) in graph {
+        let a = index[from.as_str()];
+        for to in targets {
+            let b = index[to.as_str()];
+            adj[a].push(b);
+            radj[b].push(a);
+        }
+    }
+
+    // First pass: order nodes by DFS finish time
+    let mut visited = vec![false; nodes.len()];
+    let mut order: Vec<usize> = Vec::with_capacity(nodes.len());
+    for start in 0..nodes.len() {
+        if visited[start] {
+            continue;
+        }
+        visited[start] = true;
+        let mut stack: Vec<(usize, usize)> = vec![(start, 0)];
+        while let Some((node, next)) = stack.last_mut() {
+            if let Some(&neighbor) = adj[*node].get(*next) {
+                *next += 1;
+                if !visited[neighbor] {
+                    visited[neighbor] = true;
+                    stack.push((neighbor, 0));
+                }
+            } else {
+                order.push(*node);
+                stack.pop();
+            }
+        }
+    }
+
+    // Second pass: flood fill the reversed graph in reverse finish order
+    let mut assigned = vec![false; nodes.len()];
+    let mut largest = 0;
+    for &start in order.iter().rev() {
+        if assigned[start] {
+            continue;
+        }
+        assigned[start] = true;
+        let mut size = 0;
+        let mut stack = vec![start];
+        while let Some(node) = stack.pop() {
+            size += 1;
+            for &neighbor in &radj[node] {
+                if !assigned[neighbor] {
+                    assigned[neighbor] = true;
+                    stack.push(neighbor);
+                }
+            }
+        }
+        largest = largest.max(size);
+    }
+    largest
+}
@@ -5,31 +5,50 @@
 
 use axum::{
     body::Bytes,
-    extract::{Query, Request, State},
-    http::{header, HeaderMap, StatusCode},
+    extract::{FromRequestParts, Path, Query, Request, State},
+    http::{header, request::Parts, HeaderMap, StatusCode},
     response::{AppendHeaders, Html, IntoResponse, Json, Redirect},
 };
-use log::{error, info, warn};
+use log::{debug, error, info, warn};
 use serde_json::{json, Value};
 use sqlx::PgPool;
+use std::sync::Arc;
 
-use crate::config::get_allowed_username;
+use crate::config::{AppConfig, CronTaskConfig, WebConfig};
+use crate::cronjob::{is_cron_task, run_task, JobContext, BOT_TASKS, CRON_TASKS};
+use crate::crypto::TokenKind;
 use crate::db::{
-    create_session, delete_session, get_all_following, get_all_good_vibes_degrees,
-    get_session_by_id, WebSession,
+    count_outbound_replies_by_status, create_session, delete_session, get_all_following,
+    get_all_good_vibes_degrees, get_all_good_vibes_degrees_in_category, get_bot_paused,
+    get_clusters, get_follower_counts, get_good_vibes_categories, get_graph_stats_history,
+    get_mutual_follows_without_vibes, get_recent_job_runs, get_recent_materialize_times,
+    get_recent_token_refreshes, get_session_by_id, get_stuck_replies, get_suggestion_conversion,
+    get_user_id_by_username, get_user_profile, get_user_role, get_user_roles, get_user_sessions,
+    grant_user_role, lock_session, requeue_reply, revoke_user_role, save_user, set_bot_paused,
+    update_role_username, GraphStatsRow, Role, SessionTokens, WebSession,
 };
+use crate::error::{JsonError, ReputestError};
 use crate::oauth::{
     build_authorization_url, exchange_authorization_code, generate_code_challenge,
-    generate_code_verifier, generate_oauth_state,
+    generate_code_verifier, generate_oauth_state, refresh_access_token, revoke_token,
+    TokenResponse,
 };
+use crate::recommend::{recommend_for_user, record_suggestions, SuggestionSource};
+use crate::twitter::rate_limit::RateLimitTracker;
+use crate::twitter::{lookup_user_by_username, reprocess_tweet};
 
 /// Application state for routes that need pool and OAuth config.
 #[derive(Clone)]
 pub struct AppState {
     pub pool: PgPool,
-    pub base_url: Option<String>,
+    /// Web login settings; `None` when `BASE_URL` is unset
+    pub web: Option<WebConfig>,
     pub oauth_client_id: Option<String>,
     pub oauth_client_secret: Option<String>,
+    /// The configuration loaded at startup
+    pub config: Arc<AppConfig>,
+    /// Scheduled job state, for running tasks from `/admin`
+    pub jobs: JobContext,
 }
 
 /// Parses a cookie value from the Cookie header by name.
@@ -48,7 +67,8 @@ fn get_cookie_from_header(
 }
 
 /// Handles GET /reputest: OAuth callback when ?code=&state= are present, otherwise "Reputesting!".
-/// The callback URI is https://reputest.fly.dev/reputest — X redirects here after authorization.
+/// Kept as a callback for deployments whose X Developer Portal still points at /reputest
+/// (`OAUTH_CALLBACK_PATH=/reputest`); the default callback is [`handle_oauth_callback`].
 pub async fn handle_reputest_get(
     State(state): State<AppState>,
     Query(query): Query<OAuthCallbackQuery>,
@@ -90,34 +110,71 @@ pub async fn handle_reputest_post() -> &'static str {
 /// A JSON response containing:
 /// - `status`: Always "healthy" when the service is running
 /// - `service`: The service name "reputest"
+/// - `db_pool`: Connection pool statistics (`size` open connections, `idle` connections,
+///   `max_connections`). The pool is not queried, so this stays cheap and responds even
+///   when the database is unreachable.
 ///
 /// # Example Response
 ///
 /// ```json
 /// {
 ///   "status": "healthy",
-///   "service": "reputest"
+///   "service": "reputest",
+///   "db_pool": { "size": 3, "idle": 2, "max_connections": 10 }
 /// }
 /// ```
-pub async fn handle_health() -> Json<Value> {
-    Json(json!({"status": "healthy", "service": "reputest"}))
+pub async fn handle_health(State(state): State<AppState>) -> Json<Value> {
+    Json(json!({
+        "status": "healthy",
+        "service": "reputest",
+        "db_pool": {
+            "size": state.pool.size(),
+            "idle": state.pool.num_idle(),
+            "max_connections": state.pool.options().get_max_connections(),
+        },
+    }))
+}
+
+/// Query params for the dashboard.
+#[derive(serde::Deserialize, Default)]
+pub struct DashboardQuery {
+    /// Only count edges in this category (e.g. `dev`)
+    pub category: Option<String>,
+}
+
+impl DashboardQuery {
+    /// The requested category, lowercase and without `#`; `None` if absent or empty.
+    fn category(&self) -> Option<String> {
+        self.category
+            .as_deref()
+            .map(|c| c.trim().trim_start_matches('#').to_lowercase())
+            .filter(|c| !c.is_empty())
+    }
 }
 
 /// Handles GET requests to the root `/` endpoint.
 ///
 /// This endpoint displays a table with data from the view_all_good_vibes_degrees view.
-/// It shows sensor, emitter, and all four degree path counts.
+/// It shows sensor, emitter, and all four degree path counts. With `?category=dev` the
+/// counts only use edges in that category; links to the categories in use are listed
+/// above the table.
 ///
 /// # Returns
 ///
 /// An HTML page with a table displaying the view data.
 pub async fn handle_root(
     State(state): State<AppState>,
-) -> Result<Html<String>, (StatusCode, String)> {
-    match get_all_good_vibes_degrees(&state.pool).await {
-        Ok(rows) => {
-            let mut html = String::from(
-                r#"<!DOCTYPE html>
+    Query(query): Query<DashboardQuery>,
+) -> Result<Html<String>, ReputestError> {
+    let category = query.category();
+    let rows = match &category {
+        Some(category) => get_all_good_vibes_degrees_in_category(&state.pool, category).await?,
+        None => get_all_good_vibes_degrees(&state.pool).await?,
+    };
+    let categories = get_good_vibes_categories(&state.pool).await?;
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
@@ -169,9 +226,32 @@ pub async fn handle_root(
 </head>
 <body>
     <div class="container">
-        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a></nav>
-        <h1>Good Vibes</h1>
-        <table>
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+"#,
+    );
+
+    match &category {
+        Some(category) => html.push_str(&format!(
+            "        <h1>Good Vibes in #{}</h1>\n",
+            html_escape(category)
+        )),
+        None => html.push_str("        <h1>Good Vibes</h1>\n"),
+    }
+    if !categories.is_empty() {
+        html.push_str("        <p class=\"categories\">Categories: <a href=\"/\">all</a>");
+        for (name, edge_count) in &categories {
+            html.push_str(&format!(
+                " <a href=\"/?category={}\">#{}</a> ({})",
+                urlencoding::encode(name),
+                html_escape(name),
+                edge_count
+            ));
+        }
+        html.push_str("</p>\n");
+    }
+
+    html.push_str(
+        r#"        <table>
             <thead>
                 <tr>
                     <th>sensor</th>
@@ -188,43 +268,33 @@ pub async fn handle_root(
             </thead>
             <tbody>
 "#,
-            );
+    );
 
-            for row in rows {
-                html.push_str(&format!(
-                    "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                </tr>\n",
-                    html_escape(&row.sensor_username),
-                    html_escape(&row.sensor_name),
-                    html_escape(&row.emitter_username),
-                    html_escape(&row.emitter_name),
-                    row.degree_one_path_count,
-                    row.degree_two_path_count,
-                    row.degree_three_path_count,
-                    row.degree_four_path_count,
-                    row.degree_five_path_count,
-                    row.degree_six_path_count
-                ));
-            }
+    for row in rows {
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                </tr>\n",
+            html_escape(&row.sensor_username),
+            html_escape(&row.sensor_name),
+            html_escape(&row.emitter_username),
+            html_escape(&row.emitter_name),
+            row.degree_one_path_count,
+            row.degree_two_path_count,
+            row.degree_three_path_count,
+            row.degree_four_path_count,
+            row.degree_five_path_count,
+            row.degree_six_path_count
+        ));
+    }
 
-            html.push_str(
-                r#"            </tbody>
+    html.push_str(
+        r#"            </tbody>
         </table>
     </div>
 </body>
 </html>"#,
-            );
+    );
 
-            Ok(Html(html))
-        }
-        Err(e) => {
-            // SECURITY: Log detailed error server-side but return generic message to client
-            error!("Failed to query view_all_good_vibes_degrees: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "An internal error occurred. Please try again later.".to_string(),
-            ))
-        }
-    }
+    Ok(Html(html))
 }
 
 /// Handles GET requests to the /following endpoint.
@@ -232,11 +302,15 @@ pub async fn handle_root(
 /// Displays a table of following relationships (follower, followed, created_at).
 pub async fn handle_following(
     State(state): State<AppState>,
-) -> Result<Html<String>, (StatusCode, String)> {
-    match get_all_following(&state.pool).await {
-        Ok(rows) => {
-            let mut html = String::from(
-                r#"<!DOCTYPE html>
+) -> Result<Html<String>, ReputestError> {
+    let counts = get_follower_counts(&state.pool).await?;
+
+    let mutuals = get_mutual_follows_without_vibes(&state.pool).await?;
+
+    let rows = get_all_following(&state.pool).await?;
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
 <html lang="en">
 <head>
     <meta charset="UTF-8">
@@ -267,48 +341,646 @@ pub async fn handle_following(
 </head>
 <body>
     <div class="container">
-        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a></nav>
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
         <h1>Following</h1>
+        <h2>Follower counts</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>account</th>
+                    <th>known followers</th>
+                    <th>API followers</th>
+                    <th>API fetched_at</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for row in counts {
+        let api_count = row
+            .api_follower_count
+            .map(|c| c.to_string())
+            .unwrap_or_else(|| "-".to_string());
+        let fetched_at = row
+            .api_follower_count_fetched_at
+            .map(|t| t.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            html_escape(&row.username),
+            row.known_follower_count,
+            html_escape(&api_count),
+            html_escape(&fetched_at)
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>Mutual follows with no good vibes yet</h2>
+        <p>Accounts that follow each other but have not declared good vibes in either direction.</p>
+        <table>
+            <thead>
+                <tr>
+                    <th>account</th>
+                    <th>account</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for pair in mutuals {
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            html_escape(&pair.user_a_username),
+            html_escape(&pair.user_b_username)
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>Follow edges</h2>
+        <p>A follow is trusted when the follower has also declared good vibes from the followed account.</p>
         <table>
             <thead>
                 <tr>
                     <th>follower</th>
                     <th>followed</th>
+                    <th>trusted</th>
                     <th>created_at</th>
                 </tr>
             </thead>
             <tbody>
 "#,
-            );
+    );
 
-            for row in rows {
-                let created_at = row.created_at.format("%Y-%m-%d %H:%M:%S UTC").to_string();
-                html.push_str(&format!(
-                    "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
-                    html_escape(&row.follower_username),
-                    html_escape(&row.followed_username),
-                    html_escape(&created_at)
-                ));
-            }
+    for row in rows {
+        let created_at = row.created_at.format("%Y-%m-%d %H:%M:%S UTC").to_string();
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            html_escape(&row.follower_username),
+            html_escape(&row.followed_username),
+            if row.is_trusted { "yes" } else { "no" },
+            html_escape(&created_at)
+        ));
+    }
 
-            html.push_str(
-                r#"            </tbody>
+    html.push_str(
+        r#"            </tbody>
         </table>
     </div>
 </body>
 </html>"#,
-            );
+    );
 
-            Ok(Html(html))
-        }
-        Err(e) => {
-            error!("Failed to query following: {}", e);
-            Err((
-                StatusCode::INTERNAL_SERVER_ERROR,
-                "An internal error occurred. Please try again later.".to_string(),
-            ))
+    Ok(Html(html))
+}
+
+/// Handles GET requests to the /clusters endpoint.
+///
+/// Displays the communities found by the latest community detection run: size, internal
+/// and external good vibes edges, density, top member and members.
+///
+/// # Returns
+///
+/// An HTML page with one row per cluster, or 500 on database errors.
+pub async fn handle_clusters(State(state): State<AppState>) -> Result<Html<String>, ReputestError> {
+    let clusters = get_clusters(&state.pool).await?;
+
+    let computed = clusters
+        .first()
+        .map(|c| {
+            format!(
+                "Computed {} with seed {}.",
+                c.computed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+                c.seed
+            )
+        })
+        .unwrap_or_else(|| "No clusters computed yet.".to_string());
+
+    let mut html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Clusters</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }}
+        .container {{
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }}
+        h1 {{ color: #333; margin-top: 0; }}
+        table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
+        th, td {{ padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }}
+        th {{ background-color: #f8f9fa; font-weight: 600; color: #555; }}
+        tr:hover {{ background-color: #f8f9fa; }}
+        .count {{ text-align: right; font-variant-numeric: tabular-nums; }}
+        nav a {{ margin-right: 16px; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+        <h1>Clusters</h1>
+        <p>Communities in the good vibes graph (label propagation). {}</p>
+        <table>
+            <thead>
+                <tr>
+                    <th class="count">cluster</th>
+                    <th class="count">members</th>
+                    <th class="count">internal edges</th>
+                    <th class="count">external edges</th>
+                    <th class="count">density</th>
+                    <th>top member</th>
+                    <th>members</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+        html_escape(&computed)
+    );
+
+    for cluster in &clusters {
+        let members: Vec<String> = cluster.members.iter().map(|m| html_escape(m)).collect();
+        html.push_str(&format!(
+            "                <tr>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{:.3}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            cluster.cluster_id,
+            cluster.member_count,
+            cluster.internal_edge_count,
+            cluster.external_edge_count,
+            cluster.density,
+            html_escape(cluster.top_member_username.as_deref().unwrap_or("-")),
+            members.join(", ")
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Ok(Html(html))
+}
+
+/// Handles GET requests to the /api/v1/clusters endpoint.
+///
+/// Returns the clusters from the latest community detection run as JSON:
+/// `{"clusters": [{"cluster_id", "member_count", "internal_edge_count", "external_edge_count",
+/// "density", "top_member_username", "members", "seed", "computed_at"}]}`.
+pub async fn handle_api_clusters(State(state): State<AppState>) -> Result<Json<Value>, JsonError> {
+    let clusters = get_clusters(&state.pool).await?;
+    Ok(Json(json!({ "clusters": clusters })))
+}
+
+/// Default number of days of history on the stats page and API.
+const DEFAULT_STATS_DAYS: i32 = 30;
+/// Maximum number of days of history that can be requested.
+const MAX_STATS_DAYS: i32 = 365;
+
+/// Query params for the stats page and API.
+#[derive(serde::Deserialize)]
+pub struct StatsQuery {
+    pub days: Option<i32>,
+}
+
+impl StatsQuery {
+    /// Requested history length, clamped to 1..=MAX_STATS_DAYS.
+    fn days(&self) -> i32 {
+        self.days
+            .unwrap_or(DEFAULT_STATS_DAYS)
+            .clamp(1, MAX_STATS_DAYS)
+    }
+}
+
+/// Renders a line chart of `values` (evenly spaced, oldest first) as inline SVG.
+fn svg_line_chart(values: &[f64], first_label: &str, last_label: &str) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 160.0;
+    const PAD: f64 = 40.0;
+
+    if values.is_empty() {
+        return "<p>No data yet.</p>".to_string();
+    }
+    let max = values.iter().copied().fold(0.0, f64::max);
+    let max = if max > 0.0 { max } else { 1.0 };
+    let step = if values.len() > 1 {
+        (WIDTH - 2.0 * PAD) / (values.len() - 1) as f64
+    } else {
+        0.0
+    };
+    let points: Vec<(f64, f64)> = values
+        .iter()
+        .enumerate()
+        .map(|(i, v)| {
+            let x = if values.len() > 1 {
+                PAD + i as f64 * step
+            } else {
+                WIDTH / 2.0
+            };
+            (x, HEIGHT - PAD - v / max * (HEIGHT - 2.0 * PAD))
+        })
+        .collect();
+    let polyline: Vec<String> = points
+        .iter()
+        .map(|(x, y)| format!("{:.1},{:.1}", x, y))
+        .collect();
+    let dots: String = points
+        .iter()
+        .map(|(x, y)| {
+            format!(
+                r##"<circle cx="{:.1}" cy="{:.1}" r="3" fill="#1d9bf0"/>"##,
+                x, y
+            )
+        })
+        .collect();
+    let max_label = if max.fract() == 0.0 {
+        format!("{}", max)
+    } else {
+        format!("{:.3}", max)
+    };
+
+    format!(
+        r##"<svg viewBox="0 0 {w} {h}" width="100%" role="img">
+            <line x1="{pad}" y1="{base}" x2="{right}" y2="{base}" stroke="#ccc"/>
+            <line x1="{pad}" y1="{pad}" x2="{pad}" y2="{base}" stroke="#ccc"/>
+            <text x="{label_x}" y="{pad}" font-size="11" text-anchor="end" fill="#555">{max}</text>
+            <text x="{label_x}" y="{base}" font-size="11" text-anchor="end" fill="#555">0</text>
+            <text x="{pad}" y="{date_y}" font-size="11" fill="#555">{first}</text>
+            <text x="{right}" y="{date_y}" font-size="11" text-anchor="end" fill="#555">{last}</text>
+            <polyline points="{points}" fill="none" stroke="#1d9bf0" stroke-width="2"/>
+            {dots}
+        </svg>"##,
+        w = WIDTH,
+        h = HEIGHT,
+        pad = PAD,
+        base = HEIGHT - PAD,
+        right = WIDTH - PAD,
+        label_x = PAD - 4.0,
+        date_y = HEIGHT - PAD + 16.0,
+        max = max_label,
+        first = html_escape(first_label),
+        last = html_escape(last_label),
+        points = polyline.join(" "),
+        dots = dots,
+    )
+}
+
+/// Renders a degree histogram as an inline SVG bar chart (last bucket labelled "N+").
+fn svg_degree_histogram(buckets: &[i32]) -> String {
+    const WIDTH: f64 = 600.0;
+    const HEIGHT: f64 = 160.0;
+    const PAD: f64 = 30.0;
+
+    if buckets.is_empty() {
+        return "<p>No data yet.</p>".to_string();
+    }
+    let max = buckets.iter().copied().max().unwrap_or(0).max(1) as f64;
+    let slot = (WIDTH - 2.0 * PAD) / buckets.len() as f64;
+    let mut bars = String::new();
+    for (degree, count) in buckets.iter().enumerate() {
+        let height = *count as f64 / max * (HEIGHT - 2.0 * PAD);
+        let x = PAD + degree as f64 * slot;
+        let label = if degree == buckets.len() - 1 {
+            format!("{}+", degree)
+        } else {
+            degree.to_string()
+        };
+        bars.push_str(&format!(
+            r##"<rect x="{:.1}" y="{:.1}" width="{:.1}" height="{:.1}" fill="#1d9bf0"/><text x="{:.1}" y="{:.1}" font-size="11" text-anchor="middle" fill="#555">{}</text><text x="{:.1}" y="{:.1}" font-size="11" text-anchor="middle" fill="#555">{}</text>"##,
+            x + slot * 0.1,
+            HEIGHT - PAD - height,
+            slot * 0.8,
+            height,
+            x + slot / 2.0,
+            HEIGHT - PAD - height - 4.0,
+            count,
+            x + slot / 2.0,
+            HEIGHT - PAD + 16.0,
+            label
+        ));
+    }
+    format!(
+        r##"<svg viewBox="0 0 {} {}" width="100%" role="img">{}</svg>"##,
+        WIDTH, HEIGHT, bars
+    )
+}
+
+/// Handles GET requests to the /stats endpoint.
+///
+/// Charts graph-wide statistics (one snapshot per day, `?days=` controls the range,
+/// default 30) as inline SVG, plus the degree distributions of the latest snapshot.
+pub async fn handle_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Html<String>, ReputestError> {
+    let history = get_graph_stats_history(&state.pool, query.days()).await?;
+
+    let first_label = history
+        .first()
+        .map(|s| s.computed_at.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let last_label = history
+        .last()
+        .map(|s| s.computed_at.format("%Y-%m-%d").to_string())
+        .unwrap_or_default();
+    let chart = |f: fn(&GraphStatsRow) -> f64| {
+        let values: Vec<f64> = history.iter().map(f).collect();
+        svg_line_chart(&values, &first_label, &last_label)
+    };
+
+    let summary = match history.last() {
+        Some(latest) => format!(
+            r#"<p>Latest snapshot: {}.</p>
+        <table>
+            <tbody>
+                <tr><th>nodes</th><td class="count">{}</td></tr>
+                <tr><th>edges</th><td class="count">{}</td></tr>
+                <tr><th>reciprocated edges</th><td class="count">{} ({:.1}%)</td></tr>
+                <tr><th>largest strongly connected component</th><td class="count">{}</td></tr>
+                <tr><th>new edges in the last day</th><td class="count">{}</td></tr>
+            </tbody>
+        </table>"#,
+            latest.computed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            latest.node_count,
+            latest.edge_count,
+            latest.reciprocal_edge_count,
+            latest.reciprocity_rate * 100.0,
+            latest.largest_scc_size,
+            latest.new_edges_last_day
+        ),
+        None => "<p>No snapshots recorded yet.</p>".to_string(),
+    };
+    let (in_degrees, out_degrees) = history
+        .last()
+        .map(|s| {
+            (
+                svg_degree_histogram(&s.in_degree_histogram),
+                svg_degree_histogram(&s.out_degree_histogram),
+            )
+        })
+        .unwrap_or_else(|| (svg_degree_histogram(&[]), svg_degree_histogram(&[])));
+
+    let html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Stats</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }}
+        .container {{
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }}
+        h1 {{ color: #333; margin-top: 0; }}
+        h2 {{ color: #333; margin-top: 30px; }}
+        table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
+        th, td {{ padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }}
+        th {{ background-color: #f8f9fa; font-weight: 600; color: #555; }}
+        .count {{ text-align: right; font-variant-numeric: tabular-nums; }}
+        nav a {{ margin-right: 16px; }}
+        svg {{ max-width: 600px; display: block; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+        <h1>Stats</h1>
+        <p>Growth of the good vibes graph over the last {} days (last snapshot of each day).</p>
+        {}
+        <h2>Nodes</h2>
+        {}
+        <h2>Edges</h2>
+        {}
+        <h2>Largest strongly connected component</h2>
+        {}
+        <h2>Reciprocity rate</h2>
+        {}
+        <h2>New edges in the last day</h2>
+        {}
+        <h2>In-degree distribution</h2>
+        {}
+        <h2>Out-degree distribution</h2>
+        {}
+    </div>
+</body>
+</html>"#,
+        query.days(),
+        summary,
+        chart(|s| s.node_count as f64),
+        chart(|s| s.edge_count as f64),
+        chart(|s| s.largest_scc_size as f64),
+        chart(|s| s.reciprocity_rate),
+        chart(|s| s.new_edges_last_day as f64),
+        in_degrees,
+        out_degrees
+    );
+
+    Ok(Html(html))
+}
+
+/// Handles GET requests to the /api/v1/stats endpoint.
+///
+/// Returns `{"latest": {...} | null, "history": [...]}` where history holds the last
+/// snapshot of each day (`?days=`, default 30, oldest first) with the fields
+/// `node_count`, `edge_count`, `reciprocal_edge_count`, `reciprocity_rate`,
+/// `largest_scc_size`, `in_degree_histogram`, `out_degree_histogram`,
+/// `new_edges_last_day` and `computed_at`.
+pub async fn handle_api_stats(
+    State(state): State<AppState>,
+    Query(query): Query<StatsQuery>,
+) -> Result<Json<Value>, JsonError> {
+    let history = get_graph_stats_history(&state.pool, query.days()).await?;
+    Ok(Json(
+        json!({ "latest": history.last(), "history": history }),
+    ))
+}
+
+/// Maximum number of suggestions shown on the profile page.
+const PROFILE_SUGGESTION_LIMIT: usize = 10;
+
+/// Handles GET requests to the /profile/:username endpoint.
+///
+/// Displays the user's good vibes totals and follower counts. When the logged-in user
+/// views their own profile, the page also shows "who should I vibe?" suggestions from
+/// the recommender and records them for conversion tracking; other viewers get the
+/// totals only, so anonymous traffic never runs the recommender.
+///
+/// # Returns
+///
+/// An HTML page, 404 if the username is unknown, or 500 on database errors.
+pub async fn handle_profile(
+    State(state): State<AppState>,
+    Path(username): Path<String>,
+    headers: HeaderMap,
+) -> Result<Html<String>, ReputestError> {
+    let username = username.trim_start_matches('@');
+    let profile = get_user_profile(&state.pool, username)
+        .await?
+        .ok_or_else(|| ReputestError::NotFound("User".to_string()))?;
+
+    let viewer_is_owner = get_session_from_headers(&state, &headers)
+        .await
+        .is_some_and(|session| session.user_id == profile.user_id);
+    let suggestions = if viewer_is_owner {
+        recommend_for_user(&state.pool, &profile.user_id, PROFILE_SUGGESTION_LIMIT).await?
+    } else {
+        Vec::new()
+    };
+    if !suggestions.is_empty() {
+        if let Err(e) = record_suggestions(
+            &state.pool,
+            &profile.user_id,
+            &suggestions,
+            SuggestionSource::Profile,
+        )
+        .await
+        {
+            error!(
+                "Failed to record profile suggestions for {}: {}",
+                username, e
+            );
         }
     }
+
+    let api_followers = profile
+        .api_follower_count
+        .map(|c| c.to_string())
+        .unwrap_or_else(|| "-".to_string());
+
+    let mut html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - {username}</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }}
+        .container {{
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }}
+        h1 {{ color: #333; margin-top: 0; }}
+        table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
+        th, td {{ padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }}
+        th {{ background-color: #f8f9fa; font-weight: 600; color: #555; }}
+        tr:hover {{ background-color: #f8f9fa; }}
+        .count {{ text-align: right; font-variant-numeric: tabular-nums; }}
+        nav a {{ margin-right: 16px; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+        <h1>{username}</h1>
+        <p>{name}</p>
+        <table>
+            <tbody>
+                <tr><th>good vibes declared</th><td class="count">{declared}</td></tr>
+                <tr><th>good vibes received</th><td class="count">{received}</td></tr>
+                <tr><th>known followers</th><td class="count">{known}</td></tr>
+                <tr><th>API followers</th><td class="count">{api}</td></tr>
+            </tbody>
+        </table>
+"#,
+        username = html_escape(&profile.username),
+        name = html_escape(&profile.name),
+        declared = profile.vibes_declared,
+        received = profile.vibes_received,
+        known = profile.known_follower_count,
+        api = html_escape(&api_followers),
+    );
+
+    if !viewer_is_owner {
+        html.push_str(
+            r#"    </div>
+</body>
+</html>"#,
+        );
+        return Ok(Html(html));
+    }
+
+    html.push_str(
+        r#"        <h2>Who should you vibe?</h2>
+        <p>Accounts with 2nd/3rd-degree vibe paths but no direct good vibes yet, ranked by path count, recency and follow overlap.</p>
+        <table>
+            <thead>
+                <tr>
+                    <th>account</th>
+                    <th class="count">2nd-degree paths</th>
+                    <th class="count">3rd-degree paths</th>
+                    <th class="count">follow overlap</th>
+                    <th>last vibed</th>
+                    <th class="count">score</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for suggestion in &suggestions {
+        let last_vibed = suggestion
+            .last_vibed_at
+            .map(|t| t.format("%Y-%m-%d").to_string())
+            .unwrap_or_else(|| "-".to_string());
+        html.push_str(&format!(
+            "                <tr>\n                    <td><a href=\"/profile/{}\">{}</a></td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{:.2}</td>\n                </tr>\n",
+            urlencoding::encode(&suggestion.username),
+            html_escape(&suggestion.username),
+            suggestion.degree_two_paths,
+            suggestion.degree_three_paths,
+            suggestion.follow_overlap,
+            html_escape(&last_vibed),
+            suggestion.score
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Ok(Html(html))
 }
 
 /// GET /login — Login page with "Login with X" link. If already logged in, redirect to /playground.
@@ -353,16 +1025,15 @@ pub async fn handle_login(State(state): State<AppState>, request: Request) -> im
 /// GET /login/start — Start OAuth flow: set PKCE cookies and redirect to X.
 pub async fn handle_login_start(
     State(state): State<AppState>,
-) -> Result<impl IntoResponse, (StatusCode, String)> {
-    let base_url = state.base_url.as_deref().ok_or((
-        StatusCode::SERVICE_UNAVAILABLE,
-        "Web login not configured (BASE_URL).".to_string(),
-    ))?;
-    let client_id = state.oauth_client_id.as_deref().ok_or((
-        StatusCode::SERVICE_UNAVAILABLE,
-        "Web login not configured (XAPI_CLIENT_ID).".to_string(),
-    ))?;
-    let redirect_uri = base_url.trim_end_matches('/').to_string();
+) -> Result<impl IntoResponse, ReputestError> {
+    let web = state
+        .web
+        .as_ref()
+        .ok_or_else(|| ReputestError::Config("Web login not configured (BASE_URL).".to_string()))?;
+    let client_id = state.oauth_client_id.as_deref().ok_or_else(|| {
+        ReputestError::Config("Web login not configured (XAPI_CLIENT_ID).".to_string())
+    })?;
+    let redirect_uri = web.callback_url();
     let code_verifier = generate_code_verifier();
     let code_challenge = generate_code_challenge(&code_verifier);
     let oauth_state = generate_oauth_state();
@@ -372,18 +1043,19 @@ pub async fn handle_login_start(
     // Set cookies: 10 min max-age, Path=/, HttpOnly.
     // SameSite=None; Secure so cookies are sent when Twitter redirects back (cross-site).
     // SameSite=Lax can drop cookies on redirect in Safari and some other browsers.
-    let cookie_attrs = if base_url.starts_with("https://") {
+    let cookie_attrs = if web.is_https() {
         "SameSite=None; Secure; Max-Age=600"
     } else {
         "SameSite=Lax; Max-Age=600"
     };
+    let domain_attr = web.cookie_domain_attribute();
     let state_cookie = format!(
-        "oauth_state={}; Path=/; HttpOnly; {}",
-        oauth_state, cookie_attrs
+        "oauth_state={}; Path=/; HttpOnly; {}{}",
+        oauth_state, cookie_attrs, domain_attr
     );
     let verifier_cookie = format!(
-        "oauth_code_verifier={}; Path=/; HttpOnly; {}",
-        code_verifier, cookie_attrs
+        "oauth_code_verifier={}; Path=/; HttpOnly; {}{}",
+        code_verifier, cookie_attrs, domain_attr
     );
 
     Ok((
@@ -408,23 +1080,40 @@ pub struct OAuthCallbackQuery {
     pub state: Option<String>,
 }
 
+/// Handles GET on the OAuth callback path (`OAUTH_CALLBACK_PATH`, default `/oauth/callback`).
+///
+/// X redirects here after authorization; see [`oauth_callback_response`].
+pub async fn handle_oauth_callback(
+    State(state): State<AppState>,
+    Query(query): Query<OAuthCallbackQuery>,
+    request: Request,
+) -> axum::response::Response {
+    oauth_callback_response(state, query, request)
+        .await
+        .into_response()
+}
+
 /// Performs the OAuth callback: exchange code for tokens, create session, redirect to /playground.
-/// Used when GET /reputest is hit with ?code=&state= (callback URI is https://reputest.fly.dev/reputest).
+/// Used by [`handle_oauth_callback`], and by GET /reputest when it is hit with ?code=&state=.
 async fn oauth_callback_response(
     state: AppState,
     query: OAuthCallbackQuery,
     request: Request,
 ) -> impl IntoResponse {
-    if state.base_url.is_none()
-        || state.oauth_client_id.is_none()
-        || state.oauth_client_secret.is_none()
-    {
-        return (
-            StatusCode::SERVICE_UNAVAILABLE,
-            "Web login not configured.".to_string(),
-        )
-            .into_response();
-    }
+    let web = match (
+        &state.web,
+        &state.oauth_client_id,
+        &state.oauth_client_secret,
+    ) {
+        (Some(web), Some(_), Some(_)) => web.clone(),
+        _ => {
+            return (
+                StatusCode::SERVICE_UNAVAILABLE,
+                "Web login not configured.".to_string(),
+            )
+                .into_response();
+        }
+    };
     let cookie_header = request.headers().get(header::COOKIE);
     let stored_state = get_cookie_from_header(cookie_header, "oauth_state");
     let code_verifier = get_cookie_from_header(cookie_header, "oauth_code_verifier");
@@ -471,16 +1160,12 @@ async fn oauth_callback_response(
             .into_response();
     }
 
-    let redirect_uri = state
-        .base_url
-        .as_deref()
-        .unwrap_or("")
-        .trim_end_matches('/')
-        .to_string();
+    let redirect_uri = web.callback_url();
     let client_id = state.oauth_client_id.as_deref().unwrap_or("");
     let client_secret = state.oauth_client_secret.as_deref().unwrap_or("");
 
-    let (access_token, refresh_token) = match exchange_authorization_code(
+    let issued_at = chrono::Utc::now();
+    let tokens = match exchange_authorization_code(
         client_id,
         client_secret,
         redirect_uri.as_str(),
@@ -489,7 +1174,7 @@ async fn oauth_callback_response(
     )
     .await
     {
-        Ok(tokens) => tokens,
+        Ok(tokens) => session_tokens(tokens, issued_at),
         Err(e) => {
             error!("OAuth token exchange failed: {}", e);
             return (
@@ -505,7 +1190,7 @@ async fn oauth_callback_response(
     let me_url = "https://api.twitter.com/2/users/me?user.fields=id,username";
     let me_response = client
         .get(me_url)
-        .header("Authorization", format!("Bearer {}", access_token))
+        .header("Authorization", format!("Bearer {}", tokens.access_token))
         .send()
         .await;
     let (user_id, username) = match me_response {
@@ -543,11 +1228,16 @@ async fn oauth_callback_response(
         }
     };
 
-    if let Some(allowed) = get_allowed_username() {
-        if username != allowed {
+    match get_user_role(&state.pool, &user_id).await {
+        Ok(Some(_)) => {
+            if let Err(e) = update_role_username(&state.pool, &user_id, &username).await {
+                warn!("Failed to record the username of {}: {}", user_id, e);
+            }
+        }
+        Ok(None) => {
             warn!(
-                "Login rejected: username {} not in ALLOWED_USERNAME",
-                username
+                "Login rejected: @{} ({}) has no role in user_roles",
+                username, user_id
             );
             return (
                 StatusCode::FORBIDDEN,
@@ -555,16 +1245,27 @@ async fn oauth_callback_response(
             )
                 .into_response();
         }
+        Err(e) => return e.into_response(),
     }
 
     let expires_at = chrono::Utc::now() + chrono::Duration::days(7);
+    let user_agent = request
+        .headers()
+        .get(header::USER_AGENT)
+        .and_then(|value| value.to_str().ok())
+        .map(|value| {
+            value
+                .chars()
+                .take(MAX_USER_AGENT_LENGTH)
+                .collect::<String>()
+        });
     let session_id = match create_session(
         &state.pool,
         &user_id,
         &username,
-        &access_token,
-        refresh_token.as_deref(),
+        &tokens,
         expires_at,
+        user_agent.as_deref(),
     )
     .await
     {
@@ -579,18 +1280,21 @@ async fn oauth_callback_response(
         }
     };
 
-    let secure_attr = if redirect_uri.starts_with("https://") {
-        "; Secure"
-    } else {
-        ""
-    };
+    let secure_attr = if web.is_https() { "; Secure" } else { "" };
+    let domain_attr = web.cookie_domain_attribute();
     let session_cookie = format!(
-        "session_id={}; Path=/; HttpOnly; SameSite=Lax; Max-Age=604800{}",
-        session_id, secure_attr
+        "session_id={}; Path=/; HttpOnly; SameSite=Lax; Max-Age=604800{}{}",
+        session_id, secure_attr, domain_attr
     );
     // Clear OAuth cookies
-    let clear_state = "oauth_state=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0";
-    let clear_verifier = "oauth_code_verifier=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0";
+    let clear_state = format!(
+        "oauth_state=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0{}",
+        domain_attr
+    );
+    let clear_verifier = format!(
+        "oauth_code_verifier=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0{}",
+        domain_attr
+    );
 
     (
         AppendHeaders([
@@ -612,29 +1316,210 @@ async fn oauth_callback_response(
         .into_response()
 }
 
+/// Longest User-Agent stored with a session.
+const MAX_USER_AGENT_LENGTH: usize = 256;
+
+/// How long before its expiry a session's access token is refreshed.
+const SESSION_REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// Converts tokens issued by X at `issued_at` to the tokens stored with a session.
+fn session_tokens(
+    tokens: TokenResponse,
+    issued_at: chrono::DateTime<chrono::Utc>,
+) -> SessionTokens {
+    SessionTokens {
+        expires_at: tokens.expires_at(issued_at),
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token,
+    }
+}
+
 /// Loads session from Cookie header if present and valid. Returns None if missing or expired.
+///
+/// A session whose access token expires within five minutes is refreshed first (see
+/// [`refresh_session`]).
 async fn get_session_from_headers(state: &AppState, headers: &HeaderMap) -> Option<WebSession> {
     let cookie_header = headers.get(header::COOKIE);
     let sid = get_cookie_from_header(cookie_header, "session_id")?;
     let id = sqlx::types::Uuid::parse_str(&sid).ok()?;
-    get_session_by_id(&state.pool, id).await.ok().flatten()
+    let session = get_session_by_id(&state.pool, id).await.ok().flatten()?;
+
+    let margin = chrono::Duration::seconds(SESSION_REFRESH_MARGIN_SECS);
+    let expiring = session
+        .token_expires_at
+        .is_some_and(|expires_at| expires_at - chrono::Utc::now() <= margin);
+    if expiring {
+        return Some(refresh_session(state, session).await);
+    }
+    Some(session)
 }
 
-/// GET /playground — API explorer page (requires login).
-pub async fn handle_playground_get(
-    State(state): State<AppState>,
-    headers: HeaderMap,
-) -> axum::response::Response {
-    let session = get_session_from_headers(&state, &headers).await;
-    match session {
-        None => Redirect::to("/login").into_response(),
-        Some(session) => {
-            let html = playground_html(&session.username, "", "GET", "", None);
-            Html(html).into_response()
+/// Refreshes a session's access token with its refresh token and stores the new tokens.
+///
+/// The session row is locked while it refreshes (see [`lock_session`]): X rotates the
+/// refresh token, so a second request refreshing the same session with the same refresh
+/// token would fail. A request that waited for another one's refresh uses its tokens.
+/// Refreshes of different sessions do not wait for each other. When the session cannot be
+/// refreshed (no refresh token or client credentials, or X refused) it is returned
+/// unchanged.
+async fn refresh_session(state: &AppState, session: WebSession) -> WebSession {
+    let lock = match lock_session(&state.pool, session.id).await {
+        Ok(Some(lock)) => lock,
+        Ok(None) => return session,
+        Err(e) => {
+            warn!("Failed to lock session {}: {}", session.id, e);
+            return session;
+        }
+    };
+    if lock.session.access_token != session.access_token {
+        debug!("Session {} was refreshed by another request", session.id);
+        let current = lock.session.clone();
+        if let Err(e) = lock.release().await {
+            warn!("Failed to unlock session {}: {}", session.id, e);
+        }
+        return current;
+    }
+    let session = lock.session.clone();
+    let (Some(client_id), Some(client_secret), Some(refresh_token)) = (
+        state.oauth_client_id.as_deref(),
+        state.oauth_client_secret.as_deref(),
+        session.refresh_token.as_deref(),
+    ) else {
+        debug!("Session {} cannot be refreshed", session.id);
+        return session;
+    };
+
+    let issued_at = chrono::Utc::now();
+    let tokens = match refresh_access_token(client_id, client_secret, refresh_token).await {
+        Ok(tokens) => session_tokens(tokens, issued_at),
+        Err(e) => {
+            warn!("Failed to refresh session of @{}: {}", session.username, e);
+            return session;
+        }
+    };
+    if let Err(e) = lock.save_tokens(&tokens).await {
+        warn!(
+            "Failed to save refreshed tokens of session {}, using them for this request only: {}",
+            session.id, e
+        );
+    }
+    info!("Refreshed session tokens of @{}", session.username);
+
+    WebSession {
+        access_token: tokens.access_token,
+        refresh_token: tokens.refresh_token.or(session.refresh_token),
+        token_expires_at: tokens.expires_at,
+        ..session
+    }
+}
+
+/// Ends a session: revokes its tokens at X and deletes it.
+///
+/// Revocation is best effort: the session is deleted even when X cannot be reached.
+async fn end_session(state: &AppState, session: &WebSession) -> Result<(), ReputestError> {
+    if let (Some(client_id), Some(client_secret)) = (
+        state.oauth_client_id.as_deref(),
+        state.oauth_client_secret.as_deref(),
+    ) {
+        let tokens = [
+            Some((session.access_token.as_str(), TokenKind::Access)),
+            session
+                .refresh_token
+                .as_deref()
+                .map(|token| (token, TokenKind::Refresh)),
+        ];
+        for (token, kind) in tokens.into_iter().flatten() {
+            if let Err(e) = revoke_token(client_id, client_secret, token, kind).await {
+                warn!(
+                    "Failed to revoke a token of session {} at X: {}",
+                    session.id, e
+                );
+            }
+        }
+    }
+    delete_session(&state.pool, session.id).await
+}
+
+/// A role a route requires, for [`Authorized`].
+pub trait RequiredRole {
+    const ROLE: Role;
+}
+
+/// Routes for any logged-in account (read-only pages).
+pub struct ViewerRole;
+
+/// Routes that change what the bot does.
+pub struct OperatorRole;
+
+/// Routes that manage roles.
+pub struct AdminRole;
+
+impl RequiredRole for ViewerRole {
+    const ROLE: Role = Role::Viewer;
+}
+
+impl RequiredRole for OperatorRole {
+    const ROLE: Role = Role::Operator;
+}
+
+impl RequiredRole for AdminRole {
+    const ROLE: Role = Role::Admin;
+}
+
+/// Extractor for a logged-in account with at least the role `R`.
+///
+/// The role is read from `user_roles` by the session's X user ID on every request, so a
+/// revoked or lowered role takes effect at once. Without a session the request is redirected to `/login`; with a role
+/// below `R` it gets 403.
+pub struct Authorized<R> {
+    pub session: WebSession,
+    /// The account's own role (at least `R::ROLE`)
+    pub role: Role,
+    required: std::marker::PhantomData<R>,
+}
+
+#[axum::async_trait]
+impl<R: RequiredRole> FromRequestParts<AppState> for Authorized<R> {
+    type Rejection = axum::response::Response;
+
+    async fn from_request_parts(
+        parts: &mut Parts,
+        state: &AppState,
+    ) -> Result<Self, Self::Rejection> {
+        let Some(session) = get_session_from_headers(state, &parts.headers).await else {
+            return Err(Redirect::to("/login").into_response());
+        };
+        match get_user_role(&state.pool, &session.user_id).await {
+            Ok(Some(role)) if role >= R::ROLE => Ok(Authorized {
+                session,
+                role,
+                required: std::marker::PhantomData,
+            }),
+            Ok(role) => {
+                warn!(
+                    "Access to {} rejected for @{} (role {:?}, requires {})",
+                    parts.uri.path(),
+                    session.username,
+                    role.map(|role| role.as_str()),
+                    R::ROLE.as_str()
+                );
+                Err((
+                    StatusCode::FORBIDDEN,
+                    format!("This page requires the {} role.", R::ROLE.as_str()),
+                )
+                    .into_response())
+            }
+            Err(e) => Err(e.into_response()),
         }
     }
 }
 
+/// GET /playground — API explorer page (requires login).
+pub async fn handle_playground_get(auth: Authorized<ViewerRole>) -> axum::response::Response {
+    let html = playground_html(&auth.session.username, "", "GET", "", None);
+    Html(html).into_response()
+}
+
 /// Form body for playground POST.
 #[derive(serde::Deserialize)]
 pub struct PlaygroundForm {
@@ -645,16 +1530,15 @@ pub struct PlaygroundForm {
 }
 
 /// POST /playground — Run X API request and re-render page with response.
+///
+/// Calls X with the session's access token. When X answers 401 the session is refreshed
+/// and the call is made once more.
 pub async fn handle_playground_post(
     State(state): State<AppState>,
-    headers: HeaderMap,
+    auth: Authorized<ViewerRole>,
     body: Bytes,
 ) -> axum::response::Response {
-    let session = get_session_from_headers(&state, &headers).await;
-    let session = match session {
-        None => return Redirect::to("/login").into_response(),
-        Some(s) => s,
-    };
+    let session = auth.session;
 
     let form: PlaygroundForm = match serde_urlencoded::from_bytes(&body) {
         Ok(f) => f,
@@ -706,16 +1590,30 @@ pub async fn handle_playground_post(
         reqwest::Method::GET
     };
 
-    let req = reqwest::Client::new()
-        .request(method.clone(), &url)
-        .header("Authorization", format!("Bearer {}", session.access_token));
-
-    let response = if method == reqwest::Method::POST && !form.body.trim().is_empty() {
-        req.body(form.body.clone()).send().await
-    } else {
-        req.send().await
+    let client = reqwest::Client::new();
+    let send = |access_token: &str| {
+        let req = client
+            .request(method.clone(), &url)
+            .header("Authorization", format!("Bearer {}", access_token));
+        if method == reqwest::Method::POST && !form.body.trim().is_empty() {
+            req.body(form.body.clone()).send()
+        } else {
+            req.send()
+        }
     };
 
+    let mut response = send(&session.access_token).await;
+    if matches!(&response, Ok(resp) if resp.status() == reqwest::StatusCode::UNAUTHORIZED) {
+        let refreshed = refresh_session(&state, session.clone()).await;
+        if refreshed.access_token != session.access_token {
+            info!(
+                "Retrying playground request of @{} after a session refresh",
+                session.username
+            );
+            response = send(&refreshed.access_token).await;
+        }
+    }
+
     let result = match response {
         Ok(resp) => {
             let status = resp.status().as_u16();
@@ -763,12 +1661,14 @@ fn playground_html(
         button {{ margin-top: 12px; padding: 10px 20px; background: #1DA1F2; color: white; border: none; border-radius: 8px; font-weight: 600; cursor: pointer; }}
         button:hover {{ background: #1a91da; }}
         a {{ color: #1DA1F2; }}
+        .logout {{ display: inline; }}
+        .logout button {{ margin-top: 0; padding: 0; background: none; color: #1DA1F2; font-weight: normal; }}
     </style>
 </head>
 <body>
     <div class="container">
         <h1>X API Playground</h1>
-        <p>Logged in as <strong>{}</strong> · <a href="/logout">Logout</a></p>
+        <p>Logged in as <strong>{}</strong> · <a href="/sessions">Sessions</a> · {}</p>
         <form method="post" action="/playground">
             <label>X API path (e.g. 2/users/me)</label>
             <input type="text" name="path" value="{}" placeholder="2/users/me" />
@@ -789,6 +1689,7 @@ fn playground_html(
 </body>
 </html>"#,
         html_escape(username),
+        LOGOUT_FORM,
         path_attr,
         method_get,
         method_post,
@@ -806,16 +1707,42 @@ fn playground_response(
     playground_html(username, &form.path, &form.method, &form.body, result)
 }
 
-/// GET /logout — Delete session and redirect to /login.
+/// POST /logout — Revoke the session's tokens at X, delete the session and redirect to /login.
+///
+/// A POST form like the other session actions, so that following a link (or a prefetch)
+/// cannot log anyone out.
 pub async fn handle_logout(State(state): State<AppState>, request: Request) -> impl IntoResponse {
     let cookie_header = request.headers().get(header::COOKIE);
     let sid = get_cookie_from_header(cookie_header, "session_id");
     if let Some(s) = sid {
         if let Ok(id) = sqlx::types::Uuid::parse_str(&s) {
-            let _ = delete_session(&state.pool, id).await;
+            match get_session_by_id(&state.pool, id).await {
+                Ok(Some(session)) => {
+                    if let Err(e) = end_session(&state, &session).await {
+                        warn!("Failed to end session {}: {}", id, e);
+                    }
+                }
+                // Tokens that cannot be read cannot be revoked, but the session still goes
+                _ => {
+                    let _ = delete_session(&state.pool, id).await;
+                }
+            }
         }
     }
-    let clear_cookie = "session_id=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0";
+    logged_out(&state)
+}
+
+/// Clears the session cookie and redirects to /login.
+fn logged_out(state: &AppState) -> axum::response::Response {
+    // A cookie set with a Domain is only cleared by a cookie with the same Domain
+    let clear_cookie = format!(
+        "session_id=; Path=/; HttpOnly; SameSite=Lax; Max-Age=0{}",
+        state
+            .web
+            .as_ref()
+            .map(WebConfig::cookie_domain_attribute)
+            .unwrap_or_default()
+    );
     (
         AppendHeaders([(
             header::SET_COOKIE,
@@ -826,6 +1753,1150 @@ pub async fn handle_logout(State(state): State<AppState>, request: Request) -> i
         .into_response()
 }
 
+/// The Logout button: a form posting to `/logout`.
+const LOGOUT_FORM: &str = r#"<form method="post" action="/logout" class="logout"><button type="submit">Logout</button></form>"#;
+
+/// GET /sessions — The logged-in account's active sessions (requires login).
+///
+/// Lists every unexpired session of the account with its browser, when it logged in and
+/// when it was last used. Other sessions can be ended from here; the current one is ended
+/// with Logout.
+///
+/// # Returns
+///
+/// - The page on success, a redirect to `/login` without a session, or an error response
+pub async fn handle_sessions(
+    State(state): State<AppState>,
+    auth: Authorized<ViewerRole>,
+) -> axum::response::Response {
+    let sessions = match get_user_sessions(&state.pool, &auth.session.user_id).await {
+        Ok(sessions) => sessions,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut html = format!(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Sessions</title>
+    <style>
+        body {{
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }}
+        .container {{
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }}
+        h1 {{ color: #333; margin-top: 0; }}
+        table {{ width: 100%; border-collapse: collapse; margin-top: 20px; }}
+        th, td {{ padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }}
+        th {{ background-color: #f8f9fa; font-weight: 600; color: #555; }}
+        tr:hover {{ background-color: #f8f9fa; }}
+        form {{ display: inline; }}
+        nav a {{ margin-right: 16px; }}
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/playground">Playground</a> {}</nav>
+        <h1>Sessions</h1>
+        <p>Where @{} is logged in. Ending a session logs that browser out and revokes its X tokens.</p>
+        <table>
+            <thead>
+                <tr>
+                    <th>browser</th>
+                    <th>logged in</th>
+                    <th>last active</th>
+                    <th>expires</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+        LOGOUT_FORM,
+        html_escape(&auth.session.username)
+    );
+
+    for session in &sessions {
+        let action = if session.id == auth.session.id {
+            format!("This session · {}", LOGOUT_FORM)
+        } else {
+            format!(
+                "<form method=\"post\" action=\"/sessions/{}/revoke\"><button type=\"submit\">End session</button></form>",
+                session.id
+            )
+        };
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            session
+                .user_agent
+                .as_deref()
+                .map(html_escape)
+                .unwrap_or_else(|| "-".to_string()),
+            session.created_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            session.last_seen_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            session.expires_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            action
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Handler for `POST /sessions/:id/revoke`: ends another session of the logged-in account.
+///
+/// The session's tokens are revoked at X and the session is deleted. Ending the current
+/// session logs out, like `POST /logout`.
+///
+/// # Returns
+///
+/// - A redirect back to `/sessions` (or to `/login`, clearing the cookie, for the current
+///   session), a redirect to `/login` without a session, 404 if the account has no such session, or an error
+///   response
+pub async fn handle_revoke_session(
+    State(state): State<AppState>,
+    auth: Authorized<ViewerRole>,
+    Path(id): Path<String>,
+) -> axum::response::Response {
+    let Ok(id) = sqlx::types::Uuid::parse_str(&id) else {
+        return ReputestError::NotFound("Session".to_string()).into_response();
+    };
+    if id == auth.session.id {
+        if let Err(e) = end_session(&state, &auth.session).await {
+            warn!("Failed to end session {}: {}", id, e);
+        }
+        return logged_out(&state);
+    }
+
+    match get_session_by_id(&state.pool, id).await {
+        Ok(Some(session)) if session.user_id == auth.session.user_id => {
+            match end_session(&state, &session).await {
+                Ok(()) => {
+                    info!("Session {} of @{} ended", id, auth.session.username);
+                    Redirect::to("/sessions").into_response()
+                }
+                Err(e) => e.into_response(),
+            }
+        }
+        Ok(_) => ReputestError::NotFound("Session".to_string()).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// GET /admin/rate-limits — Current X API rate limit budgets per endpoint (requires login).
+///
+/// Budgets come from the `x-rate-limit-*` headers of the most recent response for each
+/// endpoint, so an endpoint only appears once it has been called since the last restart.
+pub async fn handle_admin_rate_limits(_auth: Authorized<ViewerRole>) -> axum::response::Response {
+    let now = chrono::Utc::now();
+    let budgets = RateLimitTracker::global().snapshot();
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Rate Limits</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }
+        .container {
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }
+        h1 { color: #333; margin-top: 0; }
+        table { width: 100%; border-collapse: collapse; margin-top: 20px; }
+        th, td { padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }
+        th { background-color: #f8f9fa; font-weight: 600; color: #555; }
+        tr:hover { background-color: #f8f9fa; }
+        .count { text-align: right; font-variant-numeric: tabular-nums; }
+        .exhausted { color: #c0392b; font-weight: 600; }
+        nav a { margin-right: 16px; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+        <h1>X API Rate Limits</h1>
+        <p>Latest budget reported by the X API for each endpoint called since the last restart.</p>
+        <table>
+            <thead>
+                <tr>
+                    <th>endpoint</th>
+                    <th class="count">remaining</th>
+                    <th class="count">limit</th>
+                    <th>resets at</th>
+                    <th class="count">resets in</th>
+                    <th>updated at</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for budget in &budgets {
+        let exhausted = budget.remaining == 0 && budget.reset_at > now;
+        let resets_in = if budget.reset_at > now {
+            let secs = (budget.reset_at - now).num_seconds();
+            format!("{}m {:02}s", secs / 60, secs % 60)
+        } else {
+            "reset".to_string()
+        };
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td class=\"count{}\">{}</td>\n                    <td class=\"count\">{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            html_escape(&budget.endpoint),
+            if exhausted { " exhausted" } else { "" },
+            budget.remaining,
+            budget
+                .limit
+                .map(|l| l.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            budget.reset_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            resets_in,
+            budget.updated_at.format("%Y-%m-%d %H:%M:%S UTC")
+        ));
+    }
+
+    if budgets.is_empty() {
+        html.push_str("                <tr><td colspan=\"6\">No X API calls made yet.</td></tr>\n");
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Handler for the `/admin/replies` page: outbound reply queue health.
+///
+/// Shows how many queued replies are in each state and lists dead-lettered replies and
+/// pending replies that have failed at least once. Operators can requeue dead replies.
+/// Requires a logged-in session.
+///
+/// # Parameters
+///
+/// - `state`: Application state containing the database pool
+/// - `auth`: The logged-in account
+///
+/// # Returns
+///
+/// - The page on success, a redirect to `/login` without a session, or an error response
+pub async fn handle_admin_replies(
+    State(state): State<AppState>,
+    auth: Authorized<ViewerRole>,
+) -> axum::response::Response {
+    let counts = match count_outbound_replies_by_status(&state.pool).await {
+        Ok(counts) => counts,
+        Err(e) => return e.into_response(),
+    };
+    let replies = match get_stuck_replies(&state.pool, 50).await {
+        Ok(replies) => replies,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Reply Queue</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }
+        .container {
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }
+        h1 { color: #333; margin-top: 0; }
+        table { width: 100%; border-collapse: collapse; margin-top: 20px; }
+        th, td { padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }
+        th { background-color: #f8f9fa; font-weight: 600; color: #555; }
+        tr:hover { background-color: #f8f9fa; }
+        .count { text-align: right; font-variant-numeric: tabular-nums; }
+        .dead { color: #c0392b; font-weight: 600; }
+        .error { color: #666; font-size: 0.9em; }
+        nav a { margin-right: 16px; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/following">Following</a> <a href="/clusters">Clusters</a> <a href="/stats">Stats</a></nav>
+        <h1>Reply Queue</h1>
+        <p>"#,
+    );
+
+    let summary: Vec<String> = ["pending", "sent", "dead"]
+        .iter()
+        .map(|status| {
+            let count = counts
+                .iter()
+                .find(|(s, _)| s == status)
+                .map(|(_, c)| *c)
+                .unwrap_or(0);
+            format!("{}: {}", status, count)
+        })
+        .collect();
+    html.push_str(&summary.join(" &middot; "));
+
+    html.push_str(
+        r#"</p>
+        <table>
+            <thead>
+                <tr>
+                    <th class="count">id</th>
+                    <th>in reply to</th>
+                    <th>reply</th>
+                    <th>status</th>
+                    <th class="count">attempts</th>
+                    <th>last error</th>
+                    <th>next attempt</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for reply in &replies {
+        let dead = reply.status == "dead";
+        let action = if dead && auth.role >= Role::Operator {
+            format!(
+                "<form method=\"post\" action=\"/admin/replies/{}/requeue\"><button type=\"submit\">Requeue</button></form>",
+                reply.id
+            )
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            "                <tr>\n                    <td class=\"count\">{}</td>\n                    <td><a href=\"https://x.com/i/status/{}\">{}</a></td>\n                    <td>{}</td>\n                    <td{}>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"error\">{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            reply.id,
+            html_escape(&reply.in_reply_to_tweet_id),
+            html_escape(&reply.in_reply_to_tweet_id),
+            html_escape(&reply.reply_text),
+            if dead { " class=\"dead\"" } else { "" },
+            html_escape(&reply.status),
+            reply.attempts,
+            html_escape(reply.last_error.as_deref().unwrap_or("")),
+            if dead {
+                "-".to_string()
+            } else {
+                reply.next_attempt_at.format("%Y-%m-%d %H:%M:%S UTC").to_string()
+            },
+            action
+        ));
+    }
+
+    if replies.is_empty() {
+        html.push_str("                <tr><td colspan=\"8\">No stuck replies.</td></tr>\n");
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Handler for `POST /admin/replies/:id/requeue`: moves a dead reply back to pending.
+///
+/// The reply is retried by the next worker run with a fresh attempt count. Requires the
+/// operator role.
+///
+/// # Parameters
+///
+/// - `state`: Application state containing the database pool
+/// - `auth`: The logged-in operator
+/// - `id`: ID of the `outbound_replies` row
+///
+/// # Returns
+///
+/// - A redirect back to `/admin/replies`, a redirect to `/login` without a session, 403
+///   without the operator role, or an error response (404 if no dead reply has this ID)
+pub async fn handle_admin_requeue_reply(
+    State(state): State<AppState>,
+    auth: Authorized<OperatorRole>,
+    Path(id): Path<i64>,
+) -> axum::response::Response {
+    match requeue_reply(&state.pool, id).await {
+        Ok(true) => {
+            info!("Requeued dead reply {} (by @{})", id, auth.session.username);
+            Redirect::to("/admin/replies").into_response()
+        }
+        Ok(false) => ReputestError::NotFound("Dead reply".to_string()).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Query params for the admin console: what the last action did.
+#[derive(serde::Deserialize, Default)]
+pub struct AdminQuery {
+    /// A task that was just started
+    pub started: Option<String>,
+    /// A tweet that was just reprocessed
+    pub reprocessed: Option<String>,
+    /// The revocation that keeps the reprocessed declaration revoked
+    pub revoked_by: Option<String>,
+    /// Status of the reply the reprocessed tweet already had, so none was queued
+    pub existing_reply: Option<String>,
+}
+
+/// Handler for the `/admin` page: job control.
+///
+/// Lists the scheduled tasks with their schedule and last run, each with a button to run
+/// it now, the bot's paused state with a button to pause or resume it, a form to
+/// reprocess a tweet, the recent `job_runs`, `vibe_materialize_time` and
+/// `token_refreshes` history, and the suggestion conversion totals.
+/// Viewers see the page without the controls, which need the operator role.
+///
+/// # Parameters
+///
+/// - `state`: Application state containing the database pool
+/// - `auth`: The logged-in account
+/// - `query`: What the last action did, shown as a notice
+///
+/// # Returns
+///
+/// - The page on success, a redirect to `/login` without a session, or an error response
+pub async fn handle_admin(
+    State(state): State<AppState>,
+    auth: Authorized<ViewerRole>,
+    Query(query): Query<AdminQuery>,
+) -> axum::response::Response {
+    let can_operate = auth.role >= Role::Operator;
+
+    let paused = match get_bot_paused(&state.pool).await {
+        Ok(paused) => paused,
+        Err(e) => return e.into_response(),
+    };
+    let runs = match get_recent_job_runs(&state.pool, None, 50).await {
+        Ok(runs) => runs,
+        Err(e) => return e.into_response(),
+    };
+    let materialize_times = match get_recent_materialize_times(&state.pool, 20).await {
+        Ok(times) => times,
+        Err(e) => return e.into_response(),
+    };
+    let token_refreshes = match get_recent_token_refreshes(&state.pool, 20).await {
+        Ok(refreshes) => refreshes,
+        Err(e) => return e.into_response(),
+    };
+    let conversion = match get_suggestion_conversion(&state.pool).await {
+        Ok(conversion) => conversion,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Admin</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }
+        .container {
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }
+        h1 { color: #333; margin-top: 0; }
+        h2 { color: #333; margin-top: 30px; }
+        table { width: 100%; border-collapse: collapse; margin-top: 20px; }
+        th, td { padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }
+        th { background-color: #f8f9fa; font-weight: 600; color: #555; }
+        tr:hover { background-color: #f8f9fa; }
+        form { display: inline; }
+        .count { text-align: right; font-variant-numeric: tabular-nums; }
+        .failed { color: #c0392b; font-weight: 600; }
+        .paused { color: #c0392b; font-weight: 600; }
+        .notice { background-color: #eef7ee; padding: 10px; border-radius: 4px; }
+        .error { color: #666; font-size: 0.9em; }
+        nav a { margin-right: 16px; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/stats">Stats</a> <a href="/admin/replies">Reply Queue</a> <a href="/admin/rate-limits">Rate Limits</a></nav>
+        <h1>Admin</h1>
+"#,
+    );
+
+    html.push_str(&format!(
+        "        <p>Logged in as @{} ({}).{}</p>\n",
+        html_escape(&auth.session.username),
+        auth.role.as_str(),
+        if auth.role >= Role::Admin {
+            " <a href=\"/admin/roles\">Manage roles</a>"
+        } else {
+            ""
+        }
+    ));
+    if let Some(task) = &query.started {
+        html.push_str(&format!(
+            "        <p class=\"notice\">Started {}. Refresh to see its run below.</p>\n",
+            html_escape(task)
+        ));
+    }
+    if let Some(tweet_id) = &query.reprocessed {
+        let mut notice = format!("Reprocessed tweet {}.", html_escape(tweet_id));
+        if let Some(revocation) = &query.revoked_by {
+            notice.push_str(&format!(
+                " It was revoked by tweet {} and stays revoked.",
+                html_escape(revocation)
+            ));
+        }
+        if let Some(status) = &query.existing_reply {
+            notice.push_str(&format!(
+                " It already has a reply ({}), so no reply was queued.",
+                html_escape(status)
+            ));
+        }
+        html.push_str(&format!("        <p class=\"notice\">{}</p>\n", notice));
+    }
+
+    html.push_str("        <h2>Bot</h2>\n");
+    if paused {
+        html.push_str(&format!(
+            "        <p><span class=\"paused\">Paused</span>: scheduled runs of {} are skipped. {}</p>\n",
+            BOT_TASKS.join(", "),
+            if can_operate {
+                "<form method=\"post\" action=\"/admin/bot/resume\"><button type=\"submit\">Resume</button></form>"
+            } else {
+                ""
+            }
+        ));
+    } else {
+        html.push_str(&format!(
+            "        <p>Running. {}</p>\n",
+            if can_operate {
+                "<form method=\"post\" action=\"/admin/bot/pause\"><button type=\"submit\">Pause</button></form>"
+            } else {
+                ""
+            }
+        ));
+    }
+    if can_operate {
+        html.push_str(
+            r#"        <form method="post" action="/admin/reprocess">
+            <label>Reprocess tweet ID <input type="text" name="tweet_id" inputmode="numeric" required></label>
+            <button type="submit">Reprocess</button>
+        </form>
+"#,
+        );
+    }
+    html.push_str(
+        r#"        <h2>Tasks</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>task</th>
+                    <th>schedule</th>
+                    <th>last run</th>
+                    <th>outcome</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for &(name, default_schedule) in CRON_TASKS {
+        let config = state
+            .config
+            .cron_task(name)
+            .cloned()
+            .unwrap_or_else(|| CronTaskConfig::from_env(name, default_schedule));
+        let last_run = runs.iter().find(|run| run.task == name);
+        let outcome = if state.jobs.is_running(name) {
+            "running".to_string()
+        } else {
+            last_run.map(|run| run.outcome.clone()).unwrap_or_default()
+        };
+        let action = if can_operate {
+            format!(
+                "<form method=\"post\" action=\"/admin/tasks/{}/run\"><button type=\"submit\">Run now</button></form>",
+                name
+            )
+        } else {
+            String::new()
+        };
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td><code>{}</code>{}</td>\n                    <td>{}</td>\n                    <td{}>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            name,
+            html_escape(&config.schedule),
+            if config.enabled { "" } else { " (disabled)" },
+            last_run
+                .map(|run| run.started_at.format("%Y-%m-%d %H:%M:%S UTC").to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            if outcome == "failed" { " class=\"failed\"" } else { "" },
+            html_escape(&outcome),
+            action
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>Recent runs</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>task</th>
+                    <th>started</th>
+                    <th class="count">duration</th>
+                    <th>outcome</th>
+                    <th class="count">items</th>
+                    <th>triggered by</th>
+                    <th>error</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for run in &runs {
+        let duration = run
+            .finished_at
+            .map(|finished| format!("{} ms", (finished - run.started_at).num_milliseconds()))
+            .unwrap_or_else(|| "-".to_string());
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td{}>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td>{}</td>\n                    <td class=\"error\">{}</td>\n                </tr>\n",
+            html_escape(&run.task),
+            run.started_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            duration,
+            if run.outcome == "failed" { " class=\"failed\"" } else { "" },
+            html_escape(&run.outcome),
+            run.items
+                .map(|items| items.to_string())
+                .unwrap_or_else(|| "-".to_string()),
+            run.triggered_by
+                .as_deref()
+                .map(|username| format!("@{}", html_escape(username)))
+                .unwrap_or_else(|| "schedule".to_string()),
+            html_escape(run.error.as_deref().unwrap_or(""))
+        ));
+    }
+
+    if runs.is_empty() {
+        html.push_str("                <tr><td colspan=\"7\">No runs recorded yet.</td></tr>\n");
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>View refreshes</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>view</th>
+                    <th>refreshed at</th>
+                    <th class="count">time taken</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for time in &materialize_times {
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td class=\"count\">{} ms</td>\n                </tr>\n",
+            time.degree
+                .map(|degree| format!("degree {}", degree))
+                .unwrap_or_else(|| "combined".to_string()),
+            time.refresh_time.format("%Y-%m-%d %H:%M:%S UTC"),
+            time.time_taken_ms
+        ));
+    }
+
+    if materialize_times.is_empty() {
+        html.push_str(
+            "                <tr><td colspan=\"3\">No refreshes recorded yet.</td></tr>\n",
+        );
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>Token refreshes</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>refreshed at</th>
+                    <th>reason</th>
+                    <th>triggered by</th>
+                    <th>outcome</th>
+                    <th>refresh token</th>
+                    <th>error</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for refresh in &token_refreshes {
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td{}>{}</td>\n                    <td>{}</td>\n                    <td class=\"error\">{}</td>\n                </tr>\n",
+            refresh.refreshed_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            html_escape(&refresh.reason),
+            html_escape(&refresh.triggered_by),
+            if refresh.outcome == "failed" { " class=\"failed\"" } else { "" },
+            html_escape(&refresh.outcome),
+            if refresh.refresh_rotated { "rotated" } else { "kept" },
+            html_escape(refresh.error.as_deref().unwrap_or(""))
+        ));
+    }
+
+    if token_refreshes.is_empty() {
+        html.push_str(
+            "                <tr><td colspan=\"6\">No token refreshes recorded yet.</td></tr>\n",
+        );
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+        <h2>Suggestion conversion</h2>
+        <table>
+            <thead>
+                <tr>
+                    <th>source</th>
+                    <th class="count">shown</th>
+                    <th class="count">converted</th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for row in &conversion {
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td class=\"count\">{}</td>\n                    <td class=\"count\">{}</td>\n                </tr>\n",
+            html_escape(&row.source),
+            row.shown_count,
+            row.converted_count
+        ));
+    }
+
+    if conversion.is_empty() {
+        html.push_str(
+            "                <tr><td colspan=\"3\">No suggestions shown yet.</td></tr>\n",
+        );
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Handler for `POST /admin/tasks/:name/run`: runs a scheduled task now.
+///
+/// The task runs in the background and is recorded in `job_runs` with the admin's
+/// username. It runs even while the bot is paused, but is skipped if a run of the task is
+/// already in progress. Requires the operator role.
+///
+/// # Returns
+///
+/// - A redirect back to `/admin`, a redirect to `/login` without a session, 403 without
+///   the operator role, or 404 for an unknown task
+pub async fn handle_admin_run_task(
+    State(state): State<AppState>,
+    auth: Authorized<OperatorRole>,
+    Path(name): Path<String>,
+) -> axum::response::Response {
+    let session = auth.session;
+    if !is_cron_task(&name) {
+        return ReputestError::NotFound(format!("Task {}", name)).into_response();
+    }
+
+    info!("Task {} triggered by @{}", name, session.username);
+    let ctx = state.jobs.clone();
+    let task = name.clone();
+    tokio::spawn(async move {
+        run_task(&ctx, &task, Some(&session.username)).await;
+    });
+    Redirect::to(&format!("/admin?started={}", name)).into_response()
+}
+
+/// Handler for `POST /admin/bot/pause`: pauses the bot (requires the operator role).
+///
+/// While paused, scheduled runs of the hashtag search, mentions and outbound reply tasks
+/// are skipped and recorded as `paused`.
+pub async fn handle_admin_pause_bot(
+    State(state): State<AppState>,
+    auth: Authorized<OperatorRole>,
+) -> axum::response::Response {
+    set_bot_paused_response(&state, &auth.session, true).await
+}
+
+/// Handler for `POST /admin/bot/resume`: resumes a paused bot (requires the operator role).
+pub async fn handle_admin_resume_bot(
+    State(state): State<AppState>,
+    auth: Authorized<OperatorRole>,
+) -> axum::response::Response {
+    set_bot_paused_response(&state, &auth.session, false).await
+}
+
+/// Pauses or resumes the bot for the current operator, then redirects back to `/admin`.
+async fn set_bot_paused_response(
+    state: &AppState,
+    session: &WebSession,
+    paused: bool,
+) -> axum::response::Response {
+    match set_bot_paused(&state.pool, paused, &session.username).await {
+        Ok(()) => Redirect::to("/admin").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Form body for `POST /admin/reprocess`.
+#[derive(serde::Deserialize)]
+pub struct ReprocessForm {
+    tweet_id: String,
+}
+
+/// Handler for `POST /admin/reprocess`: runs one tweet through the tweet event pipeline again.
+///
+/// See [`reprocess_tweet`]. Requires the operator role.
+///
+/// # Returns
+///
+/// - A redirect back to `/admin`, a redirect to `/login` without a session, 403 without
+///   the operator role, 400 for an invalid tweet ID, 404 if the tweet does not exist, or
+///   an error response
+pub async fn handle_admin_reprocess(
+    State(state): State<AppState>,
+    auth: Authorized<OperatorRole>,
+    body: Bytes,
+) -> axum::response::Response {
+    let session = auth.session;
+    let form: ReprocessForm = match serde_urlencoded::from_bytes(&body) {
+        Ok(form) => form,
+        Err(_) => return ReputestError::Parse("Invalid form data".to_string()).into_response(),
+    };
+    let tweet_id = form.tweet_id.trim();
+    if tweet_id.is_empty() || tweet_id.len() > 19 || !tweet_id.bytes().all(|b| b.is_ascii_digit()) {
+        return ReputestError::Parse("Tweet ID must be a number".to_string()).into_response();
+    }
+
+    info!("Tweet {} reprocessed by @{}", tweet_id, session.username);
+    match reprocess_tweet(&state.pool, tweet_id).await {
+        Ok(report) => {
+            let mut location = format!("/admin?reprocessed={}", tweet_id);
+            if let Some(revocation) = &report.revoked_by {
+                location.push_str(&format!("&revoked_by={}", urlencoding::encode(revocation)));
+            }
+            if let Some(status) = &report.existing_reply {
+                location.push_str(&format!("&existing_reply={}", urlencoding::encode(status)));
+            }
+            Redirect::to(&location).into_response()
+        }
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Handler for the `/admin/roles` page: who may log in, and with which role.
+///
+/// Lists the role grants, each with a button to revoke it, and a form to grant a role.
+/// Requires the admin role.
+///
+/// # Returns
+///
+/// - The page on success, a redirect to `/login` without a session, 403 without the admin
+///   role, or an error response
+pub async fn handle_admin_roles(
+    State(state): State<AppState>,
+    auth: Authorized<AdminRole>,
+) -> axum::response::Response {
+    let grants = match get_user_roles(&state.pool).await {
+        Ok(grants) => grants,
+        Err(e) => return e.into_response(),
+    };
+
+    let mut html = String::from(
+        r#"<!DOCTYPE html>
+<html lang="en">
+<head>
+    <meta charset="UTF-8">
+    <meta name="viewport" content="width=device-width, initial-scale=1.0">
+    <title>Reputest - Roles</title>
+    <style>
+        body {
+            font-family: -apple-system, BlinkMacSystemFont, 'Segoe UI', Roboto, Oxygen, Ubuntu, Cantarell, sans-serif;
+            margin: 0;
+            padding: 20px;
+            background-color: #f5f5f5;
+        }
+        .container {
+            max-width: 1200px;
+            margin: 0 auto;
+            background-color: white;
+            padding: 30px;
+            border-radius: 8px;
+            box-shadow: 0 2px 4px rgba(0,0,0,0.1);
+        }
+        h1 { color: #333; margin-top: 0; }
+        table { width: 100%; border-collapse: collapse; margin-top: 20px; }
+        th, td { padding: 12px; text-align: left; border-bottom: 1px solid #ddd; }
+        th { background-color: #f8f9fa; font-weight: 600; color: #555; }
+        tr:hover { background-color: #f8f9fa; }
+        form { display: inline; }
+        nav a { margin-right: 16px; }
+    </style>
+</head>
+<body>
+    <div class="container">
+        <nav><a href="/">Good Vibes</a> <a href="/admin">Admin</a></nav>
+        <h1>Roles</h1>
+        <p>Viewers see the read-only pages. Operators can also run tasks, pause the bot, reprocess tweets and requeue replies. Admins can also manage roles. Accounts without a role cannot log in.</p>
+        <form method="post" action="/admin/roles">
+            <label>Username <input type="text" name="username" placeholder="@username" required></label>
+            <select name="role">
+"#,
+    );
+
+    for role in Role::ALL {
+        html.push_str(&format!(
+            "                <option value=\"{0}\">{0}</option>\n",
+            role.as_str()
+        ));
+    }
+
+    html.push_str(
+        r#"            </select>
+            <button type="submit">Grant</button>
+        </form>
+        <table>
+            <thead>
+                <tr>
+                    <th>username</th>
+                    <th>user ID</th>
+                    <th>role</th>
+                    <th>granted by</th>
+                    <th>granted at</th>
+                    <th></th>
+                </tr>
+            </thead>
+            <tbody>
+"#,
+    );
+
+    for grant in &grants {
+        // Admins cannot revoke their own role, so there is always one left
+        let action = if grant.user_id == auth.session.user_id {
+            String::new()
+        } else {
+            format!(
+                "<form method=\"post\" action=\"/admin/roles/{}/revoke\"><button type=\"submit\">Revoke</button></form>",
+                urlencoding::encode(&grant.user_id)
+            )
+        };
+        html.push_str(&format!(
+            "                <tr>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                    <td>{}</td>\n                </tr>\n",
+            grant
+                .username
+                .as_deref()
+                .map(|username| format!("@{}", html_escape(username)))
+                .unwrap_or_else(|| "(not logged in yet)".to_string()),
+            html_escape(&grant.user_id),
+            grant.role.as_str(),
+            grant
+                .granted_by
+                .as_deref()
+                .map(|username| format!("@{}", html_escape(username)))
+                .unwrap_or_else(|| "-".to_string()),
+            grant.granted_at.format("%Y-%m-%d %H:%M:%S UTC"),
+            action
+        ));
+    }
+
+    html.push_str(
+        r#"            </tbody>
+        </table>
+    </div>
+</body>
+</html>"#,
+    );
+
+    Html(html).into_response()
+}
+
+/// Form body for `POST /admin/roles`.
+#[derive(serde::Deserialize)]
+pub struct GrantRoleForm {
+    username: String,
+    role: String,
+}
+
+/// Parses an X username typed in a form, with or without the leading `@`.
+fn parse_form_username(value: &str) -> Option<&str> {
+    let username = value.trim().trim_start_matches('@');
+    let valid = !username.is_empty()
+        && username.len() <= 15
+        && username
+            .bytes()
+            .all(|b| b.is_ascii_alphanumeric() || b == b'_');
+    valid.then_some(username)
+}
+
+/// Resolves the username typed in the grant form to the account's X user ID.
+///
+/// Uses the `users` table first and falls back to the X API, saving the user it finds.
+async fn resolve_role_account(
+    pool: &PgPool,
+    username: &str,
+) -> Result<Option<String>, ReputestError> {
+    if let Some(user_id) = get_user_id_by_username(pool, username).await? {
+        return Ok(Some(user_id));
+    }
+    let Some((user_id, name, created_at, followers)) =
+        lookup_user_by_username(pool, username).await?
+    else {
+        return Ok(None);
+    };
+    if let Err(e) = save_user(pool, &user_id, username, &name, created_at, followers).await {
+        warn!("Failed to save user @{}: {}", username, e);
+    }
+    Ok(Some(user_id))
+}
+
+/// Handler for `POST /admin/roles`: grants a role, replacing the account's current one.
+///
+/// The username is resolved to the account's X user ID, which the grant is keyed on.
+/// Requires the admin role. Admins cannot change their own role.
+///
+/// # Returns
+///
+/// - A redirect back to `/admin/roles`, a redirect to `/login` without a session, 403
+///   without the admin role, 400 for an invalid username or role, 404 if X does not know
+///   the username, or an error response
+pub async fn handle_admin_grant_role(
+    State(state): State<AppState>,
+    auth: Authorized<AdminRole>,
+    body: Bytes,
+) -> axum::response::Response {
+    let form: GrantRoleForm = match serde_urlencoded::from_bytes(&body) {
+        Ok(form) => form,
+        Err(_) => return ReputestError::Parse("Invalid form data".to_string()).into_response(),
+    };
+    let Some(username) = parse_form_username(&form.username) else {
+        return ReputestError::Parse("Invalid username".to_string()).into_response();
+    };
+    let Some(role) = Role::parse(&form.role) else {
+        return ReputestError::Parse(format!("Unknown role {}", form.role)).into_response();
+    };
+    let user_id = match resolve_role_account(&state.pool, username).await {
+        Ok(Some(user_id)) => user_id,
+        Ok(None) => return ReputestError::NotFound(format!("User @{}", username)).into_response(),
+        Err(e) => return e.into_response(),
+    };
+    if user_id == auth.session.user_id {
+        return ReputestError::Parse("You cannot change your own role".to_string()).into_response();
+    }
+
+    match grant_user_role(
+        &state.pool,
+        &user_id,
+        username,
+        role,
+        &auth.session.username,
+    )
+    .await
+    {
+        Ok(()) => Redirect::to("/admin/roles").into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
+/// Handler for `POST /admin/roles/:user_id/revoke`: removes an account's role.
+///
+/// The account can no longer log in, and its open sessions lose access at their next
+/// request. Requires the admin role. Admins cannot revoke their own role.
+///
+/// # Returns
+///
+/// - A redirect back to `/admin/roles`, a redirect to `/login` without a session, 403
+///   without the admin role, 400 for the admin's own role, 404 if the account has no role,
+///   or an error response
+pub async fn handle_admin_revoke_role(
+    State(state): State<AppState>,
+    auth: Authorized<AdminRole>,
+    Path(user_id): Path<String>,
+) -> axum::response::Response {
+    if user_id == auth.session.user_id {
+        return ReputestError::Parse("You cannot revoke your own role".to_string()).into_response();
+    }
+
+    match revoke_user_role(&state.pool, &user_id).await {
+        Ok(true) => {
+            info!(
+                "Role of user {} revoked by @{}",
+                user_id, auth.session.username
+            );
+            Redirect::to("/admin/roles").into_response()
+        }
+        Ok(false) => ReputestError::NotFound(format!("Role of user {}", user_id)).into_response(),
+        Err(e) => e.into_response(),
+    }
+}
+
 /// Escapes HTML special characters to prevent XSS attacks.
 fn html_escape(s: &str) -> String {
     s.replace('&', "&amp;")
@@ -27,18 +27,26 @@
 //! - `POST /reputest`: Returns "Reputesting!" message
 //! - `GET /health`: Returns service health status
 
+pub mod clusters;
 pub mod config;
 pub mod cronjob;
 pub mod crypto;
 pub mod db;
+pub mod error;
+pub mod graph;
 pub mod handlers;
+pub mod migrations;
 pub mod oauth;
+pub mod recommend;
+pub mod replies;
+pub mod stats;
 pub mod twitter;
 
 // Re-export commonly used types and functions
-pub use config::{get_server_port, TwitterConfig};
-pub use cronjob::{run_gmgv_cronjob, start_gmgv_cronjob};
+pub use config::get_server_port;
+pub use cronjob::{run_gmgv_cronjob, start_gmgv_cronjob, JobContext};
 pub use crypto::validate_encryption_config;
+pub use error::{JsonError, ReputestError};
 pub use handlers::{handle_health, handle_reputest_get, handle_reputest_post, handle_root};
 pub use oauth::build_oauth2_user_context_header;
 pub use twitter::search_tweets_with_hashtag;
@@ -40,20 +40,31 @@ use tower_governor::{
 };
 use tower_http::{set_header::SetResponseHeaderLayer, trace::TraceLayer};
 
+mod clusters;
 mod config;
 mod cronjob;
 mod crypto;
 mod db;
+mod error;
+mod graph;
 mod handlers;
+mod migrations;
 mod oauth;
+mod recommend;
+mod replies;
+mod stats;
 mod twitter;
 
-use config::get_server_port;
-use cronjob::start_gmgv_cronjob;
+use config::{load_config_file, AppConfig, BotMode, DEFAULT_OAUTH_CALLBACK_PATH};
+use cronjob::{start_gmgv_cronjob, JobContext};
 use handlers::{
-    handle_following, handle_health, handle_login, handle_login_start, handle_logout,
-    handle_playground_get, handle_playground_post, handle_reputest_get, handle_reputest_post,
-    handle_root, AppState,
+    handle_admin, handle_admin_grant_role, handle_admin_pause_bot, handle_admin_rate_limits,
+    handle_admin_replies, handle_admin_reprocess, handle_admin_requeue_reply,
+    handle_admin_resume_bot, handle_admin_revoke_role, handle_admin_roles, handle_admin_run_task,
+    handle_api_clusters, handle_api_stats, handle_clusters, handle_following, handle_health,
+    handle_login, handle_login_start, handle_logout, handle_oauth_callback, handle_playground_get,
+    handle_playground_post, handle_profile, handle_reputest_get, handle_reputest_post,
+    handle_revoke_session, handle_root, handle_sessions, handle_stats, AppState,
 };
 
 /// Main entry point for the reputest web service.
@@ -73,9 +84,22 @@ use handlers::{
 ///
 /// The server includes HTTP request tracing middleware for logging and debugging.
 ///
+/// # Schema Migrations
+///
+/// `reputest migrate` applies pending schema migrations and exits. The server itself
+/// exits at startup if the schema is behind or has drifted from this binary, unless
+/// `MIGRATE_ON_STARTUP=true` lets it apply pending migrations first.
+///
+/// # Configuration
+///
+/// Settings are read from the environment, falling back to the optional `reputest.toml`
+/// (or the file named by `REPUTEST_CONFIG`). The effective configuration is validated and
+/// logged, with secrets redacted, before the server starts; an invalid setting exits.
+///
 /// # Port Configuration
 ///
-/// The server port is determined by the `PORT` environment variable, defaulting to 3000.
+/// The server port is determined by the `PORT` environment variable (or `[server] port`),
+/// defaulting to 3000.
 ///
 /// # Logging
 ///
@@ -93,6 +117,9 @@ use handlers::{
 ///
 /// # Run with debug logging
 /// RUST_LOG=debug cargo run
+///
+/// # Apply pending schema migrations
+/// cargo run --bin reputest -- migrate
 /// ```
 ///
 /// # Panics
@@ -105,23 +132,64 @@ async fn main() {
     // Initialize the logging system
     env_logger::init();
 
-    // Validate security configuration at startup
-    if let Err(e) = crypto::validate_encryption_config() {
-        log::error!(
-            "SECURITY ERROR: Token encryption is not properly configured: {}",
-            e
-        );
-        log::error!("Set TOKEN_ENCRYPTION_KEY environment variable with a 32-byte hex key.");
-        log::error!("Generate a key with: openssl rand -hex 32");
-        log::error!("Refusing to start without encryption configured.");
-        std::process::exit(1);
+    // Load reputest.toml (if present) underneath the environment variables
+    match load_config_file() {
+        Ok(Some(path)) => info!("Loaded configuration file {}", path),
+        Ok(None) => {}
+        Err(e) => {
+            log::error!("{}", e);
+            std::process::exit(1);
+        }
+    }
+
+    // `reputest migrate` applies pending schema migrations and exits
+    if std::env::args().nth(1).as_deref() == Some("migrate") {
+        std::process::exit(match run_migrate_command().await {
+            Ok(()) => 0,
+            Err(e) => {
+                log::error!("Migration failed: {}", e);
+                1
+            }
+        });
     }
-    info!("Security configuration validated successfully");
+
+    // Validate security configuration at startup
+    let keyring = match crypto::validate_encryption_config() {
+        Ok(keyring) => keyring,
+        Err(e) => {
+            log::error!(
+                "SECURITY ERROR: Token encryption is not properly configured: {}",
+                e
+            );
+            log::error!("Set TOKEN_ENCRYPTION_KEY environment variable with a 32-byte hex key, or select a key file or passphrase with TOKEN_ENCRYPTION_KEY_PROVIDER.");
+            log::error!("Generate a key with: openssl rand -hex 32");
+            log::error!("Refusing to start without encryption configured.");
+            std::process::exit(1);
+        }
+    };
+    info!(
+        "Security configuration validated successfully (tokens are encrypted with key {})",
+        keyring.current_key_id()
+    );
+
+    // Validate every setting up front instead of falling back to defaults later
+    // Read once and shared with the handlers, the jobs and the X API client
+    let app_config = match AppConfig::load() {
+        Ok(app_config) => app_config.install(),
+        Err(e) => {
+            log::error!("Invalid configuration: {}", e);
+            std::process::exit(1);
+        }
+    };
+    info!(
+        "Effective configuration:\n{}",
+        app_config.to_string().trim_end()
+    );
 
     // Note: Tokens are now loaded directly from the database when needed
     // No need to pre-load them as environment variables
 
-    // Create database pool
+    // Create the database pool shared by the HTTP handlers and the scheduled jobs
     let db_pool = match db::get_db_pool().await {
         Ok(pool) => {
             info!("Database pool created successfully");
@@ -136,9 +204,83 @@ async fn main() {
         }
     };
 
+    // Refuse to start against a schema that is behind (or has drifted from) this binary
+    if app_config.migrate_on_startup {
+        if let Err(e) = migrations::run_migrations(&db_pool).await {
+            log::error!("Failed to apply migrations at startup: {}", e);
+            std::process::exit(1);
+        }
+    }
+    if let Err(e) = migrations::check_schema(&db_pool).await {
+        log::error!("Database schema check failed: {}", e);
+        log::error!(
+            "Run `reputest migrate` (or set MIGRATE_ON_STARTUP=true) to apply pending migrations."
+        );
+        std::process::exit(1);
+    }
+    info!(
+        "Database schema is at version {}",
+        migrations::expected_version()
+    );
+
+    // Bind tokens stored before they were bound to their row, and move tokens still
+    // sealed with a previous key to the current one
+    match db::reencrypt_tokens(&db_pool, &keyring).await {
+        Ok(report) => {
+            let reencrypted = report.access_tokens + report.refresh_tokens + report.sessions;
+            if reencrypted > 0 {
+                info!(
+                    "Re-encrypted {} stored token row(s) with key {}",
+                    reencrypted,
+                    keyring.current_key_id()
+                );
+            }
+            if report.failed > 0 {
+                log::warn!(
+                    "{} stored token row(s) could not be decrypted with any configured key",
+                    report.failed
+                );
+            }
+        }
+        Err(e) => log::error!("Failed to re-encrypt stored tokens: {}", e),
+    }
+
+    // Apply the hashtags of the configuration file to the registry
+    for hashtag in &app_config.hashtags {
+        if let Err(e) = db::upsert_hashtag_rule(&db_pool, &hashtag.rule, hashtag.enabled).await {
+            log::error!(
+                "Failed to save hashtag #{} from the configuration file: {}",
+                hashtag.rule.tag,
+                e
+            );
+            std::process::exit(1);
+        }
+    }
+
+    // Make the configured account admin, e.g. the first admin of a new deployment
+    if let Some(user_id) = &app_config.admin_user_id {
+        match db::seed_admin_role(&db_pool, user_id).await {
+            Ok(true) => info!("Granted the admin role to ADMIN_USER_ID {}", user_id),
+            Ok(false) => {}
+            Err(e) => log::error!("Failed to grant the admin role to ADMIN_USER_ID: {}", e),
+        }
+    }
+
+    let web_config = app_config.web.clone();
+    match &web_config {
+        Some(web) => info!("Web login OAuth callback URL: {}", web.callback_url()),
+        None => info!("BASE_URL not set: web login is disabled"),
+    }
+
+    if app_config.bot_mode == BotMode::Shadow {
+        log::warn!("BOT_MODE=shadow: replies are recorded in shadow_replies, not posted to X");
+    }
+
     // Start the cronjob scheduler for GMGV hashtag monitoring
-    let cronjob_handle = tokio::spawn(async {
-        match start_gmgv_cronjob().await {
+    let job_ctx = JobContext::new(db_pool.clone(), app_config.clone());
+    let cron_ctx = job_ctx.clone();
+    let cronjob_handle = tokio::spawn(async move {
+        match start_gmgv_cronjob(cron_ctx).await {
             Ok(scheduler) => {
                 info!("Starting GMGV hashtag monitoring cronjob");
                 if let Err(e) = scheduler.start().await {
@@ -156,11 +298,11 @@ async fn main() {
         }
     });
 
-    // Configure rate limiting: 30 requests per minute per IP
+    // Configure rate limiting per IP (HTTP_RATE_LIMIT_PER_SECOND, HTTP_RATE_LIMIT_BURST)
     let governor_config = Arc::new(
         GovernorConfigBuilder::default()
-            .per_second(2) // Refill rate: 2 tokens per second
-            .burst_size(30) // Maximum burst: 30 requests
+            .per_second(app_config.rate_limit.per_second)
+            .burst_size(app_config.rate_limit.burst)
             .key_extractor(SmartIpKeyExtractor)
             .finish()
             .expect("Failed to create rate limiter config"),
@@ -179,57 +321,87 @@ async fn main() {
 
     let app_state = AppState {
         pool: db_pool.clone(),
-        base_url: config::get_base_url().ok(),
-        oauth_client_id: std::env::var("XAPI_CLIENT_ID").ok(),
-        oauth_client_secret: std::env::var("XAPI_CLIENT_SECRET").ok(),
+        web: web_config.clone(),
+        oauth_client_id: app_config.oauth_client_id.clone(),
+        oauth_client_secret: app_config.oauth_client_secret.clone(),
+        config: app_config.clone(),
+        jobs: job_ctx,
     };
 
     // Build the HTTP application with all routes and middleware
-    let app = Router::new()
+    let mut routes = Router::new()
         .route("/", get(handle_root))
         .route("/following", get(handle_following))
+        .route("/profile/:username", get(handle_profile))
+        .route("/clusters", get(handle_clusters))
+        .route("/api/v1/clusters", get(handle_api_clusters))
+        .route("/stats", get(handle_stats))
+        .route("/api/v1/stats", get(handle_api_stats))
         .route("/reputest", get(handle_reputest_get))
         .route("/reputest", post(handle_reputest_post))
         .route("/health", get(handle_health))
         .route("/login", get(handle_login))
         .route("/login/start", get(handle_login_start))
+        .route(DEFAULT_OAUTH_CALLBACK_PATH, get(handle_oauth_callback))
         .route("/playground", get(handle_playground_get))
         .route("/playground", post(handle_playground_post))
-        .route("/logout", get(handle_logout))
-        .with_state(app_state)
-        .layer(
-            ServiceBuilder::new()
-                .layer(TraceLayer::new_for_http())
-                .layer(GovernorLayer {
-                    config: governor_config,
-                })
-                // SECURITY: Add security headers to all responses
-                .layer(SetResponseHeaderLayer::overriding(
-                    axum::http::header::X_CONTENT_TYPE_OPTIONS,
-                    HeaderValue::from_static("nosniff"),
-                ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    axum::http::header::X_FRAME_OPTIONS,
-                    HeaderValue::from_static("DENY"),
-                ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    axum::http::header::X_XSS_PROTECTION,
-                    HeaderValue::from_static("1; mode=block"),
-                ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    axum::http::header::CONTENT_SECURITY_POLICY,
-                    HeaderValue::from_static(
-                        "default-src 'self'; style-src 'self' 'unsafe-inline'",
-                    ),
-                ))
-                .layer(SetResponseHeaderLayer::overriding(
-                    axum::http::header::REFERRER_POLICY,
-                    HeaderValue::from_static("strict-origin-when-cross-origin"),
-                )),
+        .route("/logout", post(handle_logout))
+        .route("/sessions", get(handle_sessions))
+        .route("/sessions/:id/revoke", post(handle_revoke_session))
+        .route("/admin", get(handle_admin))
+        .route("/admin/tasks/:name/run", post(handle_admin_run_task))
+        .route("/admin/bot/pause", post(handle_admin_pause_bot))
+        .route("/admin/bot/resume", post(handle_admin_resume_bot))
+        .route("/admin/reprocess", post(handle_admin_reprocess))
+        .route("/admin/roles", get(handle_admin_roles))
+        .route("/admin/roles", post(handle_admin_grant_role))
+        .route(
+            "/admin/roles/:user_id/revoke",
+            post(handle_admin_revoke_role),
+        )
+        .route("/admin/rate-limits", get(handle_admin_rate_limits))
+        .route("/admin/replies", get(handle_admin_replies))
+        .route(
+            "/admin/replies/:id/requeue",
+            post(handle_admin_requeue_reply),
         );
+    // GET /reputest already handles callbacks, for deployments registered with it at X
+    if let Some(web) = &web_config {
+        if web.callback_path != DEFAULT_OAUTH_CALLBACK_PATH && web.callback_path != "/reputest" {
+            routes = routes.route(&web.callback_path, get(handle_oauth_callback));
+        }
+    }
+    let app = routes.with_state(app_state).layer(
+        ServiceBuilder::new()
+            .layer(TraceLayer::new_for_http())
+            .layer(GovernorLayer {
+                config: governor_config,
+            })
+            // SECURITY: Add security headers to all responses
+            .layer(SetResponseHeaderLayer::overriding(
+                axum::http::header::X_CONTENT_TYPE_OPTIONS,
+                HeaderValue::from_static("nosniff"),
+            ))
+            .layer(SetResponseHeaderLayer::overriding(
+                axum::http::header::X_FRAME_OPTIONS,
+                HeaderValue::from_static("DENY"),
+            ))
+            .layer(SetResponseHeaderLayer::overriding(
+                axum::http::header::X_XSS_PROTECTION,
+                HeaderValue::from_static("1; mode=block"),
+            ))
+            .layer(SetResponseHeaderLayer::overriding(
+                axum::http::header::CONTENT_SECURITY_POLICY,
+                HeaderValue::from_static("default-src 'self'; style-src 'self' 'unsafe-inline'"),
+            ))
+            .layer(SetResponseHeaderLayer::overriding(
+                axum::http::header::REFERRER_POLICY,
+                HeaderValue::from_static("strict-origin-when-cross-origin"),
+            )),
+    );
 
     // Get the server port and bind address
-    let port = get_server_port();
+    let port = app_config.port;
     let addr: SocketAddr = ([0, 0, 0, 0], port).into();
 
     info!("Starting reputest server on {}", addr);
@@ -250,5 +422,22 @@ async fn main() {
     }
 }
 
+/// Runs the `migrate` subcommand: connects to DATABASE_URL and applies pending migrations.
+///
+/// # Returns
+///
+/// - `Ok(())`: If the schema is up to date
+/// - `Err(ReputestError)`: If connecting or migrating fails
+async fn run_migrate_command() -> Result<(), error::ReputestError> {
+    let pool = db::get_db_pool().await?;
+    let applied = migrations::run_migrations(&pool).await?;
+    info!(
+        "Migrations complete: {} applied, schema at version {}",
+        applied,
+        migrations::expected_version()
+    );
+    Ok(())
+}
+
 #[cfg(test)]
 mod tests;
@@ -0,0 +1,339 @@
+//! Versioned schema migrations embedded in the binary.
+//!
+//! An empty database is bootstrapped from `sql/database_ddl.sql`, which already contains
+//! the latest schema, and every migration is recorded as applied. Databases created from
+//! an older schema are brought up to date by applying the files in `sql/migrations/` in
+//! order (they are idempotent, so databases that predate the runner can be adopted by
+//! applying all of them).
+//!
+//! Each applied migration is recorded in `schema_migrations` with a SHA-256 checksum of
+//! its SQL. If a recorded checksum no longer matches the embedded file, or the database
+//! has a migration this binary does not know, the schema has drifted and the runner
+//! refuses to continue.
+
+use log::{info, warn};
+use sha2::{Digest, Sha256};
+use sqlx::{PgConnection, PgPool, Row};
+
+use crate::error::ReputestError;
+
+/// A schema migration embedded from `sql/migrations/`.
+#[derive(Debug)]
+pub struct Migration {
+    /// Numeric prefix of the file name
+    pub version: i32,
+    /// File name without the prefix and extension
+    pub name: &'static str,
+    pub sql: &'static str,
+}
+
+impl Migration {
+    /// Hex-encoded SHA-256 of the migration SQL.
+    pub fn checksum(&self) -> String {
+        hex::encode(Sha256::digest(self.sql.as_bytes()))
+    }
+}
+
+/// All migrations known to this binary, in the order they are applied.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        name: "add_following",
+        sql: include_str!("../sql/migrations/001_add_following.sql"),
+    },
+    Migration {
+        version: 2,
+        name: "split_follower_counts",
+        sql: include_str!("../sql/migrations/002_split_follower_counts.sql"),
+    },
+    Migration {
+        version: 3,
+        name: "add_vibe_suggestions",
+        sql: include_str!("../sql/migrations/003_add_vibe_suggestions.sql"),
+    },
+    Migration {
+        version: 4,
+        name: "add_clusters",
+        sql: include_str!("../sql/migrations/004_add_clusters.sql"),
+    },
+    Migration {
+        version: 5,
+        name: "add_graph_stats",
+        sql: include_str!("../sql/migrations/005_add_graph_stats.sql"),
+    },
+    Migration {
+        version: 6,
+        name: "refresh_materialized_views_function",
+        sql: include_str!("../sql/migrations/006_refresh_materialized_views_function.sql"),
+    },
+    Migration {
+        version: 7,
+        name: "add_outbound_replies",
+        sql: include_str!("../sql/migrations/007_add_outbound_replies.sql"),
+    },
+    Migration {
+        version: 8,
+        name: "add_shadow_replies",
+        sql: include_str!("../sql/migrations/008_add_shadow_replies.sql"),
+    },
+    Migration {
+        version: 9,
+        name: "add_hashtag_registry",
+        sql: include_str!("../sql/migrations/009_add_hashtag_registry.sql"),
+    },
+    Migration {
+        version: 10,
+        name: "add_job_runs",
+        sql: include_str!("../sql/migrations/010_add_job_runs.sql"),
+    },
+    Migration {
+        version: 11,
+        name: "add_bot_control",
+        sql: include_str!("../sql/migrations/011_add_bot_control.sql"),
+    },
+    Migration {
+        version: 12,
+        name: "add_user_roles",
+        sql: include_str!("../sql/migrations/012_add_user_roles.sql"),
+    },
+    Migration {
+        version: 13,
+        name: "add_token_refreshes",
+        sql: include_str!("../sql/migrations/013_add_token_refreshes.sql"),
+    },
+    Migration {
+        version: 14,
+        name: "add_access_token_expiry",
+        sql: include_str!("../sql/migrations/014_add_access_token_expiry.sql"),
+    },
+    Migration {
+        version: 15,
+        name: "add_session_refresh",
+        sql: include_str!("../sql/migrations/015_add_session_refresh.sql"),
+    },
+    Migration {
+        version: 16,
+        name: "follower_count_on_update",
+        sql: include_str!("../sql/migrations/016_follower_count_on_update.sql"),
+    },
+    Migration {
+        version: 17,
+        name: "add_good_vibes_categories",
+        sql: include_str!("../sql/migrations/017_add_good_vibes_categories.sql"),
+    },
+    Migration {
+        version: 18,
+        name: "key_user_roles_by_user_id",
+        sql: include_str!("../sql/migrations/018_key_user_roles_by_user_id.sql"),
+    },
+    Migration {
+        version: 19,
+        name: "add_manual_token_refresh_reason",
+        sql: include_str!("../sql/migrations/019_add_manual_token_refresh_reason.sql"),
+    },
+    Migration {
+        version: 20,
+        name: "add_vibe_request_revoked_by",
+        sql: include_str!("../sql/migrations/020_add_vibe_request_revoked_by.sql"),
+    },
+];
+
+/// Full schema used to bootstrap an empty database.
+const BOOTSTRAP_SQL: &str = include_str!("../sql/database_ddl.sql");
+
+/// Creates the bookkeeping table on databases that predate the runner
+/// (same definition as in `database_ddl.sql`).
+const CREATE_SCHEMA_MIGRATIONS_SQL: &str = r#"
+    CREATE TABLE IF NOT EXISTS schema_migrations (
+        version    INTEGER                   PRIMARY KEY,
+        name       TEXT                      NOT NULL,
+        checksum   TEXT                      NOT NULL,
+        applied_at TIMESTAMP WITH TIME ZONE  NOT NULL DEFAULT NOW()
+    )
+"#;
+
+/// Advisory lock key held while migrating, so concurrent runs (e.g. two machines
+/// starting at once) apply each migration only once.
+const MIGRATION_LOCK_KEY: i64 = 0x7265_7075_7465_7374;
+
+/// Returns the schema version this binary expects (the highest migration version).
+pub fn expected_version() -> i32 {
+    MIGRATIONS.iter().map(|m| m.version).max().unwrap_or(0)
+}
+
+/// Works out which migrations still need to be applied (pure, no database access).
+///
+/// # Parameters
+///
+/// - `applied`: `(version, checksum)` of every row in `schema_migrations`
+///
+/// # Returns
+///
+/// - `Ok(Vec<&Migration>)`: Migrations not yet applied, in order
+/// - `Err(ReputestError)`: If the database has drifted: a recorded
+///   checksum differs from the embedded migration, or a recorded version is unknown
+pub fn pending_migrations(
+    applied: &[(i32, String)],
+) -> Result<Vec<&'static Migration>, ReputestError> {
+    let mut problems = Vec::new();
+    for (version, checksum) in applied {
+        match MIGRATIONS.iter().find(|m| m.version == *version) {
+            Some(migration) if migration.checksum() != *checksum => problems.push(format!(
+                "migration {} ({}) was changed after it was applied",
+                version, migration.name
+            )),
+            Some(_) => {}
+            None => problems.push(format!(
+                "migration {} is applied but unknown to this binary (expected at most {})",
+                version,
+                expected_version()
+            )),
+        }
+    }
+    if !problems.is_empty() {
+        return Err(ReputestError::Schema(format!(
+            "Schema drift detected: {}",
+            problems.join("; ")
+        )));
+    }
+
+    Ok(MIGRATIONS
+        .iter()
+        .filter(|m| !applied.iter().any(|(version, _)| *version == m.version))
+        .collect())
+}
+
+/// Checks that the database schema matches what this binary expects, without changing it.
+///
+/// # Returns
+///
+/// - `Ok(())`: If every migration is applied and no drift is detected
+/// - `Err(ReputestError)`: If migrations are pending, the schema
+///   has drifted, or the database cannot be queried
+pub async fn check_schema(pool: &PgPool) -> Result<(), ReputestError> {
+    let mut conn = pool.acquire().await?;
+    if !table_exists(&mut conn, "schema_migrations").await? {
+        return Err(ReputestError::Schema(
+            "schema_migrations table is missing (database not managed by the migration runner)"
+                .to_string(),
+        ));
+    }
+
+    let applied = get_applied(&mut conn).await?;
+    let pending = pending_migrations(&applied)?;
+    if !pending.is_empty() {
+        let versions: Vec<String> = pending.iter().map(|m| m.version.to_string()).collect();
+        return Err(ReputestError::Schema(format!(
+            "Schema is behind: {} pending migration(s) ({}), expected version {}",
+            pending.len(),
+            versions.join(", "),
+            expected_version()
+        )));
+    }
+    Ok(())
+}
+
+/// Applies pending migrations, bootstrapping an empty database from the full schema.
+///
+/// Runs under a PostgreSQL advisory lock; each migration is applied in its own
+/// transaction together with its `schema_migrations` row.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+///
+/// # Returns
+///
+/// - `Ok(usize)`: Number of migrations applied (or recorded, for a bootstrap)
+/// - `Err(ReputestError)`: On drift or if any statement fails
+pub async fn run_migrations(pool: &PgPool) -> Result<usize, ReputestError> {
+    let mut conn = pool.acquire().await?;
+    sqlx::query("SELECT pg_advisory_lock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+
+    let result = apply_pending(&mut conn).await;
+
+    sqlx::query("SELECT pg_advisory_unlock($1)")
+        .bind(MIGRATION_LOCK_KEY)
+        .execute(&mut *conn)
+        .await?;
+    result
+}
+
+async fn apply_pending(conn: &mut PgConnection) -> Result<usize, ReputestError> {
+    if !table_exists(conn, "users").await? {
+        info!("Empty database, bootstrapping from the full schema");
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+        sqlx::raw_sql(BOOTSTRAP_SQL).execute(&mut *tx).await?;
+        for migration in MIGRATIONS {
+            record_migration(&mut tx, migration).await?;
+        }
+        tx.commit().await?;
+        info!(
+            "Bootstrapped schema at version {} ({} migrations recorded)",
+            expected_version(),
+            MIGRATIONS.len()
+        );
+        return Ok(MIGRATIONS.len());
+    }
+
+    if !table_exists(conn, "schema_migrations").await? {
+        warn!("Existing database without schema_migrations; applying every migration to adopt it");
+    }
+    sqlx::raw_sql(CREATE_SCHEMA_MIGRATIONS_SQL)
+        .execute(&mut *conn)
+        .await?;
+
+    let applied = get_applied(conn).await?;
+    let pending = pending_migrations(&applied)?;
+    for migration in &pending {
+        info!(
+            "Applying migration {} ({})",
+            migration.version, migration.name
+        );
+        let mut tx = sqlx::Connection::begin(&mut *conn).await?;
+        sqlx::raw_sql(migration.sql).execute(&mut *tx).await?;
+        record_migration(&mut tx, migration).await?;
+        tx.commit().await?;
+    }
+
+    info!(
+        "Schema is at version {} ({} migration(s) applied)",
+        expected_version(),
+        pending.len()
+    );
+    Ok(pending.len())
+}
+
+async fn record_migration(
+    conn: &mut PgConnection,
+    migration: &Migration,
+) -> Result<(), ReputestError> {
+    sqlx::query("INSERT INTO schema_migrations (version, name, checksum) VALUES ($1, $2, $3)")
+        .bind(migration.version)
+        .bind(migration.name)
+        .bind(migration.checksum())
+        .execute(conn)
+        .await?;
+    Ok(())
+}
+
+async fn get_applied(conn: &mut PgConnection) -> Result<Vec<(i32, String)>, ReputestError> {
+    let rows = sqlx::query("SELECT version, checksum FROM schema_migrations ORDER BY version")
+        .fetch_all(conn)
+        .await?;
+    Ok(rows
+        .into_iter()
+        .map(|row| (row.get("version"), row.get("checksum")))
+        .collect())
+}
+
+async fn table_exists(conn: &mut PgConnection, table: &str) -> Result<bool, ReputestError> {
+    let exists: bool = sqlx::query_scalar("SELECT to_regclass($1) IS NOT NULL")
+        .bind(format!("public.{}", table))
+        .fetch_one(conn)
+        .await?;
+    Ok(exists)
+}
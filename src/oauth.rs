@@ -9,6 +9,12 @@ use log::{debug, error};
 use std::collections::HashMap;
 use url::Url;
 
+use crate::crypto::TokenKind;
+use crate::error::ReputestError;
+
+/// X's OAuth 2.0 token endpoint, for code exchanges and refreshes.
+pub const TOKEN_URL: &str = "https://api.twitter.com/2/oauth2/token";
+
 /// Builds the Authorization header for OAuth 2.0 User Context authentication.
 ///
 /// This function creates the proper Authorization header for OAuth 2.0 User Context
@@ -43,6 +49,27 @@ pub fn build_oauth2_user_context_header(access_token: &str) -> String {
     format!("Bearer {}", access_token)
 }
 
+/// Tokens returned by the X token endpoint for a refresh.
+#[derive(Debug, Clone)]
+pub struct TokenResponse {
+    pub access_token: String,
+    /// A new refresh token, when X rotated it (the previous one no longer works)
+    pub refresh_token: Option<String>,
+    /// Seconds until the access token expires
+    pub expires_in: Option<u64>,
+}
+
+impl TokenResponse {
+    /// When the access token expires, counted from `issued_at`.
+    pub fn expires_at(
+        &self,
+        issued_at: chrono::DateTime<chrono::Utc>,
+    ) -> Option<chrono::DateTime<chrono::Utc>> {
+        let seconds = i64::try_from(self.expires_in?).ok()?;
+        issued_at.checked_add_signed(chrono::Duration::seconds(seconds))
+    }
+}
+
 /// Refreshes an OAuth 2.0 User Context access token using a refresh token.
 ///
 /// This function refreshes an expired access token using the provided
@@ -57,8 +84,9 @@ pub fn build_oauth2_user_context_header(access_token: &str) -> String {
 ///
 /// # Returns
 ///
-/// - `Ok((String, Option<String>))`: The new access token and optionally a new refresh token on successful refresh
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the refresh fails
+/// - `Ok(TokenResponse)`: The new access token, a new refresh token if X rotated it, and
+///   the lifetime of the access token
+/// - `Err(ReputestError)`: If the refresh fails
 ///
 /// # Example
 ///
@@ -73,9 +101,9 @@ pub fn build_oauth2_user_context_header(access_token: &str) -> String {
 ///         "your_refresh_token"
 ///     ).await;
 ///     match result {
-///         Ok((new_token, new_refresh)) => {
-///             println!("New access token: {}", new_token);
-///             if let Some(refresh) = new_refresh {
+///         Ok(tokens) => {
+///             println!("New access token: {}", tokens.access_token);
+///             if let Some(refresh) = tokens.refresh_token {
 ///                 println!("New refresh token: {}", refresh);
 ///             }
 ///         },
@@ -87,11 +115,20 @@ pub async fn refresh_access_token(
     client_id: &str,
     client_secret: &str,
     refresh_token: &str,
-) -> Result<(String, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<TokenResponse, ReputestError> {
+    refresh_access_token_at(TOKEN_URL, client_id, client_secret, refresh_token).await
+}
+
+/// Like [`refresh_access_token`], but against the token endpoint at `url`.
+pub async fn refresh_access_token_at(
+    url: &str,
+    client_id: &str,
+    client_secret: &str,
+    refresh_token: &str,
+) -> Result<TokenResponse, ReputestError> {
     debug!("Starting OAuth 2.0 access token refresh process");
 
     let client = reqwest::Client::new();
-    let url = "https://api.twitter.com/2/oauth2/token";
 
     debug!("Making token refresh request to: {}", url);
 
@@ -128,7 +165,8 @@ pub async fn refresh_access_token(
             });
 
             // Log token expiration info (safe - no sensitive data)
-            if let Some(expires_in) = json.get("expires_in").and_then(|v| v.as_u64()) {
+            let expires_in = json.get("expires_in").and_then(|v| v.as_u64());
+            if let Some(expires_in) = expires_in {
                 let hours = expires_in / 3600;
                 let minutes = (expires_in % 3600) / 60;
                 if hours > 0 {
@@ -141,15 +179,24 @@ pub async fn refresh_access_token(
                 }
             }
 
-            Ok((access_token.to_string(), new_refresh_token))
+            Ok(TokenResponse {
+                access_token: access_token.to_string(),
+                refresh_token: new_refresh_token,
+                expires_in,
+            })
         } else {
             error!("No access_token found in refresh response");
-            Err("No access_token in refresh response".into())
+            Err(ReputestError::OAuth(
+                "No access_token in refresh response".to_string(),
+            ))
         }
     } else {
         // Don't log the full error response as it might contain sensitive info
         error!("Token refresh failed with status {}", status);
-        Err(format!("Token refresh failed with status {}", status).into())
+        Err(ReputestError::OAuth(format!(
+            "Token refresh failed with status {}",
+            status
+        )))
     }
 }
 
@@ -211,14 +258,15 @@ pub fn build_authorization_url(
 
 /// Exchanges an authorization code for access and refresh tokens (web login flow).
 ///
-/// Returns `(access_token, Option<refresh_token>)`.
+/// Returns the access token, the refresh token (requested with the `offline.access` scope)
+/// and the lifetime of the access token.
 pub async fn exchange_authorization_code(
     client_id: &str,
     client_secret: &str,
     redirect_uri: &str,
     code: &str,
     code_verifier: &str,
-) -> Result<(String, Option<String>), Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<TokenResponse, ReputestError> {
     debug!("Exchanging authorization code for tokens");
 
     let mut params = HashMap::new();
@@ -229,7 +277,7 @@ pub async fn exchange_authorization_code(
     params.insert("code_verifier", code_verifier);
 
     let response = reqwest::Client::new()
-        .post("https://api.twitter.com/2/oauth2/token")
+        .post(TOKEN_URL)
         .basic_auth(client_id, Some(client_secret))
         .form(&params)
         .send()
@@ -240,20 +288,79 @@ pub async fn exchange_authorization_code(
 
     if !status.is_success() {
         error!("Token exchange failed with status {}", status);
-        return Err(format!("Token exchange failed: {}", response_text).into());
+        return Err(ReputestError::OAuth(format!(
+            "Token exchange failed: {}",
+            response_text
+        )));
     }
 
     let json: serde_json::Value = serde_json::from_str(&response_text)?;
     let access_token = json
         .get("access_token")
         .and_then(|v| v.as_str())
-        .ok_or("No access_token in response")?
+        .ok_or_else(|| ReputestError::OAuth("No access_token in response".to_string()))?
         .to_string();
     let refresh_token = json
         .get("refresh_token")
         .and_then(|v| v.as_str())
         .map(String::from);
+    let expires_in = json.get("expires_in").and_then(|v| v.as_u64());
 
     debug!("Authorization code exchanged successfully");
-    Ok((access_token, refresh_token))
+    Ok(TokenResponse {
+        access_token,
+        refresh_token,
+        expires_in,
+    })
+}
+
+/// Revokes an access or refresh token at X, so it can no longer be used.
+///
+/// # Parameters
+///
+/// - `client_id`: The OAuth 2.0 Client ID
+/// - `client_secret`: The OAuth 2.0 Client Secret
+/// - `token`: The token to revoke
+/// - `kind`: Whether `token` is an access or a refresh token
+///
+/// # Returns
+///
+/// - `Ok(())`: If X revoked the token (or did not know it)
+/// - `Err(ReputestError)`: If the request failed or X refused it
+pub async fn revoke_token(
+    client_id: &str,
+    client_secret: &str,
+    token: &str,
+    kind: TokenKind,
+) -> Result<(), ReputestError> {
+    let token_type_hint = match kind {
+        TokenKind::Access => "access_token",
+        TokenKind::Refresh => "refresh_token",
+    };
+    debug!("Revoking {} at X", token_type_hint);
+
+    let mut params = HashMap::new();
+    params.insert("token", token);
+    params.insert("token_type_hint", token_type_hint);
+    params.insert("client_id", client_id);
+
+    let response = reqwest::Client::new()
+        .post("https://api.twitter.com/2/oauth2/revoke")
+        .basic_auth(client_id, Some(client_secret))
+        .form(&params)
+        .send()
+        .await?;
+
+    let status = response.status();
+    if !status.is_success() {
+        // Don't log the response body as it might echo the token
+        error!("Token revocation failed with status {}", status);
+        return Err(ReputestError::OAuth(format!(
+            "Token revocation failed with status {}",
+            status
+        )));
+    }
+
+    debug!("Revoked {} at X", token_type_hint);
+    Ok(())
 }
@@ -0,0 +1,214 @@
+//! "Who should I vibe?" recommendation engine.
+//!
+//! Suggests accounts the requester has strong 2nd/3rd-degree vibe paths to but no
+//! direct good vibes edge with (in either direction). Candidates are ranked by path
+//! count, how recently they received good vibes, and how many accounts the requester
+//! follows also follow them. Suggestions shown through the bot or the profile page are
+//! recorded in `vibe_suggestions` so conversion can be measured.
+
+use chrono::{DateTime, Utc};
+use log::info;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+use crate::db::{
+    get_following_graph, get_good_vibes_graph, get_latest_vibe_times, get_usernames_by_ids,
+    save_vibe_suggestions,
+};
+use crate::error::ReputestError;
+use crate::graph::{count_paths_from, reverse_graph};
+
+/// Weight of each 2nd-degree path in the score.
+const DEGREE_TWO_WEIGHT: f64 = 2.0;
+/// Weight of each 3rd-degree path in the score.
+const DEGREE_THREE_WEIGHT: f64 = 1.0;
+/// Weight of each followed account that also follows the candidate.
+const FOLLOW_OVERLAP_WEIGHT: f64 = 1.5;
+/// Age (in days) at which the recency bonus has halved.
+const RECENCY_HALF_LIFE_DAYS: f64 = 30.0;
+
+/// Where a suggestion was shown, stored in `vibe_suggestions.source`.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SuggestionSource {
+    /// Reply to "@reputest suggest"
+    Bot,
+    /// The user's profile page
+    Profile,
+}
+
+impl SuggestionSource {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SuggestionSource::Bot => "bot",
+            SuggestionSource::Profile => "profile",
+        }
+    }
+}
+
+/// A ranked candidate for the requester to vibe.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Suggestion {
+    pub user_id: String,
+    /// Filled in by [`recommend_for_user`]; empty when produced by [`rank_candidates`]
+    pub username: String,
+    pub degree_two_paths: usize,
+    pub degree_three_paths: usize,
+    /// Accounts the requester follows that also follow the candidate
+    pub follow_overlap: usize,
+    /// When good vibes from the candidate were most recently declared
+    pub last_vibed_at: Option<DateTime<Utc>>,
+    pub score: f64,
+}
+
+/// Ranks recommendation candidates for a requester (pure, no database access).
+///
+/// # Parameters
+///
+/// - `vibes_graph`: Good vibes graph as emitter -> sensors (as returned by `get_good_vibes_graph`)
+/// - `following_graph`: Follow graph as follower -> followed
+/// - `latest_vibe_times`: Latest good vibes declaration per emitter
+/// - `requester_id`: The user to recommend for (the would-be sensor)
+/// - `now`: Reference time for the recency factor
+///
+/// # Returns
+///
+/// Candidates with at least one 2nd or 3rd-degree path and no direct edge with the
+/// requester, highest score first (ties broken by user ID).
+pub fn rank_candidates(
+    vibes_graph: &HashMap<String, Vec<String>>,
+    following_graph: &HashMap<String, Vec<String>>,
+    latest_vibe_times: &HashMap<String, DateTime<Utc>>,
+    requester_id: &str,
+    now: DateTime<Utc>,
+) -> Vec<Suggestion> {
+    // The requester is the sensor: walk sensor -> emitters, i.e. the reversed graph
+    let declared_graph = reverse_graph(vibes_graph);
+    let paths = count_paths_from(&declared_graph, requester_id, 3);
+
+    // Direct edges in either direction rule a candidate out
+    let mut direct: HashSet<&str> = HashSet::new();
+    direct.extend(
+        declared_graph
+            .get(requester_id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str),
+    );
+    direct.extend(
+        vibes_graph
+            .get(requester_id)
+            .into_iter()
+            .flatten()
+            .map(String::as_str),
+    );
+
+    // For each account, how many of the requester's follows follow it
+    let mut follow_overlap: HashMap<&str, usize> = HashMap::new();
+    for followed in following_graph.get(requester_id).into_iter().flatten() {
+        for candidate in following_graph.get(followed).into_iter().flatten() {
+            *follow_overlap.entry(candidate.as_str()).or_default() += 1;
+        }
+    }
+
+    let mut suggestions: Vec<Suggestion> = paths
+        .into_iter()
+        .filter(|(id, _)| id != requester_id && !direct.contains(id.as_str()))
+        .filter(|(_, counts)| counts[1] + counts[2] > 0)
+        .map(|(id, counts)| {
+            let overlap = follow_overlap.get(id.as_str()).copied().unwrap_or(0);
+            let last_vibed_at = latest_vibe_times.get(&id).copied();
+            let base = DEGREE_TWO_WEIGHT * counts[1] as f64
+                + DEGREE_THREE_WEIGHT * counts[2] as f64
+                + FOLLOW_OVERLAP_WEIGHT * overlap as f64;
+            Suggestion {
+                user_id: id,
+                username: String::new(),
+                degree_two_paths: counts[1],
+                degree_three_paths: counts[2],
+                follow_overlap: overlap,
+                last_vibed_at,
+                score: base * recency_factor(last_vibed_at, now),
+            }
+        })
+        .collect();
+
+    suggestions.sort_by(|a, b| {
+        b.score
+            .total_cmp(&a.score)
+            .then_with(|| a.user_id.cmp(&b.user_id))
+    });
+    suggestions
+}
+
+/// Multiplier between 0.5 and 1.0 favouring candidates who received good vibes recently.
+fn recency_factor(last_vibed_at: Option<DateTime<Utc>>, now: DateTime<Utc>) -> f64 {
+    match last_vibed_at {
+        Some(t) => {
+            let days = (now - t).num_seconds().max(0) as f64 / 86_400.0;
+            0.5 + 0.5 * 0.5f64.powf(days / RECENCY_HALF_LIFE_DAYS)
+        }
+        None => 0.5,
+    }
+}
+
+/// Computes the top suggestions for a user from the current database state.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `requester_id`: The user ID to recommend for
+/// - `limit`: Maximum number of suggestions to return
+///
+/// # Returns
+///
+/// - `Ok(Vec<Suggestion>)`: Ranked suggestions with usernames filled in
+/// - `Err(ReputestError)`: If a query fails
+pub async fn recommend_for_user(
+    pool: &PgPool,
+    requester_id: &str,
+    limit: usize,
+) -> Result<Vec<Suggestion>, ReputestError> {
+    let (vibes_graph, following_graph, latest_vibe_times) = tokio::try_join!(
+        get_good_vibes_graph(pool),
+        get_following_graph(pool),
+        get_latest_vibe_times(pool)
+    )?;
+
+    let mut suggestions = rank_candidates(
+        &vibes_graph,
+        &following_graph,
+        &latest_vibe_times,
+        requester_id,
+        Utc::now(),
+    );
+    suggestions.truncate(limit);
+
+    let ids: Vec<String> = suggestions.iter().map(|s| s.user_id.clone()).collect();
+    let usernames = get_usernames_by_ids(pool, &ids).await?;
+    for suggestion in &mut suggestions {
+        if let Some(username) = usernames.get(&suggestion.user_id) {
+            suggestion.username = username.clone();
+        }
+    }
+
+    info!(
+        "Computed {} suggestions for {}",
+        suggestions.len(),
+        requester_id
+    );
+    Ok(suggestions)
+}
+
+/// Records suggestions shown to a user (see [`save_vibe_suggestions`]).
+pub async fn record_suggestions(
+    pool: &PgPool,
+    requester_id: &str,
+    suggestions: &[Suggestion],
+    source: SuggestionSource,
+) -> Result<u64, ReputestError> {
+    let rows: Vec<(String, f64)> = suggestions
+        .iter()
+        .map(|s| (s.user_id.clone(), s.score))
+        .collect();
+    save_vibe_suggestions(pool, requester_id, &rows, source.as_str()).await
+}
@@ -0,0 +1,203 @@
+//! Durable outbound reply queue.
+//!
+//! Bot replies are not posted inline. The code that answers a tweet queues the reply in
+//! `outbound_replies` and marks the tweet processed, and a worker drains the queue every
+//! minute. A worker holds a reply for a lease while sending it; the lease is renewed just
+//! before each send and covers the longest a single send can take, so another instance
+//! only picks the reply up again after the worker died. Failed sends are retried with exponential backoff. A reply is dead-lettered
+//! after `OUTBOUND_REPLY_MAX_ATTEMPTS` attempts, or at once when X rejects it permanently
+//! (for example a deleted tweet or duplicate content). Stuck and dead replies are listed on
+//! `/admin/replies`.
+
+use chrono::{DateTime, Duration, Utc};
+use log::{error, info, warn};
+use sqlx::PgPool;
+
+use crate::config::{AppConfig, TwitterRetryConfig};
+use crate::db::{
+    claim_due_replies, mark_reply_failed, mark_reply_sent, renew_reply_lease, OutboundReply,
+};
+use crate::error::ReputestError;
+use crate::twitter::rate_limit::MAX_BACKOFF;
+use crate::twitter::reply_to_tweet;
+
+/// Maximum number of replies sent per worker run.
+pub const REPLY_BATCH_SIZE: i64 = 20;
+
+/// Added to the longest send duration when sizing the lease.
+const LEASE_MARGIN_SECS: i64 = 60;
+
+/// Delay before the first retry; doubled for every further failed attempt.
+const RETRY_BASE_MINUTES: i64 = 1;
+
+/// Longest delay between two attempts.
+const MAX_RETRY_MINUTES: i64 = 60;
+
+/// Outcome of one worker run.
+#[derive(Debug, Default, PartialEq)]
+pub struct DrainSummary {
+    pub sent: usize,
+    pub retried: usize,
+    pub dead: usize,
+}
+
+/// Delay before the next attempt after `attempts` failed attempts (1 minute after the
+/// first failure, then doubling, capped at an hour).
+pub fn retry_delay(attempts: i32) -> Duration {
+    let exponent = (attempts - 1).clamp(0, 16) as u32;
+    Duration::minutes((RETRY_BASE_MINUTES << exponent).min(MAX_RETRY_MINUTES))
+}
+
+/// Returns true for errors that retrying cannot fix.
+///
+/// X answers 400, 403 and 404 when the reply itself is rejected (the tweet was deleted,
+/// the author blocked the bot, or the text is a duplicate). Rate limits, expired tokens,
+/// server errors and local failures are all treated as transient.
+pub fn is_permanent_failure(error: &ReputestError) -> bool {
+    matches!(
+        error,
+        ReputestError::TwitterApi {
+            status: 400 | 403 | 404,
+            ..
+        }
+    )
+}
+
+/// Decides when a reply that just failed should be attempted again.
+///
+/// # Parameters
+///
+/// - `error`: The error of the failed attempt
+/// - `attempts`: Attempts made so far, including the failed one
+/// - `max_attempts`: Attempts allowed before the reply is dead-lettered
+/// - `now`: Current time
+///
+/// # Returns
+///
+/// `Some(retry_at)`, or `None` if the reply should be dead-lettered.
+pub fn next_attempt_at(
+    error: &ReputestError,
+    attempts: i32,
+    max_attempts: i32,
+    now: DateTime<Utc>,
+) -> Option<DateTime<Utc>> {
+    if is_permanent_failure(error) || attempts >= max_attempts {
+        return None;
+    }
+    // Never retry before the X API rate limit window has reset
+    let backoff = now + retry_delay(attempts);
+    match error {
+        ReputestError::TwitterApi {
+            rate_limit_reset: Some(reset),
+            ..
+        } if *reset > backoff => Some(*reset),
+        _ => Some(backoff),
+    }
+}
+
+/// How long a claimed reply is held before another worker may claim it again.
+///
+/// The lease is renewed before every send, so it has to cover one send only: every
+/// attempt of [`crate::twitter::send_with_retries`] waiting the longest backoff or rate
+/// limit wait, twice because a 401 is retried with a refreshed token, plus a margin.
+pub fn reply_lease(retry: &TwitterRetryConfig) -> Duration {
+    let longest_wait = retry.max_wait.max(MAX_BACKOFF).as_secs() as i64;
+    let attempts = i64::from(retry.max_retries) + 1;
+    Duration::seconds(2 * attempts * longest_wait + LEASE_MARGIN_SECS)
+}
+
+/// Extracts the ID of the posted tweet from a `POST /2/tweets` response body.
+fn posted_tweet_id(response: &str) -> Option<String> {
+    serde_json::from_str::<serde_json::Value>(response)
+        .ok()?
+        .get("data")?
+        .get("id")?
+        .as_str()
+        .map(String::from)
+}
+
+/// Sends every due reply in the queue (up to [`REPLY_BATCH_SIZE`]).
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+/// - `config`: The application configuration (X API retries and `OUTBOUND_REPLY_MAX_ATTEMPTS`)
+///
+/// # Returns
+///
+/// - `Ok(DrainSummary)`: How many replies were sent, scheduled for a retry or dead-lettered
+/// - `Err(ReputestError)`: If the queue cannot be read
+pub async fn drain_outbound_replies(
+    pool: &PgPool,
+    config: &AppConfig,
+) -> Result<DrainSummary, ReputestError> {
+    let lease = reply_lease(&config.twitter_retry);
+    let replies = claim_due_replies(pool, REPLY_BATCH_SIZE, lease).await?;
+    let max_attempts = config.reply_max_attempts;
+    let mut summary = DrainSummary::default();
+
+    for reply in &replies {
+        // Earlier sends of the batch may have used up the lease of this one
+        match renew_reply_lease(pool, reply.id, reply.attempts, lease).await {
+            Ok(true) => {}
+            Ok(false) => {
+                warn!(
+                    "Lease of reply {} expired and it was claimed again, not sending it",
+                    reply.id
+                );
+                continue;
+            }
+            Err(e) => {
+                error!("Failed to renew lease of reply {}: {}", reply.id, e);
+                continue;
+            }
+        }
+        match reply_to_tweet(pool, &reply.reply_text, &reply.in_reply_to_tweet_id).await {
+            Ok(response) => {
+                let reply_tweet_id = posted_tweet_id(&response);
+                info!(
+                    "Sent queued reply {} to tweet {} (attempt {})",
+                    reply.id, reply.in_reply_to_tweet_id, reply.attempts
+                );
+                if let Err(e) = mark_reply_sent(pool, reply.id, reply_tweet_id.as_deref()).await {
+                    error!("Failed to mark reply {} as sent: {}", reply.id, e);
+                }
+                summary.sent += 1;
+            }
+            Err(e) => {
+                record_failure(pool, reply, &e, max_attempts, &mut summary).await;
+            }
+        }
+    }
+
+    Ok(summary)
+}
+
+async fn record_failure(
+    pool: &PgPool,
+    reply: &OutboundReply,
+    error: &ReputestError,
+    max_attempts: i32,
+    summary: &mut DrainSummary,
+) {
+    let retry_at = next_attempt_at(error, reply.attempts, max_attempts, Utc::now());
+    match retry_at {
+        Some(at) => {
+            warn!(
+                "Reply {} to tweet {} failed (attempt {}/{}), retrying at {}: {}",
+                reply.id, reply.in_reply_to_tweet_id, reply.attempts, max_attempts, at, error
+            );
+            summary.retried += 1;
+        }
+        None => {
+            error!(
+                "Reply {} to tweet {} dead-lettered after {} attempt(s): {}",
+                reply.id, reply.in_reply_to_tweet_id, reply.attempts, error
+            );
+            summary.dead += 1;
+        }
+    }
+    if let Err(e) = mark_reply_failed(pool, reply.id, &error.to_string(), retry_at).await {
+        error!("Failed to record failure of reply {}: {}", reply.id, e);
+    }
+}
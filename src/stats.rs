@@ -0,0 +1,114 @@
+//! Graph-wide statistics over the good vibes graph.
+//!
+//! Every cron run appends a snapshot (node and edge counts, reciprocity, largest
+//! strongly connected component, degree distribution and new edges in the last day)
+//! to the graph_stats table, so growth can be followed over time on the stats page.
+
+use chrono::{Duration, Utc};
+use log::info;
+use sqlx::PgPool;
+use std::collections::{HashMap, HashSet};
+
+use crate::db::{count_good_vibes_since, get_good_vibes_graph, save_graph_stats, GraphStats};
+use crate::error::ReputestError;
+use crate::graph::largest_scc_size;
+
+/// Number of degree histogram buckets; the last bucket counts every larger degree.
+pub const DEGREE_HISTOGRAM_BUCKETS: usize = 11;
+
+/// Computes snapshot statistics for a graph (pure, no database access).
+///
+/// Self-loops are ignored, and a user only counts as a node if they have at least one
+/// other edge. `new_edges_last_day` is left at 0 since the graph carries no timestamps.
+///
+/// # Parameters
+///
+/// - `graph`: Good vibes graph as emitter -> sensors
+///
+/// # Returns
+///
+/// The statistics; the degree histograms have [`DEGREE_HISTOGRAM_BUCKETS`] elements.
+pub fn compute_graph_stats(graph: &HashMap<String, Vec<String>>) -> GraphStats {
+    let edges: HashSet<(&str, &str)> = graph
+        .iter()
+        .flat_map(|(from, targets)| targets.iter().map(move |to| (from.as_str(), to.as_str())))
+        .filter(|(from, to)| from != to)
+        .collect();
+
+    let mut in_degree: HashMap<&str, usize> = HashMap::new();
+    let mut out_degree: HashMap<&str, usize> = HashMap::new();
+    for (from, to) in &edges {
+        *out_degree.entry(from).or_default() += 1;
+        *in_degree.entry(to).or_default() += 1;
+    }
+    let nodes: HashSet<&str> = in_degree.keys().chain(out_degree.keys()).copied().collect();
+
+    let reciprocal = edges
+        .iter()
+        .filter(|(from, to)| edges.contains(&(*to, *from)))
+        .count();
+
+    let histogram = |degrees: &HashMap<&str, usize>| {
+        let mut buckets = vec![0i32; DEGREE_HISTOGRAM_BUCKETS];
+        for node in &nodes {
+            let degree = degrees.get(node).copied().unwrap_or(0);
+            buckets[degree.min(DEGREE_HISTOGRAM_BUCKETS - 1)] += 1;
+        }
+        buckets
+    };
+
+    let mut simple_graph: HashMap<String, Vec<String>> = HashMap::new();
+    for (from, to) in &edges {
+        simple_graph
+            .entry(from.to_string())
+            .or_default()
+            .push(to.to_string());
+    }
+
+    GraphStats {
+        node_count: nodes.len() as i32,
+        edge_count: edges.len() as i32,
+        reciprocal_edge_count: reciprocal as i32,
+        reciprocity_rate: if edges.is_empty() {
+            0.0
+        } else {
+            reciprocal as f64 / edges.len() as f64
+        },
+        largest_scc_size: largest_scc_size(&simple_graph) as i32,
+        in_degree_histogram: histogram(&in_degree),
+        out_degree_histogram: histogram(&out_degree),
+        new_edges_last_day: 0,
+    }
+}
+
+/// Computes statistics for the current good vibes graph and appends a snapshot.
+///
+/// # Parameters
+///
+/// - `pool`: A reference to the PostgreSQL connection pool
+///
+/// # Returns
+///
+/// - `Ok(GraphStats)`: The stored snapshot
+/// - `Err(ReputestError)`: If loading or storing fails
+pub async fn record_graph_stats(pool: &PgPool) -> Result<GraphStats, ReputestError> {
+    let since = Utc::now() - Duration::days(1);
+    let (graph, new_edges) = tokio::try_join!(
+        get_good_vibes_graph(pool),
+        count_good_vibes_since(pool, since)
+    )?;
+
+    let mut stats = compute_graph_stats(&graph);
+    stats.new_edges_last_day = new_edges as i32;
+    save_graph_stats(pool, &stats).await?;
+
+    info!(
+        "Graph stats: {} nodes, {} edges, reciprocity {:.3}, largest SCC {}, {} new edges in the last day",
+        stats.node_count,
+        stats.edge_count,
+        stats.reciprocity_rate,
+        stats.largest_scc_size,
+        stats.new_edges_last_day
+    );
+    Ok(stats)
+}
@@ -3,11 +3,16 @@
 //! This module contains low-level API utilities for making authenticated requests
 //! to the Twitter API, including automatic token refresh on 401 errors.
 
+use chrono::Utc;
 use log::{debug, error, info, warn};
-use reqwest::Client;
+use reqwest::{Client, StatusCode};
 use sqlx::PgPool;
+use std::time::Duration;
 
-use crate::config::TwitterConfig;
+use super::rate_limit::{backoff_delay, endpoint_key, is_retryable, Admission, RateLimitTracker};
+use super::tokens::TokenManager;
+use crate::config::AppConfig;
+use crate::error::ReputestError;
 use crate::oauth::build_oauth2_user_context_header;
 
 /// Sanitizes text for safe logging by truncating and escaping control characters.
@@ -53,35 +58,52 @@ pub(crate) fn sanitize_for_logging(text: &str, max_len: usize) -> String {
 ///
 /// This helper function handles the common pattern of making authenticated requests to the Twitter API
 /// and automatically refreshing the access token if a 401 Unauthorized response is received.
+/// The access token comes from the shared [`TokenManager`], which refreshes it before it
+/// expires and makes sure only one refresh runs at a time. Requests go through
+/// [`send_with_retries`], so exhausted rate limits are waited for or skipped and 429/5xx
+/// responses are retried.
 ///
 /// # Parameters
 ///
-/// - `config`: Mutable reference to TwitterConfig (may be updated with new token)
-/// - `pool`: A reference to the PostgreSQL connection pool for saving refreshed tokens
-/// - `request_builder`: A configured reqwest::RequestBuilder ready to send
+/// - `pool`: A reference to the PostgreSQL connection pool for loading and saving tokens
+/// - `request_builder`: A configured reqwest::RequestBuilder without an `Authorization` header
 /// - `operation_name`: Human-readable name for the operation (for logging)
 ///
 /// # Returns
 ///
 /// - `Ok(String)`: The API response body on success
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the request fails or token refresh fails
+/// - `Err(ReputestError)`: If the request fails or token refresh fails
 pub(crate) async fn make_authenticated_request(
-    config: &mut TwitterConfig,
     pool: &PgPool,
     request_builder: reqwest::RequestBuilder,
     operation_name: &str,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<String, ReputestError> {
     info!(
         "Making authenticated request for operation: {}",
         operation_name
     );
 
+    let tokens = TokenManager::global();
+    let authorized = |access_token: &str| {
+        request_builder
+            .try_clone()
+            .map(|builder| {
+                builder.header(
+                    "Authorization",
+                    build_oauth2_user_context_header(access_token),
+                )
+            })
+            .ok_or_else(|| {
+                ReputestError::Config(format!(
+                    "Request for operation '{}' cannot be cloned",
+                    operation_name
+                ))
+            })
+    };
+
     // First attempt with current token
-    let response = request_builder
-        .try_clone()
-        .ok_or("Failed to clone request builder")?
-        .send()
-        .await?;
+    let access_token = tokens.access_token(pool, operation_name).await?;
+    let response = send_with_retries(&authorized(&access_token)?, operation_name).await?;
 
     let status = response.status();
     info!(
@@ -107,103 +129,207 @@ pub(crate) async fn make_authenticated_request(
             operation_name
         );
 
-        if config.can_refresh_token() {
-            info!(
-                "Attempting automatic token refresh for operation '{}'",
-                operation_name
-            );
+        match tokens
+            .refresh_after_unauthorized(pool, &access_token, operation_name)
+            .await
+        {
+            Ok(Some(new_access_token)) => {
+                info!(
+                    "Got a new access token, retrying operation '{}'",
+                    operation_name
+                );
 
-            match config.refresh_access_token(pool).await {
-                Ok(_) => {
-                    info!(
-                        "Token refreshed successfully, retrying operation '{}'",
-                        operation_name
-                    );
-
-                    // Retry the request with the new token
-                    let new_auth_header = build_oauth2_user_context_header(&config.access_token);
+                let retry_builder = authorized(&new_access_token)?;
+                let retry_response = send_with_retries(&retry_builder, operation_name).await?;
 
-                    // Rebuild the request with the new authorization header
-                    let retry_response = request_builder
-                        .header("Authorization", new_auth_header)
-                        .send()
-                        .await?;
+                let retry_status = retry_response.status();
+                info!(
+                    "Retry response status: {} for operation '{}'",
+                    retry_status, operation_name
+                );
 
-                    let retry_status = retry_response.status();
+                if retry_status.is_success() {
+                    let response_text = retry_response.text().await?;
                     info!(
-                        "Retry response status: {} for operation '{}'",
-                        retry_status, operation_name
+                        "Operation '{}' completed successfully after token refresh",
+                        operation_name
                     );
-
-                    if retry_status.is_success() {
-                        let response_text = retry_response.text().await?;
-                        info!(
-                            "Operation '{}' completed successfully after token refresh",
-                            operation_name
-                        );
-                        debug!(
-                            "Response summary for '{}' (after refresh): {} bytes received",
-                            operation_name,
-                            response_text.len()
-                        );
-                        return Ok(response_text);
-                    } else {
-                        let error_text = retry_response.text().await?;
-                        error!(
-                            "Operation '{}' failed after token refresh - Status: {}; body: {}",
-                            operation_name,
-                            retry_status,
-                            sanitize_for_logging(&error_text, 1000)
-                        );
-                        return Err(format!(
-                            "Twitter API error after token refresh ({}): {}",
-                            retry_status,
-                            sanitize_for_logging(&error_text, 500)
-                        )
-                        .into());
-                    }
-                }
-                Err(e) => {
+                    debug!(
+                        "Response summary for '{}' (after refresh): {} bytes received",
+                        operation_name,
+                        response_text.len()
+                    );
+                    return Ok(response_text);
+                } else {
+                    let e = api_error(retry_response).await;
                     error!(
-                        "Token refresh failed for operation '{}': {}",
+                        "Operation '{}' failed after token refresh: {}",
                         operation_name, e
                     );
-                    return Err(format!(
-                        "Token refresh failed for operation '{}': {}",
-                        operation_name, e
-                    )
-                    .into());
+                    return Err(e);
                 }
             }
-        } else {
-            error!(
-                "Cannot refresh token for operation '{}' - missing refresh credentials",
-                operation_name
-            );
-            let error_text = response.text().await?;
-            return Err(format!(
-                "Twitter API error (401) for operation '{}' and token refresh not available: {}",
-                operation_name, error_text
-            )
-            .into());
+            Ok(None) => {
+                error!(
+                    "Cannot refresh token for operation '{}' - missing refresh credentials",
+                    operation_name
+                );
+                return Err(api_error(response).await);
+            }
+            Err(e) => {
+                error!(
+                    "Token refresh failed for operation '{}': {}",
+                    operation_name, e
+                );
+                return Err(e);
+            }
         }
     }
 
     // Handle other error status codes
-    let error_text = response.text().await?;
-    error!(
-        "Operation '{}' failed - Status: {}; body: {}",
-        operation_name,
-        status,
-        sanitize_for_logging(&error_text, 1000)
-    );
-    Err(format!(
-        "Twitter API error for operation '{}' ({}): {}",
-        operation_name,
-        status,
-        sanitize_for_logging(&error_text, 500)
-    )
-    .into())
+    let e = api_error(response).await;
+    error!("Operation '{}' failed: {}", operation_name, e);
+    Err(e)
+}
+
+/// Sends a request, respecting the tracked rate limit budget of its endpoint.
+///
+/// Before each attempt the endpoint's budget is checked: if it is exhausted the call waits
+/// for the window to reset, or fails with a 429 [`ReputestError::TwitterApi`] without
+/// sending anything when the reset is further away than `TWITTER_RATE_LIMIT_MAX_WAIT_SECS`.
+/// Every response updates the budget. 429 responses, and 5xx responses to idempotent
+/// requests (see [`is_retryable`]), are retried up to `TWITTER_MAX_RETRIES` times, after
+/// the rate limit reset for a 429 when it is known and otherwise after a jittered
+/// exponential backoff.
+///
+/// # Parameters
+///
+/// - `request_builder`: The request to send (cloned for every attempt)
+/// - `operation_name`: Human-readable name for the operation (for logging)
+///
+/// # Returns
+///
+/// - `Ok(reqwest::Response)`: The last response, which may still be an error status
+/// - `Err(ReputestError)`: If the request could not be sent or the call was skipped
+pub(crate) async fn send_with_retries(
+    request_builder: &reqwest::RequestBuilder,
+    operation_name: &str,
+) -> Result<reqwest::Response, ReputestError> {
+    let retry_config = AppConfig::current().twitter_retry.clone();
+    let tracker = RateLimitTracker::global();
+    let clone_request = || {
+        request_builder.try_clone().ok_or_else(|| {
+            ReputestError::Config(format!(
+                "Request for operation '{}' cannot be cloned for retry",
+                operation_name
+            ))
+        })
+    };
+    let request = clone_request()?.build()?;
+    let method = request.method().clone();
+    let endpoint = endpoint_key(method.as_str(), request.url());
+
+    let mut attempt: u32 = 0;
+    loop {
+        match tracker.check(&endpoint, Utc::now(), retry_config.max_wait) {
+            Admission::Proceed => {}
+            Admission::Wait(wait) => {
+                warn!(
+                    "Rate limit for {} exhausted, waiting {}s before operation '{}'",
+                    endpoint,
+                    wait.as_secs(),
+                    operation_name
+                );
+                tokio::time::sleep(wait).await;
+            }
+            Admission::Skip(reset_at) => {
+                warn!(
+                    "Rate limit for {} exhausted until {}, skipping operation '{}'",
+                    endpoint, reset_at, operation_name
+                );
+                return Err(ReputestError::TwitterApi {
+                    status: 429,
+                    title: "Rate limit exhausted".to_string(),
+                    detail: format!("No requests left for {} until the window resets", endpoint),
+                    rate_limit_reset: Some(reset_at),
+                });
+            }
+        }
+
+        let response = clone_request()?.send().await?;
+        tracker.record(&endpoint, response.headers());
+
+        let status = response.status();
+        if !is_retryable(&method, status) || attempt >= retry_config.max_retries {
+            return Ok(response);
+        }
+
+        let delay = if status == StatusCode::TOO_MANY_REQUESTS {
+            match tracker.check(&endpoint, Utc::now(), retry_config.max_wait) {
+                // The next check waits for the reset
+                Admission::Wait(_) => Duration::ZERO,
+                // Too far away to wait for: give the 429 back to the caller
+                Admission::Skip(_) => return Ok(response),
+                Admission::Proceed => backoff_delay(attempt, retry_config.backoff_base),
+            }
+        } else {
+            backoff_delay(attempt, retry_config.backoff_base)
+        };
+        attempt += 1;
+        warn!(
+            "Operation '{}' got {} from {}, retry {}/{} in {}ms",
+            operation_name,
+            status,
+            endpoint,
+            attempt,
+            retry_config.max_retries,
+            delay.as_millis()
+        );
+        tokio::time::sleep(delay).await;
+    }
+}
+
+/// Builds a [`ReputestError::TwitterApi`] from a non-success X API response.
+///
+/// Takes `title` and `detail` from the JSON error body (either at the top level or
+/// in the first entry of `errors`) and the rate limit reset time from the
+/// `x-rate-limit-reset` header (Unix seconds).
+async fn api_error(response: reqwest::Response) -> ReputestError {
+    let status = response.status();
+    let rate_limit_reset = response
+        .headers()
+        .get("x-rate-limit-reset")
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| v.parse::<i64>().ok())
+        .and_then(|secs| chrono::DateTime::from_timestamp(secs, 0));
+    let body = response.text().await.unwrap_or_default();
+    let (title, detail) = parse_api_error_body(&body);
+
+    ReputestError::TwitterApi {
+        status: status.as_u16(),
+        title: title.unwrap_or_else(|| status.canonical_reason().unwrap_or("Error").to_string()),
+        detail: detail.unwrap_or_else(|| sanitize_for_logging(&body, 500)),
+        rate_limit_reset,
+    }
+}
+
+/// Extracts `(title, detail)` from an X API error body.
+pub(crate) fn parse_api_error_body(body: &str) -> (Option<String>, Option<String>) {
+    let Ok(json) = serde_json::from_str::<serde_json::Value>(body) else {
+        return (None, None);
+    };
+    let first_error = json
+        .get("errors")
+        .and_then(|e| e.as_array())
+        .and_then(|e| e.first());
+    let field = |name: &str| {
+        json.get(name)
+            .or_else(|| first_error.and_then(|e| e.get(name)))
+            .and_then(|v| v.as_str())
+            .map(|v| sanitize_for_logging(v, 500))
+    };
+    let detail = field("detail").or_else(|| field("message"));
+    (field("title"), detail)
 }
 
 /// Looks up a user by username using the Twitter API v2.
@@ -213,7 +339,6 @@ pub(crate) async fn make_authenticated_request(
 ///
 /// # Parameters
 ///
-/// - `config`: Mutable reference to TwitterConfig (may be updated with new token)
 /// - `pool`: A reference to the PostgreSQL connection pool
 /// - `username`: The Twitter username to look up
 ///
@@ -221,15 +346,11 @@ pub(crate) async fn make_authenticated_request(
 ///
 /// - `Ok(Some((user_id, name, created_at, followers_count)))`: User information if found
 /// - `Ok(None)`: If user not found
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If the API request fails
+/// - `Err(ReputestError)`: If the API request fails
 pub(crate) async fn lookup_user_by_username(
-    config: &mut TwitterConfig,
     pool: &PgPool,
     username: &str,
-) -> Result<
-    Option<(String, String, chrono::DateTime<chrono::Utc>, Option<i32>)>,
-    Box<dyn std::error::Error + Send + Sync>,
-> {
+) -> Result<Option<(String, String, chrono::DateTime<chrono::Utc>, Option<i32>)>, ReputestError> {
     info!("Looking up user by username: {}", username);
 
     let client = Client::new();
@@ -238,11 +359,9 @@ pub(crate) async fn lookup_user_by_username(
         username
     );
 
-    let auth_header = build_oauth2_user_context_header(&config.access_token);
-    let request_builder = client.get(&url).header("Authorization", auth_header);
+    let request_builder = client.get(&url);
 
-    let response_text =
-        make_authenticated_request(config, pool, request_builder, "lookup_user").await?;
+    let response_text = make_authenticated_request(pool, request_builder, "lookup_user").await?;
     let json_response: serde_json::Value = serde_json::from_str(&response_text)?;
 
     if let Some(data) = json_response.get("data") {
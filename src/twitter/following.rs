@@ -5,9 +5,8 @@ use reqwest::Client;
 use sqlx::PgPool;
 
 use super::api::make_authenticated_request;
-use crate::config::TwitterConfig;
 use crate::db::save_user;
-use crate::oauth::build_oauth2_user_context_header;
+use crate::error::ReputestError;
 
 /// Represents a user from the Twitter following API response.
 #[derive(Debug)]
@@ -26,7 +25,6 @@ pub struct FollowedUser {
 ///
 /// # Parameters
 ///
-/// - `config`: Mutable reference to TwitterConfig (may be updated with new token)
 /// - `pool`: A reference to the PostgreSQL connection pool
 /// - `follower_user_id`: The Twitter user ID whose following list to fetch
 ///
@@ -35,10 +33,9 @@ pub struct FollowedUser {
 /// - `Ok(Vec<FollowedUser>)`: All followed users
 /// - `Err(...)`: If the API request fails (e.g. 403 for protected account)
 pub async fn fetch_user_following(
-    config: &mut TwitterConfig,
     pool: &PgPool,
     follower_user_id: &str,
-) -> Result<Vec<FollowedUser>, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<Vec<FollowedUser>, ReputestError> {
     info!("Fetching following list for user {}", follower_user_id);
 
     let client = Client::new();
@@ -58,26 +55,28 @@ pub async fn fetch_user_following(
             None => base_url.clone(),
         };
 
-        let auth_header = build_oauth2_user_context_header(&config.access_token);
-        let request_builder = client
-            .get(&url)
-            .header("Authorization", auth_header.clone());
+        let request_builder = client.get(&url);
 
         let response_text =
-            make_authenticated_request(config, pool, request_builder, "fetch_user_following")
-                .await?;
+            make_authenticated_request(pool, request_builder, "fetch_user_following").await?;
         let json_response: serde_json::Value = serde_json::from_str(&response_text)?;
 
-        // Check for API errors
+        // Check for API errors (partial errors come back with a 200 status)
         if let Some(errors) = json_response.get("errors").and_then(|e| e.as_array()) {
             for err in errors {
                 if let Some(title) = err.get("title").and_then(|v| v.as_str()) {
                     error!("Twitter API error: {}", title);
-                    if title.contains("Forbidden") || title.contains("403") {
-                        return Err(
-                            "User's following list is not accessible (protected or suspended)"
-                                .into(),
-                        );
+                    if title.contains("Forbidden") || title.contains("Authorization Error") {
+                        return Err(ReputestError::TwitterApi {
+                            status: 403,
+                            title: title.to_string(),
+                            detail: err
+                                .get("detail")
+                                .and_then(|v| v.as_str())
+                                .unwrap_or("User's following list is not accessible")
+                                .to_string(),
+                            rate_limit_reset: None,
+                        });
                     }
                 }
             }
@@ -107,7 +106,7 @@ pub async fn fetch_user_following(
                         }
                     };
 
-                    // Upsert user into database (with follower_count from API when available)
+                    // Upsert user into database (with api_follower_count when available)
                     if let Err(e) =
                         save_user(pool, id, username, name, created_at_utc, followers_count).await
                     {
@@ -151,9 +150,6 @@ pub async fn fetch_user_following(
             );
             break;
         }
-
-        // Brief delay to respect rate limits
-        tokio::time::sleep(tokio::time::Duration::from_millis(500)).await;
     }
 
     Ok(all_followed)
@@ -7,21 +7,38 @@
 mod api;
 mod following;
 mod parsing;
+mod pipeline;
+pub mod rate_limit;
 mod search;
+pub mod tokens;
 mod tweets;
 
 // Re-export public API
 #[allow(unused_imports)]
-pub use parsing::{extract_mention_with_following, extract_mention_with_question};
-pub use search::{search_mentions, search_tweets_with_hashtag};
+pub use parsing::{
+    extract_mention_with_category, extract_mention_with_following, extract_mention_with_question,
+    extract_mention_with_trusted, is_mutuals_request, is_suggest_request,
+};
+#[allow(unused_imports)]
+pub use search::{reprocess_tweet, search_mentions, search_tweets_with_hashtag, ReprocessReport};
 pub use tweets::reply_to_tweet;
 
 // Crate-internal re-exports (used by tests and other modules)
 #[allow(unused_imports)]
-pub(crate) use api::{lookup_user_by_username, sanitize_for_logging};
+pub(crate) use api::{
+    lookup_user_by_username, parse_api_error_body, sanitize_for_logging, send_with_retries,
+};
 #[allow(unused_imports)]
 pub(crate) use following::fetch_user_following;
 #[allow(unused_imports)]
 pub(crate) use parsing::{
-    extract_megajoule_transfer, extract_vibe_emitter, tweet_text_mentions_reputest,
+    extract_megajoule_transfer, extract_tag_category, extract_tagged_transfer,
+    extract_tagged_username, extract_vibe_emitter, tweet_text_mentions_reputest,
 };
+#[allow(unused_imports)]
+pub(crate) use pipeline::{
+    classify, parse_search_page, process_search_page, GmgvDeclaration, MegajouleTransfer,
+    TweetKind, VibeRevocation,
+};
+#[allow(unused_imports)]
+pub(crate) use search::reprocess_lookup;
@@ -20,7 +20,32 @@ const MAX_INPUT_LENGTH: usize = 500;
 ///
 /// - `Some(username)`: The username before #gmgv (without @ prefix)
 /// - `None`: If no valid pattern is found or input exceeds maximum length
+#[allow(dead_code)] // Default #gmgv form; the pipeline uses extract_tagged_username
 pub(crate) fn extract_vibe_emitter(text: &str, exclude_username: Option<&str>) -> Option<String> {
+    extract_tagged_username(text, "gmgv", exclude_username)
+}
+
+/// Extracts the username directly before `#tag`, as in "@alice #gmgv".
+///
+/// Generalizes [`extract_vibe_emitter`] to any hashtag of the registry (vibe and revoke
+/// hashtags use the same "username #tag" form). The hashtag must end at a word boundary,
+/// so `#help` does not match `#helpful`.
+///
+/// # Parameters
+///
+/// - `text`: The tweet text to search
+/// - `tag`: The hashtag without `#`
+/// - `exclude_username`: Optional username to exclude from matching (e.g., reply target)
+///
+/// # Returns
+///
+/// - `Some(username)`: The username before `#tag` (without @ prefix)
+/// - `None`: If no valid pattern is found or input exceeds maximum length
+pub(crate) fn extract_tagged_username(
+    text: &str,
+    tag: &str,
+    exclude_username: Option<&str>,
+) -> Option<String> {
     // SECURITY: Limit input length to prevent ReDoS attacks
     if text.len() > MAX_INPUT_LENGTH {
         log::warn!(
@@ -42,9 +67,13 @@ pub(crate) fn extract_vibe_emitter(text: &str, exclude_username: Option<&str>) -
         "then", "out", "about",
     ];
 
-    // Match optional @ followed by username, then optional whitespace, then #gmgv
+    // Match optional @ followed by username, then optional whitespace, then #tag
     // The pattern requires either start of string, whitespace, or @ before the username
-    let re = regex::Regex::new(r"(?:^|[\s@])@?(\w{1,15})\s*#gmgv").ok()?;
+    let re = regex::Regex::new(&format!(
+        r"(?:^|[\s@])@?(\w{{1,15}})\s*#{}\b",
+        regex::escape(tag)
+    ))
+    .ok()?;
 
     // Find captures and check each one
     for cap in re.captures_iter(text) {
@@ -63,6 +92,33 @@ pub(crate) fn extract_vibe_emitter(text: &str, exclude_username: Option<&str>) -
     None
 }
 
+/// Extracts the category hashtag directly after `#tag`, as in "alice #gmgv #dev".
+///
+/// The category is returned lowercase. Only the hashtag immediately following `#tag`
+/// counts, so unrelated hashtags elsewhere in the tweet do not label the edge.
+///
+/// # Parameters
+///
+/// - `text`: The tweet text to search
+/// - `tag`: The vibe (or revoke) hashtag without `#`
+///
+/// # Returns
+///
+/// - `Some(category)`: The following hashtag without `#`, lowercase
+/// - `None`: If `#tag` is not directly followed by another hashtag
+pub(crate) fn extract_tag_category(text: &str, tag: &str) -> Option<String> {
+    if text.len() > MAX_INPUT_LENGTH {
+        return None;
+    }
+    let re = regex::Regex::new(&format!(
+        r"#{}\s+#([a-zA-Z0-9_]{{1,50}})\b",
+        regex::escape(tag)
+    ))
+    .ok()?;
+    let captures = re.captures(text)?;
+    Some(captures.get(1)?.as_str().to_lowercase())
+}
+
 /// Extracts a username from a tweet that specifically queries the bot in the format "@reputest username ?" or "@reputest @username ?".
 ///
 /// This function matches patterns where "@reputest" appears in the tweet (allowing for replies where
@@ -194,6 +250,116 @@ pub fn extract_mention_with_following(text: &str) -> Option<String> {
     None
 }
 
+/// Extracts a username from a tweet that queries the bot for trusted vibe scores: "@reputest @username trusted?".
+///
+/// Trusted scores only count vibe paths through accounts the querying user follows.
+/// Matches like [`extract_mention_with_following`]; use this before extract_mention_with_question when classifying.
+///
+/// # Parameters
+///
+/// - `text`: The tweet text to analyze
+///
+/// # Returns
+///
+/// - `Some(username)`: The username if found in the trusted-query format
+/// - `None`: If the tweet doesn't match or input exceeds maximum length
+pub fn extract_mention_with_trusted(text: &str) -> Option<String> {
+    if text.len() > MAX_INPUT_LENGTH {
+        log::warn!(
+            "Input text exceeds maximum length ({} > {}), rejecting",
+            text.len(),
+            MAX_INPUT_LENGTH
+        );
+        return None;
+    }
+
+    let re = regex::Regex::new(r"(?:^|\s)@reputest\s+(@?[a-zA-Z0-9_]{1,15})\s+trusted\?").ok()?;
+
+    let captures = re.captures(text)?;
+    let username = captures.get(1)?.as_str();
+    let clean_username = username.strip_prefix('@').unwrap_or(username);
+
+    let excluded_words = ["reputest", "trusted", "mutuals", "suggest"];
+    if excluded_words.contains(&clean_username.to_lowercase().as_str()) {
+        return None;
+    }
+    Some(clean_username.to_string())
+}
+
+/// Extracts the username and category from a category vibe query: "@reputest vibes for @username in #category".
+///
+/// Category scores only count paths whose edges all carry the category. Case-insensitive;
+/// the category is returned lowercase. Use this before extract_mention_with_question when classifying.
+///
+/// # Parameters
+///
+/// - `text`: The tweet text to analyze
+///
+/// # Returns
+///
+/// - `Some((username, category))`: The username (without @) and category if found
+/// - `None`: If the tweet doesn't match or input exceeds maximum length
+pub fn extract_mention_with_category(text: &str) -> Option<(String, String)> {
+    if text.len() > MAX_INPUT_LENGTH {
+        log::warn!(
+            "Input text exceeds maximum length ({} > {}), rejecting",
+            text.len(),
+            MAX_INPUT_LENGTH
+        );
+        return None;
+    }
+
+    let re = regex::Regex::new(
+        r"(?i)(?:^|\s)@reputest\s+vibes\s+for\s+(@?[a-zA-Z0-9_]{1,15})\s+in\s+#([a-zA-Z0-9_]{1,50})\b",
+    )
+    .ok()?;
+
+    let captures = re.captures(text)?;
+    let username = captures.get(1)?.as_str();
+    let clean_username = username.strip_prefix('@').unwrap_or(username);
+    if clean_username.eq_ignore_ascii_case("reputest") {
+        return None;
+    }
+    Some((
+        clean_username.to_string(),
+        captures.get(2)?.as_str().to_lowercase(),
+    ))
+}
+
+/// Returns true if the tweet asks the bot for mutual follows without vibes: "@reputest mutuals?".
+///
+/// Case-insensitive. Precedence: check this before extract_mention_with_question, which
+/// would otherwise read "mutuals" as a username.
+pub fn is_mutuals_request(text: &str) -> bool {
+    if text.len() > MAX_INPUT_LENGTH {
+        log::warn!(
+            "Input text exceeds maximum length ({} > {}), rejecting",
+            text.len(),
+            MAX_INPUT_LENGTH
+        );
+        return false;
+    }
+    let re = regex::Regex::new(r"(?i)(?:^|\s)@reputest\s+mutuals\s*\?").ok();
+    re.map(|r| r.is_match(text)).unwrap_or(false)
+}
+
+/// Returns true if the tweet asks the bot who to vibe: "@reputest suggest" (optionally followed by "?").
+///
+/// Case-insensitive. Precedence: check this before extract_mention_with_question, which
+/// would otherwise read "suggest" as a username.
+pub fn is_suggest_request(text: &str) -> bool {
+    if text.len() > MAX_INPUT_LENGTH {
+        log::warn!(
+            "Input text exceeds maximum length ({} > {}), rejecting",
+            text.len(),
+            MAX_INPUT_LENGTH
+        );
+        return false;
+    }
+    let re = regex::Regex::new(r"(?i)(?:^|\s)@reputest\s+suggest\b").ok();
+    re.map(|r| r.is_match(text)).unwrap_or(false)
+}
+
 /// Returns true if the tweet text mentions the reputest bot (`@reputest`), case-insensitive.
 ///
 /// Requires `@reputest` at a word boundary, preceded by start-of-string or ASCII whitespace
@@ -229,7 +395,25 @@ pub(crate) fn tweet_text_mentions_reputest(text: &str) -> bool {
 ///
 /// - `Some((amount, receiver_username))`: The amount and receiver username if found
 /// - `None`: If no valid pattern is found or input exceeds maximum length
+#[allow(dead_code)] // Default #megajoules form; the pipeline uses extract_tagged_transfer
 pub(crate) fn extract_megajoule_transfer(text: &str) -> Option<(i32, String)> {
+    extract_tagged_transfer(text, "megajoules")
+}
+
+/// Extracts `INTEGER #tag to @username` for a transfer hashtag of the registry.
+///
+/// Generalizes [`extract_megajoule_transfer`]; see it for the accepted forms.
+///
+/// # Parameters
+///
+/// - `text`: The tweet text to search
+/// - `tag`: The hashtag without `#`
+///
+/// # Returns
+///
+/// - `Some((amount, receiver_username))`: The amount and receiver username if found
+/// - `None`: If no valid pattern is found or input exceeds maximum length
+pub(crate) fn extract_tagged_transfer(text: &str, tag: &str) -> Option<(i32, String)> {
     // SECURITY: Limit input length to prevent ReDoS attacks
     if text.len() > MAX_INPUT_LENGTH {
         log::warn!(
@@ -240,8 +424,12 @@ pub(crate) fn extract_megajoule_transfer(text: &str) -> Option<(i32, String)> {
         return None;
     }
 
-    // First match: digits + "#megajoules" + "to" + username (Twitter handle rules)
-    let re = regex::Regex::new(r"(\d+)\s+#megajoules\s+to\s+@?(\w{1,15})").ok()?;
+    // First match: digits + "#tag" + "to" + username (Twitter handle rules)
+    let re = regex::Regex::new(&format!(
+        r"(\d+)\s+#{}\s+to\s+@?(\w{{1,15}})",
+        regex::escape(tag)
+    ))
+    .ok()?;
 
     if let Some(captures) = re.captures(text) {
         if let (Some(amount_match), Some(username_match)) = (captures.get(1), captures.get(2)) {
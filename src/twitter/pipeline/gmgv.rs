@@ -0,0 +1,173 @@
+//! Handler for good vibes declarations (`#gmgv` and other vibe hashtags).
+
+use log::{error, info, warn};
+
+use crate::db::{
+    get_good_vibes_tweet_id, has_good_vibes_record, has_good_vibes_tweet, has_vibe_request,
+    save_good_vibes, save_good_vibes_category,
+};
+
+use super::{
+    queue_reply, resolve_user, GmgvDeclaration, PipelineContext, TweetAuthor, TweetEvent,
+    TweetHandler,
+};
+
+/// Records a good vibes edge from the emitter to the poster (the sensor).
+///
+/// Each emitter-sensor pair is recorded once. A hashtag with a category also labels the
+/// edge, so "alice #helpful" after "alice #gmgv" adds the category to the existing edge.
+/// A repeated declaration gets a reply linking the tweet that was recorded.
+pub(crate) struct GmgvHandler;
+
+impl TweetHandler for GmgvHandler {
+    type Command = GmgvDeclaration;
+
+    async fn handle(
+        &self,
+        ctx: &PipelineContext<'_>,
+        event: &TweetEvent,
+        poster: &TweetAuthor,
+        declaration: GmgvDeclaration,
+    ) {
+        let emitter_username = &declaration.emitter_username;
+        let category = declaration.category.as_deref();
+        info!(
+            "  Poster (vibe receiver): {} (@{})",
+            poster.name, poster.username
+        );
+        info!(
+            "  Vibe emitter: {} (category: {:?})",
+            emitter_username, category
+        );
+
+        let Some(emitter_id) = resolve_user(ctx, event, emitter_username, "emitter").await else {
+            return;
+        };
+
+        // Revoked declarations are still in the search window: vibe_requests keeps them
+        // from being recorded again
+        let processed = match has_good_vibes_tweet(ctx.pool, &event.tweet_id).await {
+            Ok(true) => Ok(true),
+            Ok(false) => has_vibe_request(ctx.pool, &event.tweet_id).await,
+            Err(e) => Err(e),
+        };
+        match processed {
+            Ok(true) => {
+                info!(
+                    "Skipping tweet {} from @{} mentioning @{} (posted at {}) - already processed for good vibes",
+                    event.tweet_id, poster.username, emitter_username, event.created_at
+                );
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(
+                    "Failed to check if tweet {} has been processed: {}",
+                    event.tweet_id, e
+                );
+                return;
+            }
+        }
+
+        let noted_reply = match category {
+            Some(category) => format!(
+                "Your good vibes from {} have been noted under #{}.",
+                emitter_username, category
+            ),
+            None => format!("Your good vibes from {} have been noted.", emitter_username),
+        };
+
+        match has_good_vibes_record(ctx.pool, &poster.id, &emitter_id).await {
+            Ok(true) => {
+                if let Some(category) = category {
+                    match save_good_vibes_category(
+                        ctx.pool,
+                        &event.tweet_id,
+                        &emitter_id,
+                        &poster.id,
+                        category,
+                        event.created_at,
+                    )
+                    .await
+                    {
+                        Ok(true) => {
+                            queue_reply(ctx.pool, &event.tweet_id, &noted_reply, "confirmation")
+                                .await;
+                            return;
+                        }
+                        // The edge already has this category: a duplicate
+                        Ok(false) => {}
+                        Err(e) => {
+                            error!("Failed to save good vibes category {}: {}", category, e);
+                            return;
+                        }
+                    }
+                }
+
+                // Already declared: point to the tweet that was recorded
+                match get_good_vibes_tweet_id(ctx.pool, &emitter_id, &poster.id).await {
+                    Ok(Some(original_tweet_id)) => {
+                        let reply_text = format!(
+                            "You've already declared these vibes! See your previous tweet: https://twitter.com/i/status/{}",
+                            original_tweet_id
+                        );
+                        queue_reply(
+                            ctx.pool,
+                            &event.tweet_id,
+                            &reply_text,
+                            "duplicate vibes message",
+                        )
+                        .await;
+                    }
+                    Ok(None) => {
+                        warn!(
+                            "Good vibes record exists but no tweet_id found for emitter {} and sensor {}",
+                            emitter_id, poster.id
+                        );
+                    }
+                    Err(e) => {
+                        error!(
+                            "Failed to get original tweet ID for duplicate vibes check: {}",
+                            e
+                        );
+                    }
+                }
+            }
+            Ok(false) => {
+                if let Err(e) = save_good_vibes(
+                    ctx.pool,
+                    &event.tweet_id,
+                    &emitter_id, // emitter_id (person who sent good vibes)
+                    &poster.id,  // sensor_id (person who received good vibes)
+                    event.created_at,
+                )
+                .await
+                {
+                    error!(
+                        "Failed to save good vibes data (non-constraint error): {}",
+                        e
+                    );
+                    return;
+                }
+                if let Some(category) = category {
+                    if let Err(e) = save_good_vibes_category(
+                        ctx.pool,
+                        &event.tweet_id,
+                        &emitter_id,
+                        &poster.id,
+                        category,
+                        event.created_at,
+                    )
+                    .await
+                    {
+                        error!("Failed to save good vibes category {}: {}", category, e);
+                    }
+                }
+                queue_reply(ctx.pool, &event.tweet_id, &noted_reply, "confirmation").await;
+            }
+            Err(e) => {
+                error!("Failed to check for existing good vibes record: {}", e);
+            }
+        }
+    }
+}
@@ -0,0 +1,91 @@
+//! Handler for `#megajoules` transfers.
+
+use log::{error, info};
+
+use crate::db::{has_megajoule_tweet, save_megajoule};
+
+use super::{
+    queue_reply, resolve_user, MegajouleTransfer, PipelineContext, TweetAuthor, TweetEvent,
+    TweetHandler,
+};
+
+/// Records a megajoule transfer from the poster (the sender) to the receiver.
+///
+/// Each tweet is recorded once.
+pub(crate) struct MegajouleHandler;
+
+impl TweetHandler for MegajouleHandler {
+    type Command = MegajouleTransfer;
+
+    async fn handle(
+        &self,
+        ctx: &PipelineContext<'_>,
+        event: &TweetEvent,
+        poster: &TweetAuthor,
+        transfer: MegajouleTransfer,
+    ) {
+        let MegajouleTransfer {
+            amount,
+            receiver_username,
+        } = transfer;
+        info!(
+            "  Poster (megajoule sender): {} (@{})",
+            poster.name, poster.username
+        );
+        info!("  Receiver: @{}", receiver_username);
+        info!("  Amount: {}", amount);
+
+        let Some(receiver_id) = resolve_user(ctx, event, &receiver_username, "receiver").await
+        else {
+            return;
+        };
+
+        match has_megajoule_tweet(ctx.pool, &event.tweet_id).await {
+            Ok(true) => {
+                info!(
+                    "Skipping tweet {} from @{} sending {} megajoules to @{} (posted at {}) - already processed",
+                    event.tweet_id, poster.username, amount, receiver_username, event.created_at
+                );
+            }
+            Ok(false) => {
+                if let Err(e) = save_megajoule(
+                    ctx.pool,
+                    &event.tweet_id,
+                    &poster.id,   // sender_id
+                    &receiver_id, // receiver_id
+                    amount,
+                    event.created_at,
+                )
+                .await
+                {
+                    error!(
+                        "Failed to save megajoule transfer (non-constraint error): {}",
+                        e
+                    );
+                    return;
+                }
+                info!(
+                    "Megajoule row recorded from hashtag tweet {} (posted {}): sender @{} ({}) → receiver @{} ({}) amount {}",
+                    event.tweet_id,
+                    event.created_at,
+                    poster.username,
+                    poster.id,
+                    receiver_username,
+                    receiver_id,
+                    amount
+                );
+                let reply_text = format!(
+                    "Your {} megajoules to {} have been noted.",
+                    amount, receiver_username
+                );
+                queue_reply(ctx.pool, &event.tweet_id, &reply_text, "confirmation").await;
+            }
+            Err(e) => {
+                error!(
+                    "Failed to check if tweet {} has been processed: {}",
+                    event.tweet_id, e
+                );
+            }
+        }
+    }
+}
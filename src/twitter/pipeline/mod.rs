@@ -0,0 +1,431 @@
+//! Tweet event pipeline for hashtag search results.
+//!
+//! A page of search results goes through three stages:
+//!
+//! 1. [`parse_search_page`] turns the API JSON into [`TweetEvent`]s, plus the authors from
+//!    the `includes.users` expansion.
+//! 2. [`classify`] uses the hashtag registry (the `hashtags` table) to decide what each
+//!    tweet is: a good vibes declaration, a revocation, a megajoule transfer, or neither.
+//! 3. The [`TweetHandler`] for that kind records it and queues the bot's reply.
+//!
+//! Parsing and classification are pure; only the handlers touch the database and the X
+//! API. A new hashtag for an existing action is only a registry row. A new kind of command
+//! gets a [`TweetKind`] variant, a rule in [`classify`] and its own handler module, without
+//! changes to the existing handlers.
+
+mod gmgv;
+mod megajoule;
+mod revoke;
+
+use chrono::{DateTime, Utc};
+use log::{error, info, warn};
+use serde_json::Value;
+use sqlx::PgPool;
+use std::collections::HashMap;
+
+use crate::db::{enqueue_reply, get_user_info_by_username, save_user, HashtagAction, HashtagRule};
+
+use super::api::lookup_user_by_username;
+use super::parsing::{
+    extract_tag_category, extract_tagged_transfer, extract_tagged_username,
+    tweet_text_mentions_reputest,
+};
+
+pub(crate) use gmgv::GmgvHandler;
+pub(crate) use megajoule::MegajouleHandler;
+pub(crate) use revoke::RevokeHandler;
+
+/// A tweet author from the `includes.users` expansion.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TweetAuthor {
+    pub id: String,
+    pub username: String,
+    pub name: String,
+    /// Account creation time; `None` if the API value could not be parsed
+    pub created_at: Option<DateTime<Utc>>,
+}
+
+/// One tweet from a page of search results.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct TweetEvent {
+    pub tweet_id: String,
+    pub text: String,
+    pub created_at: DateTime<Utc>,
+    /// The poster, if the API included them
+    pub author: Option<TweetAuthor>,
+    /// Username of the user this tweet replies to, if the API included them
+    pub reply_to_username: Option<String>,
+}
+
+/// A parsed page of search results.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct SearchPage {
+    pub authors: Vec<TweetAuthor>,
+    pub events: Vec<TweetEvent>,
+    /// Pagination token for the next page, if there is one
+    pub next_token: Option<String>,
+}
+
+/// "emitter #gmgv" (or another vibe hashtag): the poster declares they got good vibes
+/// from the emitter.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct GmgvDeclaration {
+    pub emitter_username: String,
+    /// Edge category, from the hashtag used or the hashtag after it ("#gmgv #dev")
+    pub category: Option<String>,
+}
+
+/// "emitter #tag" with a revoke hashtag: the poster withdraws good vibes from the emitter.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct VibeRevocation {
+    pub emitter_username: String,
+    /// Category to remove from the edge; `None` removes the whole edge
+    pub category: Option<String>,
+}
+
+/// "@reputest 20 #megajoules to receiver" (or another transfer hashtag): the poster sends
+/// megajoules to the receiver.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct MegajouleTransfer {
+    pub amount: i32,
+    pub receiver_username: String,
+}
+
+/// What a tweet asks the bot to do.
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) enum TweetKind {
+    GmgvDeclaration(GmgvDeclaration),
+    VibeRevocation(VibeRevocation),
+    MegajouleTransfer(MegajouleTransfer),
+    Other,
+}
+
+/// Shared state for the handlers of one page.
+pub(crate) struct PipelineContext<'a> {
+    pub pool: &'a PgPool,
+}
+
+/// Handles one kind of classified tweet.
+pub(crate) trait TweetHandler {
+    /// What [`classify`] extracted from the tweet for this handler
+    type Command;
+
+    /// Records the command and queues the bot's reply to `event`.
+    ///
+    /// Failures are logged rather than returned: one bad tweet never stops the page, and
+    /// a tweet that was not recorded is picked up again by the next search.
+    async fn handle(
+        &self,
+        ctx: &PipelineContext<'_>,
+        event: &TweetEvent,
+        poster: &TweetAuthor,
+        command: Self::Command,
+    );
+}
+
+/// Parses a page of `GET /2/tweets/search/recent` results.
+///
+/// Tweets without an ID, text or valid `created_at` are dropped (the latter with an error
+/// in the log); everything else about a tweet is optional.
+pub(crate) fn parse_search_page(json: &Value) -> SearchPage {
+    let authors: Vec<TweetAuthor> = json
+        .pointer("/includes/users")
+        .and_then(Value::as_array)
+        .into_iter()
+        .flatten()
+        .filter_map(parse_author)
+        .collect();
+    let authors_by_id: HashMap<&str, &TweetAuthor> =
+        authors.iter().map(|a| (a.id.as_str(), a)).collect();
+
+    let events = match json.get("data") {
+        Some(Value::Array(tweets)) => tweets
+            .iter()
+            .filter_map(|tweet| parse_tweet(tweet, &authors_by_id))
+            .collect(),
+        Some(_) => {
+            warn!("Unexpected response format: data is not an array");
+            Vec::new()
+        }
+        None => Vec::new(),
+    };
+
+    let next_token = json
+        .pointer("/meta/next_token")
+        .and_then(Value::as_str)
+        .map(String::from);
+
+    SearchPage {
+        authors,
+        events,
+        next_token,
+    }
+}
+
+fn parse_author(user: &Value) -> Option<TweetAuthor> {
+    let field = |name: &str| user.get(name).and_then(Value::as_str);
+    let (id, username, name, created_at) = (
+        field("id")?,
+        field("username")?,
+        field("name")?,
+        field("created_at")?,
+    );
+    let created_at = match DateTime::parse_from_rfc3339(created_at) {
+        Ok(dt) => Some(dt.with_timezone(&Utc)),
+        Err(e) => {
+            error!("Failed to parse user created_at '{}': {}", created_at, e);
+            None
+        }
+    };
+    Some(TweetAuthor {
+        id: id.to_string(),
+        username: username.to_string(),
+        name: name.to_string(),
+        created_at,
+    })
+}
+
+fn parse_tweet(tweet: &Value, authors_by_id: &HashMap<&str, &TweetAuthor>) -> Option<TweetEvent> {
+    let field = |name: &str| tweet.get(name).and_then(Value::as_str);
+    let (tweet_id, text) = (field("id")?, field("text")?);
+    let created_at = match field("created_at") {
+        Some(s) => match DateTime::parse_from_rfc3339(s) {
+            Ok(dt) => dt.with_timezone(&Utc),
+            Err(e) => {
+                error!("Failed to parse created_at '{}': {}", s, e);
+                return None;
+            }
+        },
+        None => {
+            error!("Tweet {} missing created_at field", tweet_id);
+            return None;
+        }
+    };
+    Some(TweetEvent {
+        tweet_id: tweet_id.to_string(),
+        text: text.to_string(),
+        created_at,
+        author: field("author_id")
+            .and_then(|id| authors_by_id.get(id))
+            .map(|a| (*a).clone()),
+        reply_to_username: field("in_reply_to_user_id")
+            .and_then(|id| authors_by_id.get(id))
+            .map(|a| a.username.clone()),
+    })
+}
+
+/// Decides what a tweet asks the bot to do, using the enabled hashtags of the registry.
+///
+/// Transfers are checked first and only count when the tweet mentions `@reputest`; such
+/// a tweet is never also treated as a declaration. Revocations come before declarations,
+/// so "alice #gmgv" followed by a revoke hashtag in the same tweet is not recorded. The
+/// user a tweet replies to is never taken as the emitter.
+///
+/// A vibe or revoke hashtag without a category of its own takes one from the hashtag
+/// directly after it ("alice #gmgv #dev"), unless that hashtag is itself registered.
+pub(crate) fn classify(event: &TweetEvent, rules: &[HashtagRule]) -> TweetKind {
+    let rules_for = |action: HashtagAction| rules.iter().filter(move |r| r.action == action);
+    let category_for = |rule: &HashtagRule| {
+        rule.category.clone().or_else(|| {
+            extract_tag_category(&event.text, &rule.tag)
+                .filter(|category| !rules.iter().any(|r| &r.tag == category))
+        })
+    };
+
+    if tweet_text_mentions_reputest(&event.text) {
+        for rule in rules_for(HashtagAction::Transfer) {
+            if let Some((amount, receiver_username)) =
+                extract_tagged_transfer(&event.text, &rule.tag)
+            {
+                return TweetKind::MegajouleTransfer(MegajouleTransfer {
+                    amount,
+                    receiver_username,
+                });
+            }
+        }
+    }
+
+    let reply_to = event.reply_to_username.as_deref();
+    for rule in rules_for(HashtagAction::Revoke) {
+        if let Some(emitter_username) = extract_tagged_username(&event.text, &rule.tag, reply_to) {
+            return TweetKind::VibeRevocation(VibeRevocation {
+                emitter_username,
+                category: category_for(rule),
+            });
+        }
+    }
+    for rule in rules_for(HashtagAction::Vibe) {
+        if let Some(emitter_username) = extract_tagged_username(&event.text, &rule.tag, reply_to) {
+            return TweetKind::GmgvDeclaration(GmgvDeclaration {
+                emitter_username,
+                category: category_for(rule),
+            });
+        }
+    }
+    TweetKind::Other
+}
+
+/// Runs a page of search results through the pipeline.
+///
+/// Saves every included author, then classifies each tweet and passes it to its handler.
+///
+/// # Parameters
+///
+/// - `json`: The JSON response from the search endpoint
+/// - `rules`: The enabled hashtags of the registry
+/// - `pool`: A reference to the PostgreSQL connection pool
+///
+/// # Returns
+///
+/// The `next_token` for the following page, or `None` if this was the last page.
+pub(crate) async fn process_search_page(
+    json: &Value,
+    rules: &[HashtagRule],
+    pool: &PgPool,
+) -> Option<String> {
+    let page = parse_search_page(json);
+
+    for author in &page.authors {
+        let Some(created_at) = author.created_at else {
+            continue;
+        };
+        if let Err(e) = save_user(
+            pool,
+            &author.id,
+            &author.username,
+            &author.name,
+            created_at,
+            None,
+        )
+        .await
+        {
+            error!("Failed to save user data for {}: {}", author.username, e);
+        }
+    }
+
+    if page.events.is_empty() {
+        info!("No tweets found in this page");
+    } else {
+        info!("Found {} tweets in this page", page.events.len());
+    }
+
+    let ctx = PipelineContext { pool };
+    for (i, event) in page.events.iter().enumerate() {
+        info!("Tweet {} (ID: {}): {}", i + 1, event.tweet_id, event.text);
+        let kind = classify(event, rules);
+        let Some(poster) = &event.author else {
+            if kind != TweetKind::Other {
+                warn!(
+                    "Skipping tweet {}: author not included in the response",
+                    event.tweet_id
+                );
+            }
+            continue;
+        };
+        match kind {
+            TweetKind::GmgvDeclaration(declaration) => {
+                GmgvHandler.handle(&ctx, event, poster, declaration).await
+            }
+            TweetKind::VibeRevocation(revocation) => {
+                RevokeHandler.handle(&ctx, event, poster, revocation).await
+            }
+            TweetKind::MegajouleTransfer(transfer) => {
+                MegajouleHandler.handle(&ctx, event, poster, transfer).await
+            }
+            TweetKind::Other => {}
+        }
+    }
+
+    page.next_token
+}
+
+/// Resolves a username mentioned in a tweet to a user ID.
+///
+/// Uses the `users` table first and falls back to the X API, saving the user it finds.
+/// If X does not know the username, the tweet gets a "couldn't find" reply.
+///
+/// # Parameters
+///
+/// - `ctx`: Pipeline context
+/// - `event`: The tweet that mentions the user (replied to if the user does not exist)
+/// - `username`: The mentioned username
+/// - `role`: What the user is in the tweet ("emitter", "receiver"), for the log
+///
+/// # Returns
+///
+/// The user ID, or `None` if the user does not exist or the lookup failed.
+async fn resolve_user(
+    ctx: &PipelineContext<'_>,
+    event: &TweetEvent,
+    username: &str,
+    role: &str,
+) -> Option<String> {
+    match get_user_info_by_username(ctx.pool, username).await {
+        Ok(Some((user_id, _, _))) => {
+            info!("Using cached user info for @{} from database", username);
+            return Some(user_id);
+        }
+        Ok(None) => {
+            info!(
+                "User @{} not found in database, looking up via Twitter API",
+                username
+            );
+        }
+        Err(e) => {
+            error!("Failed to check database for user @{}: {}", username, e);
+            return None;
+        }
+    }
+
+    match lookup_user_by_username(ctx.pool, username).await {
+        Ok(Some((user_id, name, created_at, follower_count))) => {
+            // Save the user data for future use
+            if let Err(e) = save_user(
+                ctx.pool,
+                &user_id,
+                username,
+                &name,
+                created_at,
+                follower_count,
+            )
+            .await
+            {
+                error!("Failed to save {} user data: {}", role, e);
+            }
+            Some(user_id)
+        }
+        Ok(None) => {
+            warn!("User @{} ({}) not found via Twitter API", username, role);
+            let reply_text = format!(
+                "I couldn't find a Twitter user with the handle '{}'. Please check the spelling and try again.",
+                username
+            );
+            queue_reply(
+                ctx.pool,
+                &event.tweet_id,
+                &reply_text,
+                "user not found message",
+            )
+            .await;
+            None
+        }
+        Err(e) => {
+            error!(
+                "Failed to lookup {} user {} via Twitter API: {}",
+                role, username, e
+            );
+            None
+        }
+    }
+}
+
+/// Queues a reply for the reply worker; a failure is logged and otherwise ignored.
+async fn queue_reply(pool: &PgPool, tweet_id: &str, reply_text: &str, what: &str) {
+    info!(
+        "Queueing reply to tweet {} with {}: {}",
+        tweet_id, what, reply_text
+    );
+    if let Err(e) = enqueue_reply(pool, tweet_id, reply_text).await {
+        warn!("Failed to queue reply to tweet {}: {}", tweet_id, e);
+    }
+}
@@ -0,0 +1,108 @@
+//! Handler for good vibes revocations (revoke hashtags of the registry).
+
+use log::{error, info};
+
+use crate::db::{get_user_id_by_username, has_vibe_request, revoke_good_vibes, save_vibe_request};
+
+use super::{queue_reply, PipelineContext, TweetAuthor, TweetEvent, TweetHandler, VibeRevocation};
+
+/// Removes the good vibes edge from the emitter to the poster, or one of its categories.
+///
+/// Only the sensor can revoke an edge, so the poster of the revocation is always the
+/// sensor. Each revocation tweet is handled once (tracked in `vibe_requests`).
+pub(crate) struct RevokeHandler;
+
+impl TweetHandler for RevokeHandler {
+    type Command = VibeRevocation;
+
+    async fn handle(
+        &self,
+        ctx: &PipelineContext<'_>,
+        event: &TweetEvent,
+        poster: &TweetAuthor,
+        revocation: VibeRevocation,
+    ) {
+        let emitter_username = &revocation.emitter_username;
+        let category = revocation.category.as_deref();
+        info!(
+            "  Poster (vibe receiver): {} (@{})",
+            poster.name, poster.username
+        );
+        info!(
+            "  Revoking vibes from: {} (category: {:?})",
+            emitter_username, category
+        );
+
+        match has_vibe_request(ctx.pool, &event.tweet_id).await {
+            Ok(true) => {
+                info!(
+                    "Skipping revocation tweet {} from @{} - already processed",
+                    event.tweet_id, poster.username
+                );
+                return;
+            }
+            Ok(false) => {}
+            Err(e) => {
+                error!(
+                    "Failed to check if tweet {} has been processed: {}",
+                    event.tweet_id, e
+                );
+                return;
+            }
+        }
+
+        // An emitter who is not in the database cannot have an edge to revoke
+        let emitter_id = match get_user_id_by_username(ctx.pool, emitter_username).await {
+            Ok(id) => id,
+            Err(e) => {
+                error!(
+                    "Failed to check database for user @{}: {}",
+                    emitter_username, e
+                );
+                return;
+            }
+        };
+        let revoked = match &emitter_id {
+            Some(emitter_id) => {
+                match revoke_good_vibes(ctx.pool, &event.tweet_id, emitter_id, &poster.id, category)
+                    .await
+                {
+                    Ok(revoked) => revoked,
+                    Err(e) => {
+                        error!(
+                            "Failed to revoke good vibes from {}: {}",
+                            emitter_username, e
+                        );
+                        return;
+                    }
+                }
+            }
+            None => {
+                if let Err(e) = save_vibe_request(ctx.pool, &event.tweet_id).await {
+                    error!(
+                        "Failed to mark tweet {} as processed: {}",
+                        event.tweet_id, e
+                    );
+                }
+                false
+            }
+        };
+
+        let vibes = match category {
+            Some(category) => format!("#{} vibes", category),
+            None => "good vibes".to_string(),
+        };
+        let reply_text = if revoked {
+            format!(
+                "Your {} from {} have been revoked.",
+                vibes, emitter_username
+            )
+        } else {
+            format!(
+                "There are no {} from {} to revoke.",
+                vibes, emitter_username
+            )
+        };
+        queue_reply(ctx.pool, &event.tweet_id, &reply_text, "revocation result").await;
+    }
+}
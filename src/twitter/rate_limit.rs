@@ -0,0 +1,202 @@
+//! Rate limit tracking for the X API.
+//!
+//! Every X API response carries `x-rate-limit-limit`, `x-rate-limit-remaining` and
+//! `x-rate-limit-reset` headers for the endpoint that was called. The tracker keeps the
+//! latest values per endpoint so that calls can wait for (or skip) an exhausted window
+//! instead of spending a request on a guaranteed 429. The current budgets are shown on
+//! the `/admin/rate-limits` page.
+
+use chrono::{DateTime, Utc};
+use rand::Rng;
+use reqwest::header::HeaderMap;
+use std::collections::HashMap;
+use std::sync::{Mutex, OnceLock};
+use std::time::Duration;
+
+/// Longest backoff between retries, however many attempts have been made.
+pub const MAX_BACKOFF: Duration = Duration::from_secs(60);
+
+/// Latest known rate limit budget of one endpoint.
+#[derive(Debug, Clone, PartialEq, serde::Serialize)]
+pub struct RateLimitBudget {
+    /// Endpoint key, e.g. `GET /2/users/:id/following`
+    pub endpoint: String,
+    /// Requests allowed per window (`x-rate-limit-limit`), if sent
+    pub limit: Option<u32>,
+    /// Requests left in the current window (`x-rate-limit-remaining`)
+    pub remaining: u32,
+    /// When the current window resets (`x-rate-limit-reset`)
+    pub reset_at: DateTime<Utc>,
+    /// When the budget was last read from a response
+    pub updated_at: DateTime<Utc>,
+}
+
+/// What to do before calling an endpoint.
+#[derive(Debug, Clone, PartialEq)]
+pub enum Admission {
+    /// Budget left (or unknown): make the call
+    Proceed,
+    /// Budget exhausted, but the window resets soon: wait this long first
+    Wait(Duration),
+    /// Budget exhausted until the given time, which is too far away to wait for
+    Skip(DateTime<Utc>),
+}
+
+/// Per-endpoint rate limit budgets, updated from X API response headers.
+#[derive(Debug, Default)]
+pub struct RateLimitTracker {
+    budgets: Mutex<HashMap<String, RateLimitBudget>>,
+}
+
+impl RateLimitTracker {
+    /// Creates a tracker with no known budgets.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Returns the process-wide tracker shared by all X API calls.
+    pub fn global() -> &'static RateLimitTracker {
+        static TRACKER: OnceLock<RateLimitTracker> = OnceLock::new();
+        TRACKER.get_or_init(RateLimitTracker::new)
+    }
+
+    /// Records the budget from a response's `x-rate-limit-*` headers.
+    ///
+    /// Responses without `x-rate-limit-remaining` and `x-rate-limit-reset` are ignored.
+    pub fn record(&self, endpoint: &str, headers: &HeaderMap) {
+        let header = |name: &str| {
+            headers
+                .get(name)
+                .and_then(|v| v.to_str().ok())
+                .and_then(|v| v.trim().parse::<i64>().ok())
+        };
+        let (Some(remaining), Some(reset)) = (
+            header("x-rate-limit-remaining"),
+            header("x-rate-limit-reset"),
+        ) else {
+            return;
+        };
+        let Some(reset_at) = DateTime::from_timestamp(reset, 0) else {
+            return;
+        };
+        self.update(
+            endpoint,
+            header("x-rate-limit-limit").map(|v| v.max(0) as u32),
+            remaining.max(0) as u32,
+            reset_at,
+        );
+    }
+
+    /// Stores the budget of an endpoint, replacing any previous value.
+    pub fn update(
+        &self,
+        endpoint: &str,
+        limit: Option<u32>,
+        remaining: u32,
+        reset_at: DateTime<Utc>,
+    ) {
+        let budget = RateLimitBudget {
+            endpoint: endpoint.to_string(),
+            limit,
+            remaining,
+            reset_at,
+            updated_at: Utc::now(),
+        };
+        self.budgets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .insert(endpoint.to_string(), budget);
+    }
+
+    /// Decides whether a call to `endpoint` can be made now.
+    ///
+    /// # Parameters
+    ///
+    /// - `endpoint`: Endpoint key (see [`endpoint_key`])
+    /// - `now`: Current time
+    /// - `max_wait`: Longest wait for the window to reset before skipping the call
+    ///
+    /// # Returns
+    ///
+    /// [`Admission::Proceed`] if budget is left, the budget is unknown or the window has
+    /// already reset; otherwise [`Admission::Wait`] or [`Admission::Skip`].
+    pub fn check(&self, endpoint: &str, now: DateTime<Utc>, max_wait: Duration) -> Admission {
+        let budgets = self.budgets.lock().unwrap_or_else(|e| e.into_inner());
+        let Some(budget) = budgets.get(endpoint) else {
+            return Admission::Proceed;
+        };
+        if budget.remaining > 0 || budget.reset_at <= now {
+            return Admission::Proceed;
+        }
+        // Wait one extra second so the reset has happened on X's side too
+        let wait = (budget.reset_at - now + chrono::Duration::seconds(1))
+            .to_std()
+            .unwrap_or_default();
+        if wait <= max_wait {
+            Admission::Wait(wait)
+        } else {
+            Admission::Skip(budget.reset_at)
+        }
+    }
+
+    /// Returns all known budgets, sorted by endpoint.
+    pub fn snapshot(&self) -> Vec<RateLimitBudget> {
+        let mut budgets: Vec<RateLimitBudget> = self
+            .budgets
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .values()
+            .cloned()
+            .collect();
+        budgets.sort_by(|a, b| a.endpoint.cmp(&b.endpoint));
+        budgets
+    }
+}
+
+/// Builds the key a request is tracked under: the method and URL path, with user IDs,
+/// tweet IDs and usernames replaced by placeholders (X limits per endpoint, not per ID).
+///
+/// For example `GET https://api.x.com/2/users/123/following?max_results=1000` becomes
+/// `GET /2/users/:id/following`.
+pub fn endpoint_key(method: &str, url: &reqwest::Url) -> String {
+    let mut path = String::new();
+    let mut previous = "";
+    for (i, segment) in url.path_segments().into_iter().flatten().enumerate() {
+        path.push('/');
+        if i == 0 {
+            // API version, e.g. "2"
+            path.push_str(segment);
+        } else if previous == "username" {
+            path.push_str(":username");
+        } else if !segment.is_empty() && segment.chars().all(|c| c.is_ascii_digit()) {
+            path.push_str(":id");
+        } else {
+            path.push_str(segment);
+        }
+        previous = segment;
+    }
+    format!("{} {}", method, path)
+}
+
+/// Whether a response with `status` to a `method` request may be sent again.
+///
+/// A 429 means X did not process the request, so it is always retried. A 5xx may come
+/// after X already acted on it (e.g. created the tweet), so only idempotent requests are
+/// retried: sending a POST again could post a duplicate reply. Failed replies are retried
+/// by the `outbound_replies` worker instead, which sees X's duplicate error.
+pub fn is_retryable(method: &reqwest::Method, status: reqwest::StatusCode) -> bool {
+    status == reqwest::StatusCode::TOO_MANY_REQUESTS
+        || (status.is_server_error() && method.is_idempotent())
+}
+
+/// Delay before retry number `attempt` (0-based): exponential backoff from `base`,
+/// capped at one minute, with the upper half randomized so that concurrent callers
+/// do not retry in lockstep.
+pub fn backoff_delay(attempt: u32, base: Duration) -> Duration {
+    let exponential = base
+        .saturating_mul(2u32.saturating_pow(attempt))
+        .min(MAX_BACKOFF);
+    let half = exponential / 2;
+    let jitter_ms = rand::thread_rng().gen_range(0..=half.as_millis() as u64);
+    half + Duration::from_millis(jitter_ms)
+}
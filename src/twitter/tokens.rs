@@ -0,0 +1,466 @@
+//! Shared access to the bot's OAuth 2.0 tokens.
+//!
+//! Every X API call reads the bot's access token through the process-wide
+//! [`TokenManager`] instead of loading its own copy. Refreshes are single-flight: the
+//! manager holds its lock while it refreshes, so concurrent callers wait for the one
+//! refresh in progress and then use its token. This matters because X rotates the
+//! refresh token on every refresh, and two refreshes racing with the same refresh token
+//! leave one of them with a token X no longer accepts.
+//!
+//! The same holds across processes sharing the database: a refresh and the saving of its
+//! tokens run under the token refresh lock ([`db::lock_token_refresh`]), and the tokens
+//! are reloaded once the lock is held, so a process that waited for another one's refresh
+//! uses the new tokens instead of refreshing again. The reload, the saves and the audit
+//! record all use the connection holding the lock, so a refresh takes one connection from
+//! the pool.
+//!
+//! The access token is refreshed proactively when it is about to expire (using the
+//! `expires_in` of the token endpoint, stored in `access_tokens.expires_at`), and after X
+//! answers 401 Unauthorized.
+
+use chrono::{DateTime, Duration, Utc};
+use log::{debug, error, info, warn};
+use sqlx::{PgConnection, PgPool};
+use std::sync::OnceLock;
+use tokio::sync::Mutex;
+
+use crate::config::AppConfig;
+use crate::db::{self, TokenRefreshReason};
+use crate::error::ReputestError;
+use crate::oauth;
+
+/// How long before its expiry the access token is refreshed.
+const REFRESH_MARGIN_SECS: i64 = 5 * 60;
+
+/// How long to wait after a failed proactive refresh before trying again.
+const REFRESH_RETRY_SECS: i64 = 60;
+
+/// The bot's tokens as last loaded from the database or refreshed.
+#[derive(Debug, Clone)]
+struct BotTokens {
+    access_token: String,
+    refresh_token: Option<String>,
+    expires_at: Option<DateTime<Utc>>,
+}
+
+impl BotTokens {
+    /// Whether the access token expires within the refresh margin.
+    fn is_expiring(&self, now: DateTime<Utc>) -> bool {
+        self.expires_at
+            .is_some_and(|expires_at| expires_at - now <= Duration::seconds(REFRESH_MARGIN_SECS))
+    }
+}
+
+#[derive(Debug, Default)]
+struct TokenState {
+    tokens: Option<BotTokens>,
+    /// No proactive refresh is attempted before this time (set after one failed)
+    retry_refresh_at: Option<DateTime<Utc>>,
+}
+
+/// The OAuth client the access token is refreshed with.
+#[derive(Debug, Clone)]
+pub struct OAuthClient {
+    /// The token endpoint, [`oauth::TOKEN_URL`] for X
+    pub token_url: String,
+    pub client_id: String,
+    pub client_secret: String,
+}
+
+/// Hands out the bot's access token and refreshes it, one refresh at a time.
+#[derive(Debug, Default)]
+pub struct TokenManager {
+    state: Mutex<TokenState>,
+    /// Refreshes with this client instead of `XAPI_CLIENT_ID` and `XAPI_CLIENT_SECRET`
+    client: Option<OAuthClient>,
+}
+
+impl TokenManager {
+    /// Creates a manager that loads the tokens from the database on first use.
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Creates a manager that refreshes the access token with `client`.
+    #[allow(dead_code)] // Used by the tests
+    pub fn with_client(client: OAuthClient) -> Self {
+        Self {
+            client: Some(client),
+            ..Self::default()
+        }
+    }
+
+    /// Returns the process-wide manager shared by all X API calls.
+    pub fn global() -> &'static TokenManager {
+        static MANAGER: OnceLock<TokenManager> = OnceLock::new();
+        MANAGER.get_or_init(TokenManager::new)
+    }
+
+    /// Returns the access token to call X with.
+    ///
+    /// The token is loaded from the database on first use. If it expires within five
+    /// minutes it is refreshed first; when that refresh fails the current token is
+    /// returned and the 401 handling of the call takes over.
+    ///
+    /// # Parameters
+    ///
+    /// - `pool`: A reference to the PostgreSQL connection pool
+    /// - `operation`: The X API operation that needs the token, e.g. `search_mentions`
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(String)`: The access token
+    /// - `Err(ReputestError)`: If there is no access token in the database
+    pub async fn access_token(
+        &self,
+        pool: &PgPool,
+        operation: &str,
+    ) -> Result<String, ReputestError> {
+        let mut state = self.state.lock().await;
+        let now = Utc::now();
+        let tokens = match &state.tokens {
+            Some(tokens) => tokens.clone(),
+            None => load_tokens(&mut *pool.acquire().await?).await?,
+        };
+        if !tokens.is_expiring(now) || state.retry_refresh_at.is_some_and(|at| now < at) {
+            let access_token = tokens.access_token.clone();
+            state.tokens = Some(tokens);
+            return Ok(access_token);
+        }
+        let Some(client) = self.client() else {
+            debug!("Access token is expiring but client credentials are missing - not refreshing");
+            let access_token = tokens.access_token.clone();
+            state.tokens = Some(tokens);
+            return Ok(access_token);
+        };
+
+        match refresh_expiring(pool, &client, now, operation).await {
+            Ok(refreshed) => {
+                let access_token = refreshed.access_token.clone();
+                state.tokens = Some(refreshed);
+                state.retry_refresh_at = None;
+                Ok(access_token)
+            }
+            Err(e) => {
+                warn!(
+                    "Proactive token refresh failed, using the current token: {}",
+                    e
+                );
+                let access_token = tokens.access_token.clone();
+                state.tokens = Some(tokens);
+                state.retry_refresh_at = Some(now + Duration::seconds(REFRESH_RETRY_SECS));
+                Ok(access_token)
+            }
+        }
+    }
+
+    /// Gets a new access token after X rejected `rejected` with 401 Unauthorized.
+    ///
+    /// When another call (or another process) has already replaced the rejected token,
+    /// the newer token is returned without refreshing again. Otherwise the token is
+    /// refreshed. Every refresh attempt is recorded in `token_refreshes`.
+    ///
+    /// # Parameters
+    ///
+    /// - `pool`: A reference to the PostgreSQL connection pool
+    /// - `rejected`: The access token X answered 401 to
+    /// - `operation`: The X API operation that got the 401, e.g. `search_mentions`
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(Some(String))`: The access token to retry with
+    /// - `Ok(None)`: If the token cannot be refreshed (no client credentials or refresh token)
+    /// - `Err(ReputestError)`: If the refresh failed
+    pub async fn refresh_after_unauthorized(
+        &self,
+        pool: &PgPool,
+        rejected: &str,
+        operation: &str,
+    ) -> Result<Option<String>, ReputestError> {
+        let mut state = self.state.lock().await;
+        let client = self.client();
+        let mut lock = db::lock_token_refresh(pool).await?;
+        let result = refresh_rejected(lock.conn(), client.as_ref(), rejected, operation).await;
+        lock.release().await?;
+
+        let Some(tokens) = result? else {
+            return Ok(None);
+        };
+        let access_token = tokens.access_token.clone();
+        state.tokens = Some(tokens);
+        state.retry_refresh_at = None;
+        Ok(Some(access_token))
+    }
+
+    /// Refreshes the access token now, whether or not it is about to expire.
+    ///
+    /// Used by the `refresh_token` binary; the refresh is recorded as `manual`.
+    ///
+    /// # Parameters
+    ///
+    /// - `pool`: A reference to the PostgreSQL connection pool
+    ///
+    /// # Returns
+    ///
+    /// - `Ok(String)`: The new access token, already saved
+    /// - `Err(ReputestError)`: If there are no credentials or tokens, or the refresh failed
+    #[allow(dead_code)] // Used by the refresh_token binary
+    pub async fn refresh_now(&self, pool: &PgPool) -> Result<String, ReputestError> {
+        let mut state = self.state.lock().await;
+        let client = self.client().ok_or_else(|| {
+            ReputestError::Config("XAPI_CLIENT_ID and XAPI_CLIENT_SECRET must be set".to_string())
+        })?;
+        let mut lock = db::lock_token_refresh(pool).await?;
+        let result = match load_tokens(lock.conn()).await {
+            Ok(tokens) => {
+                refresh(
+                    lock.conn(),
+                    &tokens,
+                    &client,
+                    TokenRefreshReason::Manual,
+                    "refresh_token",
+                )
+                .await
+            }
+            Err(e) => Err(e),
+        };
+        lock.release().await?;
+        let refreshed = result?;
+        let access_token = refreshed.access_token.clone();
+        state.tokens = Some(refreshed);
+        state.retry_refresh_at = None;
+        Ok(access_token)
+    }
+
+    /// Returns the client to refresh with: the manager's own, or the configured
+    /// `XAPI_CLIENT_ID` and `XAPI_CLIENT_SECRET` if both are set.
+    fn client(&self) -> Option<OAuthClient> {
+        if let Some(client) = &self.client {
+            return Some(client.clone());
+        }
+        let config = AppConfig::current();
+        Some(OAuthClient {
+            token_url: oauth::TOKEN_URL.to_string(),
+            client_id: config.oauth_client_id.clone()?,
+            client_secret: config.oauth_client_secret.clone()?,
+        })
+    }
+}
+
+/// Refreshes the access token unless, once the refresh lock is held, the latest token
+/// in the database no longer expires within the margin (another process refreshed it).
+async fn refresh_expiring(
+    pool: &PgPool,
+    client: &OAuthClient,
+    now: DateTime<Utc>,
+    operation: &str,
+) -> Result<BotTokens, ReputestError> {
+    let mut lock = db::lock_token_refresh(pool).await?;
+    let result = refresh_if_expiring(lock.conn(), client, now, operation).await;
+    lock.release().await?;
+    result
+}
+
+/// The part of [`refresh_expiring`] that runs on the connection holding the lock.
+async fn refresh_if_expiring(
+    conn: &mut PgConnection,
+    client: &OAuthClient,
+    now: DateTime<Utc>,
+    operation: &str,
+) -> Result<BotTokens, ReputestError> {
+    let tokens = load_tokens(conn).await?;
+    if !tokens.is_expiring(now) {
+        debug!("Access token was already refreshed by another process");
+        return Ok(tokens);
+    }
+
+    info!(
+        "Access token expires at {:?}, refreshing it for operation '{}'",
+        tokens.expires_at, operation
+    );
+    refresh(
+        conn,
+        &tokens,
+        client,
+        TokenRefreshReason::Expiring,
+        operation,
+    )
+    .await
+}
+
+/// Gets the tokens to retry with after X rejected `rejected`, on the connection holding
+/// the refresh lock: the latest tokens if they already replaced it, else refreshed ones.
+///
+/// Returns `None`, after recording the failed attempt, when the token cannot be refreshed.
+async fn refresh_rejected(
+    conn: &mut PgConnection,
+    client: Option<&OAuthClient>,
+    rejected: &str,
+    operation: &str,
+) -> Result<Option<BotTokens>, ReputestError> {
+    let tokens = load_tokens(conn).await?;
+    if tokens.access_token != rejected {
+        info!(
+            "Access token was already replaced, retrying operation '{}' without a refresh",
+            operation
+        );
+        return Ok(Some(tokens));
+    }
+
+    let (Some(client), Some(_)) = (client, &tokens.refresh_token) else {
+        error!("Cannot refresh token: missing required credentials");
+        if client.is_none() {
+            error!("Missing XAPI_CLIENT_ID or XAPI_CLIENT_SECRET");
+        }
+        if tokens.refresh_token.is_none() {
+            error!("Missing refresh token (should be loaded from database)");
+        }
+        record_refresh(
+            conn,
+            TokenRefreshReason::Unauthorized,
+            operation,
+            false,
+            false,
+            Some("Missing required credentials for token refresh"),
+        )
+        .await;
+        return Ok(None);
+    };
+
+    refresh(
+        conn,
+        &tokens,
+        client,
+        TokenRefreshReason::Unauthorized,
+        operation,
+    )
+    .await
+    .map(Some)
+}
+
+/// Loads the latest access and refresh tokens from the database.
+async fn load_tokens(conn: &mut PgConnection) -> Result<BotTokens, ReputestError> {
+    let access = match db::get_latest_access_token(&mut *conn).await? {
+        Some(access) if !access.token.is_empty() => access,
+        Some(_) => {
+            error!("Access token is empty");
+            return Err(ReputestError::Config(
+                "Access token cannot be empty".to_string(),
+            ));
+        }
+        None => {
+            error!("No access token found in database");
+            return Err(ReputestError::Config(
+                "No access token found in database".to_string(),
+            ));
+        }
+    };
+
+    let refresh_token = match db::get_latest_refresh_token(&mut *conn).await {
+        Ok(token) => token,
+        Err(e) => {
+            warn!("Failed to load refresh token from database: {}", e);
+            None
+        }
+    };
+
+    Ok(BotTokens {
+        access_token: access.token,
+        refresh_token,
+        expires_at: access.expires_at,
+    })
+}
+
+/// Refreshes the access token with the refresh token in `tokens`, saves the new tokens
+/// and records the attempt in `token_refreshes`.
+///
+/// A failure to save the new tokens is recorded but does not fail the refresh: the new
+/// access token is still used in memory.
+async fn refresh(
+    conn: &mut PgConnection,
+    tokens: &BotTokens,
+    client: &OAuthClient,
+    reason: TokenRefreshReason,
+    operation: &str,
+) -> Result<BotTokens, ReputestError> {
+    let refresh_token = tokens.refresh_token.as_deref().ok_or_else(|| {
+        ReputestError::Config("Missing required credentials for token refresh".to_string())
+    })?;
+
+    let issued_at = Utc::now();
+    let response = match oauth::refresh_access_token_at(
+        &client.token_url,
+        &client.client_id,
+        &client.client_secret,
+        refresh_token,
+    )
+    .await
+    {
+        Ok(response) => response,
+        Err(e) => {
+            error!("Failed to refresh access token: {}", e);
+            record_refresh(conn, reason, operation, false, false, Some(&e.to_string())).await;
+            return Err(e);
+        }
+    };
+    debug!("Access token refreshed successfully");
+
+    let expires_at = response.expires_at(issued_at);
+    let mut save_errors = Vec::new();
+    if let Err(e) = db::save_access_token(&mut *conn, &response.access_token, expires_at).await {
+        warn!("Failed to save access token to database, using it in memory only");
+        save_errors.push(format!("access token not saved: {}", e));
+    }
+
+    let refresh_rotated = response.refresh_token.is_some();
+    if let Some(new_refresh) = &response.refresh_token {
+        debug!("Updating refresh token with new token from Twitter");
+        if let Err(e) = db::save_refresh_token(&mut *conn, new_refresh).await {
+            warn!("Failed to save refresh token to database, using it in memory only");
+            save_errors.push(format!("refresh token not saved: {}", e));
+        }
+    }
+
+    let save_error = (!save_errors.is_empty()).then(|| save_errors.join("; "));
+    record_refresh(
+        conn,
+        reason,
+        operation,
+        true,
+        refresh_rotated,
+        save_error.as_deref(),
+    )
+    .await;
+
+    Ok(BotTokens {
+        access_token: response.access_token,
+        refresh_token: response
+            .refresh_token
+            .or_else(|| tokens.refresh_token.clone()),
+        expires_at,
+    })
+}
+
+/// Records a refresh attempt in `token_refreshes`. The audit log is best effort: a
+/// failure to write it is logged and does not fail the refresh.
+async fn record_refresh(
+    conn: &mut PgConnection,
+    reason: TokenRefreshReason,
+    triggered_by: &str,
+    succeeded: bool,
+    refresh_rotated: bool,
+    error: Option<&str>,
+) {
+    if let Err(e) = db::record_token_refresh(
+        conn,
+        reason,
+        triggered_by,
+        succeeded,
+        refresh_rotated,
+        error,
+    )
+    .await
+    {
+        warn!("Failed to record token refresh: {}", e);
+    }
+}
@@ -1,14 +1,16 @@
 //! Tweet operations for Twitter API.
 //!
 //! This module contains functions for replying to tweets using the Twitter API v2.
+//! With `BOT_MODE=shadow` replies are recorded in `shadow_replies` instead of posted.
 
 use log::{debug, info};
 use reqwest::Client;
 use serde_json::json;
+use sqlx::PgPool;
 
-use crate::config::TwitterConfig;
-use crate::db;
-use crate::oauth::build_oauth2_user_context_header;
+use crate::config::{AppConfig, BotMode};
+use crate::db::save_shadow_reply;
+use crate::error::ReputestError;
 
 use super::api::{make_authenticated_request, sanitize_for_logging};
 
@@ -19,23 +21,25 @@ use super::api::{make_authenticated_request, sanitize_for_logging};
 ///
 /// # Parameters
 ///
+/// - `pool`: A reference to the PostgreSQL connection pool (for credentials)
 /// - `text`: The text content of the reply tweet
 /// - `reply_to_tweet_id`: The ID of the tweet to reply to
 ///
 /// # Returns
 ///
-/// - `Ok(String)`: The API response body on successful reply posting
-/// - `Err(Box<dyn std::error::Error + Send + Sync>)`: If authentication fails, network error, or API error
+/// - `Ok(String)`: The API response body on successful reply posting. In shadow mode, a
+///   `{"shadow": true}` body without a tweet ID
+/// - `Err(ReputestError)`: If authentication fails, network error, or API error
 ///
 /// # Requirements
 ///
 /// The following must be available:
-/// - Database connection (DATABASE_URL environment variable)
 /// - Access token in the `access_tokens` table (OAuth 2.0 User Context Access Token for posting tweets)
 pub async fn reply_to_tweet(
+    pool: &PgPool,
     text: &str,
     reply_to_tweet_id: &str,
-) -> Result<String, Box<dyn std::error::Error + Send + Sync>> {
+) -> Result<String, ReputestError> {
     // SECURITY: Sanitize user input before logging to prevent log injection
     info!(
         "Starting reply operation to tweet {} with text: '{}'",
@@ -43,11 +47,14 @@ pub async fn reply_to_tweet(
         sanitize_for_logging(text, 100)
     );
 
-    // Get database pool and load Twitter API credentials from database
-    info!("Loading Twitter configuration from database");
-    let pool = db::get_db_pool().await?;
-    let mut config = TwitterConfig::from_env(&pool).await?;
-    debug!("Twitter config loaded successfully");
+    if AppConfig::current().bot_mode == BotMode::Shadow {
+        let id = save_shadow_reply(pool, reply_to_tweet_id, text).await?;
+        info!(
+            "Shadow mode: recorded reply to tweet {} as shadow reply {} instead of posting",
+            reply_to_tweet_id, id
+        );
+        return Ok(json!({ "shadow": true, "shadow_reply_id": id }).to_string());
+    }
 
     let client = Client::new();
     let url = "https://api.x.com/2/tweets";
@@ -62,10 +69,6 @@ pub async fn reply_to_tweet(
     });
     debug!("Reply payload: {}", serde_json::to_string_pretty(&payload)?);
 
-    // Build the Authorization header with OAuth 2.0 User Context Access Token
-    debug!("Building OAuth 2.0 User Context authorization header");
-    let auth_header = build_oauth2_user_context_header(&config.access_token);
-
     // Log request details
     info!("Sending POST request to Twitter API v2 for reply");
     debug!("Request URL: {}", url);
@@ -75,13 +78,12 @@ pub async fn reply_to_tweet(
         serde_json::to_string_pretty(&payload)?
     );
 
-    // Create the request builder
+    // Create the request builder (the helper adds the Authorization header)
     let request_builder = client
         .post(url)
-        .header("Authorization", auth_header)
         .header("Content-Type", "application/json")
         .json(&payload);
 
     // Use the authenticated request helper with automatic token refresh
-    make_authenticated_request(&mut config, &pool, request_builder, "reply_to_tweet").await
+    make_authenticated_request(pool, request_builder, "reply_to_tweet").await
 }